@@ -0,0 +1,389 @@
+// Feeds arbitrary sequences of messages, full of values no sensible caller
+// would send (NaN and infinite positions, negative radii, zero-size
+// resizes), into `Grid::tick`, and checks that it neither panics nor lets a
+// NaN into the simulation.
+//
+// Run it with `cargo +nightly fuzz run grid_messages` from the repository's
+// root, after `cargo install cargo-fuzz`.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use iced::{Point, Size};
+use libfuzzer_sys::fuzz_target;
+use simulation::config::SimulationConfig;
+use simulation::physics::{
+    Circle, Distribution, EntityId, GravityWell, Grid, GridFrame, GridMessage, KinematicPath,
+    KinematicRectangle, KinematicSpinner, Portal, PortalPair, Sink, SpawnProfile, Spawner,
+    StaticCapsule, StaticCircle, StaticRectangle, VelocityDistribution,
+};
+
+// The same size as the app's window.
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 480.0;
+const MAX_TICKS: usize = 30;
+
+// A message as the fuzzer generates it. Bodies are given by the values they're
+// built from, and circles in the grid by their index among the last frame's
+// circles, so that most inputs refer to ones that exist.
+#[derive(Debug, Arbitrary)]
+enum FuzzMessage {
+    AddCircle(FuzzCircle),
+    Spawn(FuzzCircle),
+    AddStaticCircle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    AddStaticRectangle {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    AddStaticCapsule {
+        a: (f32, f32),
+        b: (f32, f32),
+        radius: f32,
+    },
+    AddStaticSegment {
+        a: (f32, f32),
+        b: (f32, f32),
+        thickness: f32,
+    },
+    AddKinematicRectangle {
+        width: f32,
+        height: f32,
+        path: FuzzPath,
+    },
+    AddKinematicSpinner {
+        pivot: (f32, f32),
+        length: f32,
+        thickness: f32,
+        angular_velocity: f32,
+    },
+    AddPortalPair {
+        a: (f32, f32, f32),
+        b: (f32, f32, f32),
+        rotation: f32,
+    },
+    AddSink {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    AddGravityWell {
+        x: f32,
+        y: f32,
+        strength: f32,
+        radius: f32,
+    },
+    AddSpawner {
+        position: (f32, f32),
+        radius: f32,
+        velocity: (f32, f32),
+        interval: u8,
+    },
+    AddRevoluteJoint {
+        a: u8,
+        b: u8,
+    },
+    SetVelocity {
+        circle: u8,
+        velocity: (f32, f32),
+    },
+    Remove {
+        circle: u8,
+    },
+    Resize {
+        width: f32,
+        height: f32,
+    },
+    UpdateConfig(FuzzConfig),
+    SetGravity((f32, f32)),
+    SetTimeScale(f32),
+    Explode {
+        x: f32,
+        y: f32,
+        strength: f32,
+        radius: f32,
+    },
+    SpawnBurst {
+        x: f32,
+        y: f32,
+    },
+    Batch(Vec<FuzzMessage>),
+    SetPaused(bool),
+    Step,
+    ClearDynamic,
+    ClearStatic,
+    ResetAll,
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzCircle {
+    x: f32,
+    y: f32,
+    radius: f32,
+    velocity: (f32, f32),
+    density: f32,
+    charge: f32,
+}
+
+#[derive(Debug, Arbitrary)]
+enum FuzzPath {
+    Linear {
+        from: (f32, f32),
+        to: (f32, f32),
+        period: f32,
+    },
+    Orbit {
+        center: (f32, f32),
+        radius: f32,
+        period: f32,
+    },
+}
+
+// The values of the config that the simulation does arithmetic with. The rest
+// are left at their defaults.
+#[derive(Debug, Arbitrary)]
+struct FuzzConfig {
+    time_scale: f32,
+    elasticity_coefficient: f32,
+    air_density: f32,
+    gravity: (f32, f32),
+    wind: (f32, f32),
+    position_correction: f32,
+    friction_coefficient: f32,
+    radius_scale: (f32, f32),
+    density: (f32, f32),
+}
+
+fuzz_target!(|ticks: Vec<Vec<FuzzMessage>>| {
+    let config = SimulationConfig {
+        rewind_seconds: 1.0,
+        ..SimulationConfig::default()
+    };
+    let mut grid = Grid::new_detached(WIDTH, HEIGHT, config);
+
+    let mut ids = Vec::new();
+    for (tick, messages) in ticks.into_iter().take(MAX_TICKS).enumerate() {
+        let description = format!("{messages:?}");
+        let messages = messages
+            .into_iter()
+            .filter_map(|message| message.into_grid_message(&ids))
+            .collect();
+
+        let grid_frame = grid.tick(messages);
+        if let Err(problem) = check_frame(&grid_frame) {
+            panic!("{problem} on tick {tick}, after {description}");
+        }
+        ids = grid_frame
+            .get_circles()
+            .iter()
+            .map(Circle::get_id)
+            .collect();
+    }
+});
+
+impl FuzzMessage {
+    // `None` if it refers to a circle and there aren't any.
+    fn into_grid_message(self, ids: &[EntityId]) -> Option<GridMessage> {
+        let message = match self {
+            FuzzMessage::AddCircle(circle) => GridMessage::AddCircle(circle.into(), None),
+            FuzzMessage::Spawn(circle) => GridMessage::Spawn(circle.into()),
+            FuzzMessage::AddStaticCircle { x, y, radius } => {
+                GridMessage::AddStaticCircle(StaticCircle::new(x, y, radius))
+            }
+            FuzzMessage::AddStaticRectangle {
+                x,
+                y,
+                width,
+                height,
+            } => GridMessage::AddStaticRectangle(StaticRectangle::new(x, y, width, height)),
+            FuzzMessage::AddStaticCapsule { a, b, radius } => {
+                GridMessage::AddStaticCapsule(StaticCapsule::new(point(a), point(b), radius))
+            }
+            FuzzMessage::AddStaticSegment { a, b, thickness } => GridMessage::AddStaticSegment {
+                a: point(a),
+                b: point(b),
+                thickness,
+            },
+            FuzzMessage::AddKinematicRectangle {
+                width,
+                height,
+                path,
+            } => GridMessage::AddKinematicRectangle(KinematicRectangle::new(
+                width,
+                height,
+                path.into(),
+            )),
+            FuzzMessage::AddKinematicSpinner {
+                pivot,
+                length,
+                thickness,
+                angular_velocity,
+            } => GridMessage::AddKinematicSpinner(KinematicSpinner::new(
+                point(pivot),
+                length,
+                thickness,
+                angular_velocity,
+            )),
+            FuzzMessage::AddPortalPair { a, b, rotation } => {
+                GridMessage::AddPortalPair(PortalPair::new(portal(a), portal(b), rotation))
+            }
+            FuzzMessage::AddSink {
+                x,
+                y,
+                width,
+                height,
+            } => GridMessage::AddSink(Sink::new(x, y, width, height)),
+            FuzzMessage::AddGravityWell {
+                x,
+                y,
+                strength,
+                radius,
+            } => GridMessage::AddGravityWell(GravityWell::new(x, y, strength, radius)),
+            FuzzMessage::AddSpawner {
+                position,
+                radius,
+                velocity,
+                interval,
+            } => GridMessage::AddSpawner(Spawner::new(
+                point(position),
+                radius,
+                VelocityDistribution::Fixed(velocity),
+                interval.into(),
+            )),
+            FuzzMessage::AddRevoluteJoint { a, b } => GridMessage::AddRevoluteJoint {
+                a: pick(ids, a)?,
+                b: pick(ids, b)?,
+            },
+            FuzzMessage::SetVelocity { circle, velocity } => {
+                GridMessage::SetVelocity(pick(ids, circle)?, velocity)
+            }
+            FuzzMessage::Remove { circle } => GridMessage::Remove(pick(ids, circle)?),
+            FuzzMessage::Resize { width, height } => GridMessage::Resize(Size::new(width, height)),
+            FuzzMessage::UpdateConfig(config) => GridMessage::UpdateConfig(config.into()),
+            FuzzMessage::SetGravity(gravity) => GridMessage::SetGravity(gravity),
+            FuzzMessage::SetTimeScale(time_scale) => GridMessage::SetTimeScale(time_scale),
+            FuzzMessage::Explode {
+                x,
+                y,
+                strength,
+                radius,
+            } => GridMessage::Explode {
+                x,
+                y,
+                strength,
+                radius,
+            },
+            FuzzMessage::SpawnBurst { x, y } => GridMessage::SpawnBurst { x, y },
+            FuzzMessage::Batch(messages) => GridMessage::Batch(
+                messages
+                    .into_iter()
+                    .filter_map(|message| message.into_grid_message(ids))
+                    .collect(),
+            ),
+            FuzzMessage::SetPaused(paused) => GridMessage::SetPaused(paused),
+            FuzzMessage::Step => GridMessage::Step,
+            FuzzMessage::ClearDynamic => GridMessage::ClearDynamic,
+            FuzzMessage::ClearStatic => GridMessage::ClearStatic,
+            FuzzMessage::ResetAll => GridMessage::ResetAll,
+        };
+        Some(message)
+    }
+}
+
+impl From<FuzzCircle> for Circle {
+    fn from(fuzz_circle: FuzzCircle) -> Self {
+        let mut circle = Circle::new(
+            fuzz_circle.x,
+            fuzz_circle.y,
+            fuzz_circle.radius,
+            fuzz_circle.velocity,
+        );
+        circle.density = fuzz_circle.density;
+        circle.charge = fuzz_circle.charge;
+        circle
+    }
+}
+
+impl From<FuzzPath> for KinematicPath {
+    fn from(fuzz_path: FuzzPath) -> Self {
+        match fuzz_path {
+            FuzzPath::Linear { from, to, period } => KinematicPath::Linear { from, to, period },
+            FuzzPath::Orbit {
+                center,
+                radius,
+                period,
+            } => KinematicPath::Orbit {
+                center,
+                radius,
+                period,
+            },
+        }
+    }
+}
+
+impl From<FuzzConfig> for SimulationConfig {
+    fn from(fuzz_config: FuzzConfig) -> Self {
+        SimulationConfig {
+            time_scale: fuzz_config.time_scale,
+            elasticity_coefficient: fuzz_config.elasticity_coefficient,
+            air_density: fuzz_config.air_density,
+            gravity: fuzz_config.gravity,
+            wind: fuzz_config.wind,
+            position_correction: fuzz_config.position_correction,
+            friction_coefficient: fuzz_config.friction_coefficient,
+            spawn_profile: SpawnProfile {
+                radius_scale: Distribution::Normal {
+                    mean: fuzz_config.radius_scale.0,
+                    std_dev: fuzz_config.radius_scale.1,
+                },
+                density: Distribution::Uniform {
+                    min: fuzz_config.density.0,
+                    max: fuzz_config.density.1,
+                },
+                ..SpawnProfile::default()
+            },
+            rewind_seconds: 1.0,
+            ..SimulationConfig::default()
+        }
+    }
+}
+
+fn point((x, y): (f32, f32)) -> Point {
+    Point::new(x, y)
+}
+
+fn portal((x, y, radius): (f32, f32, f32)) -> Portal {
+    Portal {
+        center: Point::new(x, y),
+        radius,
+    }
+}
+
+fn pick(ids: &[EntityId], index: u8) -> Option<EntityId> {
+    (!ids.is_empty()).then(|| ids[usize::from(index) % ids.len()])
+}
+
+fn check_frame(grid_frame: &GridFrame) -> Result<(), String> {
+    let size = grid_frame.get_size();
+    if !(size.width.is_finite() && size.height.is_finite()) {
+        return Err(format!("the grid's size became {size:?}"));
+    }
+    for circle in grid_frame.get_circles() {
+        let values = [
+            circle.x_pos,
+            circle.y_pos,
+            circle.radius,
+            circle.velocity.0,
+            circle.velocity.1,
+        ];
+        if values.iter().any(|value| !value.is_finite()) {
+            return Err(format!("a circle isn't finite: {circle:?}"));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+// Collision sound effects. These need the `audio` feature and aren't
+// available on wasm32; otherwise collisions are silent.
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+pub use enabled::CollisionSounds;
+
+#[cfg(not(all(feature = "audio", not(target_arch = "wasm32"))))]
+pub use disabled::CollisionSounds;
+
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+mod enabled {
+    use rodio::{source::SineWave, DeviceSinkBuilder, MixerDeviceSink, Source};
+    use simulation::physics::{CollisionEvent, GridFrame};
+    use tracing::warn;
+
+    use std::time::Duration;
+
+    use crate::config_file::AppConfig;
+
+    // Each impact is a short, fading sine tone. Smaller circles ring higher
+    // and harder impacts are louder.
+    const TONE_DURATION: Duration = Duration::from_millis(80);
+    const REFERENCE_RADIUS: f32 = 10.0;
+    const REFERENCE_FREQUENCY: f32 = 440.0;
+    // Caps the number of tones started per frame, so that a pile of circles
+    // landing at once doesn't turn into noise.
+    const MAX_TONES_PER_FRAME: usize = 4;
+
+    pub struct CollisionSounds {
+        sink: Option<MixerDeviceSink>,
+        min_impulse: f32,
+        volume: f32,
+    }
+
+    impl CollisionSounds {
+        pub fn new(config: &AppConfig) -> Self {
+            let sink = match DeviceSinkBuilder::open_default_sink() {
+                Ok(mut sink) => {
+                    sink.log_on_drop(false);
+                    Some(sink)
+                }
+                Err(err) => {
+                    warn!("Failed to open audio output, collisions will be silent: {err}");
+                    None
+                }
+            };
+
+            Self {
+                sink,
+                min_impulse: config.collision_sound_min_impulse,
+                volume: config.collision_sound_volume,
+            }
+        }
+
+        pub fn update_config(&mut self, config: &AppConfig) {
+            self.min_impulse = config.collision_sound_min_impulse;
+            self.volume = config.collision_sound_volume;
+        }
+
+        pub fn play(&self, grid_frame: &GridFrame) {
+            let Some(sink) = &self.sink else {
+                return;
+            };
+            if self.volume <= 0.0 {
+                return;
+            }
+
+            let mut loud_events: Vec<&CollisionEvent> = grid_frame
+                .get_collision_events()
+                .iter()
+                .filter(|event| event.impulse >= self.min_impulse)
+                .collect();
+            loud_events.sort_by(|a, b| b.impulse.total_cmp(&a.impulse));
+
+            for event in loud_events.into_iter().take(MAX_TONES_PER_FRAME) {
+                // Every doubling of the impulse past the threshold gets a bit
+                // louder, up to the configured volume.
+                let loudness = 0.25 + 0.25 * (event.impulse / self.min_impulse.max(1.0)).log2();
+                let radius = grid_frame
+                    .get_circle(event.a)
+                    .map_or(REFERENCE_RADIUS, |circle| circle.radius);
+                let frequency = REFERENCE_FREQUENCY
+                    * (REFERENCE_RADIUS / radius.max(1.0)).sqrt().clamp(0.25, 4.0);
+
+                sink.mixer().add(
+                    SineWave::new(frequency)
+                        .take_duration(TONE_DURATION)
+                        .fade_out(TONE_DURATION)
+                        .amplify(self.volume * loudness.clamp(0.0, 1.0)),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(not(all(feature = "audio", not(target_arch = "wasm32"))))]
+mod disabled {
+    use simulation::physics::GridFrame;
+
+    use crate::config_file::AppConfig;
+
+    pub struct CollisionSounds;
+
+    impl CollisionSounds {
+        pub fn new(_config: &AppConfig) -> Self {
+            Self
+        }
+
+        pub fn update_config(&mut self, _config: &AppConfig) {}
+
+        pub fn play(&self, _grid_frame: &GridFrame) {}
+    }
+}
@@ -0,0 +1,178 @@
+use iced::Size;
+use simulation::config::SimulationConfig;
+use simulation::physics::{GridBuilder, PhaseTimings, TICKS_PER_SECOND};
+use simulation::scene::stress;
+use simulation::simd;
+use simulation::time::Instant;
+
+use std::time::Duration;
+
+// Circles in the integration benchmark are spaced out on a lattice with this
+// much room per circle.
+const LATTICE_SPACING: f32 = 12.0;
+
+// Ticks the stress-test scene of `circle_count` circles `frame_count` times
+// without opening a window and prints statistics about how long each tick
+// took.
+pub fn run_headless_benchmark(frame_count: u32, circle_count: usize, config: SimulationConfig) {
+    let Size { width, height } = stress::world_size(circle_count);
+
+    println!(
+        "Running headless benchmark: {frame_count} frames, {circle_count} circles, {width}x{height} world."
+    );
+
+    // Built with the scene in it, so that building the scene isn't counted
+    // as part of the first tick.
+    let mut grid = GridBuilder::new(width, height)
+        .with_config(config)
+        .with_scene(stress::build(circle_count, width, height))
+        .build_detached();
+    let tick_seconds = 1.0 / TICKS_PER_SECOND as f32;
+
+    let mut tick_durations = Vec::with_capacity(frame_count as usize);
+    let mut phase_totals = PhaseTimings::default();
+    let mut remaining_circle_count = 0;
+    let mut sleeping_circle_count = 0;
+    let benchmark_start = Instant::now();
+
+    for _ in 0..frame_count {
+        let tick_start = Instant::now();
+        let grid_frame = grid.step(tick_seconds, &[]);
+        tick_durations.push(tick_start.elapsed());
+        phase_totals += grid_frame.get_phase_timings();
+        remaining_circle_count = grid_frame.get_circle_count();
+        sleeping_circle_count = grid_frame.get_sleeping_circle_count();
+    }
+
+    let total_duration = benchmark_start.elapsed();
+
+    print_statistics(&mut tick_durations, total_duration);
+    print_phase_means(&phase_totals, frame_count);
+    println!(
+        "Circles remaining after last frame: {remaining_circle_count} ({sleeping_circle_count} sleeping)"
+    );
+}
+
+// The subtick length used by the integration benchmark, as if every tick
+// were split into four subticks.
+const INTEGRATION_DT: f32 = 0.25;
+
+type AddToAwake = fn(&mut [(f32, f32)], (f32, f32), &[bool]);
+type Scale = fn(&mut [(f32, f32)], &[f32]);
+type AdvanceAwake = fn(&mut [(f32, f32)], &[(f32, f32)], f32, &[bool]);
+
+// Times `frame_count` passes of the gravity, drag and position update loops
+// over `circle_count` circles, first one circle at a time and then
+// vectorized, and prints how long a pass took on average with each.
+pub fn run_integration_benchmark(frame_count: u32, circle_count: usize) {
+    println!("Running integration benchmark: {frame_count} passes over {circle_count} circles.");
+    if cfg!(not(feature = "simd")) {
+        println!("Built without the `simd` feature, so both runs use the scalar loops.");
+    }
+
+    let scalar_duration = time_integration_passes(
+        frame_count,
+        circle_count,
+        simd::scalar::add_to_awake,
+        simd::scalar::scale,
+        simd::scalar::advance_awake,
+    );
+    let vectorized_duration = time_integration_passes(
+        frame_count,
+        circle_count,
+        simd::add_to_awake,
+        simd::scale,
+        simd::advance_awake,
+    );
+
+    let per_pass = |duration: Duration| as_millis(duration) / frame_count.max(1) as f64;
+    println!("Pass time (ms):");
+    println!("  scalar:     {:>9.3}", per_pass(scalar_duration));
+    println!(
+        "  vectorized: {:>9.3} ({:.2}x)",
+        per_pass(vectorized_duration),
+        scalar_duration.as_secs_f64() / vectorized_duration.as_secs_f64().max(f64::EPSILON)
+    );
+}
+
+fn time_integration_passes(
+    frame_count: u32,
+    circle_count: usize,
+    add_to_awake: AddToAwake,
+    scale: Scale,
+    advance_awake: AdvanceAwake,
+) -> Duration {
+    let columns = (circle_count as f32).sqrt().ceil().max(1.0) as usize;
+    let mut positions: Vec<(f32, f32)> = (0..circle_count)
+        .map(|i| {
+            (
+                (i % columns) as f32 * LATTICE_SPACING,
+                (i / columns) as f32 * LATTICE_SPACING,
+            )
+        })
+        .collect();
+    let mut velocities: Vec<(f32, f32)> = (0..circle_count)
+        .map(|i| (((i * 7919) % 1000) as f32 / 250.0 - 2.0, 0.0))
+        .collect();
+    let sleeping = vec![false; circle_count];
+    let retained = vec![0.999; circle_count];
+
+    let start = Instant::now();
+    for _ in 0..frame_count {
+        add_to_awake(&mut velocities, (0.0, 0.1 * INTEGRATION_DT), &sleeping);
+        scale(&mut velocities, &retained);
+        advance_awake(&mut positions, &velocities, INTEGRATION_DT, &sleeping);
+        std::hint::black_box((&mut positions, &mut velocities));
+    }
+    start.elapsed()
+}
+
+fn print_statistics(tick_durations: &mut [Duration], total_duration: Duration) {
+    if tick_durations.is_empty() {
+        println!("No frames were run.");
+        return;
+    }
+
+    tick_durations.sort();
+
+    let percentile = |p: f64| {
+        let index = ((tick_durations.len() - 1) as f64 * p).round() as usize;
+        tick_durations[index]
+    };
+    let mean = tick_durations.iter().sum::<Duration>() / tick_durations.len() as u32;
+
+    println!("Tick time (ms):");
+    println!("  min:  {:>9.3}", as_millis(tick_durations[0]));
+    println!("  mean: {:>9.3}", as_millis(mean));
+    println!("  p50:  {:>9.3}", as_millis(percentile(0.50)));
+    println!("  p95:  {:>9.3}", as_millis(percentile(0.95)));
+    println!("  p99:  {:>9.3}", as_millis(percentile(0.99)));
+    println!(
+        "  max:  {:>9.3}",
+        as_millis(tick_durations[tick_durations.len() - 1])
+    );
+    println!(
+        "Total: {:.3} s ({:.1} ticks/s)",
+        total_duration.as_secs_f64(),
+        tick_durations.len() as f64 / total_duration.as_secs_f64()
+    );
+}
+
+fn print_phase_means(phase_totals: &PhaseTimings, frame_count: u32) {
+    if frame_count == 0 {
+        return;
+    }
+
+    println!("Mean time per phase (ms):");
+    for (phase, total) in phase_totals.named() {
+        println!(
+            "  {:<18} {:>9.3}",
+            format!("{phase}:"),
+            as_millis(total / frame_count)
+        );
+    }
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+const CELL_SIZE: f32 = 50.0;
+
+// Finds the pairs of circles that might be touching, so that only those have
+// to be checked exactly. A pair may be given more than once. The same circles
+// in the same places must always give the same pairs in the same order, which
+// keeps the simulation deterministic.
+pub trait Broadphase: Send {
+    fn find_pairs(
+        &mut self,
+        positions: &[(f32, f32)],
+        radii: &[f32],
+        pairs: &mut Vec<(usize, usize)>,
+    );
+}
+
+// Puts every circle in each of the square cells its bounding box covers, and
+// pairs up the circles that share a cell, once for every cell they share.
+// Cheap to build, and good at circles spread evenly over the grid, but a
+// crowded cell pairs up everything in it.
+pub struct UniformGrid;
+
+impl Broadphase for UniformGrid {
+    fn find_pairs(
+        &mut self,
+        positions: &[(f32, f32)],
+        radii: &[f32],
+        pairs: &mut Vec<(usize, usize)>,
+    ) {
+        // The hasher has a fixed seed so that the cells, and so the pairs,
+        // come out in the same order on every run.
+        let mut grid: HashMap<(i32, i32), Vec<usize>, BuildHasherDefault<DefaultHasher>> =
+            HashMap::default();
+
+        for (i, (&(x_pos, y_pos), &radius)) in positions.iter().zip(radii).enumerate() {
+            let min_cell_x = ((x_pos - radius) / CELL_SIZE).floor() as i32;
+            let max_cell_x = ((x_pos + radius) / CELL_SIZE).floor() as i32;
+            let min_cell_y = ((y_pos - radius) / CELL_SIZE).floor() as i32;
+            let max_cell_y = ((y_pos + radius) / CELL_SIZE).floor() as i32;
+
+            for cell_x in min_cell_x..=max_cell_x {
+                for cell_y in min_cell_y..=max_cell_y {
+                    grid.entry((cell_x, cell_y)).or_default().push(i);
+                }
+            }
+        }
+
+        for circle_indices in grid.values() {
+            for (idx1, &i) in circle_indices.iter().enumerate() {
+                for &j in &circle_indices[(idx1 + 1)..] {
+                    pairs.push((i, j));
+                }
+            }
+        }
+    }
+}
+
+// Sorts the circles by where their bounding boxes start along one axis, then
+// sweeps along it, pairing each circle with the ones whose boxes start before
+// its own ends and that overlap it on the other axis too. The sweep goes
+// along whichever axis the circles are more spread out on, so circles settled
+// into horizontal layers are swept from left to right with only a column of
+// them open at a time. The order is kept between calls, and since circles
+// hardly move in a subtick it's nearly sorted already, which the sort is
+// quick at.
+#[derive(Default)]
+pub struct SweepAndPrune {
+    order: Vec<usize>,
+}
+
+impl Broadphase for SweepAndPrune {
+    fn find_pairs(
+        &mut self,
+        positions: &[(f32, f32)],
+        radii: &[f32],
+        pairs: &mut Vec<(usize, usize)>,
+    ) {
+        if self.order.len() != positions.len() {
+            self.order = (0..positions.len()).collect();
+        }
+
+        let sweep_x = variance(positions.iter().map(|position| position.0))
+            >= variance(positions.iter().map(|position| position.1));
+        // The position along the sweep axis, then along the other one.
+        let along_axes = |i: usize| {
+            let (x_pos, y_pos) = positions[i];
+            if sweep_x {
+                (x_pos, y_pos)
+            } else {
+                (y_pos, x_pos)
+            }
+        };
+
+        self.order.sort_by(|&a, &b| {
+            (along_axes(a).0 - radii[a]).total_cmp(&(along_axes(b).0 - radii[b]))
+        });
+
+        for (k, &i) in self.order.iter().enumerate() {
+            let (sweep_i, cross_i) = along_axes(i);
+            let end_i = sweep_i + radii[i];
+            for &j in &self.order[(k + 1)..] {
+                let (sweep_j, cross_j) = along_axes(j);
+                if sweep_j - radii[j] > end_i {
+                    break;
+                }
+                if (cross_i - cross_j).abs() <= radii[i] + radii[j] {
+                    pairs.push((i, j));
+                }
+            }
+        }
+    }
+}
+
+fn variance(values: impl Iterator<Item = f32> + Clone) -> f32 {
+    let count = values.clone().count().max(1) as f32;
+    let mean = values.clone().sum::<f32>() / count;
+    values.map(|value| (value - mean).powi(2)).sum::<f32>() / count
+}
+
+// Selects a broadphase from the config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BroadphaseKind {
+    #[default]
+    UniformGrid,
+    SweepAndPrune,
+}
+
+impl BroadphaseKind {
+    pub fn broadphase(self) -> Box<dyn Broadphase> {
+        match self {
+            BroadphaseKind::UniformGrid => Box::new(UniformGrid),
+            BroadphaseKind::SweepAndPrune => Box::new(SweepAndPrune::default()),
+        }
+    }
+}
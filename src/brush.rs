@@ -0,0 +1,230 @@
+use iced::{
+    mouse::{self, ScrollDelta},
+    widget::canvas::{self, Frame, Geometry, Path, Program},
+    Color, Element, Length, Point, Rectangle, Renderer, Theme,
+};
+use simulation::config::SimulationConfig;
+use simulation::physics::{Circle, Distribution, GridFrame};
+use simulation::time::Instant;
+
+use std::time::Duration;
+
+use crate::{config_file::AppConfig, view::View, Message};
+
+// Slower rates, including zero and negative ones, are treated as this many
+// circles a second.
+const MIN_RATE: f32 = 0.01;
+// Each notch of the mouse wheel scales the radius by this much, within these
+// bounds.
+const RADIUS_STEP: f32 = 1.1;
+const MIN_RADIUS: f32 = 1.0;
+const MAX_RADIUS: f32 = 100.0;
+// Touchpads scroll by pixels rather than notches. This many make up a notch.
+const PIXELS_PER_STEP: f32 = 20.0;
+const PREVIEW_COLOR: Color = Color::from_rgba(1.0, 1.0, 1.0, 0.7);
+
+// Paints circles at the cursor while the left mouse button is held, at most
+// one per frame.
+pub struct Brush {
+    enabled: bool,
+    rate: f32,
+    // Starts out as the config's, and is changed with the mouse wheel until
+    // the config's changes.
+    radius: f32,
+    configured_radius: f32,
+    // What the spawn profile scales the circles' radii by. The spacing is
+    // kept with room for the largest they're likely to be.
+    radius_scale: Distribution,
+    spacing: f32,
+    // Set while the button is held.
+    stroke: Option<Stroke>,
+}
+
+struct Stroke {
+    next_circle_at: Instant,
+    // Where the last circle was painted. It may not be in the frames yet, so
+    // it's kept clear of separately.
+    last_position: Option<Point>,
+}
+
+impl Brush {
+    pub fn new(simulation_config: &SimulationConfig, app_config: &AppConfig) -> Self {
+        Self {
+            enabled: false,
+            rate: app_config.brush_rate,
+            radius: app_config.brush_radius,
+            configured_radius: app_config.brush_radius,
+            radius_scale: simulation_config.spawn_profile.radius_scale,
+            spacing: app_config.brush_spacing,
+            stroke: None,
+        }
+    }
+
+    pub fn update_config(&mut self, simulation_config: &SimulationConfig, app_config: &AppConfig) {
+        self.rate = app_config.brush_rate;
+        if app_config.brush_radius != self.configured_radius {
+            self.radius = app_config.brush_radius;
+            self.configured_radius = app_config.brush_radius;
+        }
+        self.radius_scale = simulation_config.spawn_profile.radius_scale;
+        self.spacing = app_config.brush_spacing;
+    }
+
+    // The smallest and largest radii that painted circles are likely to
+    // have once the spawn profile has scaled them, which are the same unless
+    // it scales them at random.
+    pub fn get_radius_range(&self) -> (f32, f32) {
+        (
+            self.radius * self.radius_scale.smallest(),
+            self.radius * self.radius_scale.largest(),
+        )
+    }
+
+    // Scales the radius by `steps` notches of the mouse wheel, growing it for
+    // positive ones.
+    pub fn scale_radius(&mut self, steps: i32) {
+        self.radius = (self.radius * RADIUS_STEP.powi(steps)).clamp(MIN_RADIUS, MAX_RADIUS);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.stroke = None;
+    }
+
+    // Returns whether a stroke started, which it only does while the brush
+    // is on.
+    pub fn press(&mut self) -> bool {
+        if self.enabled {
+            self.stroke = Some(Stroke {
+                next_circle_at: Instant::now(),
+                last_position: None,
+            });
+        }
+        self.enabled
+    }
+
+    pub fn release(&mut self) {
+        self.stroke = None;
+    }
+
+    // The circle to spawn at `position`, if a stroke is going, one is due and
+    // there's room for it among the circles in `grid_frame`.
+    pub fn paint(&mut self, position: Point, grid_frame: Option<&GridFrame>) -> Option<Circle> {
+        let stroke = self.stroke.as_mut()?;
+        let now = Instant::now();
+        if now < stroke.next_circle_at {
+            return None;
+        }
+
+        let largest_radius = self.radius * self.radius_scale.largest();
+        let clear_of_last = stroke.last_position.is_none_or(|last_position| {
+            last_position.distance(position) >= 2.0 * largest_radius + self.spacing
+        });
+        let has_room = grid_frame.is_none_or(|grid_frame| {
+            grid_frame.has_room_for(position, largest_radius, self.spacing)
+        });
+        if !(clear_of_last && has_room) {
+            return None;
+        }
+
+        stroke.next_circle_at = now + Duration::from_secs_f32(1.0 / self.rate.max(MIN_RATE));
+        stroke.last_position = Some(position);
+        Some(Circle::new(position.x, position.y, self.radius, (0.0, 0.0)))
+    }
+}
+
+// A ring at the cursor the size that painted circles will be, over a world
+// drawn through `view`, or two for the smallest and largest they're likely
+// to be if the spawn profile picks their sizes at random. Scrolling over it
+// resizes the brush.
+pub fn preview(radius_range: (f32, f32), view: View) -> Element<'static, Message> {
+    canvas::Canvas::new(Preview { radius_range, view })
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+struct Preview {
+    radius_range: (f32, f32),
+    view: View,
+}
+
+#[derive(Default)]
+struct PreviewState {
+    // In window coordinates, and only while the cursor is over the canvas.
+    cursor: Option<Point>,
+    // Scrolling that hasn't added up to a whole notch yet.
+    partial_steps: f32,
+}
+
+impl Program<Message> for Preview {
+    type State = PreviewState;
+
+    fn update(
+        &self,
+        state: &mut PreviewState,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (canvas::event::Status, Option<Message>) {
+        let canvas::Event::Mouse(event) = event else {
+            return (canvas::event::Status::Ignored, None);
+        };
+        match event {
+            mouse::Event::CursorMoved { .. } | mouse::Event::CursorEntered => {
+                state.cursor = cursor.position_over(bounds);
+            }
+            mouse::Event::CursorLeft => state.cursor = None,
+            mouse::Event::WheelScrolled { delta } if state.cursor.is_some() => {
+                state.partial_steps += match delta {
+                    ScrollDelta::Lines { y, .. } => y,
+                    ScrollDelta::Pixels { y, .. } => y / PIXELS_PER_STEP,
+                };
+                let steps = state.partial_steps.trunc();
+                state.partial_steps -= steps;
+                let message = (steps != 0.0).then_some(Message::ScaleBrushRadius(steps as i32));
+                return (canvas::event::Status::Captured, message);
+            }
+            _ => {}
+        }
+        // Clicks go on to paint.
+        (canvas::event::Status::Ignored, None)
+    }
+
+    fn draw(
+        &self,
+        state: &PreviewState,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let Some(cursor) = state.cursor else {
+            return Vec::new();
+        };
+
+        let mut frame = Frame::new(renderer, bounds.size());
+        frame.translate(self.view.offset);
+        frame.scale(self.view.scale);
+        let (smallest_radius, largest_radius) = self.radius_range;
+        let center = self.view.to_world(cursor);
+        let radii = if smallest_radius < largest_radius {
+            vec![smallest_radius, largest_radius]
+        } else {
+            vec![largest_radius]
+        };
+        for radius in radii {
+            frame.stroke(
+                &Path::circle(center, radius),
+                canvas::Stroke::default()
+                    .with_color(PREVIEW_COLOR)
+                    .with_width(1.5 / self.view.scale),
+            );
+        }
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::broadphase::BroadphaseKind;
+use crate::integrator::IntegratorKind;
+use crate::physics::{
+    Boundaries, EvictionPolicy, LifetimePolicy, OutOfBoundsPolicy, OverflowPolicy, ResizeContents,
+    Solver, SpawnBurst, SpawnProfile,
+};
+
+// Tunable parameters of the physics simulation. Every field has a default, so
+// a config file only needs to specify the values it wants to override.
+// Anything else in the file is ignored, so it can hold an app's settings too.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    // Each frame is split into enough subticks to keep fast circles from
+    // tunneling, within these bounds.
+    pub min_subticks_per_frame: u32,
+    pub max_subticks_per_frame: u32,
+    // How fast simulated time passes, from 0.1 (slow motion) to 10 (fast
+    // forward). Each subtick covers this much more time, and more subticks
+    // are used to keep up when it's sped up. Circles shrink at the scaled
+    // rate too, but ages, spawners and other counts of ticks aren't scaled.
+    pub time_scale: f32,
+    pub elasticity_coefficient: f32,
+    // Bodies that hit each other slower than this don't bounce, so that
+    // resting circles come to rest instead of vibrating.
+    pub restitution_threshold: f32,
+    pub air_density: f32,
+    // The lifetime policy of circles that are added without one.
+    pub lifetime: LifetimePolicy,
+    pub min_radius_size: f32,
+    // If set, `eviction_policy` keeps there from being more than this many
+    // circles, so a runaway spawner can't bog down the app.
+    pub max_circles: Option<usize>,
+    pub eviction_policy: EvictionPolicy,
+    // Acceleration applied to every circle each tick, as (x, y).
+    pub gravity: (f32, f32),
+    // Velocity of the air, as (x, y). Circles are dragged towards it with the
+    // same `air_density` and drag model that slow them down in still air, so
+    // circles without drag aren't blown around.
+    pub wind: (f32, f32),
+    // Whether circles bounce off, leave through or wrap around each edge of
+    // the world.
+    pub boundaries: Boundaries,
+    // What happens to circles found entirely outside the world, past an edge
+    // that doesn't wrap. Left alone, ones that leave through an open edge, or
+    // are stranded by the world shrinking, stay out there for good.
+    pub out_of_bounds: OutOfBoundsPolicy,
+    // How circles are moved each subtick. See `integrator.rs` for the
+    // trade-offs.
+    pub integrator: IntegratorKind,
+    pub solver: Solver,
+    // Passes over the overlapping pairs of circles per subtick. More passes
+    // let pushes travel further through a pile, so stacks settle instead of
+    // jittering. Each pass removes `position_correction` of each pair's
+    // overlap; less than 1 spreads the push over several passes and subticks,
+    // which is steadier but leaves piles a little squashed.
+    pub solver_iterations: u32,
+    pub position_correction: f32,
+    // How much circles grip each other as they slide past, as the ratio of
+    // the sideways impulse to the impulse pushing them apart. Circles can
+    // override it, and a pair uses the geometric mean of their two.
+    pub friction_coefficient: f32,
+    // How circles that might be touching are found. See `broadphase.rs` for
+    // the trade-offs.
+    pub broadphase: BroadphaseKind,
+    // Circles whose smoothed speed stays below this for `sleep_frames`
+    // consecutive frames are put to sleep until something runs into them.
+    // Set to zero to disable sleeping.
+    pub sleep_speed_threshold: f32,
+    pub sleep_frames: u32,
+    // If set, circles that collide merge into one, as long as the merged
+    // circle's radius would be no more than this.
+    pub coalesce_max_radius: Option<f32>,
+    // If set, circles hit with at least this much impulse break into
+    // `fracture_pieces` smaller circles, as long as the pieces would be no
+    // smaller than `min_radius_size`.
+    pub fracture_impulse_threshold: Option<f32>,
+    pub fracture_pieces: u32,
+    // Strength and radius of gravity wells placed with shift-click. See
+    // `GravityWell` for what they mean.
+    pub gravity_well_strength: f32,
+    pub gravity_well_radius: f32,
+    // The circles added by the spawn burst key, around the cursor. See
+    // `SpawnBurst` for what the values mean.
+    pub spawn_burst: SpawnBurst,
+    // How circles added by the brush, spawners and bursts are randomized.
+    // See `SpawnProfile` for what the values mean.
+    pub spawn_profile: SpawnProfile,
+    // Strength of the force between charged circles, and how far apart they
+    // can be and still push or pull each other. See `forces::Coulomb`.
+    pub coulomb_constant: f32,
+    pub charge_cutoff_radius: f32,
+    // How many seconds of the simulation are kept to be rewound through.
+    // Keyframes of the whole grid are kept 20 times a second, so long
+    // histories of crowded scenes take a lot of memory. Set to zero to
+    // disable rewinding.
+    pub rewind_seconds: f32,
+    // How many messages, such as circles to add, can be waiting for the next
+    // tick, and what happens to any more: `Block` waits for room, `DropOldest`
+    // makes room by dropping the one that's waited longest, and `DropNewest`
+    // drops the new one. The stats HUD counts the dropped messages. Messages
+    // that control the simulation, like pausing or resizing, don't count
+    // towards the capacity and are never dropped.
+    pub message_queue_capacity: usize,
+    pub message_overflow_policy: OverflowPolicy,
+    // What resizing the world does to the bodies in it: leave them where
+    // they are, scale them with it, or keep them as far from the nearest
+    // edge as they were. Without scaling or anchoring, shrinking the world
+    // can leave circles outside of it.
+    pub resize_contents: ResizeContents,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            min_subticks_per_frame: 1,
+            max_subticks_per_frame: 30,
+            time_scale: 1.0,
+            elasticity_coefficient: 0.9,
+            restitution_threshold: 0.5,
+            air_density: 0.007,
+            lifetime: LifetimePolicy::ShrinkAndDie { rate: 0.002 },
+            min_radius_size: 0.5,
+            max_circles: None,
+            eviction_policy: EvictionPolicy::default(),
+            gravity: (0.0, 0.2),
+            wind: (0.0, 0.0),
+            boundaries: Boundaries::default(),
+            out_of_bounds: OutOfBoundsPolicy::default(),
+            integrator: IntegratorKind::default(),
+            solver: Solver::default(),
+            solver_iterations: 4,
+            position_correction: 1.0,
+            friction_coefficient: 0.2,
+            broadphase: BroadphaseKind::default(),
+            sleep_speed_threshold: 0.25,
+            sleep_frames: 60,
+            coalesce_max_radius: None,
+            fracture_impulse_threshold: None,
+            fracture_pieces: 3,
+            gravity_well_strength: 0.5,
+            gravity_well_radius: 150.0,
+            spawn_burst: SpawnBurst::default(),
+            spawn_profile: SpawnProfile::default(),
+            coulomb_constant: 1000.0,
+            charge_cutoff_radius: 150.0,
+            rewind_seconds: 10.0,
+            message_queue_capacity: 100,
+            message_overflow_policy: OverflowPolicy::default(),
+            resize_contents: ResizeContents::default(),
+        }
+    }
+}
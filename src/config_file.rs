@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use simulation::config::SimulationConfig;
+use tracing::warn;
+
+use std::path::{Path, PathBuf};
+
+use crate::instanced::CircleRenderer;
+use crate::keybindings::Keybindings;
+use crate::palette::{ColorOverrides, Palette, ThemeKind};
+use crate::view::WindowResize;
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    futures::{channel::mpsc, stream::Stream, StreamExt},
+    notify::{RecursiveMode, Watcher},
+    tracing::error,
+};
+
+// Everything in a config file: the simulation's parameters, and the app's
+// own settings alongside them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    pub simulation: SimulationConfig,
+    pub app: AppConfig,
+}
+
+// Settings that only affect the app, not the simulation: drawing, sounds,
+// input and where files go. Like `SimulationConfig`, every field has a
+// default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    // While the brush is on, holding the left mouse button adds circles of
+    // `brush_radius` at the cursor, up to `brush_rate` a second. The mouse
+    // wheel changes the radius until this one is changed, and `spawn_profile`
+    // varies it. A circle is only added where one as big as `spawn_profile`
+    // is likely to make it would be at least `brush_spacing` from every other
+    // one, so that dragging slowly doesn't stack them up inside each other.
+    pub brush_rate: f32,
+    pub brush_radius: f32,
+    pub brush_spacing: f32,
+    // If set, per-frame aggregates are appended to this CSV file.
+    pub telemetry_csv: Option<PathBuf>,
+    // Where screenshots are saved. It's created if it doesn't exist.
+    pub screenshot_dir: PathBuf,
+    // Collisions with at least this much impulse make a sound, if the app was
+    // built with the `audio` feature. Set the volume to zero to mute them.
+    pub collision_sound_min_impulse: f32,
+    pub collision_sound_volume: f32,
+    // Collisions with at least this much impulse throw off sparks. They're
+    // only drawn, and don't affect the simulation. `None` turns them off.
+    pub spark_min_impulse: Option<f32>,
+    // With more circles than this, they're drawn the cheap way: without fill
+    // styles or animations, and skipping ones too small to see, so that
+    // drawing doesn't slow things down before the simulation does. `None`
+    // always draws them properly.
+    pub lod_circle_threshold: Option<usize>,
+    // Whether circles are drawn on the canvas with everything else, or by a
+    // shader in one draw call, which keeps up with far more of them. See
+    // `instanced.rs`.
+    pub circle_renderer: CircleRenderer,
+    // What resizing the window does: fit the world to it, show the world
+    // unscaled in the middle of it, or resize the world to match it. See
+    // `view.rs`.
+    pub window_resize: WindowResize,
+    // Spacing of the grid that shapes placed in edit mode snap to, in world
+    // units. Holding alt places them freely. Set to zero to disable snapping.
+    pub editor_grid_size: f32,
+    // Spacing of the reference grid and rulers drawn under the bodies, in
+    // world units, to help judge distances. They can be shown or hidden while
+    // running.
+    pub reference_grid_spacing: f32,
+    pub show_reference_grid: bool,
+    // Colors for bodies, the speed heat map and the overlays: `Normal`,
+    // `Deuteranopia` (safe for red-green color blindness) or `HighContrast`.
+    // Can also be cycled while running.
+    pub palette: Palette,
+    // Light or dark. Ball, static body and background colors follow it unless
+    // the palette or `color_overrides` say otherwise. Can also be toggled
+    // while running.
+    pub theme: ThemeKind,
+    pub color_overrides: ColorOverrides,
+    pub keybindings: Keybindings,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            brush_rate: 30.0,
+            brush_radius: 6.0,
+            brush_spacing: 1.0,
+            telemetry_csv: None,
+            screenshot_dir: PathBuf::from("screenshots"),
+            collision_sound_min_impulse: 400.0,
+            collision_sound_volume: 0.3,
+            spark_min_impulse: Some(400.0),
+            lod_circle_threshold: Some(5000),
+            circle_renderer: CircleRenderer::default(),
+            window_resize: WindowResize::default(),
+            editor_grid_size: 20.0,
+            reference_grid_spacing: 50.0,
+            show_reference_grid: false,
+            palette: Palette::default(),
+            theme: ThemeKind::default(),
+            color_overrides: ColorOverrides::default(),
+            keybindings: Keybindings::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "failed to read config file: {err}"),
+            ConfigError::Parse(err) => write!(f, "failed to parse config file: {err}"),
+        }
+    }
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        // Each half ignores the other's fields.
+        Ok(Self {
+            simulation: ron::from_str(&contents).map_err(ConfigError::Parse)?,
+            app: ron::from_str(&contents).map_err(ConfigError::Parse)?,
+        })
+    }
+
+    // Loads the config at `path`, falling back to the defaults if the file
+    // doesn't exist or can't be parsed. There's no filesystem on wasm32, so
+    // there the defaults are always used.
+    pub fn load_or_default(path: &Path) -> Self {
+        match Self::load(path) {
+            Ok(config) => config,
+            Err(ConfigError::Io(err))
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::NotFound | std::io::ErrorKind::Unsupported
+                ) =>
+            {
+                Self::default()
+            }
+            Err(err) => {
+                warn!("{err}. Using default config.");
+                Self::default()
+            }
+        }
+    }
+}
+
+// Watches the config file at `path` and yields a freshly parsed config every
+// time it changes on disk. Invalid edits are reported and skipped so a typo
+// doesn't take down the running simulation.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn watch_config_file(path: PathBuf) -> impl Stream<Item = ConfigFile> {
+    async_stream::stream! {
+        let (mut event_sender, mut event_receiver) = mpsc::channel(16);
+
+        // Watch the parent directory rather than the file itself, since many
+        // editors save by writing a new file and renaming it over the old one.
+        let watch_dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let file_name = path.file_name().map(|file_name| file_name.to_os_string());
+
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event.kind.is_access() {
+                    return;
+                }
+                if event.paths.iter().any(|event_path| event_path.file_name() == file_name.as_deref()) {
+                    // If the channel is full a reload is already pending.
+                    let _ = event_sender.try_send(());
+                }
+            }
+        });
+
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                error!("Failed to create config file watcher: {err}");
+                return;
+            }
+        };
+
+        if let Err(err) = watcher.watch(&watch_dir, RecursiveMode::NonRecursive) {
+            error!("Failed to watch config directory {}: {err}", watch_dir.display());
+            return;
+        }
+
+        let mut last_config = None;
+
+        while event_receiver.next().await.is_some() {
+            // Drain any events that piled up while we were waiting, since
+            // a single save usually produces several of them.
+            while let Ok(Some(())) = event_receiver.try_next() {}
+
+            match ConfigFile::load(&path) {
+                Ok(config) => {
+                    if last_config.as_ref() != Some(&config) {
+                        last_config = Some(config.clone());
+                        yield config;
+                    }
+                }
+                Err(ConfigError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => warn!("{err}. Keeping previous config."),
+            }
+        }
+    }
+}
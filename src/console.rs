@@ -0,0 +1,109 @@
+use futures::Stream;
+use simulation::physics::Circle;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing::{error, info, warn};
+
+use std::path::PathBuf;
+
+use crate::Message;
+
+const HELP: &str = "Commands:
+  spawn X Y RADIUS [VX VY]  Add a circle.
+  gravity X Y               Set gravity.
+  clear                     Remove every dynamic circle.
+  clear-static              Remove everything but the dynamic circles.
+  reset                     Remove everything.
+  pause, resume             Pause or resume the simulation.
+  save [PATH]               Save the scene, to the --scene file by default.
+  load [PATH]               Load a scene, from the --scene file by default.
+  help                      Show this list.";
+
+// Reads commands from stdin, one per line, until it's closed. Blank lines and
+// lines starting with `#` are skipped, so a file of commands can be piped in
+// as is. Commands end up as `GridMessage`s like all other input; see there.
+pub fn read_commands() -> impl Stream<Item = Message> {
+    async_stream::stream! {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    info!("Console input ended.");
+                    return;
+                }
+                Err(err) => {
+                    error!("Failed to read console input: {err}");
+                    return;
+                }
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match parse_command(line) {
+                Ok(Some(message)) => yield message,
+                Ok(None) => {}
+                Err(err) => warn!("{err}: {line}"),
+            }
+        }
+    }
+}
+
+// The message for a command, or `None` for commands that are handled by the
+// console itself.
+fn parse_command(line: &str) -> Result<Option<Message>, String> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    let args: Vec<&str> = words.collect();
+
+    let numbers = || {
+        args.iter()
+            .map(|arg| {
+                arg.parse::<f32>()
+                    .ok()
+                    .filter(|number| number.is_finite())
+                    .ok_or_else(|| format!("`{arg}` isn't a number"))
+            })
+            .collect::<Result<Vec<f32>, String>>()
+    };
+    // Paths can have spaces in them.
+    let path = || (!args.is_empty()).then(|| PathBuf::from(args.join(" ")));
+
+    let message = match command {
+        "spawn" => {
+            let (x, y, radius, velocity) = match *numbers()?.as_slice() {
+                [x, y, radius] => (x, y, radius, (0.0, 0.0)),
+                [x, y, radius, vx, vy] => (x, y, radius, (vx, vy)),
+                _ => return Err("Usage: spawn X Y RADIUS [VX VY]".to_string()),
+            };
+            if radius <= 0.0 {
+                return Err("The radius has to be positive".to_string());
+            }
+            Message::AddCircle(Circle::new(x, y, radius, velocity))
+        }
+        "gravity" => match *numbers()?.as_slice() {
+            [x, y] => Message::SetGravity((x, y)),
+            _ => return Err("Usage: gravity X Y".to_string()),
+        },
+        "clear" if args.is_empty() => Message::Clear,
+        "clear-static" if args.is_empty() => Message::ClearStatic,
+        "reset" if args.is_empty() => Message::ResetAll,
+        "pause" if args.is_empty() => Message::SetPaused(true),
+        "resume" if args.is_empty() => Message::SetPaused(false),
+        "save" => Message::SaveScene(path()),
+        "load" => Message::LoadScene(path()),
+        "help" => {
+            info!("{HELP}");
+            return Ok(None);
+        }
+        "clear" | "clear-static" | "reset" | "pause" | "resume" => {
+            return Err(format!("`{command}` takes no arguments"))
+        }
+        _ => return Err("Unknown command, try `help`".to_string()),
+    };
+
+    Ok(Some(message))
+}
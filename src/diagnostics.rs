@@ -0,0 +1,255 @@
+use iced::{
+    mouse,
+    widget::canvas::{self, Frame, Geometry, Path, Program, Stroke, Text},
+    Element, Length, Point, Rectangle, Renderer, Size, Theme,
+};
+use simulation::physics::GridFrame;
+use simulation::time::Instant;
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{palette::Palette, Message};
+
+const HISTORY_LENGTH: Duration = Duration::from_secs(3);
+const OVERLAY_WIDTH: f32 = 360.0;
+const OVERLAY_HEIGHT: f32 = 120.0;
+const LEGEND_HEIGHT: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy)]
+struct FrameSample {
+    received_at: Instant,
+    simulation_time: Duration,
+    render_time: Duration,
+    input_latency: Duration,
+}
+
+impl FrameSample {
+    fn total(&self) -> Duration {
+        self.simulation_time + self.render_time + self.input_latency
+    }
+}
+
+// Collects per-frame timings for the frame pacing overlay. Simulation time
+// comes from the grid itself, render time is measured around the canvas
+// `draw` call, and input latency is the time between sending a message to the
+// grid and receiving the first frame that applied it.
+pub struct FrameDiagnostics {
+    samples: VecDeque<FrameSample>,
+    last_render_time: Cell<Duration>,
+    pending_input_sent_at: Option<Instant>,
+    target_frame_time: Duration,
+    palette: Palette,
+}
+
+impl FrameDiagnostics {
+    pub fn new(target_fps: u64, palette: Palette) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            last_render_time: Cell::new(Duration::ZERO),
+            pending_input_sent_at: None,
+            target_frame_time: Duration::from_millis(1000 / target_fps),
+            palette,
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn record_input_sent(&mut self) {
+        // Only the oldest unacknowledged input matters for latency.
+        if self.pending_input_sent_at.is_none() {
+            self.pending_input_sent_at = Some(Instant::now());
+        }
+    }
+
+    pub fn record_frame(&mut self, grid_frame: &GridFrame) {
+        let now = Instant::now();
+
+        let input_latency = if grid_frame.get_applied_message_count() > 0 {
+            self.pending_input_sent_at
+                .take()
+                .map(|sent_at| now - sent_at)
+                .unwrap_or_default()
+        } else {
+            Duration::ZERO
+        };
+
+        self.samples.push_back(FrameSample {
+            received_at: now,
+            simulation_time: grid_frame.get_tick_duration(),
+            render_time: self.last_render_time.get(),
+            input_latency,
+        });
+
+        while let Some(oldest) = self.samples.front() {
+            if now - oldest.received_at > HISTORY_LENGTH {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    // How long the last `draw` of the program passed to `timed` took.
+    pub fn get_last_render_time(&self) -> Duration {
+        self.last_render_time.get()
+    }
+
+    // Wraps `program` so that the time spent in its `draw` is recorded as the
+    // render time of the current frame.
+    pub fn timed<P>(&self, program: P) -> TimedProgram<'_, P> {
+        TimedProgram {
+            program,
+            last_render_time: &self.last_render_time,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        canvas::Canvas::new(self)
+            .width(Length::Fixed(OVERLAY_WIDTH))
+            .height(Length::Fixed(OVERLAY_HEIGHT))
+            .into()
+    }
+}
+
+pub struct TimedProgram<'a, P> {
+    program: P,
+    last_render_time: &'a Cell<Duration>,
+}
+
+impl<P: Program<Message>> Program<Message> for TimedProgram<'_, P> {
+    type State = P::State;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (canvas::event::Status, Option<Message>) {
+        self.program.update(state, event, bounds, cursor)
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let start = Instant::now();
+        let geometry = self.program.draw(state, renderer, theme, bounds, cursor);
+        self.last_render_time.set(start.elapsed());
+        geometry
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        self.program.mouse_interaction(state, bounds, cursor)
+    }
+}
+
+impl Program<Message> for FrameDiagnostics {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let colors = self.palette.colors();
+
+        frame.fill(
+            &Path::rectangle(Point::ORIGIN, bounds.size()),
+            colors.overlay_background,
+        );
+
+        // Legend
+        let legend = [
+            ("sim", colors.simulation_time),
+            ("render", colors.render_time),
+            ("input latency", colors.input_latency),
+        ];
+        let mut legend_x = 4.0;
+        for (label, color) in legend {
+            frame.fill_text(Text {
+                content: label.to_string(),
+                position: Point::new(legend_x, 2.0),
+                color,
+                size: 12.0.into(),
+                ..Text::default()
+            });
+            legend_x += 8.0 * label.len() as f32 + 12.0;
+        }
+
+        let chart_top = LEGEND_HEIGHT;
+        let chart_height = bounds.height - chart_top;
+
+        // Scale so that the target frame time sits halfway up the chart unless
+        // some frames take even longer than that.
+        let max_total = self
+            .samples
+            .iter()
+            .map(FrameSample::total)
+            .max()
+            .unwrap_or_default()
+            .max(self.target_frame_time * 2);
+        let pixels_per_second = chart_height / max_total.as_secs_f32();
+
+        let max_bar_count = self.samples.len().max(1);
+        let bar_width = bounds.width / max_bar_count as f32;
+
+        for (i, sample) in self.samples.iter().enumerate() {
+            let x = i as f32 * bar_width;
+            let mut y = bounds.height;
+
+            for (duration, color) in [
+                (sample.simulation_time, colors.simulation_time),
+                (sample.render_time, colors.render_time),
+                (sample.input_latency, colors.input_latency),
+            ] {
+                let height = duration.as_secs_f32() * pixels_per_second;
+                if height <= 0.0 {
+                    continue;
+                }
+                y -= height;
+                frame.fill(
+                    &Path::rectangle(Point::new(x, y), Size::new(bar_width, height)),
+                    color,
+                );
+            }
+        }
+
+        let target_y = bounds.height - self.target_frame_time.as_secs_f32() * pixels_per_second;
+        frame.stroke(
+            &Path::line(
+                Point::new(0.0, target_y),
+                Point::new(bounds.width, target_y),
+            ),
+            Stroke::default()
+                .with_color(colors.target_line)
+                .with_width(1.0),
+        );
+        frame.fill_text(Text {
+            content: format!("{:.1} ms", self.target_frame_time.as_secs_f32() * 1000.0),
+            position: Point::new(bounds.width - 50.0, target_y - 14.0),
+            color: colors.target_line,
+            size: 12.0.into(),
+            ..Text::default()
+        });
+
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,932 @@
+// Drawing grid frames on the canvas. The simulation only says where bodies
+// are; how they're colored, and the sparks, fade-outs and reference grid drawn
+// with them, are the app's, so they're bundled with the frame here.
+use iced::{
+    mouse::{self, Interaction},
+    widget::canvas::{Frame, Geometry, LineCap, Path, Program, Stroke, Text},
+    Color, Point, Rectangle, Renderer, Size, Theme, Vector,
+};
+use serde::{Deserialize, Serialize};
+use simulation::physics::{
+    Circle, EntityId, FillStyle, GravityWell, GridFrame, LifetimePolicy, LinkedAnchor,
+};
+
+use std::collections::HashMap;
+
+use crate::fade_outs::FadingCircle;
+use crate::instanced::CircleRenderer;
+use crate::palette::{ColorOverrides, Colors, Palette};
+use crate::sparks::Spark;
+use crate::view::{View, WindowResize};
+use crate::Message;
+
+// Speed, in units per tick, at which circles are drawn fully red in the speed
+// heat map.
+const HEAT_MAP_MAX_SPEED: f32 = 15.0;
+const PORTAL_RING_WIDTH: f32 = 3.0;
+const GRAVITY_WELL_ARMS: u32 = 3;
+const GRAVITY_WELL_ARM_STEPS: u32 = 24;
+// How fast the swirls drawn for gravity wells turn, in radians per tick.
+const GRAVITY_WELL_SPIN_PER_TICK: f32 = 0.05;
+const JOINT_PIN_RADIUS: f32 = 3.0;
+// The reference grid isn't drawn when its lines would be closer together than
+// this many pixels on screen.
+const MIN_REFERENCE_GRID_SPACING_PIXELS: f32 = 8.0;
+// The reference grid is drawn in the theme's text color, faded to this.
+const REFERENCE_GRID_ALPHA: f32 = 0.12;
+// Ruler labels are spread out to at least this many pixels apart, skipping
+// grid lines in between if need be.
+const MIN_RULER_LABEL_SPACING_PIXELS: f32 = 40.0;
+const RULER_TICK_LENGTH: f32 = 6.0;
+const RULER_TEXT_SIZE: f32 = 10.0;
+// iced can't fill shapes with radial gradients, so they're drawn as this many
+// ever smaller and lighter copies of the shape, the last one this big and
+// this much of the way to white.
+const RADIAL_GRADIENT_STEPS: u32 = 4;
+const RADIAL_GRADIENT_HIGHLIGHT_SCALE: f32 = 0.3;
+const RADIAL_GRADIENT_HIGHLIGHT_LIGHTNESS: f32 = 0.45;
+// How far the highlight is from the center, towards the top left, as a
+// fraction of the body's size.
+const RADIAL_GRADIENT_HIGHLIGHT_OFFSET: f32 = 0.3;
+const OUTLINE_WIDTH_PIXELS: f32 = 2.0;
+const OUTLINE_FILL_ALPHA: f32 = 0.2;
+// Circles grow to full size over this many ticks after they're added, and
+// fade and shrink out over this many once they're removed, or before their
+// time to live runs out if they have one.
+const SPAWN_ANIMATION_TICKS: f32 = 6.0;
+pub const DESPAWN_ANIMATION_TICKS: f32 = 10.0;
+// How far outside the window, in pixels, bodies are still drawn.
+const CULLING_MARGIN_PIXELS: f32 = 4.0;
+// While drawing cheaply, circles smaller than this on screen, in pixels of
+// radius, are skipped, and ones smaller than this are drawn as squares.
+const LOD_MIN_RADIUS_PIXELS: f32 = 0.35;
+const LOD_SQUARE_RADIUS_PIXELS: f32 = 2.0;
+// Drops the low bits of each color channel while drawing cheaply, so that
+// similar colors share a path.
+const LOD_COLOR_MASK: u8 = 0xf0;
+// How far back sparks' streaks reach, in ticks of their current velocity.
+const SPARK_STREAK_TICKS: f32 = 1.5;
+const SPARK_WIDTH_PIXELS: f32 = 2.0;
+const DEFAULT_CIRCLE_STYLE: FillStyle = FillStyle::RadialGradient;
+const DEFAULT_STATIC_STYLE: FillStyle = FillStyle::Solid;
+const DEFAULT_KINEMATIC_STYLE: FillStyle = FillStyle::Outline;
+
+// A grid frame and everything about how it's drawn.
+#[derive(Debug, Clone)]
+pub struct DrawnFrame {
+    grid_frame: GridFrame,
+    color_mode: ColorMode,
+    palette: Palette,
+    color_overrides: ColorOverrides,
+    // Spacing of the reference grid and rulers drawn under the bodies, in
+    // world units, if they're shown.
+    reference_grid_spacing: Option<f32>,
+    // Drawn over the circles.
+    sparks: Vec<Spark>,
+    // Circles removed in the last few ticks, drawn fading out with the
+    // others.
+    fading_circles: Vec<FadingCircle>,
+    window_resize: WindowResize,
+    // Past this many circles, they're drawn the cheap way.
+    lod_circle_threshold: Option<usize>,
+    circle_renderer: CircleRenderer,
+    // The comparison grid's frame, drawn the same way.
+    comparison: Option<Box<DrawnFrame>>,
+}
+
+// How dynamic circles are colored when drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    #[default]
+    Solid,
+    // Along the palette's heat map, from still to `HEAT_MAP_MAX_SPEED` and
+    // above.
+    Speed,
+}
+
+impl ColorMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            ColorMode::Solid => ColorMode::Speed,
+            ColorMode::Speed => ColorMode::Solid,
+        }
+    }
+
+    fn circle_color(self, circle: &Circle, colors: &Colors, ball_color: Color) -> Color {
+        match (self, circle.color) {
+            (ColorMode::Solid, Some((r, g, b))) => Color::from_rgb(r, g, b),
+            (ColorMode::Solid, None) if circle.charge > 0.0 => colors.positive_charge,
+            (ColorMode::Solid, None) if circle.charge < 0.0 => colors.negative_charge,
+            (ColorMode::Solid, None) => ball_color,
+            (ColorMode::Speed, _) => colors.heat_map_color(circle.speed() / HEAT_MAP_MAX_SPEED),
+        }
+    }
+}
+
+impl DrawnFrame {
+    pub fn new(grid_frame: GridFrame) -> Self {
+        let comparison = grid_frame
+            .get_comparison()
+            .map(|comparison| Box::new(DrawnFrame::new(comparison.clone())));
+        Self {
+            grid_frame,
+            color_mode: ColorMode::default(),
+            palette: Palette::default(),
+            color_overrides: ColorOverrides::default(),
+            reference_grid_spacing: None,
+            sparks: Vec::new(),
+            fading_circles: Vec::new(),
+            window_resize: WindowResize::default(),
+            lod_circle_threshold: None,
+            circle_renderer: CircleRenderer::default(),
+            comparison,
+        }
+    }
+
+    pub fn get_grid_frame(&self) -> &GridFrame {
+        &self.grid_frame
+    }
+
+    pub fn with_color_mode(mut self, color_mode: ColorMode) -> Self {
+        self.color_mode = color_mode;
+        self.comparison = self
+            .comparison
+            .map(|comparison| Box::new(comparison.with_color_mode(color_mode)));
+        self
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self.comparison = self
+            .comparison
+            .map(|comparison| Box::new(comparison.with_palette(palette)));
+        self
+    }
+
+    pub fn with_color_overrides(mut self, color_overrides: ColorOverrides) -> Self {
+        self.color_overrides = color_overrides;
+        self.comparison = self
+            .comparison
+            .map(|comparison| Box::new(comparison.with_color_overrides(color_overrides)));
+        self
+    }
+
+    // Only for this grid, not the comparison, since they come from this
+    // grid's collisions.
+    pub fn with_sparks(mut self, sparks: Vec<Spark>) -> Self {
+        self.sparks = sparks;
+        self
+    }
+
+    pub fn with_fading_circles(mut self, fading_circles: Vec<FadingCircle>) -> Self {
+        self.fading_circles = fading_circles;
+        self
+    }
+
+    pub fn with_reference_grid(mut self, spacing: Option<f32>) -> Self {
+        self.reference_grid_spacing = spacing.filter(|spacing| *spacing > 0.0);
+        self.comparison = self
+            .comparison
+            .map(|comparison| Box::new(comparison.with_reference_grid(spacing)));
+        self
+    }
+
+    pub fn with_window_resize(mut self, window_resize: WindowResize) -> Self {
+        self.window_resize = window_resize;
+        self.comparison = self
+            .comparison
+            .map(|comparison| Box::new(comparison.with_window_resize(window_resize)));
+        self
+    }
+
+    pub fn with_lod_circle_threshold(mut self, lod_circle_threshold: Option<usize>) -> Self {
+        self.lod_circle_threshold = lod_circle_threshold;
+        self.comparison = self
+            .comparison
+            .map(|comparison| Box::new(comparison.with_lod_circle_threshold(lod_circle_threshold)));
+        self
+    }
+
+    pub fn with_circle_renderer(mut self, circle_renderer: CircleRenderer) -> Self {
+        self.circle_renderer = circle_renderer;
+        self.comparison = self
+            .comparison
+            .map(|comparison| Box::new(comparison.with_circle_renderer(circle_renderer)));
+        self
+    }
+
+    pub fn get_comparison(&self) -> Option<&DrawnFrame> {
+        self.comparison.as_deref()
+    }
+
+    // Each dynamic circle with where it's drawn, how big and how opaque,
+    // followed by the ones fading out since they were removed.
+    fn animated_circles(&self) -> impl Iterator<Item = (&Circle, Point, f32, f32)> + '_ {
+        let interpolation = self.grid_frame.get_interpolation();
+        let circles = self.grid_frame.get_circles().iter().map(move |circle| {
+            let (radius_scale, opacity) = spawn_animation(circle, interpolation);
+            (
+                circle,
+                circle.interpolated_position(interpolation),
+                circle.radius * radius_scale,
+                opacity,
+            )
+        });
+        let fading_circles = self.fading_circles.iter().map(move |fading_circle| {
+            // They start out where, and as big and opaque as, they were last
+            // drawn.
+            let circle = &fading_circle.circle;
+            let (radius_scale, opacity) = spawn_animation(circle, 1.0);
+            let remaining_life = fading_circle.get_remaining_life(interpolation);
+            (
+                circle,
+                circle.interpolated_position(1.0),
+                circle.radius * radius_scale * remaining_life.sqrt(),
+                opacity * remaining_life,
+            )
+        });
+        circles.chain(fading_circles)
+    }
+}
+
+// For the instanced renderer, which wasm32 builds don't have.
+#[cfg(not(target_arch = "wasm32"))]
+impl DrawnFrame {
+    pub fn get_circle_renderer(&self) -> CircleRenderer {
+        self.circle_renderer
+    }
+
+    pub fn get_window_resize(&self) -> WindowResize {
+        self.window_resize
+    }
+
+    // Where each dynamic circle is drawn, how big, and in what color, with
+    // its spawn animation but not its fill style.
+    pub fn get_drawn_circles<'a>(
+        &'a self,
+        theme: &Theme,
+    ) -> impl Iterator<Item = (Point, f32, Color)> + 'a {
+        let colors = self.palette.colors();
+        let ball_color = colors.ball.resolve_or(theme, self.color_overrides.ball);
+        self.animated_circles()
+            .map(move |(circle, center, radius, opacity)| {
+                let color = self.color_mode.circle_color(circle, colors, ball_color);
+                (
+                    center,
+                    radius,
+                    Color {
+                        a: color.a * opacity,
+                        ..color
+                    },
+                )
+            })
+    }
+}
+
+impl Program<Message> for DrawnFrame {
+    type State = Interaction;
+
+    fn draw(
+        &self,
+        _interaction: &Interaction,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let draw_circles = !self.circle_renderer.draws_instanced();
+        self.draw_frame(renderer, theme, bounds, draw_circles)
+    }
+}
+
+impl DrawnFrame {
+    // Everything the canvas draws, except the dynamic circles unless
+    // `draw_circles`.
+    pub fn draw_frame(
+        &self,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        draw_circles: bool,
+    ) -> Vec<Geometry> {
+        let grid_frame = &self.grid_frame;
+        let interpolation = grid_frame.get_interpolation();
+        let world_size = grid_frame.get_size();
+        let mut frame = Frame::new(renderer, bounds.size());
+        let colors = self.palette.colors();
+        let ball_color = colors.ball.resolve_or(theme, self.color_overrides.ball);
+        let static_body_color = colors
+            .static_body
+            .resolve_or(theme, self.color_overrides.static_body);
+
+        // Everything below is drawn in world coordinates.
+        let view = View::new(self.window_resize, world_size, bounds.size());
+        frame.push_transform();
+        frame.translate(view.offset);
+        frame.scale(view.scale);
+
+        // Bodies entirely outside the window aren't drawn. Checking every
+        // body's bounding box is far cheaper than tessellating it, so it's
+        // not worth keeping a broadphase around for this. The margin keeps
+        // outlines, which reach a little past their bodies, from popping in.
+        let visible = view
+            .visible_world(bounds.size())
+            .expand(CULLING_MARGIN_PIXELS / view.scale);
+
+        // Otherwise the window's own background shows through.
+        if let Some((r, g, b)) = self.color_overrides.background {
+            frame.fill(
+                &Path::rectangle(Point::ORIGIN, world_size),
+                Color::from_rgb(r, g, b),
+            );
+        }
+
+        if let Some(spacing) = self.reference_grid_spacing {
+            draw_reference_grid(&mut frame, spacing, world_size, view, theme.palette().text);
+        }
+
+        // Draw static rectangles
+        for static_rectangle in grid_frame.get_static_rectangles() {
+            let bounds = static_rectangle.bounds();
+            if !visible.intersects(&bounds) {
+                continue;
+            }
+            fill_styled(
+                &mut frame,
+                &Path::rectangle(bounds.position(), bounds.size()),
+                bounds,
+                if static_rectangle.one_way {
+                    colors.one_way_platform
+                } else if static_rectangle.surface_velocity != (0.0, 0.0) {
+                    colors.conveyor
+                } else {
+                    static_body_color
+                },
+                static_rectangle.style.unwrap_or(DEFAULT_STATIC_STYLE),
+                view.scale,
+            );
+        }
+
+        // Draw static capsules
+        for static_capsule in grid_frame.get_static_capsules() {
+            if !visible.intersects(&static_capsule.bounding_box()) {
+                continue;
+            }
+            frame.stroke(
+                &Path::line(static_capsule.a, static_capsule.b),
+                Stroke::default()
+                    .with_color(if static_capsule.surface_velocity != (0.0, 0.0) {
+                        colors.conveyor
+                    } else {
+                        static_body_color
+                    })
+                    .with_width(2.0 * static_capsule.radius)
+                    .with_line_cap(LineCap::Round),
+            );
+        }
+
+        // Draw kinematic rectangles
+        for kinematic_rectangle in grid_frame.get_kinematic_rectangles() {
+            let bounds = kinematic_rectangle.interpolated_bounds(interpolation);
+            if !visible.intersects(&bounds) {
+                continue;
+            }
+            fill_styled(
+                &mut frame,
+                &Path::rectangle(bounds.position(), bounds.size()),
+                bounds,
+                colors.kinematic_body,
+                kinematic_rectangle.style.unwrap_or(DEFAULT_KINEMATIC_STYLE),
+                view.scale,
+            );
+        }
+
+        // Draw kinematic spinners
+        for kinematic_spinner in grid_frame.get_kinematic_spinners() {
+            // Whatever angle it's drawn at, it's within this.
+            let reach = kinematic_spinner.length.hypot(kinematic_spinner.thickness) / 2.0;
+            if !visible.intersects(&circle_bounds(kinematic_spinner.pivot, reach)) {
+                continue;
+            }
+            frame.with_save(|frame| {
+                frame.translate(Vector::new(
+                    kinematic_spinner.pivot.x,
+                    kinematic_spinner.pivot.y,
+                ));
+                frame.rotate(kinematic_spinner.interpolated_angle(interpolation));
+                let local_bounds = kinematic_spinner.local_bounds();
+                fill_styled(
+                    frame,
+                    &Path::rectangle(local_bounds.position(), local_bounds.size()),
+                    local_bounds,
+                    colors.kinematic_body,
+                    DEFAULT_KINEMATIC_STYLE,
+                    view.scale,
+                );
+            });
+        }
+
+        // Draw static circles
+        for static_circle in grid_frame.get_static_circles() {
+            let center = Point::new(static_circle.x_pos, static_circle.y_pos);
+            if !visible.intersects(&circle_bounds(center, static_circle.radius)) {
+                continue;
+            }
+            fill_styled(
+                &mut frame,
+                &Path::circle(center, static_circle.radius),
+                circle_bounds(center, static_circle.radius),
+                static_body_color,
+                static_circle.style.unwrap_or(DEFAULT_STATIC_STYLE),
+                view.scale,
+            );
+        }
+
+        // Draw sinks
+        for sink in grid_frame.get_sinks() {
+            frame.fill(
+                &Path::rectangle(
+                    Point::new(sink.x_pos, sink.y_pos),
+                    Size::new(sink.width, sink.height),
+                ),
+                colors.sink,
+            );
+        }
+
+        // Draw gravity wells
+        for gravity_well in grid_frame.get_gravity_wells() {
+            frame.stroke(
+                &gravity_well_swirl(gravity_well, grid_frame.get_frame_number()),
+                Stroke::default().with_color(colors.gravity_well.resolve(theme)),
+            );
+        }
+
+        // Draw tethers
+        for circle in grid_frame.get_circles() {
+            if let Some(tether) = &circle.tether {
+                frame.stroke(
+                    &Path::line(tether.anchor, circle.interpolated_position(interpolation)),
+                    Stroke::default().with_color(colors.tether.resolve(theme)),
+                );
+            }
+        }
+
+        // Draw springs
+        let circles_by_id: HashMap<EntityId, &Circle> = grid_frame
+            .get_circles()
+            .iter()
+            .map(|circle| (circle.get_id(), circle))
+            .collect();
+        for spring in grid_frame.get_springs() {
+            if let (Some(circle_a), Some(circle_b)) =
+                (circles_by_id.get(&spring.a), circles_by_id.get(&spring.b))
+            {
+                frame.stroke(
+                    &Path::line(
+                        circle_a.interpolated_position(interpolation),
+                        circle_b.interpolated_position(interpolation),
+                    ),
+                    Stroke::default().with_color(colors.spring.resolve(theme)),
+                );
+            }
+        }
+
+        // Draw ropes
+        for rope in grid_frame.get_ropes() {
+            let anchor = match rope.anchor {
+                LinkedAnchor::Point(point) => Some(point),
+                LinkedAnchor::Circle(id) => circles_by_id
+                    .get(&id)
+                    .map(|circle| circle.interpolated_position(interpolation)),
+            };
+            let Some(anchor) = anchor else {
+                continue;
+            };
+            let path = Path::new(|builder| {
+                builder.move_to(anchor);
+                for id in &rope.circles {
+                    if let Some(circle) = circles_by_id.get(id) {
+                        builder.line_to(circle.interpolated_position(interpolation));
+                    }
+                }
+            });
+            frame.stroke(
+                &path,
+                Stroke::default().with_color(colors.rope.resolve(theme)),
+            );
+        }
+
+        // Draw joints as rods, with a dot where they're pinned to the world
+        let joint_color = colors.joint.resolve(theme);
+        for joint in grid_frame.get_joints() {
+            let Some(body) = circles_by_id.get(&joint.body) else {
+                continue;
+            };
+            let anchor = match joint.anchor {
+                LinkedAnchor::Point(point) => {
+                    frame.fill(&Path::circle(point, JOINT_PIN_RADIUS), joint_color);
+                    point
+                }
+                LinkedAnchor::Circle(id) => match circles_by_id.get(&id) {
+                    Some(circle) => circle.interpolated_position(interpolation),
+                    None => continue,
+                },
+            };
+            frame.stroke(
+                &Path::line(anchor, body.interpolated_position(interpolation)),
+                Stroke::default().with_color(joint_color),
+            );
+        }
+
+        // Draw portals as rings, colored by which end of the pair they are
+        for portal_pair in grid_frame.get_portal_pairs() {
+            for (portal, color) in [
+                (&portal_pair.a, colors.portal_a),
+                (&portal_pair.b, colors.portal_b),
+            ] {
+                frame.stroke(
+                    &Path::circle(portal.center, portal.radius),
+                    Stroke::default()
+                        .with_color(color)
+                        .with_width(PORTAL_RING_WIDTH),
+                );
+            }
+        }
+
+        // Draw dynamic circles
+        if !draw_circles {
+            // They're drawn separately, over everything else.
+        } else if self
+            .lod_circle_threshold
+            .is_some_and(|threshold| grid_frame.get_circle_count() > threshold)
+        {
+            self.draw_circles_cheaply(&mut frame, colors, ball_color, view.scale, visible);
+        } else {
+            for (circle, center, radius, opacity) in self.animated_circles() {
+                if !visible.intersects(&circle_bounds(center, radius)) {
+                    continue;
+                }
+                let color = self.color_mode.circle_color(circle, colors, ball_color);
+                fill_styled(
+                    &mut frame,
+                    &Path::circle(center, radius),
+                    circle_bounds(center, radius),
+                    Color {
+                        a: color.a * opacity,
+                        ..color
+                    },
+                    circle.style.unwrap_or(DEFAULT_CIRCLE_STYLE),
+                    view.scale,
+                );
+            }
+        }
+
+        // Draw sparks as streaks trailing behind them, fading out as they age
+        for spark in &self.sparks {
+            // Sparks are moved a tick at a time, like circles.
+            let (x, y) = spark.position;
+            let (vx, vy) = spark.velocity;
+            let head = Point::new(
+                x - vx * (1.0 - interpolation),
+                y - vy * (1.0 - interpolation),
+            );
+            let tail = Point::new(
+                head.x - vx * SPARK_STREAK_TICKS,
+                head.y - vy * SPARK_STREAK_TICKS,
+            );
+            frame.stroke(
+                &Path::line(tail, head),
+                Stroke::default()
+                    .with_color(Color {
+                        a: colors.spark.a * spark.get_remaining_life(),
+                        ..colors.spark
+                    })
+                    .with_width(SPARK_WIDTH_PIXELS / view.scale)
+                    .with_line_cap(LineCap::Round),
+            );
+        }
+
+        // Draw fluid zones
+        for fluid_zone in grid_frame.get_fluid_zones() {
+            frame.fill(
+                &Path::rectangle(
+                    Point::new(fluid_zone.x_pos, fluid_zone.y_pos),
+                    Size::new(fluid_zone.width, fluid_zone.height),
+                ),
+                colors.fluid_zone,
+            );
+        }
+
+        frame.pop_transform();
+
+        // Cover the parts of the window outside the world, including anything
+        // drawn there, like circles leaving through an open edge.
+        let world_left = view.offset.x;
+        let world_top = view.offset.y;
+        let world_right = world_left + world_size.width * view.scale;
+        let world_bottom = world_top + world_size.height * view.scale;
+        let bars = [
+            (0.0, 0.0, bounds.width, world_top),
+            (
+                0.0,
+                world_bottom,
+                bounds.width,
+                bounds.height - world_bottom,
+            ),
+            (0.0, world_top, world_left, world_bottom - world_top),
+            (
+                world_right,
+                world_top,
+                bounds.width - world_right,
+                world_bottom - world_top,
+            ),
+        ];
+        for (x, y, width, height) in bars {
+            if width > 0.0 && height > 0.0 {
+                frame.fill(
+                    &Path::rectangle(Point::new(x, y), Size::new(width, height)),
+                    colors.letterbox.resolve(theme),
+                );
+            }
+        }
+
+        // Drawn over the letterbox so that they show along the world's edges
+        // even where circles are leaving through them.
+        if let Some(spacing) = self.reference_grid_spacing {
+            draw_rulers(&mut frame, spacing, world_size, view, theme.palette().text);
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    // For scenes with so many circles that drawing each one properly would
+    // take longer than simulating them. Circles are filled in one path per
+    // color, without their fill styles or animations, circles too small to
+    // see are skipped, and ones only a few pixels across are drawn as
+    // squares.
+    fn draw_circles_cheaply(
+        &self,
+        frame: &mut Frame,
+        colors: &Colors,
+        ball_color: Color,
+        view_scale: f32,
+        visible: Rectangle,
+    ) {
+        let interpolation = self.grid_frame.get_interpolation();
+        let mut circles_by_color: Vec<(Color, Vec<(Point, f32)>)> = Vec::new();
+        for circle in self.grid_frame.get_circles() {
+            let radius_pixels = circle.radius * view_scale;
+            if radius_pixels < LOD_MIN_RADIUS_PIXELS {
+                continue;
+            }
+
+            let color = self.color_mode.circle_color(circle, colors, ball_color);
+            // So that the heat map doesn't turn into one path per circle.
+            let color = Color::from_rgba8(
+                (color.r * 255.0) as u8 & LOD_COLOR_MASK,
+                (color.g * 255.0) as u8 & LOD_COLOR_MASK,
+                (color.b * 255.0) as u8 & LOD_COLOR_MASK,
+                color.a,
+            );
+            let center = circle.interpolated_position(interpolation);
+            if !visible.intersects(&circle_bounds(center, circle.radius)) {
+                continue;
+            }
+            match circles_by_color
+                .iter_mut()
+                .find(|(other_color, _)| *other_color == color)
+            {
+                Some((_, circles)) => circles.push((center, circle.radius)),
+                None => circles_by_color.push((color, vec![(center, circle.radius)])),
+            }
+        }
+
+        for (color, circles) in circles_by_color {
+            let path = Path::new(|builder| {
+                for (center, radius) in circles {
+                    if radius * view_scale < LOD_SQUARE_RADIUS_PIXELS {
+                        builder.rectangle(
+                            Point::new(center.x - radius, center.y - radius),
+                            Size::new(2.0 * radius, 2.0 * radius),
+                        );
+                    } else {
+                        builder.circle(center, radius);
+                    }
+                }
+            });
+            frame.fill(&path, color);
+        }
+    }
+}
+
+// How big and how opaque to draw the circle, as fractions of its radius and
+// color, so that it grows in when added and fades and shrinks out before its
+// time to live runs out. Circles removed some other way fade out afterwards,
+// as `FadingCircle`s.
+fn spawn_animation(circle: &Circle, interpolation: f32) -> (f32, f32) {
+    // Ages go up before a tick is drawn, so the tick a circle was added on is
+    // its first.
+    let ticks_shown = circle.get_age().saturating_sub(1) as f32 + interpolation;
+    let grown = (ticks_shown / SPAWN_ANIMATION_TICKS).min(1.0);
+    // Eased out, so that it slows down as it reaches full size.
+    let spawn_scale = 1.0 - (1.0 - grown).powi(2);
+
+    let despawn_scale = match circle.lifetime {
+        Some(LifetimePolicy::Ttl { frames }) => {
+            let ticks_left = frames as f32 - circle.get_age() as f32 - interpolation;
+            (ticks_left / DESPAWN_ANIMATION_TICKS).clamp(0.0, 1.0)
+        }
+        _ => 1.0,
+    };
+
+    (spawn_scale * despawn_scale.sqrt(), despawn_scale)
+}
+
+// Arms spiralling in from the edge of the well, turning over time in the
+// direction circles are pulled.
+fn gravity_well_swirl(gravity_well: &GravityWell, frame_number: u32) -> Path {
+    let center = Point::new(gravity_well.x_pos, gravity_well.y_pos);
+    let spin = frame_number as f32 * GRAVITY_WELL_SPIN_PER_TICK * gravity_well.strength.signum();
+    Path::new(|builder| {
+        for arm in 0..GRAVITY_WELL_ARMS {
+            let arm_angle = std::f32::consts::TAU * arm as f32 / GRAVITY_WELL_ARMS as f32;
+            for step in 0..=GRAVITY_WELL_ARM_STEPS {
+                let t = step as f32 / GRAVITY_WELL_ARM_STEPS as f32;
+                let angle = arm_angle - spin + t * std::f32::consts::PI;
+                let distance = gravity_well.radius * (1.0 - t);
+                let point = Point::new(
+                    center.x + distance * angle.cos(),
+                    center.y + distance * angle.sin(),
+                );
+                if step == 0 {
+                    builder.move_to(point);
+                } else {
+                    builder.line_to(point);
+                }
+            }
+        }
+    })
+}
+
+// Faint lines every `spacing` units across the world, drawn in world
+// coordinates.
+fn draw_reference_grid(
+    frame: &mut Frame,
+    spacing: f32,
+    world_size: Size,
+    view: View,
+    color: Color,
+) {
+    if spacing * view.scale < MIN_REFERENCE_GRID_SPACING_PIXELS {
+        return;
+    }
+
+    let reference_grid = Path::new(|builder| {
+        for x in reference_grid_lines(spacing, world_size.width) {
+            builder.move_to(Point::new(x, 0.0));
+            builder.line_to(Point::new(x, world_size.height));
+        }
+        for y in reference_grid_lines(spacing, world_size.height) {
+            builder.move_to(Point::new(0.0, y));
+            builder.line_to(Point::new(world_size.width, y));
+        }
+    });
+    // Kept the same width on screen however the world is scaled.
+    frame.stroke(
+        &reference_grid,
+        Stroke::default()
+            .with_color(Color {
+                a: REFERENCE_GRID_ALPHA,
+                ..color
+            })
+            .with_width(1.0 / view.scale),
+    );
+}
+
+// Ticks along the top and left edges of the world at every grid line,
+// labeled with their distance from the world's origin. Drawn in window
+// coordinates so that the labels stay the same size.
+fn draw_rulers(frame: &mut Frame, spacing: f32, world_size: Size, view: View, color: Color) {
+    let spacing_pixels = spacing * view.scale;
+    if spacing_pixels < MIN_REFERENCE_GRID_SPACING_PIXELS {
+        return;
+    }
+    let label_every = (MIN_RULER_LABEL_SPACING_PIXELS / spacing_pixels).ceil() as usize;
+
+    let label = |frame: &mut Frame, distance: f32, position: Point| {
+        frame.fill_text(Text {
+            content: format!("{distance}"),
+            position,
+            color,
+            size: RULER_TEXT_SIZE.into(),
+            ..Text::default()
+        });
+    };
+
+    let ticks = Path::new(|builder| {
+        for x in reference_grid_lines(spacing, world_size.width) {
+            let window_x = view.offset.x + x * view.scale;
+            builder.move_to(Point::new(window_x, view.offset.y));
+            builder.line_to(Point::new(window_x, view.offset.y + RULER_TICK_LENGTH));
+        }
+        for y in reference_grid_lines(spacing, world_size.height) {
+            let window_y = view.offset.y + y * view.scale;
+            builder.move_to(Point::new(view.offset.x, window_y));
+            builder.line_to(Point::new(view.offset.x + RULER_TICK_LENGTH, window_y));
+        }
+    });
+    frame.stroke(&ticks, Stroke::default().with_color(color).with_width(1.0));
+
+    for x in reference_grid_lines(spacing, world_size.width).step_by(label_every) {
+        let window_x = view.offset.x + x * view.scale;
+        label(
+            frame,
+            x,
+            Point::new(window_x + 2.0, view.offset.y + RULER_TICK_LENGTH),
+        );
+    }
+    // The origin is already labeled along the top.
+    for y in reference_grid_lines(spacing, world_size.height)
+        .step_by(label_every)
+        .skip(1)
+    {
+        let window_y = view.offset.y + y * view.scale;
+        label(
+            frame,
+            y,
+            Point::new(view.offset.x + RULER_TICK_LENGTH + 2.0, window_y + 2.0),
+        );
+    }
+}
+
+// Fills `path`, which takes up `bounds`, with `color` in `style`.
+fn fill_styled(
+    frame: &mut Frame,
+    path: &Path,
+    bounds: Rectangle,
+    color: Color,
+    style: FillStyle,
+    view_scale: f32,
+) {
+    match style {
+        FillStyle::Solid => frame.fill(path, color),
+        FillStyle::RadialGradient => {
+            frame.fill(path, color);
+
+            let center = bounds.center();
+            let size = bounds.width.min(bounds.height);
+            for step in 1..RADIAL_GRADIENT_STEPS {
+                let t = step as f32 / (RADIAL_GRADIENT_STEPS - 1) as f32;
+                let scale = 1.0 - (1.0 - RADIAL_GRADIENT_HIGHLIGHT_SCALE) * t;
+                let offset = -RADIAL_GRADIENT_HIGHLIGHT_OFFSET * size / 2.0 * t;
+                let lightness = RADIAL_GRADIENT_HIGHLIGHT_LIGHTNESS * t;
+
+                // Scaled towards the highlight, which moves away from the
+                // center as the copies get smaller.
+                frame.with_save(|frame| {
+                    frame.translate(Vector::new(center.x + offset, center.y + offset));
+                    frame.scale(scale);
+                    frame.translate(Vector::new(-center.x, -center.y));
+                    frame.fill(
+                        path,
+                        Color {
+                            r: color.r + (1.0 - color.r) * lightness,
+                            g: color.g + (1.0 - color.g) * lightness,
+                            b: color.b + (1.0 - color.b) * lightness,
+                            a: color.a,
+                        },
+                    );
+                });
+            }
+        }
+        FillStyle::Outline => {
+            frame.fill(
+                path,
+                Color {
+                    a: color.a * OUTLINE_FILL_ALPHA,
+                    ..color
+                },
+            );
+            // Kept the same width on screen however the world is scaled.
+            frame.stroke(
+                path,
+                Stroke::default()
+                    .with_color(color)
+                    .with_width(OUTLINE_WIDTH_PIXELS / view_scale),
+            );
+        }
+    }
+}
+
+fn circle_bounds(center: Point, radius: f32) -> Rectangle {
+    Rectangle::new(
+        Point::new(center.x - radius, center.y - radius),
+        Size::new(2.0 * radius, 2.0 * radius),
+    )
+}
+
+// Positions of the reference grid lines with the given spacing from zero up
+// to `extent`.
+fn reference_grid_lines(spacing: f32, extent: f32) -> impl Iterator<Item = f32> {
+    (0..=(extent / spacing) as u32).map(move |i| i as f32 * spacing)
+}
@@ -0,0 +1,417 @@
+use iced::{
+    mouse,
+    widget::{
+        button,
+        canvas::{self, Frame, Geometry, Path, Program, Stroke},
+        container, row, text,
+    },
+    Color, Element, Length, Point, Rectangle, Renderer, Size, Theme,
+};
+use simulation::physics::{
+    BodyProperties, EntityId, GravityWell, GridMessage, Spawner, StaticCircle, StaticRectangle,
+    VelocityDistribution,
+};
+
+use std::fmt;
+
+use crate::{inspector::Inspector, view::View, Message};
+
+// Drags shorter than this, in world units, are taken as stray clicks and
+// don't place anything.
+const MIN_DRAG_LENGTH: f32 = 2.0;
+const SEGMENT_THICKNESS: f32 = 8.0;
+// Emitters are aimed by dragging, and fire circles this many units per tick
+// for every unit dragged.
+const EMITTER_SPEED_PER_DRAG_UNIT: f32 = 0.05;
+const EMITTER_CIRCLE_RADIUS: f32 = 6.0;
+const EMITTER_INTERVAL: u32 = 10;
+const PREVIEW_COLOR: Color = Color::from_rgba(1.0, 1.0, 1.0, 0.7);
+const SNAP_GRID_COLOR: Color = Color::from_rgba(1.0, 1.0, 1.0, 0.08);
+// The snap grid isn't drawn when its lines would be closer together than
+// this many pixels on screen.
+const MIN_SNAP_GRID_SPACING_PIXELS: f32 = 6.0;
+const GUIDE_COLOR: Color = Color::from_rgba(0.4, 0.7, 1.0, 0.35);
+// Guides through the cursor turn this color when it lines up with where the
+// drag started.
+const ALIGNED_GUIDE_COLOR: Color = Color::from_rgba(0.4, 1.0, 0.5, 0.8);
+const SELECTION_COLOR: Color = Color::from_rgb(1.0, 0.85, 0.2);
+const PALETTE_BACKGROUND_COLOR: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.6);
+const TEXT_SIZE: f32 = 14.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tool {
+    // Picks the body that's clicked on to show in the inspector.
+    #[default]
+    Select,
+    // Centered where the drag starts, reaching to where it ends.
+    StaticCircle,
+    // Spanning the corners the drag starts and ends at.
+    StaticRectangle,
+    // From where the drag starts to where it ends.
+    Segment,
+    // Placed where the drag starts, firing circles along the drag.
+    Emitter,
+    // Centered where the drag starts, pulling in circles as far away as
+    // where it ends.
+    Attractor,
+}
+
+impl Tool {
+    const ALL: [Tool; 6] = [
+        Tool::Select,
+        Tool::StaticCircle,
+        Tool::StaticRectangle,
+        Tool::Segment,
+        Tool::Emitter,
+        Tool::Attractor,
+    ];
+}
+
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Tool::Select => "Select",
+            Tool::StaticCircle => "Circle",
+            Tool::StaticRectangle => "Rectangle",
+            Tool::Segment => "Segment",
+            Tool::Emitter => "Emitter",
+            Tool::Attractor => "Attractor",
+        })
+    }
+}
+
+// Edit mode, where the simulation is paused and shapes are placed by
+// dragging them out on the canvas with the selected tool. All positions are
+// in world coordinates.
+pub struct Editor {
+    tool: Tool,
+    drag_start: Option<Point>,
+    // Spacing of the grid that positions and sizes snap to. Zero disables
+    // snapping.
+    grid_size: f32,
+    // Set while alt is held, which turns snapping off.
+    free_placement: bool,
+    // The selected body, if any.
+    inspector: Option<Inspector>,
+    // Whether the simulation was paused before editing, so that leaving edit
+    // mode puts it back the way it was.
+    was_paused: bool,
+}
+
+impl Editor {
+    pub fn new(was_paused: bool, grid_size: f32) -> Self {
+        Self {
+            tool: Tool::default(),
+            drag_start: None,
+            grid_size,
+            free_placement: false,
+            inspector: None,
+            was_paused,
+        }
+    }
+
+    pub fn set_grid_size(&mut self, grid_size: f32) {
+        self.grid_size = grid_size;
+    }
+
+    pub fn set_free_placement(&mut self, free_placement: bool) {
+        self.free_placement = free_placement;
+    }
+
+    pub fn was_paused(&self) -> bool {
+        self.was_paused
+    }
+
+    pub fn get_tool(&self) -> Tool {
+        self.tool
+    }
+
+    pub fn select_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.drag_start = None;
+    }
+
+    pub fn select_body(&mut self, body: Option<(EntityId, BodyProperties)>) {
+        self.inspector = body.map(|(id, properties)| Inspector::new(id, properties));
+    }
+
+    pub fn get_inspector(&self) -> Option<&Inspector> {
+        self.inspector.as_ref()
+    }
+
+    pub fn get_inspector_mut(&mut self) -> Option<&mut Inspector> {
+        self.inspector.as_mut()
+    }
+
+    pub fn start_drag(&mut self, position: Point) {
+        self.drag_start = Some(self.snap(position));
+    }
+
+    // Ends the drag at `position`, returning the message that adds the shape
+    // it outlines, if it's big enough to be one.
+    pub fn finish_drag(
+        &mut self,
+        position: Point,
+        gravity_well_strength: f32,
+    ) -> Option<GridMessage> {
+        let start = self.drag_start.take()?;
+        let position = self.snap(position);
+        let drag = position - start;
+        let length = self.snap_length(drag.x.hypot(drag.y));
+        if length < MIN_DRAG_LENGTH {
+            return None;
+        }
+
+        Some(match self.tool {
+            Tool::StaticCircle => {
+                GridMessage::AddStaticCircle(StaticCircle::new(start.x, start.y, length))
+            }
+            Tool::StaticRectangle => {
+                let bounds = corners_to_rectangle(start, position);
+                GridMessage::AddStaticRectangle(StaticRectangle::new(
+                    bounds.x,
+                    bounds.y,
+                    bounds.width,
+                    bounds.height,
+                ))
+            }
+            Tool::Segment => GridMessage::AddStaticSegment {
+                a: start,
+                b: position,
+                thickness: SEGMENT_THICKNESS,
+            },
+            Tool::Emitter => GridMessage::AddSpawner(Spawner::new(
+                start,
+                EMITTER_CIRCLE_RADIUS,
+                VelocityDistribution::Fixed((
+                    drag.x * EMITTER_SPEED_PER_DRAG_UNIT,
+                    drag.y * EMITTER_SPEED_PER_DRAG_UNIT,
+                )),
+                EMITTER_INTERVAL,
+            )),
+            Tool::Attractor => GridMessage::AddGravityWell(GravityWell::new(
+                start.x,
+                start.y,
+                gravity_well_strength,
+                length,
+            )),
+            // Bodies are selected on the click rather than dragged out.
+            Tool::Select => return None,
+        })
+    }
+
+    // A button for each tool, with the selected one highlighted.
+    pub fn palette(&self) -> Element<'_, Message> {
+        container(
+            row![text("Edit").size(TEXT_SIZE)]
+                .extend(Tool::ALL.into_iter().map(|tool| {
+                    button(text(tool.to_string()).size(TEXT_SIZE))
+                        .style(if tool == self.tool {
+                            button::primary
+                        } else {
+                            button::secondary
+                        })
+                        .on_press(Message::SelectTool(tool))
+                        .into()
+                }))
+                .spacing(8)
+                .align_y(iced::alignment::Vertical::Center),
+        )
+        .padding(6)
+        .style(|_theme| container::Style {
+            background: Some(PALETTE_BACKGROUND_COLOR.into()),
+            text_color: Some(Color::WHITE),
+            ..container::Style::default()
+        })
+        .into()
+    }
+
+    // The snap grid, guides through the cursor, an outline of the shape
+    // being dragged out and of the selected body, drawn over a world of `world_size` that's drawn
+    // through `view`.
+    pub fn preview(
+        &self,
+        cursor_position: Option<Point>,
+        world_size: Size,
+        view: View,
+    ) -> Element<'_, Message> {
+        let cursor = cursor_position.map(|position| self.snap(position));
+        let drag = self.drag_start.zip(cursor).map(|(start, end)| {
+            let drag = end - start;
+            (start, end, self.snap_length(drag.x.hypot(drag.y)))
+        });
+
+        canvas::Canvas::new(Preview {
+            tool: self.tool,
+            cursor,
+            drag,
+            selection: self.inspector.as_ref().map(Inspector::get_properties),
+            grid_size: self.effective_grid_size(),
+            world_size,
+            view,
+        })
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    // Zero when snapping is off.
+    fn effective_grid_size(&self) -> f32 {
+        if self.free_placement {
+            0.0
+        } else {
+            self.grid_size.max(0.0)
+        }
+    }
+
+    fn snap(&self, position: Point) -> Point {
+        Point::new(self.snap_length(position.x), self.snap_length(position.y))
+    }
+
+    fn snap_length(&self, length: f32) -> f32 {
+        let grid_size = self.effective_grid_size();
+        if grid_size > 0.0 {
+            (length / grid_size).round() * grid_size
+        } else {
+            length
+        }
+    }
+}
+
+// Positions of the grid lines with the given spacing from zero up to
+// `extent`.
+fn grid_lines(grid_size: f32, extent: f32) -> impl Iterator<Item = f32> {
+    (0..=(extent / grid_size) as u32).map(move |i| i as f32 * grid_size)
+}
+
+fn corners_to_rectangle(a: Point, b: Point) -> Rectangle {
+    Rectangle::new(
+        Point::new(a.x.min(b.x), a.y.min(b.y)),
+        Size::new((a.x - b.x).abs(), (a.y - b.y).abs()),
+    )
+}
+
+// Positions are in world coordinates, already snapped.
+struct Preview {
+    tool: Tool,
+    cursor: Option<Point>,
+    // Where the drag started, where the cursor is now and the radius the
+    // round shapes get.
+    drag: Option<(Point, Point, f32)>,
+    selection: Option<BodyProperties>,
+    grid_size: f32,
+    world_size: Size,
+    view: View,
+}
+
+impl Program<Message> for Preview {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        frame.translate(self.view.offset);
+        frame.scale(self.view.scale);
+
+        // Lines are kept the same width on screen however the world is
+        // scaled.
+        let stroke = |color: Color| {
+            Stroke::default()
+                .with_color(color)
+                .with_width(1.0 / self.view.scale)
+        };
+
+        if self.grid_size * self.view.scale >= MIN_SNAP_GRID_SPACING_PIXELS {
+            let snap_grid = Path::new(|builder| {
+                for x in grid_lines(self.grid_size, self.world_size.width) {
+                    builder.move_to(Point::new(x, 0.0));
+                    builder.line_to(Point::new(x, self.world_size.height));
+                }
+                for y in grid_lines(self.grid_size, self.world_size.height) {
+                    builder.move_to(Point::new(0.0, y));
+                    builder.line_to(Point::new(self.world_size.width, y));
+                }
+            });
+            frame.stroke(&snap_grid, stroke(SNAP_GRID_COLOR));
+        }
+
+        let start = self.drag.map(|(start, _, _)| start);
+        for guide in [start, self.cursor].into_iter().flatten() {
+            let aligned = |coordinate: fn(Point) -> f32| {
+                start.is_some_and(|start| guide != start && coordinate(guide) == coordinate(start))
+            };
+            frame.stroke(
+                &Path::line(
+                    Point::new(guide.x, 0.0),
+                    Point::new(guide.x, self.world_size.height),
+                ),
+                stroke(if aligned(|point| point.x) {
+                    ALIGNED_GUIDE_COLOR
+                } else {
+                    GUIDE_COLOR
+                }),
+            );
+            frame.stroke(
+                &Path::line(
+                    Point::new(0.0, guide.y),
+                    Point::new(self.world_size.width, guide.y),
+                ),
+                stroke(if aligned(|point| point.y) {
+                    ALIGNED_GUIDE_COLOR
+                } else {
+                    GUIDE_COLOR
+                }),
+            );
+        }
+
+        let drag_outline = self.drag.and_then(|(start, end, radius)| match self.tool {
+            Tool::StaticCircle | Tool::Attractor => Some(Path::circle(start, radius)),
+            Tool::StaticRectangle => {
+                let bounds = corners_to_rectangle(start, end);
+                Some(Path::rectangle(bounds.position(), bounds.size()))
+            }
+            Tool::Segment | Tool::Emitter => Some(Path::line(start, end)),
+            Tool::Select => None,
+        });
+        if let Some(path) = drag_outline {
+            frame.stroke(
+                &path,
+                stroke(PREVIEW_COLOR).with_width(1.5 / self.view.scale),
+            );
+        }
+
+        if let Some(selection) = self.selection {
+            let path = match selection {
+                BodyProperties::Circle {
+                    x_pos,
+                    y_pos,
+                    radius,
+                    ..
+                }
+                | BodyProperties::StaticCircle {
+                    x_pos,
+                    y_pos,
+                    radius,
+                } => Path::circle(Point::new(x_pos, y_pos), radius),
+                BodyProperties::StaticRectangle {
+                    x_pos,
+                    y_pos,
+                    width,
+                    height,
+                } => Path::rectangle(Point::new(x_pos, y_pos), Size::new(width, height)),
+            };
+            frame.stroke(
+                &path,
+                stroke(SELECTION_COLOR).with_width(2.0 / self.view.scale),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
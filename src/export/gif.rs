@@ -0,0 +1,102 @@
+use iced::Theme;
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use super::{crop, render_grid_frame};
+use crate::drawn_frame::DrawnFrame;
+
+// Most GIF viewers clamp frame delays below 2 centiseconds, so the capture is
+// downsampled to a rate they'll actually play back at.
+const GIF_FPS: u64 = 30;
+
+// Collects frames for a GIF export between a start and (optional) end frame.
+pub struct GifCapture {
+    path: PathBuf,
+    end_frame: Option<u32>,
+    frame_stride: u32,
+    next_frame: u32,
+    frames: Vec<DrawnFrame>,
+}
+
+impl GifCapture {
+    pub fn new(
+        path: PathBuf,
+        start_frame: u32,
+        end_frame: Option<u32>,
+        ticks_per_second: u64,
+    ) -> Self {
+        Self {
+            path,
+            end_frame,
+            frame_stride: (ticks_per_second / GIF_FPS).max(1) as u32,
+            next_frame: start_frame,
+            frames: Vec::new(),
+        }
+    }
+
+    // Captures `drawn_frame` if it falls within the selected range. Returns
+    // true once the end of the range has been reached.
+    pub fn capture(&mut self, drawn_frame: &DrawnFrame) -> bool {
+        let frame_number = drawn_frame.get_grid_frame().get_frame_number();
+
+        if let Some(end_frame) = self.end_frame {
+            if frame_number >= end_frame {
+                return true;
+            }
+        }
+
+        // The same frame may be seen more than once and frames may be skipped,
+        // so capture the first frame at or after each stride boundary.
+        if frame_number >= self.next_frame {
+            self.frames.push(drawn_frame.clone());
+            self.next_frame = frame_number + self.frame_stride;
+        }
+
+        false
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Rasterizes and encodes every captured frame. This is slow, so it should
+    // be run off the UI thread.
+    pub fn encode(self, theme: &Theme) -> Result<PathBuf, String> {
+        let Some(first_frame) = self.frames.first() else {
+            return Err("no frames were captured".to_string());
+        };
+
+        // GIF frames all share the logical screen size of the first frame. If
+        // the world was resized mid-capture, later frames are cropped.
+        let first_rendered_frame = render_grid_frame(first_frame, theme);
+        let width = first_rendered_frame.width.min(u16::MAX as u32) as u16;
+        let height = first_rendered_frame.height.min(u16::MAX as u32) as u16;
+
+        let file = File::create(&self.path).map_err(|err| err.to_string())?;
+        let mut encoder = ::gif::Encoder::new(BufWriter::new(file), width, height, &[])
+            .map_err(|err| err.to_string())?;
+        encoder
+            .set_repeat(::gif::Repeat::Infinite)
+            .map_err(|err| err.to_string())?;
+
+        let delay = (100 / GIF_FPS) as u16;
+
+        for drawn_frame in &self.frames {
+            let rendered_frame = render_grid_frame(drawn_frame, theme);
+            let mut rgba = crop(
+                &rendered_frame.rgba,
+                rendered_frame.width,
+                rendered_frame.height,
+                width as u32,
+                height as u32,
+            );
+            let mut frame = ::gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+            frame.delay = delay;
+            encoder.write_frame(&frame).map_err(|err| err.to_string())?;
+        }
+
+        Ok(self.path)
+    }
+}
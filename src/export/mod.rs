@@ -0,0 +1,113 @@
+use iced::{
+    advanced::graphics::{geometry::Renderer as _, Viewport},
+    Font, Pixels, Rectangle, Size, Theme,
+};
+
+use crate::drawn_frame::DrawnFrame;
+
+pub mod gif;
+pub mod png;
+pub mod video;
+
+// An off-screen rendered frame, as tightly packed 8-bit RGBA pixels.
+pub struct RenderedFrame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+// Renders `drawn_frame` with iced's software renderer, running the exact same
+// canvas drawing code that's used to draw it in the window.
+pub fn render_grid_frame(drawn_frame: &DrawnFrame, theme: &Theme) -> RenderedFrame {
+    let world_size = drawn_frame.get_grid_frame().get_size();
+    let width = world_size.width.ceil().max(1.0) as u32;
+    let height = world_size.height.ceil().max(1.0) as u32;
+    let bounds = Rectangle::with_size(Size::new(width as f32, height as f32));
+
+    let mut renderer =
+        iced::Renderer::Secondary(iced_tiny_skia::Renderer::new(Font::default(), Pixels(16.0)));
+
+    // The software renderer can't run shaders, so circles are always drawn
+    // on the canvas here.
+    let geometries = drawn_frame.draw_frame(&renderer, theme, bounds, true);
+    for geometry in geometries {
+        renderer.draw_geometry(geometry);
+    }
+
+    let iced::Renderer::Secondary(mut renderer) = renderer else {
+        unreachable!("the renderer was created as a software renderer");
+    };
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).expect("Create offscreen pixmap");
+    let mut clip_mask = tiny_skia::Mask::new(width, height).expect("Create offscreen clip mask");
+
+    renderer.draw(
+        &mut pixmap.as_mut(),
+        &mut clip_mask,
+        &Viewport::with_physical_size(Size::new(width, height), 1.0),
+        &[bounds],
+        theme.palette().background,
+        &[] as &[&str],
+    );
+
+    // The software renderer targets BGRA window surfaces, so the red and blue
+    // channels need to be swapped back. The background is opaque, so there's
+    // no premultiplied alpha to undo.
+    let mut rgba = pixmap.take();
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+
+    RenderedFrame {
+        width,
+        height,
+        rgba,
+    }
+}
+
+// Returns a file name that won't collide with earlier exports, such as
+// `capture-1700000000123.gif`.
+pub fn timestamped_file_name(prefix: &str, extension: &str) -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    format!("{prefix}-{millis}.{extension}")
+}
+
+// Runs slow export work (rasterizing and encoding) without blocking the UI.
+// There are no threads on wasm32, so there it just runs inline.
+pub async fn run_in_background<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let (result_sender, result_receiver) = futures::channel::oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = result_sender.send(work());
+        });
+        result_receiver
+            .await
+            .expect("export thread panicked before finishing")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        work()
+    }
+}
+
+// Crops or pads (with black) an RGBA image to the given size.
+fn crop(rgba: &[u8], width: u32, height: u32, new_width: u32, new_height: u32) -> Vec<u8> {
+    if width == new_width && height == new_height {
+        return rgba.to_vec();
+    }
+
+    let mut cropped = vec![0; (new_width * new_height * 4) as usize];
+    let copy_width = width.min(new_width) as usize * 4;
+    for y in 0..height.min(new_height) as usize {
+        let source = y * width as usize * 4;
+        let destination = y * new_width as usize * 4;
+        cropped[destination..destination + copy_width]
+            .copy_from_slice(&rgba[source..source + copy_width]);
+    }
+    cropped
+}
@@ -0,0 +1,116 @@
+use iced::Theme;
+use simulation::physics::{Grid, GridMessage};
+use tracing::info;
+
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+
+use super::{render_grid_frame, RenderedFrame};
+use crate::config_file::AppConfig;
+use crate::drawn_frame::DrawnFrame;
+use crate::sparks::Sparks;
+
+// Simulates `frame_count` frames as fast as possible and writes each one to
+// `dir` as a numbered PNG (`frame-000000.png`, ...), ready for ffmpeg. The
+// simulation runs on the calling thread while rendering and encoding are
+// spread across the remaining cores. Frames are drawn the way `config` says
+// the app would draw them.
+pub fn dump_frames(
+    dir: &Path,
+    frame_count: u32,
+    mut grid: Grid,
+    mut messages_for_frame: impl FnMut(u32) -> Vec<GridMessage>,
+    config: &AppConfig,
+) -> Result<(), String> {
+    let theme = &config.theme.to_theme();
+    let reference_grid = config
+        .show_reference_grid
+        .then_some(config.reference_grid_spacing);
+    std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+
+    let worker_count = std::thread::available_parallelism()
+        .map_or(1, |parallelism| parallelism.get().saturating_sub(1))
+        .max(1);
+
+    // Bounded so that the simulation can't run arbitrarily far ahead of the
+    // encoders and buffer every frame in memory.
+    let (frame_sender, frame_receiver) = mpsc::sync_channel::<DrawnFrame>(worker_count * 2);
+    let frame_receiver = Arc::new(Mutex::new(frame_receiver));
+
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..worker_count)
+            .map(|_| {
+                let frame_receiver = Arc::clone(&frame_receiver);
+                scope.spawn(move || -> Result<(), String> {
+                    loop {
+                        let Ok(drawn_frame) = frame_receiver.lock().unwrap().recv() else {
+                            return Ok(());
+                        };
+                        let frame_number = drawn_frame.get_grid_frame().get_frame_number();
+                        let path = dir.join(frame_file_name(frame_number));
+                        write_png(&render_grid_frame(&drawn_frame, theme), &path)?;
+                    }
+                })
+            })
+            .collect();
+
+        let mut sparks = Sparks::new(config);
+        for _ in 0..frame_count {
+            let messages = messages_for_frame(grid.get_frame_number());
+            let grid_frame = grid.tick(messages);
+            sparks.record_frame(&grid_frame);
+            if frame_sender
+                .send(
+                    DrawnFrame::new(grid_frame)
+                        .with_sparks(sparks.get_sparks().to_vec())
+                        .with_palette(config.palette)
+                        .with_color_overrides(config.color_overrides)
+                        .with_reference_grid(reference_grid)
+                        .with_window_resize(config.window_resize)
+                        .with_lod_circle_threshold(config.lod_circle_threshold),
+                )
+                .is_err()
+            {
+                // Every worker has bailed out, so there's an error to report.
+                break;
+            }
+        }
+        drop(frame_sender);
+
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().expect("frame dump worker panicked"))
+    })?;
+
+    info!("Wrote {frame_count} frames to {}.", dir.display());
+    Ok(())
+}
+
+// Renders `drawn_frame` to a timestamped PNG in `dir`, returning its path.
+pub fn save_screenshot(
+    drawn_frame: &DrawnFrame,
+    theme: &Theme,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|err| format!("failed to create {}: {err}", dir.display()))?;
+    let path = dir.join(super::timestamped_file_name("screenshot", "png"));
+    write_png(&render_grid_frame(drawn_frame, theme), &path)?;
+    Ok(path)
+}
+
+pub fn frame_file_name(frame_number: u32) -> PathBuf {
+    PathBuf::from(format!("frame-{frame_number:06}.png"))
+}
+
+pub fn write_png(rendered_frame: &RenderedFrame, path: &Path) -> Result<(), String> {
+    let size = tiny_skia::IntSize::from_wh(rendered_frame.width, rendered_frame.height)
+        .ok_or_else(|| "frame has no pixels".to_string())?;
+    // Rendered frames are fully opaque, so straight and premultiplied alpha
+    // are the same thing here.
+    let pixmap = tiny_skia::Pixmap::from_vec(rendered_frame.rgba.clone(), size)
+        .ok_or_else(|| "frame has the wrong number of pixels".to_string())?;
+    pixmap
+        .save_png(path)
+        .map_err(|err| format!("failed to write {}: {err}", path.display()))
+}
@@ -0,0 +1,188 @@
+// Recording video by piping rendered frames, as raw RGBA, to an ffmpeg child
+// process, which encodes them to an mp4 as they come in. Unlike a GIF capture
+// or a frame dump, nothing piles up in memory or on disk apart from the video
+// itself. There are no child processes on wasm32, so there it can't start.
+#[cfg(not(target_arch = "wasm32"))]
+pub use enabled::VideoCapture;
+
+#[cfg(target_arch = "wasm32")]
+pub use disabled::VideoCapture;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod enabled {
+    use iced::Theme;
+    use tracing::info;
+
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+    use std::process::{Child, ChildStdin, Command, Stdio};
+    use std::sync::mpsc::{sync_channel, SyncSender};
+    use std::thread::{self, JoinHandle};
+
+    use super::super::{crop, render_grid_frame};
+    use crate::drawn_frame::DrawnFrame;
+
+    // Frames waiting to be rendered and encoded. Past this, capturing waits
+    // for the encoder to catch up, since dropping frames would speed up the
+    // video.
+    const VIDEO_BACKLOG: usize = 8;
+
+    // A frame to write, and how many times, so that ticks that were never
+    // seen still take up time in the video.
+    type QueuedFrame = (DrawnFrame, u32);
+
+    pub struct VideoCapture {
+        path: PathBuf,
+        last_frame: Option<u32>,
+        frame_sender: SyncSender<QueuedFrame>,
+        encoder: JoinHandle<Result<(), String>>,
+        ffmpeg: Child,
+    }
+
+    impl VideoCapture {
+        // Starts ffmpeg writing to `path`, at one video frame per tick. The
+        // video is the size of `first_frame`'s world, give or take a pixel,
+        // and frames of other sizes are cropped or padded to fit.
+        pub fn start(
+            path: PathBuf,
+            first_frame: &DrawnFrame,
+            theme: Theme,
+            ticks_per_second: u64,
+        ) -> Result<Self, String> {
+            let world_size = first_frame.get_grid_frame().get_size();
+            // The encoder only takes even sizes.
+            let width = (world_size.width.ceil() as u32).max(2) & !1;
+            let height = (world_size.height.ceil() as u32).max(2) & !1;
+
+            let mut ffmpeg = Command::new("ffmpeg")
+                .args(["-y", "-loglevel", "error"])
+                .args(["-f", "rawvideo", "-pixel_format", "rgba"])
+                .args(["-video_size", &format!("{width}x{height}")])
+                .args(["-framerate", &ticks_per_second.to_string()])
+                .args(["-i", "-"])
+                .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+                .arg(&path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .spawn()
+                .map_err(|err| match err.kind() {
+                    io::ErrorKind::NotFound => "ffmpeg isn't installed".to_string(),
+                    _ => format!("failed to start ffmpeg: {err}"),
+                })?;
+            let stdin = ffmpeg.stdin.take().expect("ffmpeg's stdin is piped");
+
+            let (frame_sender, frame_receiver) = sync_channel::<QueuedFrame>(VIDEO_BACKLOG);
+            let encoder = thread::spawn(move || {
+                let mut stdin = stdin;
+                for (drawn_frame, repeats) in frame_receiver {
+                    let rendered_frame = render_grid_frame(&drawn_frame, &theme);
+                    let rgba = crop(
+                        &rendered_frame.rgba,
+                        rendered_frame.width,
+                        rendered_frame.height,
+                        width,
+                        height,
+                    );
+                    for _ in 0..repeats {
+                        write_frame(&mut stdin, &rgba)?;
+                    }
+                }
+                Ok(())
+            });
+
+            info!("Recording video to {}.", path.display());
+            Ok(Self {
+                path,
+                last_frame: None,
+                frame_sender,
+                encoder,
+                ffmpeg,
+            })
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        // Queues `drawn_frame` if it's for a tick after the last one captured.
+        // Rewinding doesn't go back in the video, so frames are skipped until
+        // it catches up.
+        pub fn capture(&mut self, drawn_frame: &DrawnFrame) {
+            let frame_number = drawn_frame.get_grid_frame().get_frame_number();
+            let repeats = match self.last_frame {
+                Some(last_frame) if frame_number <= last_frame => return,
+                Some(last_frame) => frame_number - last_frame,
+                None => 1,
+            };
+            self.last_frame = Some(frame_number);
+            // If the encoder has stopped, `finish` reports why.
+            let _ = self.frame_sender.send((drawn_frame.clone(), repeats));
+        }
+
+        // Waits for the queued frames to be encoded and ffmpeg to finish the
+        // file. This can take a while, so it should be run off the UI thread.
+        pub fn finish(self) -> Result<PathBuf, String> {
+            let Self {
+                path,
+                frame_sender,
+                encoder,
+                mut ffmpeg,
+                ..
+            } = self;
+            drop(frame_sender);
+            // The encoder closes ffmpeg's stdin when it's done, which tells
+            // ffmpeg that there are no more frames.
+            let encoded = encoder.join().expect("video encoder panicked");
+            let status = ffmpeg
+                .wait()
+                .map_err(|err| format!("failed to wait for ffmpeg: {err}"))?;
+            encoded?;
+            if status.success() {
+                Ok(path)
+            } else {
+                Err(format!("ffmpeg failed with {status}"))
+            }
+        }
+    }
+
+    fn write_frame(stdin: &mut ChildStdin, rgba: &[u8]) -> Result<(), String> {
+        stdin
+            .write_all(rgba)
+            .map_err(|err| format!("ffmpeg stopped taking frames: {err}"))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod disabled {
+    use iced::Theme;
+
+    use std::path::{Path, PathBuf};
+
+    use crate::drawn_frame::DrawnFrame;
+
+    // Never started, so there's never one to capture to.
+    pub enum VideoCapture {}
+
+    impl VideoCapture {
+        pub fn start(
+            _path: PathBuf,
+            _first_frame: &DrawnFrame,
+            _theme: Theme,
+            _ticks_per_second: u64,
+        ) -> Result<Self, String> {
+            Err("there are no child processes on wasm32".to_string())
+        }
+
+        pub fn path(&self) -> &Path {
+            match *self {}
+        }
+
+        pub fn capture(&mut self, _drawn_frame: &DrawnFrame) {
+            match *self {}
+        }
+
+        pub fn finish(self) -> Result<PathBuf, String> {
+            match self {}
+        }
+    }
+}
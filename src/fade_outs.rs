@@ -0,0 +1,73 @@
+use simulation::physics::{Circle, EntityId, GridFrame};
+
+use std::collections::HashSet;
+
+use crate::drawn_frame::DESPAWN_ANIMATION_TICKS;
+
+// A circle that's been removed from the grid, however it was, drawn fading
+// and shrinking out where it last was. Like sparks, it's purely visual.
+#[derive(Debug, Clone)]
+pub struct FadingCircle {
+    pub circle: Circle,
+    age: u32,
+}
+
+impl FadingCircle {
+    // How much of the fade is left, from 1 when the circle is removed to 0
+    // when it's gone, `interpolation` of the way to the next tick.
+    pub fn get_remaining_life(&self, interpolation: f32) -> f32 {
+        (1.0 - (self.age as f32 + interpolation) / DESPAWN_ANIMATION_TICKS).max(0.0)
+    }
+}
+
+// Notices circles that are in one tick but not the next, and ages the ones
+// already fading out, so that they can be drawn with the frame.
+#[derive(Default)]
+pub struct FadeOuts {
+    fading_circles: Vec<FadingCircle>,
+}
+
+impl FadeOuts {
+    pub fn get_fading_circles(&self) -> &[FadingCircle] {
+        &self.fading_circles
+    }
+
+    // Only called for new ticks, with the tick drawn before it.
+    pub fn record_frame(
+        &mut self,
+        previous_grid_frame: Option<&GridFrame>,
+        grid_frame: &GridFrame,
+    ) {
+        for fading_circle in &mut self.fading_circles {
+            fading_circle.age += 1;
+        }
+        self.fading_circles
+            .retain(|fading_circle| fading_circle.get_remaining_life(0.0) > 0.0);
+
+        let Some(previous_grid_frame) = previous_grid_frame else {
+            return;
+        };
+        // Circles from a future that was rewound away weren't removed, and
+        // shouldn't linger either.
+        if grid_frame.get_frame_number() < previous_grid_frame.get_frame_number() {
+            self.fading_circles.clear();
+            return;
+        }
+
+        let ids: HashSet<EntityId> = grid_frame
+            .get_circles()
+            .iter()
+            .map(Circle::get_id)
+            .collect();
+        self.fading_circles.extend(
+            previous_grid_frame
+                .get_circles()
+                .iter()
+                .filter(|circle| !ids.contains(&circle.get_id()))
+                .map(|circle| FadingCircle {
+                    circle: circle.clone(),
+                    age: 0,
+                }),
+        );
+    }
+}
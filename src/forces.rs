@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+use crate::config::SimulationConfig;
+use crate::physics::Bodies;
+use crate::simd;
+
+// Something that pushes circles around, applied to every circle in the grid
+// once per subtick before they move. `dt` is the length of the subtick as a
+// fraction of a tick, so a generator that adds `a * dt` to each velocity
+// accelerates circles by `a` per tick no matter how many subticks there are.
+//
+// Sleeping circles should be left alone, since they don't move until they're
+// woken up and would otherwise build up velocity while asleep. Generators
+// aren't part of recordings, so a replay only matches the original run if the
+// same generators are registered.
+pub trait ForceGenerator: Send {
+    fn apply(&mut self, bodies: &mut Bodies, dt: f32);
+}
+
+// A constant acceleration, in units per tick per tick.
+pub struct Gravity {
+    pub acceleration: (f32, f32),
+}
+
+impl ForceGenerator for Gravity {
+    fn apply(&mut self, bodies: &mut Bodies, dt: f32) {
+        let (velocities, sleeping) = bodies.velocities_and_sleeping_mut();
+        simd::add_to_awake(
+            velocities,
+            (self.acceleration.0 * dt, self.acceleration.1 * dt),
+            sleeping,
+        );
+    }
+}
+
+// How air slows a circle down. A scene picks a model for all of its circles,
+// and any circle can override it with its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DragModel {
+    // No drag at all, so the wind doesn't move the circle either.
+    None,
+    // Slows circles down by the air density's fraction of their velocity per
+    // tick, divided by the circle's own density since denser circles carry
+    // more momentum for the same amount of air in the way.
+    #[default]
+    Linear,
+    // Drag proportional to the square of the speed and to the circle's cross
+    // section, which is its diameter in 2D. Since mass grows with the square
+    // of the radius, big circles are slowed down less than small ones.
+    Quadratic,
+}
+
+// Slows circles down with each circle's drag model, falling back to `model`
+// for circles that don't have one. The air moves at `wind`, so it's really
+// each circle's velocity relative to the wind that's slowed down, which
+// carries circles along with the wind until they move as fast as it.
+pub struct AirDrag {
+    pub density: f32,
+    pub model: DragModel,
+    pub wind: (f32, f32),
+}
+
+impl ForceGenerator for AirDrag {
+    fn apply(&mut self, bodies: &mut Bodies, dt: f32) {
+        // Compounding over the subticks of a tick comes out to the same
+        // slowdown as applying it once per tick.
+        let linear_retained_for = |circle_density: f32| {
+            (1.0 - self.density / circle_density.max(f32::EPSILON))
+                .max(0.0)
+                .powf(dt)
+        };
+        // Almost every circle has the default density, so that's only worked
+        // out once.
+        let default_linear_retained = linear_retained_for(1.0);
+
+        // Works out how much of each circle's velocity is kept first, so that
+        // the velocities can all be scaled at once.
+        let retained: Vec<f32> = (0..bodies.len())
+            .map(|i| {
+                // Scaling by 1 leaves the velocity as it is.
+                if bodies.is_sleeping(i) {
+                    return 1.0;
+                }
+                let circle_density = bodies.density(i);
+                match bodies.drag_models()[i].unwrap_or(self.model) {
+                    DragModel::None => 1.0,
+                    DragModel::Linear if circle_density == 1.0 => default_linear_retained,
+                    DragModel::Linear => linear_retained_for(circle_density),
+                    DragModel::Quadratic => {
+                        // With a drag coefficient of 1, the force is
+                        // `density * radius * speed^2` and the mass is
+                        // `circle_density * radius^2`. Taking the speed at the
+                        // end of the subtick keeps this from overshooting into
+                        // reverse at high speeds.
+                        let (vx, vy) = bodies.velocities()[i];
+                        let (vx, vy) = (vx - self.wind.0, vy - self.wind.1);
+                        let speed = (vx * vx + vy * vy).sqrt();
+                        let resistance = self.density * speed
+                            / (circle_density * bodies.radii()[i]).max(f32::EPSILON);
+                        1.0 / (1.0 + resistance * dt)
+                    }
+                }
+            })
+            .collect();
+        if self.wind == (0.0, 0.0) {
+            simd::scale(bodies.velocities_mut(), &retained);
+            return;
+        }
+        let (wind_x, wind_y) = self.wind;
+        for (velocity, retained) in bodies.velocities_mut().iter_mut().zip(retained) {
+            velocity.0 = wind_x + (velocity.0 - wind_x) * retained;
+            velocity.1 = wind_y + (velocity.1 - wind_y) * retained;
+        }
+    }
+}
+
+// Electrostatic forces between charged circles, so that like charges repel
+// and opposite charges attract. The force between two circles is `constant`
+// times the product of their charges over the square of the distance between
+// them, and circles further apart than `cutoff_radius` don't affect each
+// other at all.
+pub struct Coulomb {
+    pub constant: f32,
+    pub cutoff_radius: f32,
+}
+
+impl ForceGenerator for Coulomb {
+    fn apply(&mut self, bodies: &mut Bodies, dt: f32) {
+        if self.constant == 0.0 || self.cutoff_radius <= 0.0 {
+            return;
+        }
+
+        // Only charged circles can push each other, so only they go in the
+        // spatial hash. Cells are as wide as the cutoff, so every circle in
+        // range is in one of the 3x3 cells around each circle's own. The
+        // hasher has a fixed seed to keep the simulation deterministic.
+        let cell_of = |(x_pos, y_pos): (f32, f32)| {
+            (
+                (x_pos / self.cutoff_radius).floor() as i32,
+                (y_pos / self.cutoff_radius).floor() as i32,
+            )
+        };
+        let mut cells: HashMap<(i32, i32), Vec<usize>, BuildHasherDefault<DefaultHasher>> =
+            HashMap::default();
+        let mut charged = Vec::new();
+        for (i, &charge) in bodies.charges().iter().enumerate() {
+            if charge != 0.0 {
+                cells
+                    .entry(cell_of(bodies.positions()[i]))
+                    .or_default()
+                    .push(i);
+                charged.push(i);
+            }
+        }
+
+        let cutoff_squared = self.cutoff_radius * self.cutoff_radius;
+        for &i in &charged {
+            let (cell_x, cell_y) = cell_of(bodies.positions()[i]);
+            for neighbor_x in cell_x.saturating_sub(1)..=cell_x.saturating_add(1) {
+                for neighbor_y in cell_y.saturating_sub(1)..=cell_y.saturating_add(1) {
+                    let Some(neighbors) = cells.get(&(neighbor_x, neighbor_y)) else {
+                        continue;
+                    };
+                    // Each pair is handled once, by its lower index.
+                    for &j in neighbors.iter().filter(|&&j| j > i) {
+                        let (a, b) = (bodies.positions()[i], bodies.positions()[j]);
+                        let dx = b.0 - a.0;
+                        let dy = b.1 - a.1;
+                        let distance_squared = dx * dx + dy * dy;
+                        if distance_squared >= cutoff_squared {
+                            continue;
+                        }
+
+                        // Overlapping circles are treated as touching so the
+                        // force can't blow up as their centers meet.
+                        let min_distance = bodies.radii()[i] + bodies.radii()[j];
+                        let distance = distance_squared.sqrt().max(min_distance);
+                        if distance <= 0.0 {
+                            continue;
+                        }
+                        // Positive when the circles repel.
+                        let force = self.constant * bodies.charges()[i] * bodies.charges()[j]
+                            / (distance * distance)
+                            * dt;
+                        let (nx, ny) = (dx / distance, dy / distance);
+
+                        let (mass_a, mass_b) = (bodies.mass(i), bodies.mass(j));
+                        if !bodies.is_sleeping(i) && mass_a > 0.0 {
+                            let velocity = &mut bodies.velocities_mut()[i];
+                            velocity.0 -= nx * force / mass_a;
+                            velocity.1 -= ny * force / mass_a;
+                        }
+                        if !bodies.is_sleeping(j) && mass_b > 0.0 {
+                            let velocity = &mut bodies.velocities_mut()[j];
+                            velocity.0 += nx * force / mass_b;
+                            velocity.1 += ny * force / mass_b;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The generators every grid has, configured by `SimulationConfig`.
+pub struct BuiltinForces {
+    gravity: Gravity,
+    air_drag: AirDrag,
+    coulomb: Coulomb,
+}
+
+impl BuiltinForces {
+    pub fn new(config: &SimulationConfig, drag_model: DragModel) -> Self {
+        Self {
+            gravity: Gravity {
+                acceleration: config.gravity,
+            },
+            air_drag: AirDrag {
+                density: config.air_density,
+                model: drag_model,
+                wind: config.wind,
+            },
+            coulomb: Coulomb {
+                constant: config.coulomb_constant,
+                cutoff_radius: config.charge_cutoff_radius,
+            },
+        }
+    }
+
+    pub fn generators(&mut self) -> [&mut dyn ForceGenerator; 3] {
+        [&mut self.gravity, &mut self.air_drag, &mut self.coulomb]
+    }
+}
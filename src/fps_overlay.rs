@@ -0,0 +1,180 @@
+use iced::{
+    mouse,
+    widget::{
+        canvas::{self, Frame, Geometry, Path, Program, Stroke},
+        column, container, row, text,
+    },
+    Color, Element, Length, Point, Rectangle, Renderer, Theme,
+};
+use simulation::physics::FrameTiming;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{palette::Palette, Message};
+
+// Frames shown in the sparkline, and averaged over for the numbers.
+const SAMPLE_COUNT: usize = 120;
+const SPARKLINE_WIDTH: f32 = 120.0;
+const SPARKLINE_HEIGHT: f32 = 40.0;
+const TEXT_SIZE: f32 = 14.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    frame_interval: Duration,
+    tick_duration: Duration,
+    render_time: Duration,
+}
+
+// Small corner overlay with the frame rate, the time spent ticking and
+// rendering, and a sparkline of recent frame times. Unlike the diagnostics
+// overlay it counts every frame delivered, not just new ticks.
+pub struct FpsOverlay {
+    samples: VecDeque<Sample>,
+    target_frame_time: Duration,
+    palette: Palette,
+}
+
+impl FpsOverlay {
+    pub fn new(target_fps: u64, palette: Palette) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(SAMPLE_COUNT),
+            target_frame_time: Duration::from_millis(1000 / target_fps),
+            palette,
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn record_frame(&mut self, timing: FrameTiming, render_time: Duration) {
+        if self.samples.len() == SAMPLE_COUNT {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample {
+            frame_interval: timing.frame_interval,
+            tick_duration: timing.tick_duration,
+            render_time,
+        });
+    }
+
+    fn fps(&self) -> f32 {
+        // The first frame a stream delivers has no interval.
+        let intervals = self
+            .samples
+            .iter()
+            .map(|sample| sample.frame_interval)
+            .filter(|interval| !interval.is_zero());
+        let count = intervals.clone().count();
+        let total = intervals.sum::<Duration>();
+        if total.is_zero() {
+            0.0
+        } else {
+            count as f32 / total.as_secs_f32()
+        }
+    }
+
+    fn mean_millis(&self, duration: impl Fn(&Sample) -> Duration) -> f64 {
+        let total = self.samples.iter().map(duration).sum::<Duration>();
+        total.as_secs_f64() * 1000.0 / self.samples.len().max(1) as f64
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let colors = self.palette.colors();
+        let lines = [
+            format!("FPS: {:.1}", self.fps()),
+            format!(
+                "Tick: {:.2} ms",
+                self.mean_millis(|sample| sample.tick_duration)
+            ),
+            format!(
+                "Render: {:.2} ms",
+                self.mean_millis(|sample| sample.render_time)
+            ),
+        ];
+
+        container(
+            row![
+                column(
+                    lines
+                        .into_iter()
+                        .map(|line| text(line).size(TEXT_SIZE).into())
+                ),
+                canvas::Canvas::new(self)
+                    .width(Length::Fixed(SPARKLINE_WIDTH))
+                    .height(Length::Fixed(SPARKLINE_HEIGHT)),
+            ]
+            .spacing(8),
+        )
+        .padding(6)
+        .style(move |_theme| container::Style {
+            background: Some(colors.overlay_background.into()),
+            text_color: Some(Color::WHITE),
+            ..container::Style::default()
+        })
+        .into()
+    }
+}
+
+// Draws the sparkline of frame times, oldest on the left.
+impl Program<Message> for FpsOverlay {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let colors = self.palette.colors();
+
+        // Scale so that the target frame time sits halfway up unless some
+        // frames take even longer than that.
+        let max_frame_time = self
+            .samples
+            .iter()
+            .map(|sample| sample.frame_interval)
+            .max()
+            .unwrap_or_default()
+            .max(self.target_frame_time * 2);
+        let y_for = |duration: Duration| {
+            bounds.height * (1.0 - duration.as_secs_f32() / max_frame_time.as_secs_f32())
+        };
+        let x_step = bounds.width / (SAMPLE_COUNT - 1) as f32;
+
+        let target_y = y_for(self.target_frame_time);
+        frame.stroke(
+            &Path::line(
+                Point::new(0.0, target_y),
+                Point::new(bounds.width, target_y),
+            ),
+            Stroke::default()
+                .with_color(colors.target_line)
+                .with_width(1.0),
+        );
+
+        let sparkline = Path::new(|builder| {
+            let first_x = (SAMPLE_COUNT - self.samples.len()) as f32 * x_step;
+            for (i, sample) in self.samples.iter().enumerate() {
+                let point = Point::new(first_x + i as f32 * x_step, y_for(sample.frame_interval));
+                if i == 0 {
+                    builder.move_to(point);
+                } else {
+                    builder.line_to(point);
+                }
+            }
+        });
+        frame.stroke(
+            &sparkline,
+            Stroke::default()
+                .with_color(colors.sparkline)
+                .with_width(1.0),
+        );
+
+        vec![frame.into_geometry()]
+    }
+}
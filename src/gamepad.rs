@@ -0,0 +1,117 @@
+// Gamepad input. The left stick tilts gravity, south (A on Xbox layouts)
+// spawns a burst of circles, east (B) sets off an explosion and start pauses
+// or resumes. This needs the `gamepad` feature and isn't available on wasm32;
+// otherwise gamepads are ignored.
+#[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+pub use enabled::subscription;
+
+#[cfg(not(all(feature = "gamepad", not(target_arch = "wasm32"))))]
+pub use disabled::subscription;
+
+#[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+mod enabled {
+    use futures::{channel::mpsc, SinkExt, StreamExt};
+    use gilrs::{Axis, Button, EventType, Gilrs};
+    use iced::Subscription;
+    use tracing::{error, info};
+
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::Message;
+
+    // How far gravity is tilted with the stick all the way over, on top of
+    // whatever it is otherwise.
+    const MAX_GRAVITY_TILT: f32 = 0.5;
+    // How often the gamepad thread checks whether anyone's still listening
+    // while there isn't any input.
+    const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn subscription() -> Subscription<Message> {
+        Subscription::run_with_id(
+            "gamepad",
+            async_stream::stream! {
+                let (mut message_sender, mut message_receiver) = mpsc::channel(16);
+
+                // Waiting for input blocks, so it's done on a thread of its
+                // own.
+                thread::spawn(move || {
+                    let mut gilrs = match Gilrs::new() {
+                        Ok(gilrs) => gilrs,
+                        Err(err) => {
+                            error!("Failed to set up gamepad input: {err}");
+                            return;
+                        }
+                    };
+
+                    for (_, gamepad) in gilrs.gamepads() {
+                        info!("Found gamepad {}.", gamepad.name());
+                    }
+
+                    let mut stick = (0.0, 0.0);
+                    while !message_sender.is_closed() {
+                        let Some(event) = gilrs.next_event_blocking(Some(SHUTDOWN_POLL_INTERVAL))
+                        else {
+                            continue;
+                        };
+
+                        let message = match event.event {
+                            EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+                                stick.0 = value;
+                                Some(Message::SetGravityTilt((
+                                    stick.0 * MAX_GRAVITY_TILT,
+                                    stick.1 * MAX_GRAVITY_TILT,
+                                )))
+                            }
+                            // Up on the stick is positive, but up on the
+                            // screen is negative.
+                            EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                                stick.1 = -value;
+                                Some(Message::SetGravityTilt((
+                                    stick.0 * MAX_GRAVITY_TILT,
+                                    stick.1 * MAX_GRAVITY_TILT,
+                                )))
+                            }
+                            EventType::ButtonPressed(Button::South, _) => Some(Message::SpawnBurst),
+                            EventType::ButtonPressed(Button::East, _) => Some(Message::Explode),
+                            EventType::ButtonPressed(Button::Start, _) => Some(Message::TogglePause),
+                            EventType::Connected => {
+                                info!("Gamepad {} connected.", gilrs.gamepad(event.id).name());
+                                None
+                            }
+                            // Gravity shouldn't stay tilted by a stick that
+                            // isn't there anymore.
+                            EventType::Disconnected => {
+                                info!("Gamepad {} disconnected.", gilrs.gamepad(event.id).name());
+                                stick = (0.0, 0.0);
+                                Some(Message::SetGravityTilt(stick))
+                            }
+                            _ => None,
+                        };
+
+                        if let Some(message) = message {
+                            if futures::executor::block_on(message_sender.send(message)).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+
+                while let Some(message) = message_receiver.next().await {
+                    yield message;
+                }
+            },
+        )
+    }
+}
+
+#[cfg(not(all(feature = "gamepad", not(target_arch = "wasm32"))))]
+mod disabled {
+    use iced::Subscription;
+
+    use crate::Message;
+
+    pub fn subscription() -> Subscription<Message> {
+        Subscription::none()
+    }
+}
@@ -0,0 +1,252 @@
+// HTTP API for controlling the simulation from scripts or other machines:
+//
+//   GET  /stats    The latest frame's statistics, as JSON.
+//   POST /circles  Adds a circle, given as JSON like
+//                  `{"x": 400, "y": 100, "radius": 10, "velocity": [2, 0]}`.
+//                  The velocity can be left out.
+//   PUT  /gravity  Sets gravity, given as JSON like `{"x": 0, "y": 0.5}`.
+//   POST /pause    Pauses the simulation.
+//   POST /resume   Resumes it.
+//
+// Requests end up as `GridMessage`s like all other input; see there. This
+// needs the `http_api` feature and isn't available on wasm32.
+#[cfg(all(feature = "http_api", not(target_arch = "wasm32")))]
+pub use enabled::{HttpApi, RemoteCommand};
+
+#[cfg(not(all(feature = "http_api", not(target_arch = "wasm32"))))]
+pub use disabled::HttpApi;
+
+#[cfg(all(feature = "http_api", not(target_arch = "wasm32")))]
+mod enabled {
+    use futures::{channel::mpsc, SinkExt, StreamExt};
+    use iced::Subscription;
+    use serde::{Deserialize, Serialize};
+    use simulation::physics::{Circle, GridFrame};
+    use tiny_http::{Header, Method, Request, Response, Server};
+    use tracing::{error, info, warn};
+
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    // How often the server thread checks whether anyone's still listening
+    // for its commands while there aren't any requests.
+    const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    // What a request asks the simulation to do.
+    #[derive(Debug, Clone)]
+    pub enum RemoteCommand {
+        SpawnCircle(Circle),
+        SetGravity((f32, f32)),
+        SetPaused(bool),
+    }
+
+    #[derive(Debug, Clone, Default, Serialize)]
+    struct Stats {
+        frame: u32,
+        circle_count: usize,
+        total_kinetic_energy: f32,
+        max_speed: f32,
+        collision_pair_count: u32,
+        tick_ms: f64,
+        gravity: (f32, f32),
+        time_scale: f32,
+        paused: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct SpawnCircleRequest {
+        x: f32,
+        y: f32,
+        radius: f32,
+        #[serde(default)]
+        velocity: (f32, f32),
+    }
+
+    #[derive(Deserialize)]
+    struct SetGravityRequest {
+        x: f32,
+        y: f32,
+    }
+
+    pub struct HttpApi {
+        server: Arc<Server>,
+        address: String,
+        stats: Arc<Mutex<Stats>>,
+    }
+
+    impl HttpApi {
+        pub fn start(address: &str) -> Result<Self, String> {
+            let server = Server::http(address).map_err(|err| err.to_string())?;
+            let address = server
+                .server_addr()
+                .to_ip()
+                .map_or_else(|| address.to_string(), |address| address.to_string());
+            info!("Serving the HTTP API on http://{address}.");
+
+            Ok(Self {
+                server: Arc::new(server),
+                address,
+                stats: Arc::default(),
+            })
+        }
+
+        pub fn record(
+            &self,
+            grid_frame: &GridFrame,
+            gravity: (f32, f32),
+            time_scale: f32,
+            paused: bool,
+        ) {
+            *self.stats.lock().unwrap() = Stats {
+                frame: grid_frame.get_frame_number(),
+                circle_count: grid_frame.get_circle_count(),
+                total_kinetic_energy: grid_frame.get_total_kinetic_energy(),
+                max_speed: grid_frame.get_max_speed(),
+                collision_pair_count: grid_frame.get_collision_pair_count(),
+                tick_ms: grid_frame.get_tick_duration().as_secs_f64() * 1000.0,
+                gravity,
+                time_scale,
+                paused,
+            };
+        }
+
+        // The commands from every request, for as long as the subscription
+        // is kept.
+        pub fn subscription(&self) -> Subscription<RemoteCommand> {
+            let server = Arc::clone(&self.server);
+            let stats = Arc::clone(&self.stats);
+
+            Subscription::run_with_id(
+                self.address.clone(),
+                async_stream::stream! {
+                    let (mut command_sender, mut command_receiver) = mpsc::channel(16);
+
+                    // Waiting for requests blocks, so it's done on a thread of
+                    // its own.
+                    thread::spawn(move || loop {
+                        if command_sender.is_closed() {
+                            return;
+                        }
+
+                        let mut request = match server.recv_timeout(SHUTDOWN_POLL_INTERVAL) {
+                            Ok(Some(request)) => request,
+                            Ok(None) => continue,
+                            Err(err) => {
+                                error!("The HTTP API stopped: {err}");
+                                return;
+                            }
+                        };
+
+                        let (response, command) = handle(&mut request, &stats);
+                        if let Some(command) = command {
+                            if futures::executor::block_on(command_sender.send(command)).is_err() {
+                                return;
+                            }
+                        }
+                        if let Err(err) = request.respond(response) {
+                            warn!("Failed to respond to an HTTP request: {err}");
+                        }
+                    });
+
+                    while let Some(command) = command_receiver.next().await {
+                        yield command;
+                    }
+                },
+            )
+        }
+    }
+
+    fn handle(
+        request: &mut Request,
+        stats: &Mutex<Stats>,
+    ) -> (Response<std::io::Cursor<Vec<u8>>>, Option<RemoteCommand>) {
+        let command = match (request.method(), request.url()) {
+            (Method::Get, "/stats") => {
+                let stats = stats.lock().unwrap().clone();
+                return match serde_json::to_string(&stats) {
+                    Ok(json) => (json_response(json), None),
+                    Err(err) => (error_response(500, err.to_string()), None),
+                };
+            }
+            (Method::Post, "/circles") => read_json(request).and_then(|body| {
+                let SpawnCircleRequest {
+                    x,
+                    y,
+                    radius,
+                    velocity,
+                } = body;
+                if radius <= 0.0 {
+                    return Err("radius must be positive".to_string());
+                }
+                Ok(RemoteCommand::SpawnCircle(Circle::new(
+                    x, y, radius, velocity,
+                )))
+            }),
+            (Method::Put, "/gravity") => read_json(request)
+                .map(|SetGravityRequest { x, y }| RemoteCommand::SetGravity((x, y))),
+            (Method::Post, "/pause") => Ok(RemoteCommand::SetPaused(true)),
+            (Method::Post, "/resume") => Ok(RemoteCommand::SetPaused(false)),
+            (_, "/stats" | "/circles" | "/gravity" | "/pause" | "/resume") => {
+                return (error_response(405, "method not allowed"), None);
+            }
+            _ => return (error_response(404, "not found"), None),
+        };
+
+        match command {
+            // The app applies the command once it gets to it.
+            Ok(command) => (
+                Response::from_data(Vec::new()).with_status_code(202),
+                Some(command),
+            ),
+            Err(err) => (error_response(400, err), None),
+        }
+    }
+
+    // Parses the request's body as JSON.
+    fn read_json<T: for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, String> {
+        let mut body = String::new();
+        request
+            .as_reader()
+            .read_to_string(&mut body)
+            .map_err(|err| err.to_string())?;
+        serde_json::from_str(&body).map_err(|err| err.to_string())
+    }
+
+    fn json_response(json: String) -> Response<std::io::Cursor<Vec<u8>>> {
+        Response::from_data(json.into_bytes()).with_header(
+            Header::from_bytes("Content-Type", "application/json").expect("header is valid ASCII"),
+        )
+    }
+
+    fn error_response(
+        status_code: u16,
+        message: impl Into<String>,
+    ) -> Response<std::io::Cursor<Vec<u8>>> {
+        Response::from_data(message.into().into_bytes()).with_status_code(status_code)
+    }
+}
+
+#[cfg(not(all(feature = "http_api", not(target_arch = "wasm32"))))]
+mod disabled {
+    use simulation::physics::GridFrame;
+
+    // Never started, so there's never anything to serve.
+    pub enum HttpApi {}
+
+    impl HttpApi {
+        pub fn start(_address: &str) -> Result<Self, String> {
+            Err("this build doesn't have the HTTP API, rebuild with `--features http_api`".into())
+        }
+
+        pub fn record(
+            &self,
+            _grid_frame: &GridFrame,
+            _gravity: (f32, f32),
+            _time_scale: f32,
+            _paused: bool,
+        ) {
+            match *self {}
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use iced::{
+    widget::{column, container, pick_list, row, slider, text},
+    Color, Element,
+};
+use simulation::physics::{GridFrame, MAX_TIME_SCALE, MIN_TIME_SCALE, TICKS_PER_SECOND};
+use simulation::time::Instant;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{profile::Profile, Message};
+
+const FPS_MEASUREMENT_WINDOW: Duration = Duration::from_secs(1);
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+const BACKGROUND_COLOR: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.6);
+const TEXT_SIZE: f32 = 14.0;
+const TIME_SCALE_SLIDER_WIDTH: f32 = 150.0;
+
+// On-screen overlay with live simulation statistics.
+#[derive(Default)]
+pub struct StatsHud {
+    frame_received_times: VecDeque<Instant>,
+}
+
+impl StatsHud {
+    pub fn record_frame(&mut self) {
+        let now = Instant::now();
+        self.frame_received_times.push_back(now);
+
+        while let Some(oldest) = self.frame_received_times.front() {
+            if now - *oldest > FPS_MEASUREMENT_WINDOW {
+                self.frame_received_times.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn fps(&self) -> f32 {
+        match (
+            self.frame_received_times.front(),
+            self.frame_received_times.back(),
+        ) {
+            (Some(oldest), Some(newest)) if newest > oldest => {
+                (self.frame_received_times.len() - 1) as f32 / (*newest - *oldest).as_secs_f32()
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn view<'a>(&self, grid_frame: Option<&GridFrame>) -> Element<'a, Message> {
+        let mut lines = vec![format!("FPS: {:.1}", self.fps())];
+
+        if let Some(grid_frame) = grid_frame {
+            lines.push(format!(
+                "Bodies: {} ({} sleeping)",
+                grid_frame.get_circle_count(),
+                grid_frame.get_sleeping_circle_count()
+            ));
+            lines.push(format!(
+                "Kinetic energy: {:.1}",
+                grid_frame.get_total_kinetic_energy()
+            ));
+            lines.push(format!(
+                "Total energy: {:.1}",
+                grid_frame.get_total_energy()
+            ));
+            lines.push(format!(
+                "Dropped messages: {}",
+                grid_frame.get_dropped_message_count()
+            ));
+            lines.push(format!(
+                "Rewind: {:.1} s",
+                grid_frame.get_rewind_buffered_ticks() as f32 / TICKS_PER_SECOND as f32
+            ));
+            lines.push(format!(
+                "Tick: {:.2} ms ({} subticks)",
+                grid_frame.get_tick_duration().as_secs_f64() * 1000.0,
+                grid_frame.get_sub_tick_count()
+            ));
+            for (phase, duration) in grid_frame.get_phase_timings().named() {
+                lines.push(format!(
+                    "  {}: {:.2} ms",
+                    phase.replace('_', " "),
+                    duration.as_secs_f64() * 1000.0
+                ));
+            }
+        }
+
+        container(column(
+            lines
+                .into_iter()
+                .map(|line| text(line).size(TEXT_SIZE).into()),
+        ))
+        .padding(6)
+        .style(|_theme| container::Style {
+            background: Some(BACKGROUND_COLOR.into()),
+            text_color: Some(Color::WHITE),
+            ..container::Style::default()
+        })
+        .into()
+    }
+}
+
+// Slider for the time scale. It moves along a log scale, so that slowing down
+// by some factor takes as much of it as speeding up by that factor.
+pub fn time_scale_control<'a>(time_scale: f32) -> Element<'a, Message> {
+    container(
+        row![
+            text(format!("Time: {time_scale:.2}x")).size(TEXT_SIZE),
+            slider(
+                MIN_TIME_SCALE.log10()..=MAX_TIME_SCALE.log10(),
+                time_scale.log10(),
+                |exponent| Message::SetTimeScale(10.0_f32.powf(exponent)),
+            )
+            .step(0.01)
+            .width(TIME_SCALE_SLIDER_WIDTH),
+        ]
+        .spacing(8)
+        .align_y(iced::alignment::Vertical::Center),
+    )
+    .padding(6)
+    .style(|_theme| container::Style {
+        background: Some(BACKGROUND_COLOR.into()),
+        text_color: Some(Color::WHITE),
+        ..container::Style::default()
+    })
+    .into()
+}
+
+// Dropdown for the physics profile that's applied on top of the config.
+pub fn profile_control<'a>(profile: Profile) -> Element<'a, Message> {
+    container(
+        row![
+            text("Physics:").size(TEXT_SIZE),
+            pick_list(&Profile::ALL[..], Some(profile), Message::SelectProfile)
+                .text_size(TEXT_SIZE),
+        ]
+        .spacing(8)
+        .align_y(iced::alignment::Vertical::Center),
+    )
+    .padding(6)
+    .style(|_theme| container::Style {
+        background: Some(BACKGROUND_COLOR.into()),
+        text_color: Some(Color::WHITE),
+        ..container::Style::default()
+    })
+    .into()
+}
+
+// A caption in the same style as the rest of the HUD.
+pub fn label<'a>(label: String) -> Element<'a, Message> {
+    container(text(label).size(TEXT_SIZE))
+        .padding(6)
+        .style(|_theme| container::Style {
+            background: Some(BACKGROUND_COLOR.into()),
+            text_color: Some(Color::WHITE),
+            ..container::Style::default()
+        })
+        .into()
+}
+
+// A short status message, such as why the grid rejected something, that
+// goes away by itself after a few seconds.
+#[derive(Default)]
+pub struct StatusToast {
+    message: Option<(String, Instant)>,
+}
+
+impl StatusToast {
+    pub fn show(&mut self, message: String) {
+        self.message = Some((message, Instant::now()));
+    }
+
+    pub fn view<'a>(&self) -> Option<Element<'a, Message>> {
+        self.message
+            .as_ref()
+            .filter(|(_, shown_at)| shown_at.elapsed() < TOAST_DURATION)
+            .map(|(message, _)| label(message.clone()))
+    }
+}
@@ -0,0 +1,236 @@
+use iced::{
+    widget::{column, container, row, text, text_input},
+    Color, Element, Length,
+};
+use simulation::physics::{BodyProperties, EntityId, GridMessage};
+
+use crate::Message;
+
+const PANEL_WIDTH: f32 = 220.0;
+const LABEL_WIDTH: f32 = 80.0;
+const BACKGROUND_COLOR: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.6);
+const INVALID_COLOR: Color = Color::from_rgb(1.0, 0.45, 0.45);
+const TEXT_SIZE: f32 = 14.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    X,
+    Y,
+    Radius,
+    Width,
+    Height,
+    Mass,
+    // Empty for the config's elasticity coefficient.
+    Restitution,
+    // A hex color like "#ff8800", or empty for the default.
+    Color,
+}
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::X => "X",
+            Field::Y => "Y",
+            Field::Radius => "Radius",
+            Field::Width => "Width",
+            Field::Height => "Height",
+            Field::Mass => "Mass",
+            Field::Restitution => "Restitution",
+            Field::Color => "Color",
+        }
+    }
+}
+
+// Side panel with the editable properties of the selected body. Edits are
+// applied as they're typed, as soon as they make sense, and anything that
+// doesn't is left in its input, marked as invalid.
+pub struct Inspector {
+    id: EntityId,
+    properties: BodyProperties,
+    // What's in each field's input, which can differ from `properties` while
+    // it's being typed.
+    inputs: Vec<(Field, String)>,
+}
+
+impl Inspector {
+    pub fn new(id: EntityId, properties: BodyProperties) -> Self {
+        let inputs = fields(&properties)
+            .iter()
+            .map(|&field| (field, format_field(&properties, field)))
+            .collect();
+
+        Self {
+            id,
+            properties,
+            inputs,
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+
+    pub fn get_properties(&self) -> BodyProperties {
+        self.properties
+    }
+
+    // Returns the message that applies the edit, if the input is valid.
+    pub fn edit(&mut self, field: Field, input: String) -> Option<GridMessage> {
+        let properties = with_field(self.properties, field, &input);
+        if let Some((_, field_input)) = self.inputs.iter_mut().find(|(f, _)| *f == field) {
+            *field_input = input;
+        }
+
+        self.properties = properties?;
+        Some(GridMessage::UpdateBody(self.id, self.properties))
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title = match self.properties {
+            BodyProperties::Circle { .. } => "Circle",
+            BodyProperties::StaticCircle { .. } => "Static circle",
+            BodyProperties::StaticRectangle { .. } => "Static rectangle",
+        };
+
+        let rows = self.inputs.iter().map(|(field, input)| {
+            let field = *field;
+            let valid = with_field(self.properties, field, input).is_some();
+            row![
+                text(field.label())
+                    .size(TEXT_SIZE)
+                    .width(Length::Fixed(LABEL_WIDTH))
+                    .color_maybe((!valid).then_some(INVALID_COLOR)),
+                text_input("", input)
+                    .size(TEXT_SIZE)
+                    .on_input(move |input| Message::EditProperty(field, input)),
+            ]
+            .spacing(8)
+            .align_y(iced::alignment::Vertical::Center)
+            .into()
+        });
+
+        container(column![text(title).size(TEXT_SIZE)].extend(rows).spacing(6))
+            .width(Length::Fixed(PANEL_WIDTH))
+            .padding(6)
+            .style(|_theme| container::Style {
+                background: Some(BACKGROUND_COLOR.into()),
+                text_color: Some(Color::WHITE),
+                ..container::Style::default()
+            })
+            .into()
+    }
+}
+
+fn fields(properties: &BodyProperties) -> &'static [Field] {
+    match properties {
+        BodyProperties::Circle { .. } => &[
+            Field::X,
+            Field::Y,
+            Field::Radius,
+            Field::Mass,
+            Field::Restitution,
+            Field::Color,
+        ],
+        BodyProperties::StaticCircle { .. } => &[Field::X, Field::Y, Field::Radius],
+        BodyProperties::StaticRectangle { .. } => {
+            &[Field::X, Field::Y, Field::Width, Field::Height]
+        }
+    }
+}
+
+fn format_field(properties: &BodyProperties, field: Field) -> String {
+    let number = |value: f32| format!("{value:.2}");
+    match (*properties, field) {
+        (
+            BodyProperties::Circle { x_pos, .. }
+            | BodyProperties::StaticCircle { x_pos, .. }
+            | BodyProperties::StaticRectangle { x_pos, .. },
+            Field::X,
+        ) => number(x_pos),
+        (
+            BodyProperties::Circle { y_pos, .. }
+            | BodyProperties::StaticCircle { y_pos, .. }
+            | BodyProperties::StaticRectangle { y_pos, .. },
+            Field::Y,
+        ) => number(y_pos),
+        (
+            BodyProperties::Circle { radius, .. } | BodyProperties::StaticCircle { radius, .. },
+            Field::Radius,
+        ) => number(radius),
+        (BodyProperties::StaticRectangle { width, .. }, Field::Width) => number(width),
+        (BodyProperties::StaticRectangle { height, .. }, Field::Height) => number(height),
+        (BodyProperties::Circle { mass, .. }, Field::Mass) => number(mass),
+        (BodyProperties::Circle { restitution, .. }, Field::Restitution) => {
+            restitution.map(number).unwrap_or_default()
+        }
+        (BodyProperties::Circle { color, .. }, Field::Color) => color
+            .map(|(r, g, b)| {
+                let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+            })
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+// The properties with `field` set to the parsed input, or `None` if the
+// input isn't a valid value for it.
+fn with_field(mut properties: BodyProperties, field: Field, input: &str) -> Option<BodyProperties> {
+    let input = input.trim();
+    let number = || input.parse::<f32>().ok().filter(|value| value.is_finite());
+    let positive = || number().filter(|&value| value > 0.0);
+
+    match (&mut properties, field) {
+        (
+            BodyProperties::Circle { x_pos, .. }
+            | BodyProperties::StaticCircle { x_pos, .. }
+            | BodyProperties::StaticRectangle { x_pos, .. },
+            Field::X,
+        ) => *x_pos = number()?,
+        (
+            BodyProperties::Circle { y_pos, .. }
+            | BodyProperties::StaticCircle { y_pos, .. }
+            | BodyProperties::StaticRectangle { y_pos, .. },
+            Field::Y,
+        ) => *y_pos = number()?,
+        (
+            BodyProperties::Circle { radius, .. } | BodyProperties::StaticCircle { radius, .. },
+            Field::Radius,
+        ) => *radius = positive()?,
+        (BodyProperties::StaticRectangle { width, .. }, Field::Width) => *width = positive()?,
+        (BodyProperties::StaticRectangle { height, .. }, Field::Height) => *height = positive()?,
+        (BodyProperties::Circle { mass, .. }, Field::Mass) => *mass = positive()?,
+        (BodyProperties::Circle { restitution, .. }, Field::Restitution) => {
+            *restitution = if input.is_empty() {
+                None
+            } else {
+                Some(number().filter(|&value| value >= 0.0)?)
+            };
+        }
+        (BodyProperties::Circle { color, .. }, Field::Color) => {
+            *color = if input.is_empty() {
+                None
+            } else {
+                Some(parse_hex_color(input)?)
+            };
+        }
+        _ => return None,
+    }
+
+    Some(properties)
+}
+
+// Parses colors like "#ff8800" or "ff8800".
+fn parse_hex_color(input: &str) -> Option<(f32, f32, f32)> {
+    let hex = input.strip_prefix('#').unwrap_or(input);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16)
+            .ok()
+            .map(|value| value as f32 / 255.0)
+    };
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,379 @@
+// Drawing the dynamic circles with a shader, as instanced quads in a single
+// draw call, rather than tessellating each one on the canvas. wgpu's types
+// can't be shared between threads on wasm32, which the shader widget needs,
+// so there they're always drawn on the canvas.
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use enabled::with_circles;
+
+#[cfg(target_arch = "wasm32")]
+pub use disabled::with_circles;
+
+// How the dynamic circles are drawn in the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircleRenderer {
+    // Along with everything else, with their fill styles.
+    #[default]
+    Canvas,
+    // By a shader, as one instanced quad each in a single draw call. Much
+    // faster with tens of thousands of circles, but they're solid colors and
+    // drawn over everything else. Needs the GPU renderer, and exported frames
+    // are still drawn the canvas way.
+    Instanced,
+}
+
+impl CircleRenderer {
+    pub fn draws_instanced(self) -> bool {
+        self == CircleRenderer::Instanced && cfg!(not(target_arch = "wasm32"))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod enabled {
+    use iced::advanced::graphics::color;
+    use iced::widget::shader::{self, wgpu, Shader, Storage, Viewport};
+    use iced::widget::stack;
+    use iced::{mouse, Element, Length, Rectangle, Size, Theme};
+
+    use std::sync::Arc;
+
+    use crate::drawn_frame::DrawnFrame;
+    use crate::view::{View, WindowResize};
+    use crate::Message;
+
+    const SHADER: &str = include_str!("instanced.wgsl");
+    // Each circle's center, radius, and color as linear RGBA.
+    const FLOATS_PER_CIRCLE: usize = 7;
+    const CIRCLE_STRIDE: u64 = (FLOATS_PER_CIRCLE * std::mem::size_of::<f32>()) as u64;
+    // The transform, then the render target's size, each padded to a vec4.
+    const UNIFORMS_SIZE: u64 = 8 * std::mem::size_of::<f32>() as u64;
+
+    // `canvas`, with the frame's circles drawn over it if they're instanced.
+    // `pane` tells apart frames drawn side by side, which each need their own
+    // buffers.
+    pub fn with_circles<'a>(
+        canvas: Element<'a, Message>,
+        drawn_frame: &DrawnFrame,
+        theme: &Theme,
+        pane: usize,
+    ) -> Element<'a, Message> {
+        if !drawn_frame.get_circle_renderer().draws_instanced() {
+            return canvas;
+        }
+
+        let circles = drawn_frame
+            .get_drawn_circles(theme)
+            .flat_map(|(center, radius, circle_color)| {
+                let [r, g, b, a] = color::pack(circle_color).components();
+                [center.x, center.y, radius, r, g, b, a]
+            })
+            .collect();
+        let circles = InstancedCircles {
+            pane,
+            world_size: drawn_frame.get_grid_frame().get_size(),
+            window_resize: drawn_frame.get_window_resize(),
+            circles: Arc::new(circles),
+        };
+
+        stack![
+            canvas,
+            Shader::new(circles)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        ]
+        .into()
+    }
+
+    struct InstancedCircles {
+        pane: usize,
+        world_size: Size,
+        window_resize: WindowResize,
+        circles: Arc<Vec<f32>>,
+    }
+
+    impl shader::Program<Message> for InstancedCircles {
+        type State = ();
+        type Primitive = CirclesPrimitive;
+
+        fn draw(&self, _state: &(), _cursor: mouse::Cursor, bounds: Rectangle) -> CirclesPrimitive {
+            CirclesPrimitive {
+                pane: self.pane,
+                view: View::new(self.window_resize, self.world_size, bounds.size()),
+                world_size: self.world_size,
+                circles: Arc::clone(&self.circles),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct CirclesPrimitive {
+        pane: usize,
+        view: View,
+        world_size: Size,
+        circles: Arc<Vec<f32>>,
+    }
+
+    impl shader::Primitive for CirclesPrimitive {
+        fn prepare(
+            &self,
+            device: &wgpu::Device,
+            queue: &wgpu::Queue,
+            format: wgpu::TextureFormat,
+            storage: &mut Storage,
+            bounds: &Rectangle,
+            viewport: &Viewport,
+        ) {
+            if !storage.has::<Pipeline>() {
+                storage.store(Pipeline::new(device, format));
+            }
+            let pipeline = storage.get_mut::<Pipeline>().unwrap();
+            while pipeline.panes.len() <= self.pane {
+                let pane = PaneBuffers::new(device, &pipeline.bind_group_layout);
+                pipeline.panes.push(pane);
+            }
+            let pane = &mut pipeline.panes[self.pane];
+
+            let scale_factor = viewport.scale_factor() as f32;
+            let origin_x = (bounds.x + self.view.offset.x) * scale_factor;
+            let origin_y = (bounds.y + self.view.offset.y) * scale_factor;
+            let scale = self.view.scale * scale_factor;
+            let target_size = viewport.physical_size();
+            let uniforms = [
+                origin_x,
+                origin_y,
+                scale,
+                0.0,
+                target_size.width as f32,
+                target_size.height as f32,
+                0.0,
+                0.0,
+            ];
+            queue.write_buffer(&pane.uniforms, 0, &to_bytes(&uniforms));
+
+            let circle_count = self.circles.len() / FLOATS_PER_CIRCLE;
+            let size = (circle_count as u64 * CIRCLE_STRIDE).max(CIRCLE_STRIDE);
+            if pane.circles.size() < size {
+                // Doubled so that a growing scene doesn't need a new buffer every
+                // frame.
+                pane.circles = PaneBuffers::circle_buffer(device, size * 2);
+            }
+            queue.write_buffer(&pane.circles, 0, &to_bytes(&self.circles));
+            pane.circle_count = circle_count as u32;
+
+            // Circles are cut off at the world's edges, like they are by the
+            // letterbox on the canvas.
+            let left = origin_x.max(0.0);
+            let top = origin_y.max(0.0);
+            let right = (origin_x + self.world_size.width * scale).max(left);
+            let bottom = (origin_y + self.world_size.height * scale).max(top);
+            pane.world_bounds = Rectangle {
+                x: left as u32,
+                y: top as u32,
+                width: (right - left).ceil() as u32,
+                height: (bottom - top).ceil() as u32,
+            };
+        }
+
+        fn render(
+            &self,
+            encoder: &mut wgpu::CommandEncoder,
+            storage: &Storage,
+            target: &wgpu::TextureView,
+            clip_bounds: &Rectangle<u32>,
+        ) {
+            let Some(pipeline) = storage.get::<Pipeline>() else {
+                return;
+            };
+            let Some(pane) = pipeline.panes.get(self.pane) else {
+                return;
+            };
+            let Some(scissor) = intersection(*clip_bounds, pane.world_bounds) else {
+                return;
+            };
+            if pane.circle_count == 0 {
+                return;
+            }
+
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("instanced circles"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_scissor_rect(scissor.x, scissor.y, scissor.width, scissor.height);
+            pass.set_pipeline(&pipeline.pipeline);
+            pass.set_bind_group(0, &pane.bind_group, &[]);
+            pass.set_vertex_buffer(0, pane.circles.slice(..));
+            pass.draw(0..4, 0..pane.circle_count);
+        }
+    }
+
+    // Shared by every pane, and kept for as long as the renderer is.
+    struct Pipeline {
+        pipeline: wgpu::RenderPipeline,
+        bind_group_layout: wgpu::BindGroupLayout,
+        panes: Vec<PaneBuffers>,
+    }
+
+    impl Pipeline {
+        fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("instanced circles"),
+                source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+            });
+
+            let bind_group_layout =
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("instanced circles"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                });
+
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("instanced circles"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+            let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("instanced circles"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: CIRCLE_STRIDE,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            0 => Float32x2,
+                            1 => Float32,
+                            2 => Float32x4,
+                        ],
+                    }],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            });
+
+            Self {
+                pipeline,
+                bind_group_layout,
+                panes: Vec::new(),
+            }
+        }
+    }
+
+    // What one pane's circles were last prepared with.
+    struct PaneBuffers {
+        uniforms: wgpu::Buffer,
+        bind_group: wgpu::BindGroup,
+        circles: wgpu::Buffer,
+        circle_count: u32,
+        // In physical pixels.
+        world_bounds: Rectangle<u32>,
+    }
+
+    impl PaneBuffers {
+        fn new(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+            let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("instanced circles uniforms"),
+                size: UNIFORMS_SIZE,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("instanced circles"),
+                layout: bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms.as_entire_binding(),
+                }],
+            });
+
+            Self {
+                uniforms,
+                bind_group,
+                circles: Self::circle_buffer(device, CIRCLE_STRIDE),
+                circle_count: 0,
+                world_bounds: Rectangle::default(),
+            }
+        }
+
+        fn circle_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("instanced circles"),
+                size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        }
+    }
+
+    fn to_bytes(floats: &[f32]) -> Vec<u8> {
+        floats
+            .iter()
+            .flat_map(|float| float.to_ne_bytes())
+            .collect()
+    }
+
+    fn intersection(a: Rectangle<u32>, b: Rectangle<u32>) -> Option<Rectangle<u32>> {
+        let x = a.x.max(b.x);
+        let y = a.y.max(b.y);
+        let right = (a.x + a.width).min(b.x + b.width);
+        let bottom = (a.y + a.height).min(b.y + b.height);
+        (right > x && bottom > y).then(|| Rectangle {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod disabled {
+    use iced::{Element, Theme};
+
+    use crate::drawn_frame::DrawnFrame;
+    use crate::Message;
+
+    pub fn with_circles<'a>(
+        canvas: Element<'a, Message>,
+        _drawn_frame: &DrawnFrame,
+        _theme: &Theme,
+        _pane: usize,
+    ) -> Element<'a, Message> {
+        canvas
+    }
+}
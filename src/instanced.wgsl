@@ -0,0 +1,58 @@
+// Draws each circle as a quad around it, shaded by how much of each pixel
+// the circle covers.
+
+struct Uniforms {
+    // Where the world's origin is in the render target, in pixels, then the
+    // pixels per world unit.
+    transform: vec4<f32>,
+    // The render target's width and height, in pixels.
+    target_size: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // From the circle's center, in pixels.
+    @location(0) offset: vec2<f32>,
+    @location(1) radius: f32,
+    @location(2) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) center: vec2<f32>,
+    @location(1) radius: f32,
+    @location(2) color: vec4<f32>,
+) -> VertexOutput {
+    // The quad's corners, in triangle strip order.
+    let corner = vec2<f32>(
+        f32(vertex_index & 1u) * 2.0 - 1.0,
+        f32(vertex_index >> 1u) * 2.0 - 1.0,
+    );
+    let radius_pixels = radius * uniforms.transform.z;
+    // A pixel bigger than the circle, so that its antialiased edge fits.
+    let offset = corner * (radius_pixels + 1.0);
+    let pixel = uniforms.transform.xy + center * uniforms.transform.z + offset;
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(
+        pixel.x / uniforms.target_size.x * 2.0 - 1.0,
+        1.0 - pixel.y / uniforms.target_size.y * 2.0,
+        0.0,
+        1.0,
+    );
+    out.offset = offset;
+    out.radius = radius_pixels;
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = clamp(in.radius - length(in.offset) + 0.5, 0.0, 1.0);
+    let alpha = in.color.a * coverage;
+    // Premultiplied.
+    return vec4<f32>(in.color.rgb * alpha, alpha);
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+use crate::simd;
+
+// Moves every awake circle over a subtick. Forces have already been applied to
+// the circles' velocities by the time this runs, and `start_velocities` are
+// what they were before that, so `velocity - start_velocity` is the subtick's
+// acceleration times `dt`. `dt` is the length of the subtick as a fraction of
+// a tick.
+pub trait Integrator {
+    fn integrate(
+        &self,
+        positions: &mut [(f32, f32)],
+        velocities: &[(f32, f32)],
+        start_velocities: &[(f32, f32)],
+        sleeping: &[bool],
+        dt: f32,
+    );
+}
+
+// Moves with the velocity from before the forces were applied. Simple, but
+// adds energy to anything that oscillates, such as a circle on a tether.
+pub struct ExplicitEuler;
+
+impl Integrator for ExplicitEuler {
+    fn integrate(
+        &self,
+        positions: &mut [(f32, f32)],
+        _velocities: &[(f32, f32)],
+        start_velocities: &[(f32, f32)],
+        sleeping: &[bool],
+        dt: f32,
+    ) {
+        simd::advance_awake(positions, start_velocities, dt, sleeping);
+    }
+}
+
+// Moves with the velocity after the forces were applied. Just as cheap as
+// explicit Euler, but keeps oscillations from growing.
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn integrate(
+        &self,
+        positions: &mut [(f32, f32)],
+        velocities: &[(f32, f32)],
+        _start_velocities: &[(f32, f32)],
+        sleeping: &[bool],
+        dt: f32,
+    ) {
+        simd::advance_awake(positions, velocities, dt, sleeping);
+    }
+}
+
+// Velocity Verlet, taking the acceleration to be constant over the subtick.
+// Exact for constant forces like gravity, so arcs come out the same no matter
+// how many subticks they're split into.
+pub struct Verlet;
+
+impl Integrator for Verlet {
+    fn integrate(
+        &self,
+        positions: &mut [(f32, f32)],
+        velocities: &[(f32, f32)],
+        start_velocities: &[(f32, f32)],
+        sleeping: &[bool],
+        dt: f32,
+    ) {
+        simd::advance_awake_by_average(positions, start_velocities, velocities, dt, sleeping);
+    }
+}
+
+// Selects an integrator from the config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegratorKind {
+    ExplicitEuler,
+    #[default]
+    SemiImplicitEuler,
+    Verlet,
+}
+
+impl IntegratorKind {
+    pub fn integrator(self) -> &'static dyn Integrator {
+        match self {
+            IntegratorKind::ExplicitEuler => &ExplicitEuler,
+            IntegratorKind::SemiImplicitEuler => &SemiImplicitEuler,
+            IntegratorKind::Verlet => &Verlet,
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use iced::keyboard::Key;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+// Everything that can be bound to a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    TogglePause,
+    Step,
+    // Rewinds the simulation a little, pausing it.
+    StepBack,
+    // Removes every dynamic circle.
+    Clear,
+    // Removes everything but the dynamic circles.
+    ClearStatic,
+    // Removes everything.
+    ResetAll,
+    SaveScene,
+    LoadScene,
+    SpawnBurst,
+    // Turns the brush on or off. While it's on, holding the left mouse
+    // button paints circles.
+    ToggleBrush,
+    NextPreset,
+    ToggleDiagnostics,
+    ToggleStatsHud,
+    ToggleFpsOverlay,
+    ToggleGifCapture,
+    // Starts or stops recording an mp4 with ffmpeg, if it's installed.
+    ToggleVideoCapture,
+    // Saves a PNG of the current frame to the config's `screenshot_dir`.
+    Screenshot,
+    ToggleHeatMap,
+    // Cycles through the color palettes.
+    NextPalette,
+    // Switches between the light and dark themes, in every window.
+    ToggleTheme,
+    ToggleReferenceGrid,
+    // Enters or leaves edit mode, pausing the simulation while in it.
+    ToggleEditor,
+    // Opens another window with a simulation of its own.
+    OpenWindow,
+    // Starts or stops comparing against the `--compare` config.
+    ToggleComparison,
+    // Sets off an explosion at the mouse cursor.
+    Explode,
+    SlowDownReplay,
+    SpeedUpReplay,
+    TiltGravityLeft,
+    TiltGravityRight,
+    TiltGravityUp,
+    TiltGravityDown,
+    SlowDownTime,
+    SpeedUpTime,
+}
+
+// Maps key names to actions. Named keys use iced's names (e.g. "Space",
+// "ArrowLeft", "F2") and character keys are the character itself. Names are
+// matched case-insensitively.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Keybindings(BTreeMap<String, Action>);
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self(
+            [
+                ("Space", Action::TogglePause),
+                (".", Action::Step),
+                (",", Action::StepBack),
+                ("c", Action::Clear),
+                ("x", Action::ClearStatic),
+                ("r", Action::ResetAll),
+                ("s", Action::SaveScene),
+                ("l", Action::LoadScene),
+                ("b", Action::SpawnBurst),
+                ("d", Action::ToggleBrush),
+                ("p", Action::NextPreset),
+                ("F2", Action::ToggleDiagnostics),
+                ("F3", Action::ToggleStatsHud),
+                ("F4", Action::ToggleFpsOverlay),
+                ("F9", Action::ToggleGifCapture),
+                ("F10", Action::ToggleVideoCapture),
+                ("F12", Action::Screenshot),
+                ("h", Action::ToggleHeatMap),
+                ("k", Action::NextPalette),
+                ("t", Action::ToggleTheme),
+                ("g", Action::ToggleReferenceGrid),
+                ("Tab", Action::ToggleEditor),
+                ("n", Action::OpenWindow),
+                ("v", Action::ToggleComparison),
+                ("e", Action::Explode),
+                ("-", Action::SlowDownReplay),
+                ("=", Action::SpeedUpReplay),
+                ("ArrowLeft", Action::TiltGravityLeft),
+                ("ArrowRight", Action::TiltGravityRight),
+                ("ArrowUp", Action::TiltGravityUp),
+                ("ArrowDown", Action::TiltGravityDown),
+                ("[", Action::SlowDownTime),
+                ("]", Action::SpeedUpTime),
+            ]
+            .into_iter()
+            .map(|(key_name, action)| (key_name.to_string(), action))
+            .collect(),
+        )
+    }
+}
+
+impl Keybindings {
+    pub fn action_for(&self, key: &Key) -> Option<Action> {
+        let pressed_key_name = match key {
+            Key::Named(named) => format!("{named:?}"),
+            Key::Character(c) => c.to_string(),
+            Key::Unidentified => return None,
+        };
+
+        self.0
+            .iter()
+            .find(|(key_name, _)| key_name.eq_ignore_ascii_case(&pressed_key_name))
+            .map(|(_, action)| *action)
+    }
+}
@@ -0,0 +1,10 @@
+pub mod broadphase;
+pub mod config;
+pub mod forces;
+pub mod integrator;
+pub mod physics;
+pub mod random;
+pub mod replay;
+pub mod scene;
+pub mod simd;
+pub mod time;
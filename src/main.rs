@@ -1,198 +1,1782 @@
-use futures::{channel::mpsc, StreamExt};
+use audio::CollisionSounds;
+use brush::Brush;
+use clap::Parser;
+use config_file::ConfigFile;
+use diagnostics::FrameDiagnostics;
+use drawn_frame::{ColorMode, DrawnFrame};
+use editor::{Editor, Tool};
+use export::{gif::GifCapture, video::VideoCapture};
+use fade_outs::FadeOuts;
+use fps_overlay::FpsOverlay;
+use futures::{
+    channel::{mpsc, oneshot},
+    StreamExt,
+};
+use http_api::HttpApi;
+#[cfg(all(feature = "http_api", not(target_arch = "wasm32")))]
+use http_api::RemoteCommand;
+use hud::{StatsHud, StatusToast};
 use iced::{
-    window::{settings::PlatformSpecific, Settings},
-    Element, Length, Size, Subscription, Task, Theme,
+    keyboard::{self, Key, Modifiers},
+    mouse,
+    widget::{column, container, row, Canvas, Stack},
+    window::{self, settings::PlatformSpecific, Settings},
+    Element, Length, Point, Size, Subscription, Task, Theme,
+};
+use inspector::Field;
+use instanced::CircleRenderer;
+use keybindings::{Action, Keybindings};
+use network::FrameServer;
+use palette::{ColorOverrides, Palette, ThemeKind};
+use profile::Profile;
+use script::ScriptHost;
+use simulation::config::SimulationConfig;
+use simulation::physics::{
+    self, Circle, EntityId, GravityWell, GridBuilder, GridFrame, GridMessage, Query, QueryResult,
+    MAX_TIME_SCALE, MIN_TIME_SCALE,
+};
+use simulation::replay::{
+    self, Recorder, Recording, RecordingHeader, MAX_REPLAY_SPEED, MIN_REPLAY_SPEED,
 };
-use physics::{Circle, GridFrame, GridMessage, StaticCircle, StaticRectangle};
+use simulation::scene::{self, presets::Preset, Scene};
+use sparks::Sparks;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use telemetry::TelemetrySink;
+use tracing::{debug, error, info, warn};
+use view::{View, WindowResize};
 
-mod physics;
+mod audio;
+mod benchmark;
+mod brush;
+mod config_file;
+#[cfg(not(target_arch = "wasm32"))]
+mod console;
+mod diagnostics;
+mod drawn_frame;
+mod editor;
+mod export;
+mod fade_outs;
+mod fps_overlay;
+mod gamepad;
+mod http_api;
+mod hud;
+mod inspector;
+mod instanced;
+mod keybindings;
+mod network;
+mod palette;
+mod profile;
+mod script;
+mod sparks;
+mod telemetry;
+mod view;
 
 const TARGET_FPS: u64 = 120;
 
 const APP_WIDTH: f32 = 800.0;
 const APP_HEIGHT: f32 = 480.0;
 
+// What's logged when `RUST_LOG` isn't set: this app's own messages and the
+// simulation's, and nothing from its other dependencies.
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_LOG_FILTER: &str = "physics=info,simulation=info";
+
+// How much each tilt key press tilts gravity in its direction.
+const GRAVITY_TILT_STEP: f32 = 0.02;
+// Factor each slow down or speed up key press changes the time scale by.
+const TIME_SCALE_STEP: f32 = 1.25;
+// Impulse at the center of explosions set off with the mouse, and how far
+// they reach.
+const EXPLOSION_STRENGTH: f32 = 3000.0;
+const EXPLOSION_RADIUS: f32 = 150.0;
+
+#[derive(Parser, Debug, Clone)]
+struct Args {
+    /// Path to the simulation config file. It is watched for changes while the
+    /// app is running, so edits are applied without restarting.
+    #[arg(long, default_value = "physics.ron")]
+    config: PathBuf,
+
+    /// A second simulation config to compare against. A copy of the
+    /// simulation is run with it, side by side with the one from `--config`
+    /// and given the same input. It is watched for changes like `--config`,
+    /// and `v` (by default) turns the comparison off and on again.
+    #[arg(long, conflicts_with = "replay")]
+    compare: Option<PathBuf>,
+
+    /// Run the simulation without a window and print tick-time statistics.
+    #[arg(long)]
+    headless: bool,
+
+    /// Number of frames to simulate in headless mode or when dumping frames.
+    #[arg(long, default_value_t = 1000)]
+    frames: u32,
+
+    /// Number of circles in the headless benchmark's stress-test scene,
+    /// unless `--stress` gives it.
+    #[arg(long, default_value_t = 10_000, requires = "headless")]
+    circles: usize,
+
+    /// Start with the stress-test scene of this many circles instead of a
+    /// preset: circles of random sizes and velocities above an obstacle
+    /// course, in a world sized to fit them. It's the same scene the headless
+    /// benchmark runs, so performance can be compared between the two.
+    #[arg(long)]
+    stress: Option<usize>,
+
+    /// In headless mode, only time the vectorized gravity, drag and position
+    /// update loops against their scalar versions, `--frames` times each.
+    #[arg(long, requires = "headless")]
+    integration_only: bool,
+
+    /// Record the input message log of this run to a file for later replay.
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Play back a recording instead of running a live simulation.
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// Initial playback speed multiplier for replays. Use `-` and `=` (by
+    /// default) to halve or double it while the replay is running.
+    #[arg(long, default_value_t = 1.0, requires = "replay")]
+    replay_speed: f32,
+
+    /// Export the frames between `--export-start` and `--export-end` to an
+    /// animated GIF. GIF captures can also be started and stopped with F9 (by
+    /// default).
+    #[arg(long)]
+    export_gif: Option<PathBuf>,
+
+    /// First frame number to include in the GIF export.
+    #[arg(long, default_value_t = 0, requires = "export_gif")]
+    export_start: u32,
+
+    /// Frame number to stop the GIF export at. If unset, the capture runs
+    /// until it is stopped from the keyboard.
+    #[arg(long, requires = "export_gif")]
+    export_end: Option<u32>,
+
+    /// Render `--frames` frames off-screen as fast as possible and write them
+    /// to this directory as numbered PNGs, without opening a window. Plays
+    /// back the `--replay` recording if one is given.
+    #[arg(long, conflicts_with = "headless")]
+    dump_frames: Option<PathBuf>,
+
+    /// Scene file used by the save and load scene keybindings.
+    #[arg(long, default_value = "scene.ron")]
+    scene: PathBuf,
+
+    /// Built-in scene to start with. Press `p` (by default) to switch to the
+    /// next one while the app is running.
+    #[arg(long, value_enum, default_value_t = Preset::default())]
+    preset: Preset,
+
+    /// Rhai script to run alongside the simulation. Its top level runs once
+    /// at startup, and it can define `on_tick(tick)` and `on_collision(event)`
+    /// callbacks. Needs the `scripting` feature.
+    #[arg(long, conflicts_with = "replay")]
+    script: Option<PathBuf>,
+
+    /// Stream every frame over TCP to viewers started with `--connect` at
+    /// this address, like `127.0.0.1:7878`.
+    #[arg(long)]
+    serve: Option<String>,
+
+    /// Show the frames streamed by a `--serve` instance at this address
+    /// instead of running a simulation. The remote simulation can't be
+    /// controlled from the viewer.
+    #[arg(long, conflicts_with_all = ["replay", "record", "compare", "script", "serve"])]
+    connect: Option<String>,
+
+    /// Serve the HTTP remote-control API at this address, like
+    /// `127.0.0.1:8080`. Needs the `http_api` feature.
+    #[arg(long, conflicts_with_all = ["replay", "connect"])]
+    http: Option<String>,
+
+    /// Read commands from stdin, one per line, and apply them to the
+    /// simulation in the first window. Send `help` for the list of commands.
+    #[arg(long, conflicts_with_all = ["replay", "connect"])]
+    console: bool,
+}
+
 fn main() -> iced::Result {
-    iced::application("Physics", App::update, App::view)
+    let args = Args::parse();
+
+    // Logs go to stderr, filtered by `RUST_LOG` (e.g. `RUST_LOG=simulation=trace`
+    // for every tick). There's no environment on wasm32, so nothing is logged
+    // there.
+    #[cfg(not(target_arch = "wasm32"))]
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(DEFAULT_LOG_FILTER)),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    if args.headless && args.integration_only {
+        benchmark::run_integration_benchmark(args.frames, args.circles);
+        return Ok(());
+    }
+
+    if args.headless {
+        benchmark::run_headless_benchmark(
+            args.frames,
+            args.stress.unwrap_or(args.circles),
+            ConfigFile::load_or_default(&args.config).simulation,
+        );
+        return Ok(());
+    }
+
+    if let Some(dump_dir) = &args.dump_frames {
+        if let Err(err) = dump_frames(&args, dump_dir) {
+            error!("Failed to dump frames: {err}");
+        }
+        return Ok(());
+    }
+
+    iced::daemon("Physics", App::update, App::view)
         .subscription(App::subscription)
-        .theme(|_| Theme::Dark)
-        .window(Settings {
-            size: iced::Size {
-                width: APP_WIDTH,
-                height: APP_HEIGHT,
+        .theme(App::theme)
+        .run_with(move || App::new(args))
+}
+
+fn window_settings() -> Settings {
+    Settings {
+        size: iced::Size {
+            width: APP_WIDTH,
+            height: APP_HEIGHT,
+        },
+        position: iced::window::Position::Default,
+        min_size: None,
+        max_size: None,
+        visible: true,
+        resizable: true,
+        decorations: true,
+        transparent: false,
+        level: iced::window::Level::Normal,
+        icon: None,                                     // TODO: Set icon.
+        platform_specific: PlatformSpecific::default(), // TODO: Set platform specific settings for each platform.
+        exit_on_close_request: true,
+    }
+}
+
+// Every window runs a simulation of its own, so that several experiments can
+// run side by side. They share the config file, which is applied to all of
+// them when it changes.
+struct App {
+    args: Args,
+    simulations: BTreeMap<window::Id, Simulation>,
+    // Shared by every window.
+    theme: ThemeKind,
+}
+
+#[derive(Debug, Clone)]
+enum AppMessage {
+    // A message for the simulation in the window.
+    Simulation(window::Id, Message),
+    // The config file changed. It's only watched natively.
+    #[cfg(not(target_arch = "wasm32"))]
+    UpdateConfig(ConfigFile),
+    // A message for the first window's simulation, from input that isn't
+    // tied to a window, like the console or a gamepad.
+    MainWindow(Message),
+    WindowClosed(window::Id),
+}
+
+impl App {
+    fn new(args: Args) -> (Self, Task<AppMessage>) {
+        let theme = ConfigFile::load_or_default(&args.config).app.theme;
+        let mut app = Self {
+            args,
+            simulations: BTreeMap::new(),
+            theme,
+        };
+        let open = app.open_window();
+        (app, open)
+    }
+
+    // Only the first window records, replays, runs the script and exports a
+    // GIF, as asked on the command line. The ones opened after it start
+    // fresh simulations of the same preset.
+    fn open_window(&mut self) -> Task<AppMessage> {
+        let args = if self.simulations.is_empty() {
+            self.args.clone()
+        } else {
+            Args {
+                record: None,
+                replay: None,
+                script: None,
+                export_gif: None,
+                serve: None,
+                http: None,
+                ..self.args.clone()
+            }
+        };
+
+        let (id, open) = window::open(window_settings());
+        self.simulations.insert(id, Simulation::new(args));
+        open.discard()
+    }
+
+    fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::Simulation(_, Message::OpenWindow) => self.open_window(),
+            AppMessage::Simulation(_, Message::ToggleTheme) => self.set_theme(self.theme.toggled()),
+            AppMessage::Simulation(id, message) => match self.simulations.get_mut(&id) {
+                Some(simulation) => simulation
+                    .update(message)
+                    .map(move |message| AppMessage::Simulation(id, message)),
+                None => Task::none(),
             },
-            position: iced::window::Position::Default,
-            min_size: None,
-            max_size: None,
-            visible: true,
-            resizable: true,
-            decorations: true,
-            transparent: false,
-            level: iced::window::Level::Normal,
-            icon: None,                                     // TODO: Set icon.
-            platform_specific: PlatformSpecific::default(), // TODO: Set platform specific settings for each platform.
-            exit_on_close_request: true,
-        })
-        .run()
+            #[cfg(not(target_arch = "wasm32"))]
+            AppMessage::UpdateConfig(config) => {
+                let set_theme = if config.app.theme != self.theme {
+                    self.set_theme(config.app.theme)
+                } else {
+                    Task::none()
+                };
+                let update_config =
+                    Task::batch(self.simulations.iter_mut().map(|(&id, simulation)| {
+                        simulation
+                            .update(Message::UpdateConfig(config.clone()))
+                            .map(move |message| AppMessage::Simulation(id, message))
+                    }));
+                Task::batch([set_theme, update_config])
+            }
+            AppMessage::MainWindow(message) => match self.simulations.first_key_value() {
+                Some((&id, _)) => self.update(AppMessage::Simulation(id, message)),
+                None => Task::none(),
+            },
+            AppMessage::WindowClosed(id) => {
+                self.simulations.remove(&id);
+                if self.simulations.is_empty() {
+                    iced::exit()
+                } else {
+                    Task::none()
+                }
+            }
+        }
+    }
+
+    fn set_theme(&mut self, theme: ThemeKind) -> Task<AppMessage> {
+        self.theme = theme;
+        Task::batch(self.simulations.iter_mut().map(|(&id, simulation)| {
+            simulation
+                .update(Message::SetTheme(theme))
+                .map(move |message| AppMessage::Simulation(id, message))
+        }))
+    }
+
+    fn theme(&self, _id: window::Id) -> Theme {
+        self.theme.to_theme()
+    }
+
+    fn view(&self, id: window::Id) -> Element<'_, AppMessage> {
+        match self.simulations.get(&id) {
+            Some(simulation) => simulation
+                .view()
+                .map(move |message| AppMessage::Simulation(id, message)),
+            // The window is still opening or already closing.
+            None => iced::widget::Space::new(Length::Fill, Length::Fill).into(),
+        }
+    }
+
+    fn subscription(&self) -> Subscription<AppMessage> {
+        let mut subscriptions: Vec<_> = self
+            .simulations
+            .iter()
+            .map(|(&id, simulation)| {
+                simulation
+                    .subscription(id)
+                    .with(id)
+                    .map(|(id, message)| AppMessage::Simulation(id, message))
+            })
+            .collect();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        subscriptions.push(iced::Subscription::run_with_id(
+            self.args.config.clone(),
+            config_file::watch_config_file(self.args.config.clone()).map(AppMessage::UpdateConfig),
+        ));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.args.console {
+            subscriptions.push(iced::Subscription::run_with_id(
+                "console",
+                console::read_commands().map(AppMessage::MainWindow),
+            ));
+        }
+
+        subscriptions.push(gamepad::subscription().map(AppMessage::MainWindow));
+
+        // Input goes to the simulation in the window it happened in. Keys are
+        // mapped to actions in `Simulation::update`, since the bindings come
+        // from the config and this callback can't capture anything. Positions
+        // are in window coordinates, which `cursor_world_position` maps onto
+        // the world.
+        subscriptions.push(iced::event::listen_with(|event, status, window| {
+            let message = match event {
+                // Keys typed into widgets like the inspector's inputs are
+                // theirs.
+                iced::Event::Keyboard(keyboard::Event::KeyPressed { key, .. })
+                    if status == iced::event::Status::Ignored =>
+                {
+                    Some(Message::KeyPressed(key))
+                }
+                iced::Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                    Some(Message::CursorMoved(position))
+                }
+                // Clicks on widgets like the time scale slider are theirs.
+                iced::Event::Mouse(
+                    mouse::Event::ButtonPressed(_) | mouse::Event::ButtonReleased(_),
+                ) if status == iced::event::Status::Captured => None,
+                iced::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                    Some(Message::Clicked)
+                }
+                iced::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                    Some(Message::MouseReleased)
+                }
+                iced::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                    Some(Message::Explode)
+                }
+                iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(Message::ModifiersChanged(modifiers))
+                }
+                _ => None,
+            };
+            message.map(|message| AppMessage::Simulation(window, message))
+        }));
+
+        subscriptions.push(
+            window::resize_events()
+                .map(|(id, size)| AppMessage::Simulation(id, Message::ResizeWindow(size))),
+        );
+        subscriptions.push(window::close_events().map(AppMessage::WindowClosed));
+
+        Subscription::batch(subscriptions)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     // Perform one tick/step of the physics simulation.
-    SetGridFrame(physics::GridFrame),
-    SetGridMessageSender(mpsc::Sender<physics::GridMessage>),
+    SetGridFrame(Box<GridFrame>),
+    SetGridMessageSender(physics::GridMessageSender),
+    SetReplaySpeedSender(mpsc::Sender<f32>),
+    ChangeReplaySpeed(f32),
     AddCircle(Circle),
     ResizeWindow(Size),
+    UpdateConfig(ConfigFile),
+    TiltGravity((f32, f32)),
+    SetGravity((f32, f32)),
+    // How far a gamepad's stick tilts gravity away from what it's set to.
+    #[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+    SetGravityTilt((f32, f32)),
+    // Multiplies the time scale by the factor.
+    ChangeTimeScale(f32),
+    SetTimeScale(f32),
+    // Applies the profile on top of the config file, from now on.
+    SelectProfile(Profile),
+    KeyPressed(Key),
+    CursorMoved(Point),
+    ModifiersChanged(Modifiers),
+    // A left click anywhere in the window.
+    Clicked,
+    // The left mouse button being let go of anywhere in the window.
+    MouseReleased,
+    // Sets off an explosion at the last known cursor position, or in the
+    // middle of the world if the cursor hasn't been over the window yet.
+    Explode,
+    TogglePause,
+    SetPaused(bool),
+    Step,
+    StepBack,
+    Clear,
+    ClearStatic,
+    ResetAll,
+    // To the given file, or to the `--scene` file if there isn't one.
+    SaveScene(Option<PathBuf>),
+    LoadScene(Option<PathBuf>),
+    // Spawns the config's burst of circles at the last known cursor
+    // position, or in the middle of the world.
+    SpawnBurst,
+    ToggleBrush,
+    // Scales the brush's radius by this many notches of the mouse wheel.
+    ScaleBrushRadius(i32),
+    NextPreset,
+    ToggleDiagnostics,
+    ToggleStatsHud,
+    ToggleFpsOverlay,
+    ToggleGifCapture,
+    ToggleVideoCapture,
+    Screenshot,
+    ToggleHeatMap,
+    NextPalette,
+    // Handled by `App`, which sends every simulation a `SetTheme`.
+    ToggleTheme,
+    SetTheme(ThemeKind),
+    ToggleReferenceGrid,
+    ToggleEditor,
+    SelectTool(Tool),
+    // Opens another window with a simulation of its own. Handled by `App`.
+    OpenWindow,
+    ToggleComparison,
+    UpdateComparisonConfig(SimulationConfig),
+    // The bodies under the cursor when it was clicked with the select tool,
+    // topmost first.
+    BodiesPicked(Vec<EntityId>),
+    EditProperty(Field, String),
+    ExportFinished(Result<PathBuf, String>),
+    ScreenshotSaved(Result<PathBuf, String>),
+    // A request made through the HTTP API.
+    #[cfg(all(feature = "http_api", not(target_arch = "wasm32")))]
+    RemoteCommand(RemoteCommand),
 }
 
-#[derive(Default)]
-struct App {
-    grid_message_sender: Option<mpsc::Sender<physics::GridMessage>>,
-    current_grid_frame: Option<physics::GridFrame>,
+// The simulation running in one window, and everything about how it's shown
+// and controlled.
+struct Simulation {
+    grid_message_sender: Option<physics::GridMessageSender>,
+    current_frame: Option<DrawnFrame>,
+    config_path: PathBuf,
+    compare_config_path: Option<PathBuf>,
+    diagnostics: FrameDiagnostics,
+    show_diagnostics: bool,
+    stats_hud: StatsHud,
+    show_stats_hud: bool,
+    status_toast: StatusToast,
+    fps_overlay: FpsOverlay,
+    show_fps_overlay: bool,
+    color_mode: ColorMode,
+    palette: Palette,
+    color_overrides: ColorOverrides,
+    reference_grid_spacing: f32,
+    show_reference_grid: bool,
+    lod_circle_threshold: Option<usize>,
+    circle_renderer: CircleRenderer,
+    // Only used for exports, since `App` draws the windows in its own.
+    theme: ThemeKind,
+    record_path: Option<PathBuf>,
+    replay_path: Option<PathBuf>,
+    replay_speed: f32,
+    replay_speed_sender: Option<mpsc::Sender<f32>>,
+    frame_server: Option<FrameServer>,
+    http_api: Option<HttpApi>,
+    connect_address: Option<String>,
+    gif_capture: Option<GifCapture>,
+    video_capture: Option<VideoCapture>,
+    screenshot_dir: PathBuf,
+    telemetry_sink: Option<TelemetrySink>,
+    gravity: (f32, f32),
+    // Added to `gravity` by a gamepad's stick.
+    gravity_tilt: (f32, f32),
+    time_scale: f32,
+    profile: Profile,
+    keybindings: Keybindings,
+    paused: bool,
+    // Set while in edit mode.
+    editor: Option<Editor>,
+    editor_grid_size: f32,
+    // In window coordinates. See `cursor_world_position`.
+    cursor_position: Option<Point>,
+    window_size: Size,
+    window_resize: WindowResize,
+    modifiers: Modifiers,
+    gravity_well_strength: f32,
+    gravity_well_radius: f32,
+    scene_path: PathBuf,
+    preset: Preset,
+    // How many circles the stress-test scene started with, if it was asked
+    // for instead of the preset.
+    stress: Option<usize>,
+    collision_sounds: CollisionSounds,
+    sparks: Sparks,
+    fade_outs: FadeOuts,
+    brush: Brush,
+    script: Option<ScriptHost>,
 }
 
-impl App {
+impl Simulation {
+    fn new(args: Args) -> Self {
+        let config = ConfigFile::load_or_default(&args.config);
+
+        let mut telemetry_sink = None;
+        telemetry::update_telemetry_sink(&mut telemetry_sink, config.app.telemetry_csv.as_deref());
+
+        let script = args.script.as_ref().and_then(|script_path| {
+            ScriptHost::load(script_path, APP_WIDTH, APP_HEIGHT)
+                .map_err(|err| error!("Failed to load script {}: {err}", script_path.display()))
+                .ok()
+        });
+
+        let frame_server = args.serve.as_ref().and_then(|address| {
+            FrameServer::bind(address)
+                .inspect(|frame_server| info!("Serving frames on {}.", frame_server.get_address()))
+                .map_err(|err| error!("Failed to serve frames on {address}: {err}"))
+                .ok()
+        });
+
+        let http_api = args.http.as_ref().and_then(|address| {
+            HttpApi::start(address)
+                .map_err(|err| error!("Failed to start the HTTP API on {address}: {err}"))
+                .ok()
+        });
+
+        Self {
+            grid_message_sender: None,
+            current_frame: None,
+            config_path: args.config,
+            compare_config_path: args.compare,
+            diagnostics: FrameDiagnostics::new(TARGET_FPS, config.app.palette),
+            show_diagnostics: false,
+            stats_hud: StatsHud::default(),
+            show_stats_hud: false,
+            status_toast: StatusToast::default(),
+            fps_overlay: FpsOverlay::new(TARGET_FPS, config.app.palette),
+            show_fps_overlay: false,
+            color_mode: ColorMode::default(),
+            palette: config.app.palette,
+            color_overrides: config.app.color_overrides,
+            reference_grid_spacing: config.app.reference_grid_spacing,
+            show_reference_grid: config.app.show_reference_grid,
+            lod_circle_threshold: config.app.lod_circle_threshold,
+            circle_renderer: config.app.circle_renderer,
+            theme: config.app.theme,
+            record_path: args.record,
+            replay_path: args.replay,
+            replay_speed: args.replay_speed,
+            replay_speed_sender: None,
+            frame_server,
+            http_api,
+            connect_address: args.connect,
+            gif_capture: args.export_gif.map(|path| {
+                GifCapture::new(
+                    path,
+                    args.export_start,
+                    args.export_end,
+                    physics::TICKS_PER_SECOND,
+                )
+            }),
+            video_capture: None,
+            screenshot_dir: config.app.screenshot_dir.clone(),
+            telemetry_sink,
+            gravity: config.simulation.gravity,
+            gravity_tilt: (0.0, 0.0),
+            time_scale: config.simulation.time_scale,
+            profile: Profile::default(),
+            keybindings: config.app.keybindings.clone(),
+            paused: false,
+            editor: None,
+            editor_grid_size: config.app.editor_grid_size,
+            cursor_position: None,
+            window_size: Size::new(APP_WIDTH, APP_HEIGHT),
+            window_resize: config.app.window_resize,
+            modifiers: Modifiers::default(),
+            gravity_well_strength: config.simulation.gravity_well_strength,
+            gravity_well_radius: config.simulation.gravity_well_radius,
+            scene_path: args.scene,
+            preset: args.preset,
+            stress: args.stress,
+            collision_sounds: CollisionSounds::new(&config.app),
+            sparks: Sparks::new(&config.app),
+            fade_outs: FadeOuts::default(),
+            brush: Brush::new(&config.simulation, &config.app),
+            script,
+        }
+    }
+
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::SetGridFrame(grid_frame) => {
                 let frame_number = grid_frame.get_frame_number();
+                let previous_frame_number = self
+                    .current_frame
+                    .as_ref()
+                    .map(|drawn_frame| drawn_frame.get_grid_frame().get_frame_number());
 
-                self.current_grid_frame = Some(grid_frame);
+                self.stats_hud.record_frame();
+                self.fps_overlay.record_frame(
+                    grid_frame.get_timing(),
+                    self.diagnostics.get_last_render_time(),
+                );
 
-                if frame_number % 10 == 0 {
-                    return Task::done(Message::AddCircle(Circle {
-                        x_pos: 10.0,
-                        y_pos: 10.0,
-                        radius: 10.0,
-                        velocity: (10.0, 0.0),
-                    }));
+                // The same tick is re-sent with a different interpolation when
+                // rendering outpaces the simulation, so only new ticks count.
+                let is_new_tick = previous_frame_number != Some(frame_number);
+                if is_new_tick {
+                    self.diagnostics.record_frame(&grid_frame);
+                    self.collision_sounds.play(&grid_frame);
+                    self.sparks.record_frame(&grid_frame);
+                    self.fade_outs.record_frame(
+                        self.current_frame.as_ref().map(DrawnFrame::get_grid_frame),
+                        &grid_frame,
+                    );
+
+                    match grid_frame.get_rejection_events() {
+                        [] => {}
+                        [rejection] => self
+                            .status_toast
+                            .show(format!("Ignored a message: {}", rejection.problem)),
+                        [.., rejection] => self.status_toast.show(format!(
+                            "Ignored {} messages, the last because {}",
+                            grid_frame.get_rejection_events().len(),
+                            rejection.problem
+                        )),
+                    }
+
+                    if let Some(frame_server) = &self.frame_server {
+                        frame_server.broadcast(&grid_frame);
+                    }
+
+                    if let Some(http_api) = &self.http_api {
+                        http_api.record(&grid_frame, self.gravity, self.time_scale, self.paused);
+                    }
+
+                    if let Some(telemetry_sink) = self.telemetry_sink.as_mut() {
+                        telemetry_sink.record(&grid_frame);
+                    }
+
+                    self.run_script(&grid_frame);
+                }
+                let drawn_frame = self.drawn_frame(*grid_frame);
+
+                let gif_capture_finished = self
+                    .gif_capture
+                    .as_mut()
+                    .is_some_and(|gif_capture| gif_capture.capture(&drawn_frame));
+                if let Some(video_capture) = self.video_capture.as_mut() {
+                    video_capture.capture(&drawn_frame);
+                }
+
+                // Each grid gets half of the window while comparing, so a
+                // world that follows the window has to be resized when that
+                // starts or stops.
+                let comparison_changed =
+                    drawn_frame.get_comparison().is_some() != self.is_comparing();
+
+                // Rewinding can take the selected body away.
+                if let Some(editor) = self.editor.as_mut() {
+                    if editor.get_inspector().is_some_and(|inspector| {
+                        drawn_frame
+                            .get_grid_frame()
+                            .get_body_properties(inspector.get_id())
+                            .is_none()
+                    }) {
+                        editor.select_body(None);
+                    }
+                }
+
+                self.current_frame = Some(drawn_frame);
+                self.paint_with_brush();
+
+                if comparison_changed && self.window_resize == WindowResize::ResizeWorld {
+                    self.resize_world();
+                }
+
+                if gif_capture_finished {
+                    return self.finish_gif_capture();
                 }
             }
             Message::SetGridMessageSender(grid_message_sender) => {
                 self.grid_message_sender = Some(grid_message_sender);
+                if self.compare_config_path.is_some() {
+                    return Task::done(Message::ToggleComparison);
+                }
             }
-            Message::AddCircle(circle) => {
-                if let Some(grid_message_sender) = self.grid_message_sender.as_mut() {
-                    if grid_message_sender
-                        .try_send(GridMessage::AddCircle(circle))
-                        .is_err()
-                    {
-                        println!("Failed to send AddCircle message to grid_message_sender.");
+            Message::SetReplaySpeedSender(replay_speed_sender) => {
+                self.replay_speed_sender = Some(replay_speed_sender);
+            }
+            Message::ChangeReplaySpeed(factor) => {
+                if let Some(replay_speed_sender) = self.replay_speed_sender.as_mut() {
+                    self.replay_speed =
+                        (self.replay_speed * factor).clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED);
+                    if replay_speed_sender.try_send(self.replay_speed).is_err() {
+                        warn!("Failed to change replay speed.");
+                    } else {
+                        info!("Replay speed: {}x", self.replay_speed);
                     }
-                } else {
-                    println!("No grid_message_sender to send AddCircle message to.")
                 }
             }
+            Message::AddCircle(circle) => {
+                self.send_grid_message(GridMessage::AddCircle(circle, None));
+            }
             Message::ResizeWindow(size) => {
-                if let Some(grid_message_sender) = self.grid_message_sender.as_mut() {
-                    if grid_message_sender
-                        .try_send(GridMessage::Resize(size))
-                        .is_err()
-                    {
-                        println!("Failed to resize grid window.");
+                self.window_size = size;
+                // Otherwise the world keeps its size and the view fits it to
+                // the window when it's drawn.
+                if self.window_resize == WindowResize::ResizeWorld {
+                    self.resize_world();
+                }
+            }
+            Message::UpdateConfig(config) => {
+                let ConfigFile { simulation, app } = config;
+                let simulation = self.profile.apply(simulation);
+                telemetry::update_telemetry_sink(
+                    &mut self.telemetry_sink,
+                    app.telemetry_csv.as_deref(),
+                );
+                self.screenshot_dir = app.screenshot_dir.clone();
+                self.gravity = simulation.gravity;
+                self.time_scale = simulation.time_scale;
+                self.keybindings = app.keybindings.clone();
+                self.gravity_well_strength = simulation.gravity_well_strength;
+                self.gravity_well_radius = simulation.gravity_well_radius;
+                self.editor_grid_size = app.editor_grid_size;
+                if let Some(editor) = self.editor.as_mut() {
+                    editor.set_grid_size(app.editor_grid_size);
+                }
+                if app.palette != self.palette {
+                    self.set_palette(app.palette);
+                }
+                if app.reference_grid_spacing != self.reference_grid_spacing
+                    || app.show_reference_grid != self.show_reference_grid
+                {
+                    self.reference_grid_spacing = app.reference_grid_spacing;
+                    self.show_reference_grid = app.show_reference_grid;
+                    self.apply_reference_grid();
+                }
+                if app.color_overrides != self.color_overrides {
+                    self.color_overrides = app.color_overrides;
+                    self.update_current_frame(|drawn_frame| {
+                        drawn_frame.with_color_overrides(app.color_overrides)
+                    });
+                }
+                self.lod_circle_threshold = app.lod_circle_threshold;
+                self.circle_renderer = app.circle_renderer;
+                self.update_current_frame(|drawn_frame| {
+                    drawn_frame
+                        .with_lod_circle_threshold(app.lod_circle_threshold)
+                        .with_circle_renderer(app.circle_renderer)
+                        .with_window_resize(app.window_resize)
+                });
+                self.collision_sounds.update_config(&app);
+                self.sparks.update_config(&app);
+                self.brush.update_config(&simulation, &app);
+                let resize_world = app.window_resize == WindowResize::ResizeWorld
+                    && self.window_resize != WindowResize::ResizeWorld;
+                self.window_resize = app.window_resize;
+
+                self.send_grid_message(GridMessage::UpdateConfig(simulation));
+                // The config's gravity replaces the tilted one.
+                if self.gravity_tilt != (0.0, 0.0) {
+                    self.send_grid_message(GridMessage::SetGravity(self.tilted(self.gravity)));
+                }
+
+                // Catch the world up with any resizes it missed while it kept
+                // its own size.
+                if resize_world {
+                    self.resize_world();
+                }
+            }
+            #[cfg(all(feature = "http_api", not(target_arch = "wasm32")))]
+            Message::RemoteCommand(command) => {
+                return Task::done(match command {
+                    RemoteCommand::SpawnCircle(circle) => Message::AddCircle(circle),
+                    RemoteCommand::SetGravity(gravity) => Message::SetGravity(gravity),
+                    RemoteCommand::SetPaused(paused) => Message::SetPaused(paused),
+                });
+            }
+            Message::TiltGravity((x, y)) => {
+                return Task::done(Message::SetGravity((
+                    self.gravity.0 + x,
+                    self.gravity.1 + y,
+                )));
+            }
+            Message::SetGravity(gravity) => {
+                if self.send_grid_message(GridMessage::SetGravity(self.tilted(gravity))) {
+                    self.gravity = gravity;
+                }
+            }
+            #[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+            Message::SetGravityTilt(tilt) => {
+                self.gravity_tilt = tilt;
+                self.send_grid_message(GridMessage::SetGravity(self.tilted(self.gravity)));
+            }
+            Message::ChangeTimeScale(factor) => {
+                return Task::done(Message::SetTimeScale(self.time_scale * factor));
+            }
+            Message::SetTimeScale(time_scale) => {
+                let time_scale = time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+                if self.send_grid_message(GridMessage::SetTimeScale(time_scale)) {
+                    self.time_scale = time_scale;
+                }
+            }
+            Message::SelectProfile(profile) => {
+                self.profile = profile;
+                info!("Switched to the {profile} physics profile.");
+                // The config file's values are the starting point for every
+                // profile, so that switching between them doesn't stack.
+                return Task::done(Message::UpdateConfig(ConfigFile::load_or_default(
+                    &self.config_path,
+                )));
+            }
+            Message::KeyPressed(key) => {
+                if let Some(action) = self.keybindings.action_for(&key) {
+                    return Task::done(action_message(action));
+                }
+            }
+            Message::CursorMoved(position) => {
+                self.cursor_position = Some(position);
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                if let Some(editor) = self.editor.as_mut() {
+                    editor.set_free_placement(modifiers.alt());
+                }
+            }
+            Message::Clicked => {
+                let Some(position) = self.cursor_world_position() else {
+                    return Task::none();
+                };
+
+                if let Some(editor) = self.editor.as_mut() {
+                    if editor.get_tool() == Tool::Select {
+                        return self.pick_bodies(position);
                     }
+                    editor.start_drag(position);
+                } else if self.modifiers.shift() {
+                    // Shift-clicking drops a gravity well.
+                    self.send_grid_message(GridMessage::AddGravityWell(GravityWell::new(
+                        position.x,
+                        position.y,
+                        self.gravity_well_strength,
+                        self.gravity_well_radius,
+                    )));
+                } else if self.brush.press() {
+                    self.paint_with_brush();
+                }
+            }
+            Message::MouseReleased => {
+                self.brush.release();
+                let position = self.cursor_world_position();
+                let shape = self
+                    .editor
+                    .as_mut()
+                    .and_then(|editor| editor.finish_drag(position?, self.gravity_well_strength));
+                if let Some(shape) = shape {
+                    self.send_grid_message(shape);
+                }
+            }
+            Message::Explode => {
+                let position = self.cursor_world_position().unwrap_or_else(|| {
+                    let world_size = self.world_size();
+                    Point::new(world_size.width / 2.0, world_size.height / 2.0)
+                });
+                self.send_grid_message(GridMessage::Explode {
+                    x: position.x,
+                    y: position.y,
+                    strength: EXPLOSION_STRENGTH,
+                    radius: EXPLOSION_RADIUS,
+                });
+            }
+            Message::TogglePause => {
+                return Task::done(Message::SetPaused(!self.paused));
+            }
+            Message::SetPaused(paused) => {
+                // Everything stays frozen while editing.
+                if self.editor.is_some() || paused == self.paused {
+                    return Task::none();
+                }
+
+                if self.send_grid_message(GridMessage::SetPaused(paused)) {
+                    self.paused = paused;
+                    info!("{}", if paused { "Paused." } else { "Resumed." });
                 }
             }
+            Message::Step => {
+                if self.paused && self.editor.is_none() {
+                    self.send_grid_message(GridMessage::Step);
+                }
+            }
+            Message::StepBack => {
+                if self.editor.is_some() {
+                    return Task::none();
+                }
+                // The grid pauses itself to show where it was rewound to.
+                if self.send_grid_message(GridMessage::StepBack) {
+                    self.paused = true;
+                }
+            }
+            Message::Clear => {
+                self.send_grid_message(GridMessage::ClearDynamic);
+            }
+            Message::ClearStatic => {
+                self.send_grid_message(GridMessage::ClearStatic);
+            }
+            Message::ResetAll => {
+                self.send_grid_message(GridMessage::ResetAll);
+            }
+            Message::SaveScene(path) => {
+                let path = path.as_ref().unwrap_or(&self.scene_path);
+                if let Some(current_frame) = &self.current_frame {
+                    match current_frame.get_grid_frame().get_scene().save(path) {
+                        Ok(()) => info!("Saved scene to {}.", path.display()),
+                        Err(err) => error!("Failed to save scene: {err}"),
+                    }
+                }
+            }
+            Message::LoadScene(path) => {
+                let path = path.unwrap_or_else(|| self.scene_path.clone());
+                match Scene::load(&path) {
+                    Ok(scene) => {
+                        if self.send_grid_message(GridMessage::LoadScene(scene)) {
+                            info!("Loaded scene from {}.", path.display());
+                        }
+                    }
+                    Err(err) => error!("Failed to load scene: {err}"),
+                }
+            }
+            Message::SpawnBurst => {
+                let position = self.cursor_world_position().unwrap_or_else(|| {
+                    let world_size = self.world_size();
+                    Point::new(world_size.width / 2.0, world_size.height / 2.0)
+                });
+                self.send_grid_message(GridMessage::SpawnBurst {
+                    x: position.x,
+                    y: position.y,
+                });
+            }
+            Message::ToggleBrush => {
+                self.brush.toggle();
+                self.status_toast.show(
+                    if self.brush.is_enabled() {
+                        "Brush on: hold the left button to paint, and scroll to resize"
+                    } else {
+                        "Brush off"
+                    }
+                    .to_string(),
+                );
+            }
+            Message::ScaleBrushRadius(steps) => self.brush.scale_radius(steps),
+            Message::NextPreset => {
+                if let Some(current_frame) = &self.current_frame {
+                    let size = current_frame.get_grid_frame().get_size();
+                    let preset = self.preset.next();
+                    if self.send_grid_message(GridMessage::LoadScene(
+                        preset.build(size.width, size.height),
+                    )) {
+                        self.preset = preset;
+                        info!("Loaded the {preset} preset.");
+                    }
+                }
+            }
+            Message::ToggleDiagnostics => {
+                self.show_diagnostics = !self.show_diagnostics;
+            }
+            Message::ToggleStatsHud => {
+                self.show_stats_hud = !self.show_stats_hud;
+            }
+            Message::ToggleFpsOverlay => {
+                self.show_fps_overlay = !self.show_fps_overlay;
+            }
+            Message::ToggleComparison => {
+                let Some(compare_config_path) = self.compare_config_path.clone() else {
+                    warn!("There's no config to compare against. Pass one with --compare.");
+                    return Task::none();
+                };
+
+                let comparing = self.is_comparing();
+                let config = (!comparing)
+                    .then(|| ConfigFile::load_or_default(&compare_config_path).simulation);
+                if self.send_grid_message(GridMessage::SetComparisonConfig(config)) {
+                    if comparing {
+                        info!("Stopped comparing.");
+                    } else {
+                        info!("Comparing against {}.", compare_config_path.display());
+                    }
+                }
+            }
+            Message::UpdateComparisonConfig(config) => {
+                if self.is_comparing() {
+                    self.send_grid_message(GridMessage::SetComparisonConfig(Some(config)));
+                }
+            }
+            Message::ToggleEditor => {
+                if let Some(editor) = self.editor.take() {
+                    let paused = editor.was_paused();
+                    if self.send_grid_message(GridMessage::SetPaused(paused)) {
+                        self.paused = paused;
+                    }
+                    info!("Left edit mode.");
+                } else if self.send_grid_message(GridMessage::SetPaused(true)) {
+                    let mut editor = Editor::new(self.paused, self.editor_grid_size);
+                    editor.set_free_placement(self.modifiers.alt());
+                    self.editor = Some(editor);
+                    self.paused = true;
+                    info!("Entered edit mode.");
+                }
+            }
+            Message::SelectTool(tool) => {
+                if let Some(editor) = self.editor.as_mut() {
+                    editor.select_tool(tool);
+                }
+            }
+            // `App` opens the window before the message gets here.
+            Message::OpenWindow => {}
+            Message::BodiesPicked(ids) => {
+                if let (Some(editor), Some(current_frame)) =
+                    (self.editor.as_mut(), &self.current_frame)
+                {
+                    // Bodies that can't be edited are looked through.
+                    editor.select_body(ids.into_iter().find_map(|id| {
+                        current_frame
+                            .get_grid_frame()
+                            .get_body_properties(id)
+                            .map(|properties| (id, properties))
+                    }));
+                }
+            }
+            Message::EditProperty(field, input) => {
+                let update = self
+                    .editor
+                    .as_mut()
+                    .and_then(Editor::get_inspector_mut)
+                    .and_then(|inspector| inspector.edit(field, input));
+                if let Some(update) = update {
+                    self.send_grid_message(update);
+                }
+            }
+            Message::ToggleHeatMap => {
+                self.color_mode = self.color_mode.toggled();
+                let color_mode = self.color_mode;
+                self.update_current_frame(|drawn_frame| drawn_frame.with_color_mode(color_mode));
+            }
+            // `App` switches the theme before the message gets here.
+            Message::ToggleTheme => {}
+            Message::SetTheme(theme) => self.theme = theme,
+            Message::ToggleReferenceGrid => {
+                self.show_reference_grid = !self.show_reference_grid;
+                self.apply_reference_grid();
+            }
+            Message::NextPalette => {
+                self.set_palette(self.palette.next());
+                info!("Switched to the {} palette.", self.palette);
+            }
+            Message::ToggleGifCapture => {
+                if self.gif_capture.is_some() {
+                    return self.finish_gif_capture();
+                }
+
+                let start_frame = self.current_frame.as_ref().map_or(0, |drawn_frame| {
+                    drawn_frame.get_grid_frame().get_frame_number()
+                });
+                let path = PathBuf::from(export::timestamped_file_name("capture", "gif"));
+                info!("Started GIF capture to {}.", path.display());
+                self.gif_capture = Some(GifCapture::new(
+                    path,
+                    start_frame,
+                    None,
+                    physics::TICKS_PER_SECOND,
+                ));
+            }
+            Message::ToggleVideoCapture => {
+                if let Some(video_capture) = self.video_capture.take() {
+                    info!("Finishing video {}...", video_capture.path().display());
+                    return Task::perform(
+                        export::run_in_background(move || video_capture.finish()),
+                        Message::ExportFinished,
+                    );
+                }
+
+                let Some(drawn_frame) = &self.current_frame else {
+                    return Task::none();
+                };
+                let path = PathBuf::from(export::timestamped_file_name("capture", "mp4"));
+                match VideoCapture::start(
+                    path,
+                    drawn_frame,
+                    self.theme.to_theme(),
+                    physics::TICKS_PER_SECOND,
+                ) {
+                    Ok(video_capture) => {
+                        self.status_toast.show(format!(
+                            "Recording video to {}",
+                            video_capture.path().display()
+                        ));
+                        self.video_capture = Some(video_capture);
+                    }
+                    Err(err) => {
+                        error!("Failed to start recording video: {err}");
+                        self.status_toast
+                            .show(format!("Couldn't record video: {err}"));
+                    }
+                }
+            }
+            Message::ExportFinished(result) => match result {
+                Ok(path) => info!("Exported {}.", path.display()),
+                Err(err) => error!("Export failed: {err}"),
+            },
+            Message::Screenshot => {
+                let Some(drawn_frame) = self.current_frame.clone() else {
+                    return Task::none();
+                };
+                let theme = self.theme.to_theme();
+                let screenshot_dir = self.screenshot_dir.clone();
+                return Task::perform(
+                    export::run_in_background(move || {
+                        export::png::save_screenshot(&drawn_frame, &theme, &screenshot_dir)
+                    }),
+                    Message::ScreenshotSaved,
+                );
+            }
+            Message::ScreenshotSaved(result) => match result {
+                Ok(path) => {
+                    info!("Saved a screenshot to {}.", path.display());
+                    self.status_toast
+                        .show(format!("Saved a screenshot to {}", path.display()));
+                }
+                Err(err) => {
+                    error!("Failed to save a screenshot: {err}");
+                    self.status_toast
+                        .show(format!("Couldn't save a screenshot: {err}"));
+                }
+            },
         }
 
         Task::none()
     }
 
-    fn view(&self) -> Element<Message> {
-        if let Some(current_grid_frame) = &self.current_grid_frame {
-            current_grid_frame.view()
+    // Returns whether the message was sent.
+    fn send_grid_message(&mut self, message: GridMessage) -> bool {
+        let Some(grid_message_sender) = &self.grid_message_sender else {
+            debug!("No grid to send the message to yet.");
+            return false;
+        };
+
+        if let Err(err) = grid_message_sender.send(message) {
+            warn!("Dropped a message to the grid: {err}");
+            false
         } else {
-            iced::widget::Space::new(Length::Fill, Length::Fill).into()
+            self.diagnostics.record_input_sent();
+            true
         }
     }
 
-    fn subscription(&self) -> Subscription<Message> {
-        let mut subscriptions = Vec::new();
+    // Asks the grid which bodies are at `position`, for the select tool.
+    fn pick_bodies(&mut self, position: Point) -> Task<Message> {
+        let (result_sender, result_receiver) = oneshot::channel();
+        if !self.send_grid_message(GridMessage::Query(Query::Point(position), result_sender)) {
+            return Task::none();
+        }
 
-        subscriptions.push(iced::Subscription::run_with_id(
-            std::any::TypeId::of::<GridFrame>(),
-            // We're wrapping `stream` in a `stream!` macro to make it lazy (meaning `stream` isn't
-            // created unless the outer `stream!` is actually used). This is necessary because the
-            // outer `stream!` is created on every update, but will only be polled if the subscription
-            // ID is new.
-            async_stream::stream! {
-                let (mut grid_message_sender, grid_frame_stream) =
-                    physics::new_throttled_grid_frame_stream(APP_WIDTH, APP_HEIGHT, TARGET_FPS);
+        Task::perform(result_receiver, |result| match result {
+            Ok(QueryResult::Entities(ids)) => Message::BodiesPicked(ids),
+            // The grid went away before answering.
+            Ok(QueryResult::Hit(_)) | Err(_) => Message::BodiesPicked(Vec::new()),
+        })
+    }
 
-                let square_size = 200.0;
-                for message in create_rounded_rectangle(APP_WIDTH / 2.0 - square_size / 2.0, APP_HEIGHT / 2.0 - square_size / 2.0, square_size, square_size, 20.0) {
-                    grid_message_sender.try_send(message).unwrap();
-                }
+    fn reference_grid(&self) -> Option<f32> {
+        self.show_reference_grid
+            .then_some(self.reference_grid_spacing)
+    }
 
-                yield Message::SetGridMessageSender(grid_message_sender);
+    fn apply_reference_grid(&mut self) {
+        let reference_grid = self.reference_grid();
+        self.update_current_frame(|drawn_frame| drawn_frame.with_reference_grid(reference_grid));
+    }
 
-                let mut grid_frame_stream = Box::pin(grid_frame_stream);
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.diagnostics.set_palette(palette);
+        self.fps_overlay.set_palette(palette);
+        self.update_current_frame(|drawn_frame| drawn_frame.with_palette(palette));
+    }
+
+    // The frame with everything the app draws along with it, drawn the way
+    // it's been set to.
+    fn drawn_frame(&self, grid_frame: GridFrame) -> DrawnFrame {
+        DrawnFrame::new(grid_frame)
+            .with_color_mode(self.color_mode)
+            .with_palette(self.palette)
+            .with_color_overrides(self.color_overrides)
+            .with_reference_grid(self.reference_grid())
+            .with_window_resize(self.window_resize)
+            .with_lod_circle_threshold(self.lod_circle_threshold)
+            .with_circle_renderer(self.circle_renderer)
+            .with_sparks(self.sparks.get_sparks().to_vec())
+            .with_fading_circles(self.fade_outs.get_fading_circles().to_vec())
+    }
+
+    // Changes to how frames are drawn are applied to the current frame too,
+    // so that they show while paused.
+    fn update_current_frame(&mut self, update: impl FnOnce(DrawnFrame) -> DrawnFrame) {
+        self.current_frame = self.current_frame.take().map(update);
+    }
+
+    fn resize_world(&mut self) {
+        self.send_grid_message(GridMessage::Resize(self.pane_size()));
+    }
+
+    fn is_comparing(&self) -> bool {
+        self.current_frame
+            .as_ref()
+            .is_some_and(|drawn_frame| drawn_frame.get_comparison().is_some())
+    }
+
+    // The size of the part of the window each grid is drawn in. While
+    // comparing, the window is split down the middle.
+    fn pane_size(&self) -> Size {
+        if self.is_comparing() {
+            Size::new(self.window_size.width / 2.0, self.window_size.height)
+        } else {
+            self.window_size
+        }
+    }
+
+    fn world_size(&self) -> Size {
+        self.current_frame
+            .as_ref()
+            .map_or(Size::new(APP_WIDTH, APP_HEIGHT), |drawn_frame| {
+                drawn_frame.get_grid_frame().get_size()
+            })
+    }
+
+    // How the world is fitted to the window when it's drawn. While
+    // comparing, this is for the pane the cursor is over, since the same
+    // world is drawn in both.
+    fn view_transform(&self) -> View {
+        let pane_size = self.pane_size();
+        let mut view = View::new(self.window_resize, self.world_size(), pane_size);
+        if self
+            .cursor_position
+            .is_some_and(|position| position.x >= pane_size.width)
+        {
+            view.offset.x += pane_size.width;
+        }
+        view
+    }
+
+    fn tilted(&self, gravity: (f32, f32)) -> (f32, f32) {
+        (
+            gravity.0 + self.gravity_tilt.0,
+            gravity.1 + self.gravity_tilt.1,
+        )
+    }
 
-                while let Some(msg) = grid_frame_stream.next().await {
-                    yield Message::SetGridFrame(msg);
+    // Adds a circle at the cursor if the brush is painting and one is due.
+    // Nothing is painted while editing.
+    fn paint_with_brush(&mut self) {
+        if self.editor.is_some() {
+            return;
+        }
+        let Some(position) = self.cursor_world_position() else {
+            return;
+        };
+        if let Some(circle) = self.brush.paint(
+            position,
+            self.current_frame.as_ref().map(DrawnFrame::get_grid_frame),
+        ) {
+            self.send_grid_message(GridMessage::Spawn(circle));
+        }
+    }
+
+    // Where the cursor is in the world.
+    fn cursor_world_position(&self) -> Option<Point> {
+        let view = self.view_transform();
+        self.cursor_position.map(|position| view.to_world(position))
+    }
+
+    fn run_script(&mut self, grid_frame: &GridFrame) {
+        let Some(script) = self.script.as_mut() else {
+            return;
+        };
+
+        match script.run_frame(grid_frame) {
+            Ok(messages) => {
+                for message in messages {
+                    if let GridMessage::SetGravity(gravity) = message {
+                        self.gravity = gravity;
+                    }
+                    self.send_grid_message(message);
                 }
-            },
-        ));
+            }
+            // A script that failed once would most likely fail on every
+            // frame, so it's stopped rather than reporting it over and over.
+            Err(err) => {
+                error!("Script error, stopping the script: {err}");
+                self.script = None;
+            }
+        }
+    }
 
-        subscriptions
-            .push(iced::window::resize_events().map(|(_, size)| Message::ResizeWindow(size)));
+    fn finish_gif_capture(&mut self) -> Task<Message> {
+        let Some(gif_capture) = self.gif_capture.take() else {
+            return Task::none();
+        };
 
-        iced::Subscription::batch(subscriptions)
+        info!("Encoding GIF to {}...", gif_capture.path().display());
+
+        let theme = self.theme.to_theme();
+        Task::perform(
+            export::run_in_background(move || gif_capture.encode(&theme)),
+            Message::ExportFinished,
+        )
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let grid: Element<Message> = if let Some(current_frame) = &self.current_frame {
+            let theme = self.theme.to_theme();
+            let canvas = instanced::with_circles(
+                Canvas::new(self.diagnostics.timed(current_frame))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into(),
+                current_frame,
+                &theme,
+                0,
+            );
+            match (current_frame.get_comparison(), &self.compare_config_path) {
+                (Some(comparison), Some(compare_config_path)) => row![
+                    labeled_pane(canvas, &self.config_path),
+                    labeled_pane(
+                        instanced::with_circles(
+                            Canvas::new(comparison)
+                                .width(Length::Fill)
+                                .height(Length::Fill)
+                                .into(),
+                            comparison,
+                            &theme,
+                            1,
+                        ),
+                        compare_config_path,
+                    ),
+                ]
+                .into(),
+                _ => canvas,
+            }
+        } else {
+            iced::widget::Space::new(Length::Fill, Length::Fill).into()
+        };
+
+        let mut layers = vec![grid];
+
+        if let Some(editor) = &self.editor {
+            layers.push(editor.preview(
+                self.cursor_world_position(),
+                self.world_size(),
+                self.view_transform(),
+            ));
+            layers.push(
+                container(editor.palette())
+                    .width(Length::Fill)
+                    .align_x(iced::alignment::Horizontal::Center)
+                    .padding(10)
+                    .into(),
+            );
+            if let Some(inspector) = editor.get_inspector() {
+                layers.push(
+                    container(inspector.view())
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .align_x(iced::alignment::Horizontal::Right)
+                        .align_y(iced::alignment::Vertical::Center)
+                        .padding(10)
+                        .into(),
+                );
+            }
+        }
+
+        if self.brush.is_enabled() && self.editor.is_none() {
+            layers.push(brush::preview(
+                self.brush.get_radius_range(),
+                self.view_transform(),
+            ));
+        }
+
+        if self.show_stats_hud {
+            layers.push(
+                container(
+                    self.stats_hud
+                        .view(self.current_frame.as_ref().map(DrawnFrame::get_grid_frame)),
+                )
+                .padding(10)
+                .into(),
+            );
+        }
+
+        // There's no grid to send a time scale or profile to while replaying.
+        if self.grid_message_sender.is_some() {
+            layers.push(
+                container(
+                    column![
+                        hud::profile_control(self.profile),
+                        hud::time_scale_control(self.time_scale),
+                    ]
+                    .spacing(6)
+                    .align_x(iced::alignment::Horizontal::Right),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(iced::alignment::Horizontal::Right)
+                .align_y(iced::alignment::Vertical::Bottom)
+                .padding(10)
+                .into(),
+            );
+        }
+
+        if let Some(status_toast) = self.status_toast.view() {
+            layers.push(
+                container(status_toast)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(iced::alignment::Horizontal::Center)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(10)
+                    .into(),
+            );
+        }
+
+        if self.show_fps_overlay {
+            layers.push(
+                container(self.fps_overlay.view())
+                    .width(Length::Fill)
+                    .align_x(iced::alignment::Horizontal::Right)
+                    .padding(10)
+                    .into(),
+            );
+        }
+
+        if self.show_diagnostics {
+            layers.push(
+                container(self.diagnostics.view())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(10)
+                    .into(),
+            );
+        }
+
+        Stack::with_children(layers).into()
+    }
+
+    // Runs the window's grid, or plays back the recording in it.
+    fn subscription(&self, window: window::Id) -> Subscription<Message> {
+        Subscription::batch([
+            self.grid_frame_subscription(window),
+            self.comparison_config_subscription(window),
+            self.remote_command_subscription(),
+        ])
+    }
+
+    // Edits to the compared config are picked up like edits to the main one.
+    fn comparison_config_subscription(&self, window: window::Id) -> Subscription<Message> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(compare_config_path) = self.compare_config_path.clone() {
+            return iced::Subscription::run_with_id(
+                (window, compare_config_path.clone()),
+                config_file::watch_config_file(compare_config_path)
+                    .map(|config| Message::UpdateComparisonConfig(config.simulation)),
+            );
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        let _ = window;
+
+        Subscription::none()
+    }
+
+    fn remote_command_subscription(&self) -> Subscription<Message> {
+        #[cfg(all(feature = "http_api", not(target_arch = "wasm32")))]
+        if let Some(http_api) = &self.http_api {
+            return http_api.subscription().map(Message::RemoteCommand);
+        }
+
+        Subscription::none()
+    }
+
+    fn grid_frame_subscription(&self, window: window::Id) -> Subscription<Message> {
+        if let Some(address) = self.connect_address.clone() {
+            iced::Subscription::run_with_id(
+                window,
+                async_stream::stream! {
+                    let mut grid_frame_stream = Box::pin(network::connect(address));
+
+                    // Frames are only sent once per tick, so they're drawn
+                    // where the tick left everything.
+                    while let Some(grid_frame) = grid_frame_stream.next().await {
+                        yield Message::SetGridFrame(Box::new(grid_frame.with_interpolation(1.0)));
+                    }
+                },
+            )
+        } else if let Some(replay_path) = self.replay_path.clone() {
+            let replay_speed = self.replay_speed;
+
+            iced::Subscription::run_with_id(
+                window,
+                async_stream::stream! {
+                    let recording = match Recording::load(&replay_path) {
+                        Ok(recording) => recording,
+                        Err(err) => {
+                            error!("Failed to load recording {}: {err}", replay_path.display());
+                            return;
+                        }
+                    };
+
+                    let (replay_speed_sender, grid_frame_stream) =
+                        replay::new_replay_grid_frame_stream(recording, TARGET_FPS, replay_speed);
+
+                    yield Message::SetReplaySpeedSender(replay_speed_sender);
+
+                    let mut grid_frame_stream = Box::pin(grid_frame_stream);
+
+                    while let Some(msg) = grid_frame_stream.next().await {
+                        yield Message::SetGridFrame(Box::new(msg));
+                    }
+                },
+            )
+        } else {
+            let config_path = self.config_path.clone();
+            let record_path = self.record_path.clone();
+            let preset = self.preset;
+            let stress = self.stress;
+
+            iced::Subscription::run_with_id(
+                window,
+                // We're wrapping `stream` in a `stream!` macro to make it lazy (meaning `stream` isn't
+                // created unless the outer `stream!` is actually used). This is necessary because the
+                // outer `stream!` is created on every update, but will only be polled if the subscription
+                // ID is new.
+                async_stream::stream! {
+                    let config = ConfigFile::load_or_default(&config_path).simulation;
+                    let (world_size, scene) = initial_scene(preset, stress);
+
+                    let recorder = record_path.and_then(|record_path| {
+                        let header = RecordingHeader { width: world_size.width, height: world_size.height, config: config.clone() };
+                        Recorder::create(&record_path, &header)
+                            .map_err(|err| error!("Failed to start recording to {}: {err}", record_path.display()))
+                            .ok()
+                    });
+
+                    let (grid_message_sender, grid_frame_stream) =
+                        physics::new_throttled_grid_frame_stream(GridBuilder::new(world_size.width, world_size.height).with_config(config), TARGET_FPS, recorder);
+
+                    // Sent rather than built into the grid, so that recordings
+                    // start with it.
+                    grid_message_sender
+                        .send(GridMessage::LoadScene(scene))
+                        .unwrap();
+
+                    yield Message::SetGridMessageSender(grid_message_sender);
+
+                    let mut grid_frame_stream = Box::pin(grid_frame_stream);
+
+                    while let Some(msg) = grid_frame_stream.next().await {
+                        yield Message::SetGridFrame(Box::new(msg));
+                    }
+                },
+            )
+        }
+    }
+}
+
+// The world and scene a simulation starts with: the stress test if one was
+// asked for, or else the preset.
+fn initial_scene(preset: Preset, stress: Option<usize>) -> (Size, Scene) {
+    match stress {
+        Some(circle_count) => {
+            let world_size = scene::stress::world_size(circle_count);
+            let scene = scene::stress::build(circle_count, world_size.width, world_size.height);
+            (world_size, scene)
+        }
+        None => (
+            Size::new(APP_WIDTH, APP_HEIGHT),
+            preset.build(APP_WIDTH, APP_HEIGHT),
+        ),
+    }
+}
+
+fn dump_frames(args: &Args, dump_dir: &std::path::Path) -> Result<(), String> {
+    // Recordings bring their own simulation config, but are drawn the way
+    // the config file says.
+    let config = ConfigFile::load_or_default(&args.config);
+    if let Some(replay_path) = &args.replay {
+        let mut recording = Recording::load(replay_path).map_err(|err| err.to_string())?;
+        let header = recording.header().clone();
+        let grid = GridBuilder::new(header.width, header.height)
+            .with_config(header.config.clone())
+            .build_detached();
+
+        export::png::dump_frames(
+            dump_dir,
+            args.frames,
+            grid,
+            |frame_number| recording.take_messages_for_frame(frame_number),
+            &config.app,
+        )
+    } else {
+        let (world_size, scene) = initial_scene(args.preset, args.stress);
+        let grid = GridBuilder::new(world_size.width, world_size.height)
+            .with_config(config.simulation)
+            .with_scene(scene)
+            .build_detached();
+
+        export::png::dump_frames(dump_dir, args.frames, grid, |_| Vec::new(), &config.app)
     }
 }
 
-fn create_rounded_rectangle(
-    x_pos: f32,
-    y_pos: f32,
-    width: f32,
-    height: f32,
-    border_radius: f32,
-) -> Vec<GridMessage> {
-    let mut messages = Vec::new();
-
-    // Horizontal rectangle in the middle
-    messages.push(GridMessage::AddStaticRectangle(StaticRectangle {
-        x_pos: x_pos + border_radius,
-        y_pos,
-        width: width - 2.0 * border_radius,
-        height,
-    }));
-
-    // Vertical rectangle in the middle
-    messages.push(GridMessage::AddStaticRectangle(StaticRectangle {
-        x_pos,
-        y_pos: y_pos + border_radius,
-        width,
-        height: height - 2.0 * border_radius,
-    }));
-
-    // Top-left corner
-    messages.push(GridMessage::AddStaticCircle(StaticCircle {
-        x_pos: x_pos + border_radius,
-        y_pos: y_pos + border_radius,
-        radius: border_radius,
-    }));
-
-    // Top-right corner
-    messages.push(GridMessage::AddStaticCircle(StaticCircle {
-        x_pos: x_pos + width - border_radius,
-        y_pos: y_pos + border_radius,
-        radius: border_radius,
-    }));
-
-    // Bottom-left corner
-    messages.push(GridMessage::AddStaticCircle(StaticCircle {
-        x_pos: x_pos + border_radius,
-        y_pos: y_pos + height - border_radius,
-        radius: border_radius,
-    }));
-
-    // Bottom-right corner
-    messages.push(GridMessage::AddStaticCircle(StaticCircle {
-        x_pos: x_pos + width - border_radius,
-        y_pos: y_pos + height - border_radius,
-        radius: border_radius,
-    }));
-
-    messages
+fn action_message(action: Action) -> Message {
+    match action {
+        Action::TogglePause => Message::TogglePause,
+        Action::Step => Message::Step,
+        Action::StepBack => Message::StepBack,
+        Action::Clear => Message::Clear,
+        Action::ClearStatic => Message::ClearStatic,
+        Action::ResetAll => Message::ResetAll,
+        Action::SaveScene => Message::SaveScene(None),
+        Action::LoadScene => Message::LoadScene(None),
+        Action::SpawnBurst => Message::SpawnBurst,
+        Action::ToggleBrush => Message::ToggleBrush,
+        Action::NextPreset => Message::NextPreset,
+        Action::ToggleDiagnostics => Message::ToggleDiagnostics,
+        Action::ToggleStatsHud => Message::ToggleStatsHud,
+        Action::ToggleFpsOverlay => Message::ToggleFpsOverlay,
+        Action::ToggleGifCapture => Message::ToggleGifCapture,
+        Action::ToggleVideoCapture => Message::ToggleVideoCapture,
+        Action::Screenshot => Message::Screenshot,
+        Action::ToggleHeatMap => Message::ToggleHeatMap,
+        Action::NextPalette => Message::NextPalette,
+        Action::ToggleTheme => Message::ToggleTheme,
+        Action::ToggleReferenceGrid => Message::ToggleReferenceGrid,
+        Action::ToggleEditor => Message::ToggleEditor,
+        Action::OpenWindow => Message::OpenWindow,
+        Action::ToggleComparison => Message::ToggleComparison,
+        Action::Explode => Message::Explode,
+        Action::SlowDownReplay => Message::ChangeReplaySpeed(0.5),
+        Action::SpeedUpReplay => Message::ChangeReplaySpeed(2.0),
+        Action::TiltGravityLeft => Message::TiltGravity((-GRAVITY_TILT_STEP, 0.0)),
+        Action::TiltGravityRight => Message::TiltGravity((GRAVITY_TILT_STEP, 0.0)),
+        Action::TiltGravityUp => Message::TiltGravity((0.0, -GRAVITY_TILT_STEP)),
+        Action::TiltGravityDown => Message::TiltGravity((0.0, GRAVITY_TILT_STEP)),
+        Action::SlowDownTime => Message::ChangeTimeScale(1.0 / TIME_SCALE_STEP),
+        Action::SpeedUpTime => Message::ChangeTimeScale(TIME_SCALE_STEP),
+    }
+}
+
+// One side of the split screen, labeled with the config it's simulated with.
+fn labeled_pane<'a>(
+    grid: Element<'a, Message>,
+    config_path: &std::path::Path,
+) -> Element<'a, Message> {
+    let label = config_path
+        .file_name()
+        .unwrap_or(config_path.as_os_str())
+        .to_string_lossy()
+        .into_owned();
+
+    Stack::with_children([
+        grid,
+        container(hud::label(label))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(iced::alignment::Horizontal::Center)
+            .align_y(iced::alignment::Vertical::Bottom)
+            .padding(10)
+            .into(),
+    ])
+    .into()
 }
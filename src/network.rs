@@ -0,0 +1,199 @@
+// Streaming frames to remote viewers over TCP. Each frame is sent as its
+// length in bytes, as a little-endian `u32`, followed by the frame serialized
+// with bincode. Sockets aren't available on wasm32, so there the server can't
+// be started and viewers can't connect.
+#[cfg(not(target_arch = "wasm32"))]
+pub use enabled::{connect, FrameServer};
+
+#[cfg(target_arch = "wasm32")]
+pub use disabled::{connect, FrameServer};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod enabled {
+    use futures::{channel::mpsc, SinkExt, Stream, StreamExt};
+    use simulation::physics::GridFrame;
+    use tracing::{error, info, warn};
+
+    use std::io::{self, BufReader, BufWriter, Read, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    // Frames queued for a viewer that hasn't caught up yet. Any more are
+    // dropped for that viewer rather than slowing down the simulation.
+    const VIEWER_BACKLOG: usize = 4;
+    // Frames larger than this are treated as a corrupt stream.
+    const MAX_FRAME_LEN: u32 = 64 * 1024 * 1024;
+
+    // A serialized frame, shared between every viewer it's sent to.
+    type EncodedFrame = Arc<[u8]>;
+
+    // Accepts viewers in the background and sends them every frame it's
+    // given.
+    pub struct FrameServer {
+        address: SocketAddr,
+        viewers: Arc<Mutex<Vec<SyncSender<EncodedFrame>>>>,
+    }
+
+    impl FrameServer {
+        pub fn bind(address: &str) -> io::Result<Self> {
+            let listener = TcpListener::bind(address)?;
+            let address = listener.local_addr()?;
+            let viewers = Arc::new(Mutex::new(Vec::new()));
+
+            let accepted_viewers = Arc::clone(&viewers);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let (frame_sender, frame_receiver) = sync_channel(VIEWER_BACKLOG);
+                            accepted_viewers.lock().unwrap().push(frame_sender);
+                            thread::spawn(move || send_frames(stream, frame_receiver));
+                        }
+                        Err(err) => warn!("Failed to accept a viewer: {err}"),
+                    }
+                }
+            });
+
+            Ok(Self { address, viewers })
+        }
+
+        pub fn get_address(&self) -> SocketAddr {
+            self.address
+        }
+
+        pub fn broadcast(&self, grid_frame: &GridFrame) {
+            let mut viewers = self.viewers.lock().unwrap();
+            if viewers.is_empty() {
+                return;
+            }
+
+            let frame: EncodedFrame = match bincode::serialize(grid_frame) {
+                Ok(frame) => frame.into(),
+                Err(err) => {
+                    error!("Failed to serialize frame for viewers: {err}");
+                    return;
+                }
+            };
+
+            viewers.retain(|viewer| match viewer.try_send(Arc::clone(&frame)) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+        }
+    }
+
+    fn send_frames(stream: TcpStream, frame_receiver: Receiver<EncodedFrame>) {
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| "unknown".to_string(), |peer| peer.to_string());
+        info!("Viewer {peer} connected.");
+
+        // Frames are sent as soon as they're written rather than batched up.
+        if let Err(err) = stream.set_nodelay(true) {
+            warn!("Failed to disable Nagle's algorithm for viewer {peer}: {err}");
+        }
+        let mut writer = BufWriter::new(stream);
+
+        for frame in frame_receiver {
+            let result = writer
+                .write_all(&(frame.len() as u32).to_le_bytes())
+                .and_then(|()| writer.write_all(&frame))
+                .and_then(|()| writer.flush());
+            if let Err(err) = result {
+                info!("Viewer {peer} disconnected: {err}");
+                return;
+            }
+        }
+    }
+
+    // Connects to the frame server at `address` and yields the frames it
+    // sends until either side disconnects.
+    pub fn connect(address: String) -> impl Stream<Item = GridFrame> {
+        async_stream::stream! {
+            let (mut frame_sender, mut frame_receiver) = mpsc::channel(1);
+
+            // Reads block, so they're done on a thread of their own.
+            thread::spawn(move || {
+                let stream = match TcpStream::connect(&address) {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        error!("Failed to connect to {address}: {err}");
+                        return;
+                    }
+                };
+                info!("Connected to {address}.");
+
+                let mut reader = BufReader::new(stream);
+                loop {
+                    let frame = match receive_frame(&mut reader) {
+                        Ok(frame) => frame,
+                        Err(err) => {
+                            info!("Disconnected from {address}: {err}");
+                            return;
+                        }
+                    };
+                    if futures::executor::block_on(frame_sender.send(frame)).is_err() {
+                        return;
+                    }
+                }
+            });
+
+            while let Some(frame) = frame_receiver.next().await {
+                yield frame;
+            }
+        }
+    }
+
+    fn receive_frame(reader: &mut impl Read) -> io::Result<GridFrame> {
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len);
+        if len > MAX_FRAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("frame of {len} bytes is too large"),
+            ));
+        }
+
+        let mut frame = vec![0; len as usize];
+        reader.read_exact(&mut frame)?;
+        bincode::deserialize(&frame).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod disabled {
+    use futures::Stream;
+    use simulation::physics::GridFrame;
+    use tracing::error;
+
+    use std::io;
+    use std::net::SocketAddr;
+
+    // Never bound, so there's never one to broadcast to.
+    pub enum FrameServer {}
+
+    impl FrameServer {
+        pub fn bind(_address: &str) -> io::Result<Self> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "there are no sockets on wasm32",
+            ))
+        }
+
+        pub fn get_address(&self) -> SocketAddr {
+            match *self {}
+        }
+
+        pub fn broadcast(&self, _grid_frame: &GridFrame) {
+            match *self {}
+        }
+    }
+
+    pub fn connect(address: String) -> impl Stream<Item = GridFrame> {
+        error!("Can't connect to {address}: there are no sockets on wasm32.");
+        futures::stream::empty()
+    }
+}
@@ -0,0 +1,251 @@
+use iced::{Color, Theme};
+use serde::{Deserialize, Serialize};
+
+// Which colors everything is drawn in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Normal,
+    // Tells things apart by blue against orange and yellow, and by how light
+    // they are, rather than by red against green.
+    Deuteranopia,
+    // Circles in the theme's text color, and bright, saturated everything
+    // else, with opaque backgrounds.
+    HighContrast,
+}
+
+impl Palette {
+    pub fn next(self) -> Self {
+        match self {
+            Palette::Normal => Palette::Deuteranopia,
+            Palette::Deuteranopia => Palette::HighContrast,
+            Palette::HighContrast => Palette::Normal,
+        }
+    }
+
+    pub fn colors(self) -> &'static Colors {
+        match self {
+            Palette::Normal => &NORMAL,
+            Palette::Deuteranopia => &DEUTERANOPIA,
+            Palette::HighContrast => &HIGH_CONTRAST,
+        }
+    }
+}
+
+impl std::fmt::Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Palette::Normal => "normal",
+            Palette::Deuteranopia => "deuteranopia",
+            Palette::HighContrast => "high contrast",
+        })
+    }
+}
+
+// Whether the app is drawn light on dark or dark on light.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl ThemeKind {
+    pub fn toggled(self) -> Self {
+        match self {
+            ThemeKind::Dark => ThemeKind::Light,
+            ThemeKind::Light => ThemeKind::Dark,
+        }
+    }
+
+    pub fn to_theme(self) -> Theme {
+        match self {
+            ThemeKind::Dark => Theme::Dark,
+            ThemeKind::Light => Theme::Light,
+        }
+    }
+}
+
+// Colors that replace the theme's and palette's own, as RGB from 0 to 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorOverrides {
+    pub ball: Option<(f32, f32, f32)>,
+    // Static circles, rectangles and capsules.
+    pub static_body: Option<(f32, f32, f32)>,
+    // Behind the world.
+    pub background: Option<(f32, f32, f32)>,
+}
+
+// A color that's either fixed or taken from the theme, so that it stands out
+// against the background in both light and dark themes.
+#[derive(Debug, Clone, Copy)]
+pub enum Shade {
+    Fixed(Color),
+    Primary,
+    Text,
+    // The background blended a little towards the text.
+    Weak,
+    // The background blended a lot towards the text.
+    Strong,
+}
+
+impl Shade {
+    pub fn resolve(self, theme: &Theme) -> Color {
+        let palette = theme.extended_palette();
+        match self {
+            Shade::Fixed(color) => color,
+            Shade::Primary => palette.primary.base.color,
+            Shade::Text => palette.background.base.text,
+            Shade::Weak => palette.background.weak.color,
+            Shade::Strong => palette.background.strong.color,
+        }
+    }
+
+    // The override if there is one, or else the resolved shade.
+    pub fn resolve_or(self, theme: &Theme, color_override: Option<(f32, f32, f32)>) -> Color {
+        color_override.map_or_else(|| self.resolve(theme), |(r, g, b)| Color::from_rgb(r, g, b))
+    }
+}
+
+pub struct Colors {
+    pub ball: Shade,
+    pub positive_charge: Color,
+    pub negative_charge: Color,
+    // Static circles, rectangles and capsules.
+    pub static_body: Shade,
+    pub one_way_platform: Color,
+    pub conveyor: Color,
+    pub kinematic_body: Color,
+    pub portal_a: Color,
+    pub portal_b: Color,
+    pub sink: Color,
+    // Drawn over the circles, so submerged circles show through.
+    pub fluid_zone: Color,
+    pub gravity_well: Shade,
+    pub tether: Shade,
+    pub spring: Shade,
+    pub rope: Shade,
+    pub joint: Shade,
+    // Thrown off by hard collisions.
+    pub spark: Color,
+    // Fills whatever part of the window the world doesn't cover.
+    pub letterbox: Shade,
+    // Circles in the speed heat map, from still to fastest, blended between
+    // evenly spaced stops.
+    pub heat_map: [Color; 5],
+    // Behind the diagnostics and FPS overlays.
+    pub overlay_background: Color,
+    pub simulation_time: Color,
+    pub render_time: Color,
+    pub input_latency: Color,
+    // Frame times in the FPS overlay.
+    pub sparkline: Color,
+    // Drawn on `overlay_background`, which is dark whatever the theme.
+    pub target_line: Color,
+}
+
+impl Colors {
+    // `heat` is clamped to between 0 and 1.
+    pub fn heat_map_color(&self, heat: f32) -> Color {
+        let position = heat.clamp(0.0, 1.0) * (self.heat_map.len() - 1) as f32;
+        let index = (position as usize).min(self.heat_map.len() - 2);
+        let t = position - index as f32;
+        let (from, to) = (self.heat_map[index], self.heat_map[index + 1]);
+        Color::from_rgba(
+            from.r + (to.r - from.r) * t,
+            from.g + (to.g - from.g) * t,
+            from.b + (to.b - from.b) * t,
+            from.a + (to.a - from.a) * t,
+        )
+    }
+}
+
+const NORMAL: Colors = Colors {
+    ball: Shade::Primary,
+    positive_charge: Color::from_rgb(0.95, 0.25, 0.25),
+    negative_charge: Color::from_rgb(0.25, 0.5, 1.0),
+    static_body: Shade::Strong,
+    one_way_platform: Color::from_rgb(0.45, 0.45, 0.45),
+    conveyor: Color::from_rgb(0.35, 0.3, 0.2),
+    kinematic_body: Color::from_rgb(0.2, 0.35, 0.6),
+    portal_a: Color::from_rgb(0.1, 0.5, 1.0),
+    portal_b: Color::from_rgb(1.0, 0.45, 0.1),
+    sink: Color::from_rgb(0.1, 0.1, 0.15),
+    fluid_zone: Color::from_rgba(0.2, 0.45, 0.9, 0.3),
+    gravity_well: Shade::Fixed(Color::from_rgba(0.6, 0.4, 1.0, 0.6)),
+    tether: Shade::Fixed(Color::from_rgb(0.5, 0.5, 0.5)),
+    spring: Shade::Fixed(Color::from_rgb(0.7, 0.45, 0.1)),
+    rope: Shade::Fixed(Color::from_rgb(0.6, 0.5, 0.35)),
+    joint: Shade::Fixed(Color::from_rgb(0.75, 0.75, 0.75)),
+    spark: Color::from_rgb(1.0, 0.85, 0.4),
+    letterbox: Shade::Weak,
+    // Blue, cyan, green, yellow and red, so that nearby speeds are easy to
+    // tell apart.
+    heat_map: [
+        Color::from_rgb(0.0, 0.0, 1.0),
+        Color::from_rgb(0.0, 1.0, 1.0),
+        Color::from_rgb(0.0, 1.0, 0.0),
+        Color::from_rgb(1.0, 1.0, 0.0),
+        Color::from_rgb(1.0, 0.0, 0.0),
+    ],
+    overlay_background: Color::from_rgba(0.0, 0.0, 0.0, 0.6),
+    simulation_time: Color::from_rgb(0.3, 0.6, 1.0),
+    render_time: Color::from_rgb(0.4, 0.9, 0.4),
+    input_latency: Color::from_rgb(1.0, 0.4, 0.4),
+    sparkline: Color::from_rgb(0.4, 0.9, 0.4),
+    target_line: Color::from_rgba(1.0, 1.0, 1.0, 0.5),
+};
+
+// Mostly from the Okabe-Ito palette.
+const DEUTERANOPIA: Colors = Colors {
+    ball: Shade::Fixed(Color::from_rgb(0.9, 0.6, 0.0)),
+    positive_charge: Color::from_rgb(0.94, 0.89, 0.26),
+    negative_charge: Color::from_rgb(0.0, 0.45, 0.7),
+    conveyor: Color::from_rgb(0.4, 0.33, 0.15),
+    kinematic_body: Color::from_rgb(0.0, 0.35, 0.55),
+    portal_a: Color::from_rgb(0.34, 0.71, 0.91),
+    portal_b: Color::from_rgb(0.9, 0.6, 0.0),
+    fluid_zone: Color::from_rgba(0.34, 0.71, 0.91, 0.3),
+    gravity_well: Shade::Fixed(Color::from_rgba(0.8, 0.47, 0.65, 0.7)),
+    spring: Shade::Fixed(Color::from_rgb(0.9, 0.6, 0.0)),
+    // Dark blue to yellow, getting lighter all the way.
+    heat_map: [
+        Color::from_rgb(0.0, 0.13, 0.3),
+        Color::from_rgb(0.26, 0.3, 0.42),
+        Color::from_rgb(0.49, 0.49, 0.47),
+        Color::from_rgb(0.74, 0.69, 0.45),
+        Color::from_rgb(1.0, 0.91, 0.27),
+    ],
+    simulation_time: Color::from_rgb(0.34, 0.71, 0.91),
+    render_time: Color::from_rgb(0.9, 0.6, 0.0),
+    input_latency: Color::from_rgb(0.94, 0.89, 0.26),
+    sparkline: Color::from_rgb(0.9, 0.6, 0.0),
+    ..NORMAL
+};
+
+const HIGH_CONTRAST: Colors = Colors {
+    ball: Shade::Text,
+    positive_charge: Color::from_rgb(1.0, 0.3, 1.0),
+    negative_charge: Color::from_rgb(0.0, 1.0, 1.0),
+    one_way_platform: Color::from_rgb(0.55, 0.55, 0.55),
+    conveyor: Color::from_rgb(1.0, 0.6, 0.0),
+    kinematic_body: Color::from_rgb(0.3, 0.6, 1.0),
+    portal_a: Color::from_rgb(0.0, 1.0, 1.0),
+    portal_b: Color::from_rgb(1.0, 0.5, 0.0),
+    sink: Color::BLACK,
+    fluid_zone: Color::from_rgba(0.2, 0.6, 1.0, 0.4),
+    gravity_well: Shade::Text,
+    tether: Shade::Text,
+    spring: Shade::Text,
+    rope: Shade::Text,
+    joint: Shade::Text,
+    spark: Color::from_rgb(1.0, 0.5, 0.0),
+    overlay_background: Color::from_rgba(0.0, 0.0, 0.0, 0.9),
+    simulation_time: Color::from_rgb(0.0, 1.0, 1.0),
+    render_time: Color::from_rgb(1.0, 1.0, 0.0),
+    input_latency: Color::from_rgb(1.0, 0.3, 1.0),
+    sparkline: Color::from_rgb(1.0, 1.0, 0.0),
+    target_line: Color::WHITE,
+    ..NORMAL
+};
@@ -0,0 +1,328 @@
+use super::{Circle, EntityId, FillStyle, LifetimePolicy, Tether};
+use crate::forces::DragModel;
+
+// The grid's dynamic circles, stored as a struct of arrays. Everything that
+// is read or written for every circle on every subtick has an array of its
+// own, so that the force, integration and broadphase loops walk through
+// tightly packed memory, and the rest of each circle is kept to the side in
+// `details`. Circles are added and read back out as `Circle`s.
+#[derive(Debug, Clone, Default)]
+pub struct Bodies {
+    pub(super) positions: Vec<(f32, f32)>,
+    pub(super) velocities: Vec<(f32, f32)>,
+    pub(super) radii: Vec<f32>,
+    pub(super) densities: Vec<f32>,
+    pub(super) charges: Vec<f32>,
+    pub(super) drag_models: Vec<Option<DragModel>>,
+    pub(super) frictions: Vec<Option<f32>>,
+    pub(super) restitutions: Vec<Option<f32>>,
+    pub(super) sleeping: Vec<bool>,
+    pub(super) ids: Vec<EntityId>,
+    pub(super) details: Vec<BodyDetails>,
+}
+
+// The parts of a circle that are only looked at once per tick, if at all.
+#[derive(Debug, Clone)]
+pub(super) struct BodyDetails {
+    pub(super) lifetime: Option<LifetimePolicy>,
+    pub(super) age: u32,
+    pub(super) low_speed_frames: u32,
+    pub(super) average_velocity: (f32, f32),
+    pub(super) previous_position: (f32, f32),
+    pub(super) portal_cooldown: u32,
+    pub(super) spawner: Option<EntityId>,
+    pub(super) tether: Option<Tether>,
+    pub(super) color: Option<(f32, f32, f32)>,
+    pub(super) style: Option<FillStyle>,
+}
+
+// A copy of one circle's position, velocity, size and sleep state, for the
+// collision and constraint code that works on a circle or a pair of circles
+// at a time. `Bodies::set_body` writes it back.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Body {
+    pub(super) x_pos: f32,
+    pub(super) y_pos: f32,
+    pub(super) radius: f32,
+    pub(super) velocity: (f32, f32),
+    pub(super) density: f32,
+    pub(super) restitution: Option<f32>,
+    pub(super) sleeping: bool,
+    // Set by `wake`, so that the circle's count of slow frames is reset when
+    // the body is written back.
+    woken: bool,
+}
+
+impl Body {
+    pub(super) fn mass(&self) -> f32 {
+        self.density * self.radius * self.radius
+    }
+
+    pub(super) fn inverse_mass(&self) -> f32 {
+        1.0 / self.mass().max(f32::EPSILON)
+    }
+
+    pub(super) fn speed(&self) -> f32 {
+        (self.velocity.0.powi(2) + self.velocity.1.powi(2)).sqrt()
+    }
+
+    pub(super) fn wake(&mut self) {
+        self.sleeping = false;
+        self.woken = true;
+    }
+}
+
+impl Bodies {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn positions(&self) -> &[(f32, f32)] {
+        &self.positions
+    }
+
+    pub fn velocities(&self) -> &[(f32, f32)] {
+        &self.velocities
+    }
+
+    pub fn velocities_mut(&mut self) -> &mut [(f32, f32)] {
+        &mut self.velocities
+    }
+
+    // For forces that leave sleeping circles alone.
+    pub fn velocities_and_sleeping_mut(&mut self) -> (&mut [(f32, f32)], &[bool]) {
+        (&mut self.velocities, &self.sleeping)
+    }
+
+    pub fn radii(&self) -> &[f32] {
+        &self.radii
+    }
+
+    pub fn charges(&self) -> &[f32] {
+        &self.charges
+    }
+
+    pub fn drag_models(&self) -> &[Option<DragModel>] {
+        &self.drag_models
+    }
+
+    pub fn is_sleeping(&self, i: usize) -> bool {
+        self.sleeping[i]
+    }
+
+    pub fn density(&self, i: usize) -> f32 {
+        self.densities[i]
+    }
+
+    pub fn mass(&self, i: usize) -> f32 {
+        self.densities[i] * self.radii[i] * self.radii[i]
+    }
+
+    pub fn get(&self, i: usize) -> Circle {
+        let details = &self.details[i];
+        Circle {
+            x_pos: self.positions[i].0,
+            y_pos: self.positions[i].1,
+            radius: self.radii[i],
+            velocity: self.velocities[i],
+            density: self.densities[i],
+            charge: self.charges[i],
+            drag_model: self.drag_models[i],
+            friction: self.frictions[i],
+            restitution: self.restitutions[i],
+            color: details.color,
+            style: details.style,
+            lifetime: details.lifetime,
+            age: details.age,
+            low_speed_frames: details.low_speed_frames,
+            average_velocity: details.average_velocity,
+            sleeping: self.sleeping[i],
+            previous_position: details.previous_position,
+            portal_cooldown: details.portal_cooldown,
+            spawner: details.spawner,
+            tether: details.tether.clone(),
+            id: self.ids[i],
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Circle> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    pub(super) fn index_of(&self, id: EntityId) -> Option<usize> {
+        self.ids.iter().position(|&other| other == id)
+    }
+
+    pub(super) fn push(&mut self, circle: Circle) {
+        self.positions.push((circle.x_pos, circle.y_pos));
+        self.velocities.push(circle.velocity);
+        self.radii.push(circle.radius);
+        self.densities.push(circle.density);
+        self.charges.push(circle.charge);
+        self.drag_models.push(circle.drag_model);
+        self.frictions.push(circle.friction);
+        self.restitutions.push(circle.restitution);
+        self.sleeping.push(circle.sleeping);
+        self.ids.push(circle.id);
+        self.details.push(BodyDetails {
+            lifetime: circle.lifetime,
+            age: circle.age,
+            low_speed_frames: circle.low_speed_frames,
+            average_velocity: circle.average_velocity,
+            previous_position: circle.previous_position,
+            portal_cooldown: circle.portal_cooldown,
+            spawner: circle.spawner,
+            tether: circle.tether,
+            color: circle.color,
+            style: circle.style,
+        });
+    }
+
+    pub(super) fn set(&mut self, i: usize, circle: Circle) {
+        self.positions[i] = (circle.x_pos, circle.y_pos);
+        self.velocities[i] = circle.velocity;
+        self.radii[i] = circle.radius;
+        self.densities[i] = circle.density;
+        self.charges[i] = circle.charge;
+        self.drag_models[i] = circle.drag_model;
+        self.frictions[i] = circle.friction;
+        self.restitutions[i] = circle.restitution;
+        self.sleeping[i] = circle.sleeping;
+        self.ids[i] = circle.id;
+        self.details[i] = BodyDetails {
+            lifetime: circle.lifetime,
+            age: circle.age,
+            low_speed_frames: circle.low_speed_frames,
+            average_velocity: circle.average_velocity,
+            previous_position: circle.previous_position,
+            portal_cooldown: circle.portal_cooldown,
+            spawner: circle.spawner,
+            tether: circle.tether,
+            color: circle.color,
+            style: circle.style,
+        };
+    }
+
+    pub(super) fn remove(&mut self, i: usize) {
+        self.positions.remove(i);
+        self.velocities.remove(i);
+        self.radii.remove(i);
+        self.densities.remove(i);
+        self.charges.remove(i);
+        self.drag_models.remove(i);
+        self.frictions.remove(i);
+        self.restitutions.remove(i);
+        self.sleeping.remove(i);
+        self.ids.remove(i);
+        self.details.remove(i);
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.positions.clear();
+        self.velocities.clear();
+        self.radii.clear();
+        self.densities.clear();
+        self.charges.clear();
+        self.drag_models.clear();
+        self.frictions.clear();
+        self.restitutions.clear();
+        self.sleeping.clear();
+        self.ids.clear();
+        self.details.clear();
+    }
+
+    // Keeps only the circles that `keep` returns true for, in order.
+    pub(super) fn retain(&mut self, mut keep: impl FnMut(&Circle) -> bool) {
+        let kept: Vec<bool> = (0..self.len()).map(|i| keep(&self.get(i))).collect();
+        if kept.iter().all(|&kept| kept) {
+            return;
+        }
+
+        fn retain_kept<T>(values: &mut Vec<T>, kept: &[bool]) {
+            let mut kept = kept.iter();
+            values.retain(|_| *kept.next().unwrap_or(&true));
+        }
+        retain_kept(&mut self.positions, &kept);
+        retain_kept(&mut self.velocities, &kept);
+        retain_kept(&mut self.radii, &kept);
+        retain_kept(&mut self.densities, &kept);
+        retain_kept(&mut self.charges, &kept);
+        retain_kept(&mut self.drag_models, &kept);
+        retain_kept(&mut self.frictions, &kept);
+        retain_kept(&mut self.restitutions, &kept);
+        retain_kept(&mut self.sleeping, &kept);
+        retain_kept(&mut self.ids, &kept);
+        retain_kept(&mut self.details, &kept);
+    }
+
+    pub(super) fn body(&self, i: usize) -> Body {
+        Body {
+            x_pos: self.positions[i].0,
+            y_pos: self.positions[i].1,
+            radius: self.radii[i],
+            velocity: self.velocities[i],
+            density: self.densities[i],
+            restitution: self.restitutions[i],
+            sleeping: self.sleeping[i],
+            woken: false,
+        }
+    }
+
+    pub(super) fn set_body(&mut self, i: usize, body: Body) {
+        self.positions[i] = (body.x_pos, body.y_pos);
+        self.velocities[i] = body.velocity;
+        self.sleeping[i] = body.sleeping;
+        if body.woken {
+            self.details[i].low_speed_frames = 0;
+        }
+    }
+
+    pub(super) fn update_body<T>(&mut self, i: usize, update: impl FnOnce(&mut Body) -> T) -> T {
+        let mut body = self.body(i);
+        let result = update(&mut body);
+        self.set_body(i, body);
+        result
+    }
+
+    // Like `update_body`, for two different circles at once.
+    pub(super) fn update_pair<T>(
+        &mut self,
+        i: usize,
+        j: usize,
+        update: impl FnOnce(&mut Body, &mut Body) -> T,
+    ) -> T {
+        assert!(i != j);
+        let (mut body_a, mut body_b) = (self.body(i), self.body(j));
+        let result = update(&mut body_a, &mut body_b);
+        self.set_body(i, body_a);
+        self.set_body(j, body_b);
+        result
+    }
+
+    // Runs `update` on every circle in turn, with its id.
+    pub(super) fn update_all_bodies(&mut self, mut update: impl FnMut(EntityId, &mut Body)) {
+        for i in 0..self.len() {
+            let id = self.ids[i];
+            self.update_body(i, |body| update(id, body));
+        }
+    }
+
+    // Like `update_all_bodies`, skipping sleeping circles.
+    pub(super) fn update_awake_bodies(&mut self, mut update: impl FnMut(EntityId, &mut Body)) {
+        for i in 0..self.len() {
+            if !self.sleeping[i] {
+                let id = self.ids[i];
+                self.update_body(i, |body| update(id, body));
+            }
+        }
+    }
+
+    pub(super) fn wake(&mut self, i: usize) {
+        self.sleeping[i] = false;
+        self.details[i].low_speed_frames = 0;
+    }
+}
@@ -0,0 +1,111 @@
+use tracing::warn;
+
+use super::channel::{self, GridMessageSender, OverflowPolicy};
+use super::{validation, Boundaries, Grid};
+use crate::broadphase::BroadphaseKind;
+use crate::config::SimulationConfig;
+use crate::forces::ForceGenerator;
+use crate::integrator::IntegratorKind;
+use crate::scene::Scene;
+
+// Sets up a grid with everything it should start with, so that it doesn't
+// take a round of messages after it's created to get there. Apart from the
+// world size, everything starts out as the default; `with_config` replaces
+// the whole config, so it goes before the setters for parts of it.
+pub struct GridBuilder {
+    width: f32,
+    height: f32,
+    config: SimulationConfig,
+    scene: Option<Scene>,
+    force_generators: Vec<Box<dyn ForceGenerator>>,
+}
+
+impl GridBuilder {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            config: SimulationConfig::default(),
+            scene: None,
+            force_generators: Vec::new(),
+        }
+    }
+
+    pub fn with_config(mut self, config: SimulationConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    // The bodies the grid starts with. A scene that `GridMessage::LoadScene`
+    // would reject is left out, and the grid starts empty.
+    pub fn with_scene(mut self, scene: Scene) -> Self {
+        self.scene = Some(scene);
+        self
+    }
+
+    // The grid, and a sender for messages to apply on its following ticks.
+    pub fn build(self) -> (Grid, GridMessageSender) {
+        let (message_sender, message_receiver) = channel::grid_message_queue(
+            self.config.message_queue_capacity,
+            self.config.message_overflow_policy,
+        );
+        let mut grid = self.build_detached();
+        grid.message_receiver = Some(message_receiver);
+        (grid, message_sender)
+    }
+
+    // The grid without a channel to send it messages through, for code that
+    // drives it directly with `Grid::tick` or `Grid::step`.
+    pub fn build_detached(self) -> Grid {
+        let mut grid = Grid::new_detached(self.width, self.height, self.config);
+        for force_generator in self.force_generators {
+            grid.add_force_generator(force_generator);
+        }
+        if let Some(scene) = self.scene {
+            match validation::validate_scene(&scene) {
+                Ok(()) => grid.load_scene(scene),
+                Err(problem) => warn!("Ignoring an invalid initial scene: {problem}."),
+            }
+        }
+        grid
+    }
+}
+
+impl GridBuilder {
+    pub fn with_gravity(mut self, gravity: (f32, f32)) -> Self {
+        self.config.gravity = gravity;
+        self
+    }
+
+    pub fn with_boundaries(mut self, boundaries: Boundaries) -> Self {
+        self.config.boundaries = boundaries;
+        self
+    }
+
+    pub fn with_broadphase(mut self, broadphase: BroadphaseKind) -> Self {
+        self.config.broadphase = broadphase;
+        self
+    }
+
+    pub fn with_integrator(mut self, integrator: IntegratorKind) -> Self {
+        self.config.integrator = integrator;
+        self
+    }
+
+    pub fn with_force_generator(mut self, force_generator: Box<dyn ForceGenerator>) -> Self {
+        self.force_generators.push(force_generator);
+        self
+    }
+
+    // How many messages can be waiting for the next tick, and what happens
+    // to more. Only matters for grids built with `build`.
+    pub fn with_channel_capacity(mut self, channel_capacity: usize) -> Self {
+        self.config.message_queue_capacity = channel_capacity;
+        self
+    }
+
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.config.message_overflow_policy = overflow_policy;
+        self
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use super::GridMessage;
+
+// What happens to a message sent to a grid whose queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+    // Waits until the grid takes the waiting messages on its next tick. There
+    // are no threads to wait for on wasm32, so it's `DropNewest` there.
+    Block,
+    // Makes room by dropping the message that's been waiting longest.
+    DropOldest,
+    // Drops the message being sent.
+    #[default]
+    DropNewest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError {
+    // The queue was full, so the message was dropped.
+    Full,
+    // The grid is gone.
+    Disconnected,
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Full => write!(f, "the grid's message queue is full"),
+            SendError::Disconnected => write!(f, "the grid is gone"),
+        }
+    }
+}
+
+struct Queue {
+    messages: VecDeque<GridMessage>,
+    // How many of `messages` aren't control messages, which are all that
+    // count towards the capacity.
+    bulk_count: usize,
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+    dropped_count: u64,
+    closed: bool,
+}
+
+impl Queue {
+    fn is_full(&self) -> bool {
+        // A queue with no room at all would drop everything.
+        self.bulk_count >= self.capacity.max(1)
+    }
+
+    fn push(&mut self, message: GridMessage) {
+        if !message.is_control() {
+            self.bulk_count += 1;
+        }
+        self.messages.push_back(message);
+    }
+
+    fn blocks(&self) -> bool {
+        self.overflow_policy == OverflowPolicy::Block && cfg!(not(target_arch = "wasm32"))
+    }
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    // Signalled whenever the queue is emptied or closed, for blocked senders.
+    drained: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap()
+    }
+}
+
+// Queues messages for the grid to apply on its next tick. Unlike a plain
+// channel, it can be told what to do when the queue is full, and counts the
+// messages it drops.
+#[derive(Clone)]
+pub struct GridMessageSender {
+    shared: Arc<Shared>,
+}
+
+// The grid's end of the queue. Dropping it makes every send fail.
+pub(super) struct GridMessageReceiver {
+    shared: Arc<Shared>,
+}
+
+pub(super) fn grid_message_queue(
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+) -> (GridMessageSender, GridMessageReceiver) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(Queue {
+            messages: VecDeque::new(),
+            bulk_count: 0,
+            capacity,
+            overflow_policy,
+            dropped_count: 0,
+            closed: false,
+        }),
+        drained: Condvar::new(),
+    });
+    (
+        GridMessageSender {
+            shared: Arc::clone(&shared),
+        },
+        GridMessageReceiver { shared },
+    )
+}
+
+impl GridMessageSender {
+    // Queues the message, unless the queue is full and the overflow policy
+    // drops it. `DropOldest` always queues it, at the expense of another.
+    // Control messages, like pausing, are always queued straight away.
+    pub fn send(&self, message: GridMessage) -> Result<(), SendError> {
+        let mut queue = self.shared.lock();
+        let is_control = message.is_control();
+        while !queue.closed && !is_control && queue.is_full() && queue.blocks() {
+            queue = self.shared.drained.wait(queue).unwrap();
+        }
+
+        if queue.closed {
+            Err(SendError::Disconnected)
+        } else if is_control || !queue.is_full() {
+            queue.push(message);
+            Ok(())
+        } else if queue.overflow_policy == OverflowPolicy::DropOldest {
+            // A full queue has at least one message that isn't a control
+            // message.
+            if let Some(oldest) = queue
+                .messages
+                .iter()
+                .position(|message| !message.is_control())
+            {
+                queue.messages.remove(oldest);
+                queue.bulk_count -= 1;
+            }
+            queue.push(message);
+            queue.dropped_count += 1;
+            Ok(())
+        } else {
+            queue.dropped_count += 1;
+            Err(SendError::Full)
+        }
+    }
+}
+
+impl std::fmt::Debug for GridMessageSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GridMessageSender").finish_non_exhaustive()
+    }
+}
+
+impl GridMessageReceiver {
+    // Takes every waiting message, oldest first. Of any `Resize` messages,
+    // only the last is kept, since dragging a window sends far more of them
+    // than there are ticks, and only the size it ends up at matters.
+    pub(super) fn drain(&mut self) -> Vec<GridMessage> {
+        let mut queue = self.shared.lock();
+        queue.bulk_count = 0;
+        let mut messages: Vec<_> = queue.messages.drain(..).collect();
+        drop(queue);
+        self.shared.drained.notify_all();
+
+        let last_resize = messages
+            .iter()
+            .rposition(|message| matches!(message, GridMessage::Resize(_)));
+        let mut i = 0;
+        messages.retain(|message| {
+            let keep = !matches!(message, GridMessage::Resize(_)) || Some(i) == last_resize;
+            i += 1;
+            keep
+        });
+        messages
+    }
+
+    // Applies to messages sent from now on.
+    pub(super) fn configure(&mut self, capacity: usize, overflow_policy: OverflowPolicy) {
+        let mut queue = self.shared.lock();
+        queue.capacity = capacity;
+        queue.overflow_policy = overflow_policy;
+        drop(queue);
+        // Blocked senders may fit now, or no longer be meant to wait.
+        self.shared.drained.notify_all();
+    }
+
+    // How many messages have been dropped since the queue was made.
+    pub(super) fn get_dropped_count(&self) -> u64 {
+        self.shared.lock().dropped_count
+    }
+}
+
+impl Drop for GridMessageReceiver {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.drained.notify_all();
+    }
+}
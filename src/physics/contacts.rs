@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use super::{Bodies, Contact, EntityId, Restitution};
+
+// One pair of overlapping circles, solved for the rest of the subtick.
+#[derive(Clone)]
+struct ContactConstraint {
+    i: usize,
+    j: usize,
+    // Unit vector from `i`'s center towards `j`'s.
+    normal: (f32, f32),
+    // Inverse masses, zero for a circle that's asleep and stays that way.
+    inverse_mass_i: f32,
+    inverse_mass_j: f32,
+    // Speed at which the pair should be separating once solved, so that they
+    // bounce.
+    target_separating_speed: f32,
+    // Total impulse applied along the normal so far this subtick, including
+    // the warm start. It can only ever push the pair apart.
+    normal_impulse: f32,
+    // Friction between the pair, and the total impulse it has applied along
+    // the tangent, at right angles to the normal. Friction can't push harder
+    // than `friction` times `normal_impulse`.
+    friction: f32,
+    tangent_impulse: f32,
+    // Whether the pair was closing when the contact was found, which makes
+    // it an impact rather than a resting contact.
+    impact: bool,
+}
+
+// Solves the contacts between circles with sequential impulses: each pass
+// goes over every contact in turn and applies whatever impulse brings that
+// pair to its target separating speed, given what the other contacts have
+// done so far. The total impulse per contact is clamped rather than each
+// pass's, so a later pass can take back some of an earlier push, and the
+// passes converge on impulses that hold a whole stack up at once.
+//
+// Resting contacts need about the same impulse every subtick, so each
+// contact starts from the impulse it ended the last subtick with (scaled to
+// the subtick's length) instead of from zero. That's what lets a pile settle
+// in a few passes rather than needing enough of them to build its support up
+// from nothing every time.
+#[derive(Clone, Default)]
+pub(super) struct ContactSolver {
+    constraints: Vec<ContactConstraint>,
+    // Normal and tangent impulse per unit of time that each pair of circles,
+    // by id, ended the last subtick with.
+    warm_start_impulses: HashMap<(EntityId, EntityId), (f32, f32)>,
+}
+
+impl ContactSolver {
+    // Finds the overlapping pairs among `candidate_pairs` and applies their
+    // warm start impulses. Sleeping circles are woken by circles hitting them
+    // at `wake_speed_threshold` or faster, and are otherwise left in place
+    // like static ones. Circles without a friction coefficient of their own
+    // use `default_friction`.
+    pub(super) fn find_contacts(
+        &mut self,
+        bodies: &mut Bodies,
+        candidate_pairs: &[(usize, usize)],
+        restitution: Restitution,
+        default_friction: f32,
+        wake_speed_threshold: f32,
+        dt: f32,
+    ) {
+        self.constraints.clear();
+
+        // A broadphase can give the same pair more than once, and each pair
+        // should only be solved once.
+        let mut pairs: Vec<(usize, usize)> = candidate_pairs
+            .iter()
+            .filter(|&&(i, j)| Self::overlapping(bodies, i, j))
+            .map(|&(i, j)| (i.min(j), i.max(j)))
+            .collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+
+        // Everything that's going to be woken is woken before any contact is
+        // set up, so that none of them treats a circle that's moving as
+        // being asleep and bounces the other one off it like a wall.
+        for &(i, j) in &pairs {
+            let (body_i, body_j) = (bodies.body(i), bodies.body(j));
+            if body_i.sleeping && body_j.speed() >= wake_speed_threshold {
+                bodies.wake(i);
+            }
+            if body_j.sleeping && body_i.speed() >= wake_speed_threshold {
+                bodies.wake(j);
+            }
+        }
+
+        for (i, j) in pairs {
+            let (position_i, position_j) = (bodies.positions[i], bodies.positions[j]);
+            let dx = position_j.0 - position_i.0;
+            let dy = position_j.1 - position_i.1;
+            let distance = (dx * dx + dy * dy).sqrt();
+            // Circles at the same position are pushed apart along an
+            // arbitrary axis.
+            let normal = if distance > 1e-8 {
+                (dx / distance, dy / distance)
+            } else {
+                (1.0, 0.0)
+            };
+
+            let (body_i, body_j) = (bodies.body(i), bodies.body(j));
+            let inverse_mass = |k: usize| {
+                if bodies.sleeping[k] {
+                    0.0
+                } else {
+                    bodies.body(k).inverse_mass()
+                }
+            };
+            let (inverse_mass_i, inverse_mass_j) = (inverse_mass(i), inverse_mass(j));
+            if inverse_mass_i + inverse_mass_j == 0.0 {
+                continue;
+            }
+
+            let separating_speed = Self::relative_speed(bodies, i, j, normal);
+            let restitution = restitution.for_pair(&body_i, &body_j);
+            let friction = |k: usize| bodies.frictions[k].unwrap_or(default_friction).max(0.0);
+            let key = (bodies.ids[i], bodies.ids[j]);
+            let mut constraint = ContactConstraint {
+                i,
+                j,
+                normal,
+                inverse_mass_i,
+                inverse_mass_j,
+                target_separating_speed: f32::max(
+                    -separating_speed * restitution.coefficient_at(-separating_speed),
+                    0.0,
+                ),
+                normal_impulse: 0.0,
+                friction: (friction(i) * friction(j)).sqrt(),
+                tangent_impulse: 0.0,
+                impact: separating_speed < 0.0,
+            };
+            if let Some(&(normal_impulse, tangent_impulse)) = self.warm_start_impulses.get(&key) {
+                constraint.normal_impulse = normal_impulse * dt;
+                constraint.tangent_impulse = tangent_impulse * dt;
+            }
+            self.constraints.push(constraint);
+        }
+
+        // Warm starting only once every contact's bounce is worked out, so
+        // that a push meant to hold one pair up doesn't look like another
+        // pair closing in and get bounced back harder.
+        for constraint in &self.constraints {
+            Self::apply_impulse(
+                bodies,
+                constraint,
+                constraint.normal,
+                constraint.normal_impulse,
+            );
+            Self::apply_impulse(
+                bodies,
+                constraint,
+                constraint.tangent(),
+                constraint.tangent_impulse,
+            );
+        }
+    }
+
+    // `iterations` passes of the velocity solver over every contact, to push
+    // the pairs apart, then as many to stop them sliding past each other.
+    // Friction is limited by the normal impulse, so it's only solved once
+    // that's settled: taking back friction because the normal impulse went
+    // down would speed the sliding up again, adding energy.
+    pub(super) fn solve_velocities(&mut self, bodies: &mut Bodies, iterations: u32) {
+        for _ in 0..iterations {
+            for constraint in &mut self.constraints {
+                let (i, j) = (constraint.i, constraint.j);
+                let effective_mass = constraint.effective_mass();
+
+                let separating_speed = Self::relative_speed(bodies, i, j, constraint.normal);
+                let impulse =
+                    effective_mass * (constraint.target_separating_speed - separating_speed);
+                let total_impulse = f32::max(constraint.normal_impulse + impulse, 0.0);
+                let impulse = total_impulse - constraint.normal_impulse;
+                constraint.normal_impulse = total_impulse;
+                Self::apply_impulse(bodies, constraint, constraint.normal, impulse);
+            }
+        }
+
+        for _ in 0..iterations {
+            for constraint in &mut self.constraints {
+                let (i, j) = (constraint.i, constraint.j);
+                let effective_mass = constraint.effective_mass();
+
+                // Stop the pair sliding past each other, within the friction
+                // limit.
+                let tangent = constraint.tangent();
+                let sliding_speed = Self::relative_speed(bodies, i, j, tangent);
+                let max_friction = constraint.friction * constraint.normal_impulse;
+                let total_impulse = (constraint.tangent_impulse - effective_mass * sliding_speed)
+                    .clamp(-max_friction, max_friction);
+                let impulse = total_impulse - constraint.tangent_impulse;
+                constraint.tangent_impulse = total_impulse;
+                Self::apply_impulse(bodies, constraint, tangent, impulse);
+            }
+        }
+    }
+
+    // One pass over every contact that moves the pair apart, in proportion to
+    // their inverse masses, by `position_correction` of however much they
+    // still overlap. Velocities are left alone, so this can't add energy.
+    pub(super) fn solve_positions(&self, bodies: &mut Bodies, position_correction: f32) {
+        for constraint in &self.constraints {
+            let (i, j) = (constraint.i, constraint.j);
+            let dx = bodies.positions[j].0 - bodies.positions[i].0;
+            let dy = bodies.positions[j].1 - bodies.positions[i].1;
+            let overlap = bodies.radii[i] + bodies.radii[j] - (dx * dx + dy * dy).sqrt();
+            if overlap <= 0.0 {
+                continue;
+            }
+
+            let total_inverse_mass = constraint.inverse_mass_i + constraint.inverse_mass_j;
+            let correction = overlap * position_correction / total_inverse_mass;
+            let (nx, ny) = constraint.normal;
+            bodies.positions[i].0 -= correction * nx * constraint.inverse_mass_i;
+            bodies.positions[i].1 -= correction * ny * constraint.inverse_mass_i;
+            bodies.positions[j].0 += correction * nx * constraint.inverse_mass_j;
+            bodies.positions[j].1 += correction * ny * constraint.inverse_mass_j;
+        }
+    }
+
+    // Remembers each contact's impulse for the next subtick's warm start, and
+    // returns the contacts with the index of each circle. Only impacts carry
+    // an impulse, so resting contacts aren't reported as collisions.
+    pub(super) fn finish<'a>(
+        &'a mut self,
+        bodies: &'a Bodies,
+        dt: f32,
+    ) -> impl Iterator<Item = (usize, usize, Contact)> + 'a {
+        self.warm_start_impulses.clear();
+        for constraint in &self.constraints {
+            self.warm_start_impulses.insert(
+                (bodies.ids[constraint.i], bodies.ids[constraint.j]),
+                (
+                    constraint.normal_impulse / dt,
+                    constraint.tangent_impulse / dt,
+                ),
+            );
+        }
+
+        self.constraints.iter().map(move |constraint| {
+            let (nx, ny) = constraint.normal;
+            let (x_pos, y_pos) = bodies.positions[constraint.i];
+            let radius = bodies.radii[constraint.i];
+            let contact = Contact {
+                point: (x_pos + nx * radius, y_pos + ny * radius),
+                normal: (-nx, -ny),
+                impulse: if constraint.impact {
+                    constraint.normal_impulse
+                } else {
+                    0.0
+                },
+            };
+            (constraint.i, constraint.j, contact)
+        })
+    }
+
+    fn overlapping(bodies: &Bodies, i: usize, j: usize) -> bool {
+        if i == j || (bodies.sleeping[i] && bodies.sleeping[j]) {
+            return false;
+        }
+        let dx = bodies.positions[j].0 - bodies.positions[i].0;
+        let dy = bodies.positions[j].1 - bodies.positions[i].1;
+        (dx * dx + dy * dy).sqrt() < bodies.radii[i] + bodies.radii[j]
+    }
+
+    // How fast `j` is moving relative to `i` along `direction`. Along the
+    // normal, that's negative when they're closing.
+    fn relative_speed(bodies: &Bodies, i: usize, j: usize, direction: (f32, f32)) -> f32 {
+        let (velocity_i, velocity_j) = (bodies.velocities[i], bodies.velocities[j]);
+        (velocity_j.0 - velocity_i.0) * direction.0 + (velocity_j.1 - velocity_i.1) * direction.1
+    }
+
+    // Pushes `j` along `direction` and `i` the opposite way.
+    fn apply_impulse(
+        bodies: &mut Bodies,
+        constraint: &ContactConstraint,
+        direction: (f32, f32),
+        impulse: f32,
+    ) {
+        let (dx, dy) = direction;
+        let velocity_i = &mut bodies.velocities[constraint.i];
+        velocity_i.0 -= impulse * dx * constraint.inverse_mass_i;
+        velocity_i.1 -= impulse * dy * constraint.inverse_mass_i;
+        let velocity_j = &mut bodies.velocities[constraint.j];
+        velocity_j.0 += impulse * dx * constraint.inverse_mass_j;
+        velocity_j.1 += impulse * dy * constraint.inverse_mass_j;
+    }
+}
+
+impl ContactConstraint {
+    fn effective_mass(&self) -> f32 {
+        1.0 / (self.inverse_mass_i + self.inverse_mass_j)
+    }
+
+    fn tangent(&self) -> (f32, f32) {
+        (-self.normal.1, self.normal.0)
+    }
+}
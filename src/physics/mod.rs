@@ -0,0 +1,5227 @@
+use futures::{channel::oneshot, stream::Stream};
+use iced::{Point, Rectangle, Size, Vector};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, debug_span, trace, trace_span, warn};
+
+use std::collections::{HashMap, HashSet};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// The simulation always advances at this rate, independent of how often frames
+// are rendered. Every rate-dependent value in `SimulationConfig` is per tick.
+pub const TICKS_PER_SECOND: u64 = 120;
+// Bounds on `SimulationConfig::time_scale`.
+pub const MIN_TIME_SCALE: f32 = 0.1;
+pub const MAX_TIME_SCALE: f32 = 10.0;
+// Awake circles have to be moving this many times faster than the sleep
+// threshold to wake up a sleeping circle they run into.
+const WAKE_SPEED_MULTIPLIER: f32 = 4.0;
+// Weight of the current frame in each circle's smoothed velocity.
+const VELOCITY_SMOOTHING: f32 = 0.1;
+// Maximum distance a circle may move in one subtick, as a fraction of the
+// smallest circle's radius.
+const MAX_SUBTICK_TRAVEL_FRACTION: f32 = 0.5;
+// Fraction of the difference between a circle's velocity along a conveyor and
+// the conveyor's own speed that's made up per tick of contact.
+const CONVEYOR_GRIP: f32 = 0.2;
+// Ticks after going through a portal before a circle can go through one
+// again, so that it isn't sent straight back from the exit.
+const PORTAL_COOLDOWN_TICKS: u32 = 30;
+const RNG_SEED: u64 = 0x5eed;
+// Everything sampled from a `Distribution` scales a circle or is its
+// density, so the tails of normal ones are cut off here rather than reaching
+// zero or below.
+const MIN_NORMAL_SAMPLE: f32 = 0.01;
+const SOFT_BODY_STIFFNESS: f32 = 0.3;
+const SOFT_BODY_DAMPING: f32 = 0.1;
+// Passes over every rope per subtick. Each pass only pulls neighbouring links
+// together, so long ropes need several to stop stretching.
+const ROPE_SOLVER_ITERATIONS: u32 = 8;
+// Passes over every joint per subtick, so that chains of joints hold together.
+const JOINT_SOLVER_ITERATIONS: u32 = 8;
+// Radius of the circles in a rope, as a fraction of the distance between them.
+const ROPE_LINK_RADIUS_FRACTION: f32 = 0.4;
+
+use crate::broadphase::Broadphase;
+use crate::config::SimulationConfig;
+use crate::forces::{BuiltinForces, DragModel, ForceGenerator};
+use crate::random::Rng;
+use crate::replay::Recorder;
+use crate::scene::Scene;
+#[cfg(target_arch = "wasm32")]
+use crate::time::FixedTimestep;
+use crate::time::{frame_interval, Instant, Interval};
+
+mod bodies;
+mod builder;
+mod channel;
+mod contacts;
+mod resize;
+mod rewind;
+#[cfg(test)]
+mod tests;
+mod validation;
+
+pub use bodies::Bodies;
+use bodies::Body;
+pub use builder::GridBuilder;
+use channel::GridMessageReceiver;
+pub use channel::{GridMessageSender, OverflowPolicy};
+use contacts::ContactSolver;
+pub use resize::ResizeContents;
+use rewind::RewindBuffer;
+
+// How overlapping circles are pushed apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Solver {
+    // Solves the contacts between circles for the impulses that make them
+    // bounce, or hold each other up. See `contacts.rs`.
+    #[default]
+    Impulse,
+    // Only moves overlapping circles apart, a few times over, and works out
+    // their velocities from how far they moved. Collisions between circles
+    // don't bounce, but piles settle. Together with semi-implicit Euler this
+    // is position Verlet. Static and kinematic bodies are handled the same
+    // way in both solvers.
+    PositionBased,
+}
+
+// What happens to circles that reach an edge of the world.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoundaryMode {
+    // The edge is a wall that circles bounce off.
+    #[default]
+    Bounce,
+    // Circles are removed once they're entirely past the edge.
+    Despawn,
+    // Circles whose centers cross the edge come back in through the opposite
+    // one. Circles on either side of the seam don't collide with each other.
+    Wrap,
+    // Circles carry on past the edge as if it weren't there.
+    Open,
+}
+
+// The boundary mode of each edge of the world.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Boundaries {
+    pub left: BoundaryMode,
+    pub right: BoundaryMode,
+    pub top: BoundaryMode,
+    pub bottom: BoundaryMode,
+}
+
+pub fn new_throttled_grid_frame_stream(
+    grid_builder: GridBuilder,
+    target_fps: u64,
+    recorder: Option<Recorder>,
+) -> (GridMessageSender, impl Stream<Item = GridFrame>) {
+    let (grid, grid_message_sender) = grid_builder.build();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let grid_frame_stream = run_on_physics_thread(grid, target_fps, recorder);
+
+    // There are no threads on wasm32, so the simulation shares the executor
+    // with everything else.
+    #[cfg(target_arch = "wasm32")]
+    let grid_frame_stream = run_on_executor(grid, target_fps, recorder);
+
+    (grid_message_sender, grid_frame_stream)
+}
+
+// Ticks the grid on a dedicated thread so that heavy frames never stall the
+// UI. Only the latest tick is kept, so the UI is never more than one tick
+// behind the simulation no matter how slowly it consumes frames. Collision
+// events are kept until they're delivered, though, so none are lost. The
+// thread exits once the returned stream is dropped.
+#[cfg(not(target_arch = "wasm32"))]
+fn run_on_physics_thread(
+    mut grid: Grid,
+    target_fps: u64,
+    mut recorder: Option<Recorder>,
+) -> impl Stream<Item = GridFrame> {
+    #[derive(Default)]
+    struct LatestTick {
+        grid_frame: Option<(Arc<GridFrame>, Instant)>,
+        undelivered_events: FrameEvents,
+    }
+
+    let latest_tick = Arc::new(Mutex::new(LatestTick::default()));
+    let physics_thread_latest_tick = Arc::downgrade(&latest_tick);
+
+    std::thread::Builder::new()
+        .name("physics".to_string())
+        .spawn(move || {
+            let tick_duration = Duration::from_secs_f64(1.0 / TICKS_PER_SECOND as f64);
+            let mut next_tick_at = Instant::now();
+
+            loop {
+                let now = Instant::now();
+                if next_tick_at > now {
+                    std::thread::sleep(next_tick_at - now);
+                }
+                // Don't try to catch up after a stall, since that would only
+                // make the following ticks late as well.
+                next_tick_at = (next_tick_at + tick_duration).max(Instant::now());
+
+                let grid_frame = grid.tick_with_pending_messages(&mut recorder);
+
+                let Some(latest_tick) = physics_thread_latest_tick.upgrade() else {
+                    break;
+                };
+                let mut latest_tick = latest_tick.lock().unwrap();
+                latest_tick
+                    .undelivered_events
+                    .extend(grid_frame.get_events());
+                latest_tick.grid_frame = Some((Arc::new(grid_frame), Instant::now()));
+            }
+        })
+        .expect("failed to spawn physics thread");
+
+    async_stream::stream! {
+        let mut interval = Interval::new(Duration::from_millis(1000 / target_fps));
+        let tick_seconds = 1.0 / TICKS_PER_SECOND as f32;
+        let mut last_yielded_at = None;
+
+        loop {
+            interval.tick().await;
+
+            // Only hold the lock long enough to clone the `Arc`.
+            let (grid_frame, events) = {
+                let mut latest_tick = latest_tick.lock().unwrap();
+                (
+                    latest_tick.grid_frame.clone(),
+                    std::mem::take(&mut latest_tick.undelivered_events),
+                )
+            };
+
+            if let Some((grid_frame, ticked_at)) = grid_frame {
+                let interpolation = ticked_at.elapsed().as_secs_f32() / tick_seconds;
+                yield GridFrame::clone(&grid_frame)
+                    .with_interpolation(interpolation)
+                    .with_events(events)
+                    .with_frame_interval(frame_interval(&mut last_yielded_at));
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn run_on_executor(
+    mut grid: Grid,
+    target_fps: u64,
+    mut recorder: Option<Recorder>,
+) -> impl Stream<Item = GridFrame> {
+    async_stream::stream! {
+        let mut interval = Interval::new(Duration::from_millis(1000 / target_fps));
+        let mut timestep = FixedTimestep::new(TICKS_PER_SECOND);
+        let mut latest_grid_frame = None;
+        let mut undelivered_events = FrameEvents::default();
+        let mut last_yielded_at = None;
+
+        loop {
+            interval.tick().await;
+
+            for _ in 0..timestep.advance(1.0) {
+                let grid_frame = grid.tick_with_pending_messages(&mut recorder);
+                undelivered_events.extend(grid_frame.get_events());
+                latest_grid_frame = Some(grid_frame);
+            }
+
+            // Frames are yielded even if no tick was due, so that rendering
+            // keeps interpolating towards the latest tick.
+            if let Some(grid_frame) = &latest_grid_frame {
+                yield grid_frame
+                    .clone()
+                    .with_interpolation(timestep.interpolation())
+                    .with_events(std::mem::take(&mut undelivered_events))
+                    .with_frame_interval(frame_interval(&mut last_yielded_at));
+            }
+        }
+    }
+}
+
+// Every change to the simulation goes through one of these, whether it comes
+// from the keyboard and mouse, the console, the HTTP API or a script. They're
+// what recordings are made of, so replays don't need whatever sent them.
+#[derive(Serialize, Deserialize)]
+pub enum GridMessage {
+    // The id assigned to the circle is sent back through the sender, if any.
+    AddCircle(Circle, #[serde(skip)] Option<oneshot::Sender<EntityId>>),
+    // Adds the circle randomized by the config's `spawn_profile`, like the
+    // ones spawners and bursts add.
+    Spawn(Circle),
+    AddStaticCircle(StaticCircle),
+    AddStaticRectangle(StaticRectangle),
+    AddStaticCapsule(StaticCapsule),
+    AddKinematicRectangle(KinematicRectangle),
+    AddKinematicSpinner(KinematicSpinner),
+    AddPortalPair(PortalPair),
+    AddSink(Sink),
+    AddGravityWell(GravityWell),
+    // A body of fluid filling `rect`, with the given density and drag.
+    AddFluidZone {
+        #[serde(with = "RectangleDef")]
+        rect: Rectangle,
+        density: f32,
+        drag: f32,
+    },
+    AddSpawner(Spawner),
+    AddSoftBody(SoftBody),
+    // A chain of `segments` circles hanging straight down from `anchor`,
+    // `length` long in total. If `attached_to` is a circle in the grid, the
+    // rope hangs from that circle instead and moves with it.
+    AddRope {
+        #[serde(with = "PointDef")]
+        anchor: Point,
+        segments: u32,
+        length: f32,
+        #[serde(default)]
+        attached_to: Option<EntityId>,
+    },
+    // Keeps the circle as far from `anchor` as it is now, letting it swing
+    // around the anchor like a pendulum on a rod.
+    AddPinJoint {
+        body: EntityId,
+        #[serde(with = "PointDef")]
+        anchor: Point,
+    },
+    // Keeps the two circles as far apart as they are now, letting them turn
+    // around each other.
+    AddRevoluteJoint {
+        a: EntityId,
+        b: EntityId,
+    },
+    // Same as `Remove`, but ignores ids that don't refer to a spawner.
+    RemoveSpawner(EntityId),
+    SetSpawnerEnabled(EntityId, bool),
+    // A straight wall from `a` to `b` with rounded ends, added as a capsule
+    // of half the thickness.
+    AddStaticSegment {
+        #[serde(with = "PointDef")]
+        a: Point,
+        #[serde(with = "PointDef")]
+        b: Point,
+        thickness: f32,
+    },
+    Resize(#[serde(with = "SizeDef")] Size),
+    UpdateConfig(SimulationConfig),
+    // Starts simulating a copy of the grid as it is now alongside it, with
+    // this config instead of its own, or stops if `None`. Every later message
+    // apart from config changes is applied to both, so that they can be
+    // compared side by side. While comparing, this only changes the copy's
+    // config.
+    SetComparisonConfig(Option<SimulationConfig>),
+    SetGravity((f32, f32)),
+    SetTimeScale(f32),
+    SetPaused(bool),
+    // Advances a paused simulation by a single tick.
+    Step,
+    // Pauses the simulation and puts it back to the latest keyframe in the
+    // rewind buffer from before the current tick. Unpausing or stepping
+    // carries on from there.
+    StepBack,
+    // Removes every dynamic circle, leaving static geometry in place.
+    // Recordings from before it had a name of its own call it `Clear`.
+    #[serde(alias = "Clear")]
+    ClearDynamic,
+    // Removes everything but the dynamic circles: static and kinematic
+    // bodies, portals, sinks, fluid zones, gravity wells and spawners. The
+    // circles are woken up, so that those resting on anything fall.
+    ClearStatic,
+    // Removes every body of any kind, as if an empty scene were loaded.
+    ResetAll,
+    // Replaces every body in the grid with the contents of the scene.
+    LoadScene(Scene),
+    // Removes a body of any kind. Ids that don't refer to a body in the grid
+    // are ignored.
+    Remove(EntityId),
+    SetVelocity(EntityId, (f32, f32)),
+    // Changes the editable properties of a body. Ignored if the id doesn't
+    // refer to a body of the kind the properties are for.
+    UpdateBody(EntityId, BodyProperties),
+    // Pushes every circle within `radius` of (`x`, `y`) directly away from
+    // it. The impulse is `strength` at the center, falling off linearly to
+    // nothing at `radius`, so lighter circles are thrown further.
+    Explode {
+        x: f32,
+        y: f32,
+        strength: f32,
+        radius: f32,
+    },
+    // Adds a burst of circles around (`x`, `y`), as the config's
+    // `spawn_burst` describes.
+    SpawnBurst {
+        x: f32,
+        y: f32,
+    },
+    // Answers the query with the state of the grid as of the previous tick.
+    // Queries don't affect the simulation, so they aren't recorded.
+    #[serde(skip)]
+    Query(Query, oneshot::Sender<QueryResult>),
+    // Applies the messages in order within the same tick, or none of them
+    // if any is invalid, so that something built from several bodies never
+    // shows up half built. Queries can't be batched, since they can't be
+    // recorded; a batch with one is rejected.
+    Batch(Vec<GridMessage>),
+}
+
+impl GridMessage {
+    // A chain of segments through `points`, for building ramps, funnels and
+    // other terrain outlines.
+    pub fn add_static_polyline(points: &[Point], thickness: f32) -> Vec<GridMessage> {
+        points
+            .windows(2)
+            .map(|pair| GridMessage::AddStaticSegment {
+                a: pair[0],
+                b: pair[1],
+                thickness,
+            })
+            .collect()
+    }
+
+    // Whether the message has to be recorded to replay the simulation. The
+    // grid rejects batches with queries in them, so they don't either.
+    pub fn affects_simulation(&self) -> bool {
+        match self {
+            GridMessage::Query(_, _) => false,
+            GridMessage::Batch(messages) => messages.iter().all(GridMessage::affects_simulation),
+            _ => true,
+        }
+    }
+
+    // Whether the message controls the simulation, rather than adding to
+    // it. A full queue still takes these, so that a flood of circles can't
+    // keep the user from pausing or clearing the grid.
+    fn is_control(&self) -> bool {
+        matches!(
+            self,
+            GridMessage::Resize(_)
+                | GridMessage::UpdateConfig(_)
+                | GridMessage::SetComparisonConfig(_)
+                | GridMessage::SetGravity(_)
+                | GridMessage::SetTimeScale(_)
+                | GridMessage::SetPaused(_)
+                | GridMessage::Step
+                | GridMessage::StepBack
+                | GridMessage::ClearDynamic
+                | GridMessage::ClearStatic
+                | GridMessage::ResetAll
+                | GridMessage::LoadScene(_)
+        )
+    }
+
+    // The messages to apply in place of this one, which are those in it if
+    // it's a batch, and otherwise just itself.
+    fn unbatch(self) -> Vec<GridMessage> {
+        match self {
+            GridMessage::Batch(messages) => messages
+                .into_iter()
+                .flat_map(GridMessage::unbatch)
+                .collect(),
+            message => vec![message],
+        }
+    }
+
+    // The copy of the message that's applied to the comparison grid, if it
+    // should be. Config changes only apply to the grid they're sent to, and
+    // only that grid answers queries and sends back added circles' ids.
+    fn for_comparison(&self) -> Option<GridMessage> {
+        match self {
+            GridMessage::UpdateConfig(_)
+            | GridMessage::SetComparisonConfig(_)
+            | GridMessage::Query(_, _) => None,
+            GridMessage::AddCircle(circle, _) => Some(GridMessage::AddCircle(circle.clone(), None)),
+            message => message.try_clone(),
+        }
+    }
+
+    // A copy of the message, unless it sends something back to whoever sent
+    // it: a query's answer, or an added circle's id. There's only the one
+    // sender to send it through.
+    pub fn try_clone(&self) -> Option<GridMessage> {
+        let message = match self {
+            GridMessage::AddCircle(circle, None) => GridMessage::AddCircle(circle.clone(), None),
+            GridMessage::AddCircle(_, Some(_)) | GridMessage::Query(_, _) => return None,
+            GridMessage::Spawn(circle) => GridMessage::Spawn(circle.clone()),
+            GridMessage::AddStaticCircle(static_circle) => {
+                GridMessage::AddStaticCircle(static_circle.clone())
+            }
+            GridMessage::AddStaticRectangle(static_rectangle) => {
+                GridMessage::AddStaticRectangle(static_rectangle.clone())
+            }
+            GridMessage::AddStaticCapsule(static_capsule) => {
+                GridMessage::AddStaticCapsule(static_capsule.clone())
+            }
+            GridMessage::AddKinematicRectangle(kinematic_rectangle) => {
+                GridMessage::AddKinematicRectangle(kinematic_rectangle.clone())
+            }
+            GridMessage::AddKinematicSpinner(kinematic_spinner) => {
+                GridMessage::AddKinematicSpinner(kinematic_spinner.clone())
+            }
+            GridMessage::AddPortalPair(portal_pair) => {
+                GridMessage::AddPortalPair(portal_pair.clone())
+            }
+            GridMessage::AddSink(sink) => GridMessage::AddSink(sink.clone()),
+            GridMessage::AddGravityWell(gravity_well) => {
+                GridMessage::AddGravityWell(gravity_well.clone())
+            }
+            &GridMessage::AddFluidZone {
+                rect,
+                density,
+                drag,
+            } => GridMessage::AddFluidZone {
+                rect,
+                density,
+                drag,
+            },
+            GridMessage::AddSpawner(spawner) => GridMessage::AddSpawner(spawner.clone()),
+            GridMessage::AddSoftBody(soft_body) => GridMessage::AddSoftBody(soft_body.clone()),
+            &GridMessage::AddRope {
+                anchor,
+                segments,
+                length,
+                attached_to,
+            } => GridMessage::AddRope {
+                anchor,
+                segments,
+                length,
+                attached_to,
+            },
+            &GridMessage::AddPinJoint { body, anchor } => GridMessage::AddPinJoint { body, anchor },
+            &GridMessage::AddRevoluteJoint { a, b } => GridMessage::AddRevoluteJoint { a, b },
+            &GridMessage::RemoveSpawner(id) => GridMessage::RemoveSpawner(id),
+            &GridMessage::SetSpawnerEnabled(id, enabled) => {
+                GridMessage::SetSpawnerEnabled(id, enabled)
+            }
+            &GridMessage::AddStaticSegment { a, b, thickness } => {
+                GridMessage::AddStaticSegment { a, b, thickness }
+            }
+            &GridMessage::Resize(size) => GridMessage::Resize(size),
+            GridMessage::UpdateConfig(config) => GridMessage::UpdateConfig(config.clone()),
+            GridMessage::SetComparisonConfig(config) => {
+                GridMessage::SetComparisonConfig(config.clone())
+            }
+            &GridMessage::SetGravity(gravity) => GridMessage::SetGravity(gravity),
+            &GridMessage::SetTimeScale(time_scale) => GridMessage::SetTimeScale(time_scale),
+            &GridMessage::SetPaused(paused) => GridMessage::SetPaused(paused),
+            GridMessage::Step => GridMessage::Step,
+            GridMessage::StepBack => GridMessage::StepBack,
+            GridMessage::ClearDynamic => GridMessage::ClearDynamic,
+            GridMessage::ClearStatic => GridMessage::ClearStatic,
+            GridMessage::ResetAll => GridMessage::ResetAll,
+            GridMessage::LoadScene(scene) => GridMessage::LoadScene(scene.clone()),
+            &GridMessage::Remove(id) => GridMessage::Remove(id),
+            &GridMessage::SetVelocity(id, velocity) => GridMessage::SetVelocity(id, velocity),
+            &GridMessage::UpdateBody(id, properties) => GridMessage::UpdateBody(id, properties),
+            &GridMessage::Explode {
+                x,
+                y,
+                strength,
+                radius,
+            } => GridMessage::Explode {
+                x,
+                y,
+                strength,
+                radius,
+            },
+            &GridMessage::SpawnBurst { x, y } => GridMessage::SpawnBurst { x, y },
+            GridMessage::Batch(messages) => GridMessage::Batch(
+                messages
+                    .iter()
+                    .map(GridMessage::try_clone)
+                    .collect::<Option<_>>()?,
+            ),
+        };
+        Some(message)
+    }
+}
+
+// What can be changed about a body after it's added, for
+// `GridMessage::UpdateBody`. Positions are of the center of circles and the
+// top left corner of rectangles.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BodyProperties {
+    // A dynamic circle. Changing its mass changes its density.
+    Circle {
+        x_pos: f32,
+        y_pos: f32,
+        radius: f32,
+        mass: f32,
+        restitution: Option<f32>,
+        color: Option<(f32, f32, f32)>,
+    },
+    StaticCircle {
+        x_pos: f32,
+        y_pos: f32,
+        radius: f32,
+    },
+    StaticRectangle {
+        x_pos: f32,
+        y_pos: f32,
+        width: f32,
+        height: f32,
+    },
+}
+
+pub enum Query {
+    // Every body containing the point.
+    Point(Point),
+    // The nearest body along the ray.
+    Ray { origin: Point, direction: Vector },
+    // Every body overlapping the rectangle.
+    Aabb(Rectangle),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryResult {
+    Entities(Vec<EntityId>),
+    Hit(Option<Hit>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub id: EntityId,
+    pub point: Point,
+    // Distance from the ray origin to `point`.
+    pub distance: f32,
+}
+
+// Identifies a body for as long as it's in the grid. Slots of removed bodies
+// are reused with a new generation, so a stale id never refers to a different
+// body. The default id never refers to any body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Clone, Default)]
+struct EntityAllocator {
+    // Current generation of every slot. Generations start at 1.
+    generations: Vec<u32>,
+    free_indices: Vec<u32>,
+}
+
+impl EntityAllocator {
+    fn allocate(&mut self) -> EntityId {
+        match self.free_indices.pop() {
+            Some(index) => EntityId {
+                index,
+                generation: self.generations[index as usize],
+            },
+            None => {
+                self.generations.push(1);
+                EntityId {
+                    index: (self.generations.len() - 1) as u32,
+                    generation: 1,
+                }
+            }
+        }
+    }
+
+    // Frees every id at once, leaving the slots to be reused from the first.
+    fn free_all(&mut self) {
+        for generation in &mut self.generations {
+            *generation += 1;
+        }
+        self.free_indices = (0..self.generations.len() as u32).rev().collect();
+    }
+
+    fn free(&mut self, id: EntityId) {
+        if let Some(generation) = self.generations.get_mut(id.index as usize) {
+            if *generation == id.generation {
+                *generation += 1;
+                self.free_indices.push(id.index);
+            }
+        }
+    }
+}
+
+// An impact between a dynamic circle `a` and another body `b`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CollisionEvent {
+    pub a: EntityId,
+    pub b: EntityId,
+    // Magnitude of the change in momentum of `a`.
+    pub impulse: f32,
+    pub point: (f32, f32),
+}
+
+// A dynamic circle that was taken out of the grid by the simulation itself.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RemovalEvent {
+    pub id: EntityId,
+    // Where the circle's center was when it was removed.
+    pub point: (f32, f32),
+    pub cause: RemovalCause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RemovalCause {
+    // The circle's center entered the sink with this id.
+    Sink(EntityId),
+    // The circle coalesced into the circle with this id.
+    Merged(EntityId),
+    // The circle was hit hard enough to break into smaller circles.
+    Fractured,
+    // The circle shrank away or outlived its time to live.
+    Expired,
+    // The circle left the world through an edge that despawns circles, with
+    // a `DespawnOffscreen` lifetime, or with `OutOfBoundsPolicy::Despawn`.
+    Offscreen,
+    // The grid had more circles than `max_circles` allows.
+    Evicted,
+}
+
+// A message that the grid dropped instead of applying, because it would
+// have corrupted the simulation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectionEvent {
+    // What was wrong with the message, such as "the circle's radius is out of
+    // range".
+    pub problem: String,
+}
+
+// Everything of note that happened during one or more ticks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FrameEvents {
+    collisions: Vec<CollisionEvent>,
+    removals: Vec<RemovalEvent>,
+    rejections: Vec<RejectionEvent>,
+}
+
+impl FrameEvents {
+    pub fn extend(&mut self, other: &FrameEvents) {
+        self.collisions.extend_from_slice(&other.collisions);
+        self.removals.extend_from_slice(&other.removals);
+        self.rejections.extend_from_slice(&other.rejections);
+    }
+}
+
+// How bouncy collisions are. Bodies that hit each other slower than
+// `threshold` don't bounce at all, so that resting circles settle instead of
+// vibrating on whatever they rest on.
+#[derive(Debug, Clone, Copy)]
+struct Restitution {
+    coefficient: f32,
+    threshold: f32,
+}
+
+impl Restitution {
+    // The fraction of the closing speed that two bodies hitting each other
+    // at `closing_speed` separate at.
+    fn coefficient_at(&self, closing_speed: f32) -> f32 {
+        if closing_speed < self.threshold {
+            0.0
+        } else {
+            self.coefficient
+        }
+    }
+
+    // How many times over a circle's velocity into a static surface is taken
+    // away when it bounces off, given the dot product of its velocity and
+    // the surface's normal. A circle that's below the threshold just stops
+    // moving into the surface.
+    fn reflection_factor(&self, v_dot_n: f32) -> f32 {
+        if v_dot_n < 0.0 && -v_dot_n < self.threshold {
+            1.0
+        } else {
+            2.0 * self.coefficient
+        }
+    }
+
+    // The restitution for collisions of the circle with static surfaces,
+    // taking its own coefficient if it has one.
+    fn for_body(self, body: &Body) -> Self {
+        Self {
+            coefficient: body.restitution.unwrap_or(self.coefficient),
+            ..self
+        }
+    }
+
+    // Two circles hitting each other bounce as much as the bouncier of them
+    // would on its own.
+    fn for_pair(self, a: &Body, b: &Body) -> Self {
+        Self {
+            coefficient: self
+                .for_body(a)
+                .coefficient
+                .max(self.for_body(b).coefficient),
+            ..self
+        }
+    }
+}
+
+// Where two overlapping bodies touch and how hard they hit, before it's known
+// which bodies they are.
+struct Contact {
+    point: (f32, f32),
+    // Unit normal of `b`'s surface at the contact point, pointing towards `a`.
+    normal: (f32, f32),
+    impulse: f32,
+}
+
+impl Contact {
+    // Bodies that are resting against each other or already separating
+    // don't produce any impulse, and aren't reported.
+    fn into_collision_event(self, a: EntityId, b: EntityId) -> Option<CollisionEvent> {
+        (self.impulse > 0.0).then_some(CollisionEvent {
+            a,
+            b,
+            impulse: self.impulse,
+            point: self.point,
+        })
+    }
+}
+
+// How long a frame took to make, and how long after the one before it it was
+// delivered.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FrameTiming {
+    pub tick_duration: Duration,
+    pub phases: PhaseTimings,
+    // Zero for the first frame a stream delivers.
+    pub frame_interval: Duration,
+}
+
+// Where a tick's time went, summed over its subticks. Whatever isn't covered
+// here, like applying messages, integrating and the rope and joint solvers,
+// is the rest of `FrameTiming::tick_duration`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    // Gravity, drag, registered forces, fluid zones, gravity wells and
+    // springs.
+    pub forces: Duration,
+    pub broadphase: Duration,
+    // Resolving the candidate pairs from the broadphase.
+    pub narrowphase: Duration,
+    // Collisions with static and kinematic bodies.
+    pub static_collisions: Duration,
+    // Copying the grid into the `GridFrame`.
+    pub snapshot: Duration,
+}
+
+impl PhaseTimings {
+    // Each phase with a name for it, in the order they run.
+    pub fn named(&self) -> [(&'static str, Duration); 5] {
+        [
+            ("forces", self.forces),
+            ("broadphase", self.broadphase),
+            ("narrowphase", self.narrowphase),
+            ("static_collisions", self.static_collisions),
+            ("snapshot", self.snapshot),
+        ]
+    }
+}
+
+impl std::ops::AddAssign for PhaseTimings {
+    fn add_assign(&mut self, other: Self) {
+        self.forces += other.forces;
+        self.broadphase += other.broadphase;
+        self.narrowphase += other.narrowphase;
+        self.static_collisions += other.static_collisions;
+        self.snapshot += other.snapshot;
+    }
+}
+
+// Frames are serialized for remote viewers. Bodies are serialized the same
+// way as in scene files, without the state that only the grid itself needs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridFrame {
+    frame_number: u32,
+    width: f32,
+    height: f32,
+    circles: Vec<Circle>,
+    static_circles: Vec<StaticCircle>,
+    static_rectangles: Vec<StaticRectangle>,
+    static_capsules: Vec<StaticCapsule>,
+    kinematic_rectangles: Vec<KinematicRectangle>,
+    kinematic_spinners: Vec<KinematicSpinner>,
+    portal_pairs: Vec<PortalPair>,
+    sinks: Vec<Sink>,
+    fluid_zones: Vec<FluidZone>,
+    gravity_wells: Vec<GravityWell>,
+    spawners: Vec<Spawner>,
+    springs: Vec<LinkedSpring>,
+    ropes: Vec<LinkedRope>,
+    joints: Vec<LinkedJoint>,
+    drag_model: DragModel,
+    timing: FrameTiming,
+    applied_message_count: usize,
+    // Messages the grid's queue has dropped since it started, because it was
+    // full. See `OverflowPolicy`.
+    dropped_message_count: u64,
+    collision_pair_count: u32,
+    // See `Grid::total_energy`.
+    total_energy: f32,
+    events: FrameEvents,
+    sub_tick_count: u32,
+    // How far back, in ticks, the grid could be rewound.
+    rewind_buffered_ticks: u32,
+    // Fraction of the way from each circle's previous position to its current
+    // one that it should be drawn at.
+    interpolation: f32,
+    // The comparison grid as of the same tick, if there is one.
+    comparison: Option<Box<GridFrame>>,
+}
+
+// How a body's shape is filled in when drawn. Each kind of body has its own
+// default, so that static, kinematic and dynamic bodies look different even
+// in similar colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FillStyle {
+    Solid,
+    // Lighter towards a highlight up and to the left of the center, so that
+    // the body looks rounded.
+    RadialGradient,
+    // A line around the edge over a faint fill.
+    Outline,
+}
+
+impl GridFrame {
+    pub fn get_frame_number(&self) -> u32 {
+        self.frame_number
+    }
+
+    pub fn with_interpolation(mut self, interpolation: f32) -> Self {
+        self.interpolation = interpolation.clamp(0.0, 1.0);
+        self.comparison = self
+            .comparison
+            .map(|comparison| Box::new(comparison.with_interpolation(interpolation)));
+        self
+    }
+
+    pub fn get_comparison(&self) -> Option<&GridFrame> {
+        self.comparison.as_deref()
+    }
+
+    pub fn get_size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    pub fn get_tick_duration(&self) -> Duration {
+        self.timing.tick_duration
+    }
+
+    pub fn get_phase_timings(&self) -> PhaseTimings {
+        self.timing.phases
+    }
+
+    pub fn get_timing(&self) -> FrameTiming {
+        self.timing
+    }
+
+    pub fn get_applied_message_count(&self) -> usize {
+        self.applied_message_count
+    }
+
+    pub fn get_dropped_message_count(&self) -> u64 {
+        self.dropped_message_count
+    }
+
+    pub fn get_circle(&self, id: EntityId) -> Option<&Circle> {
+        self.circles.iter().find(|circle| circle.id == id)
+    }
+
+    pub fn get_circle_count(&self) -> usize {
+        self.circles.len()
+    }
+
+    pub fn get_circles(&self) -> &[Circle] {
+        &self.circles
+    }
+
+    pub fn get_static_circles(&self) -> &[StaticCircle] {
+        &self.static_circles
+    }
+
+    pub fn get_static_rectangles(&self) -> &[StaticRectangle] {
+        &self.static_rectangles
+    }
+
+    pub fn get_static_capsules(&self) -> &[StaticCapsule] {
+        &self.static_capsules
+    }
+
+    pub fn get_kinematic_rectangles(&self) -> &[KinematicRectangle] {
+        &self.kinematic_rectangles
+    }
+
+    pub fn get_kinematic_spinners(&self) -> &[KinematicSpinner] {
+        &self.kinematic_spinners
+    }
+
+    pub fn get_portal_pairs(&self) -> &[PortalPair] {
+        &self.portal_pairs
+    }
+
+    pub fn get_sinks(&self) -> &[Sink] {
+        &self.sinks
+    }
+
+    pub fn get_fluid_zones(&self) -> &[FluidZone] {
+        &self.fluid_zones
+    }
+
+    pub fn get_gravity_wells(&self) -> &[GravityWell] {
+        &self.gravity_wells
+    }
+
+    pub fn get_springs(&self) -> &[LinkedSpring] {
+        &self.springs
+    }
+
+    pub fn get_ropes(&self) -> &[LinkedRope] {
+        &self.ropes
+    }
+
+    pub fn get_joints(&self) -> &[LinkedJoint] {
+        &self.joints
+    }
+
+    pub fn get_interpolation(&self) -> f32 {
+        self.interpolation
+    }
+
+    // Whether a circle of `radius` at `center` would be at least `gap` from
+    // the edge of every dynamic circle.
+    pub fn has_room_for(&self, center: Point, radius: f32, gap: f32) -> bool {
+        self.circles.iter().all(|circle| {
+            let dx = circle.x_pos - center.x;
+            let dy = circle.y_pos - center.y;
+            (dx * dx + dy * dy).sqrt() >= circle.radius + radius + gap
+        })
+    }
+
+    // The editable properties of the body, if it's a kind that can be edited.
+    pub fn get_body_properties(&self, id: EntityId) -> Option<BodyProperties> {
+        if let Some(circle) = self.get_circle(id) {
+            return Some(BodyProperties::Circle {
+                x_pos: circle.x_pos,
+                y_pos: circle.y_pos,
+                radius: circle.radius,
+                mass: circle.mass(),
+                restitution: circle.restitution,
+                color: circle.color,
+            });
+        }
+        if let Some(static_circle) = self
+            .static_circles
+            .iter()
+            .find(|static_circle| static_circle.id == id)
+        {
+            return Some(BodyProperties::StaticCircle {
+                x_pos: static_circle.x_pos,
+                y_pos: static_circle.y_pos,
+                radius: static_circle.radius,
+            });
+        }
+        self.static_rectangles
+            .iter()
+            .find(|static_rectangle| static_rectangle.id == id)
+            .map(|static_rectangle| BodyProperties::StaticRectangle {
+                x_pos: static_rectangle.x_pos,
+                y_pos: static_rectangle.y_pos,
+                width: static_rectangle.width,
+                height: static_rectangle.height,
+            })
+    }
+
+    pub fn get_scene(&self) -> Scene {
+        let circle_indices: HashMap<EntityId, usize> = self
+            .circles
+            .iter()
+            .enumerate()
+            .map(|(i, circle)| (circle.id, i))
+            .collect();
+
+        Scene {
+            circles: self.circles.clone(),
+            static_circles: self.static_circles.clone(),
+            static_rectangles: self.static_rectangles.clone(),
+            static_capsules: self.static_capsules.clone(),
+            kinematic_rectangles: self.kinematic_rectangles.clone(),
+            kinematic_spinners: self.kinematic_spinners.clone(),
+            portal_pairs: self.portal_pairs.clone(),
+            sinks: self.sinks.clone(),
+            fluid_zones: self.fluid_zones.clone(),
+            gravity_wells: self.gravity_wells.clone(),
+            spawners: self.spawners.clone(),
+            springs: self
+                .springs
+                .iter()
+                .filter_map(|spring| {
+                    Some(Spring {
+                        a: *circle_indices.get(&spring.a)?,
+                        b: *circle_indices.get(&spring.b)?,
+                        rest_length: spring.rest_length,
+                        stiffness: spring.stiffness,
+                        damping: spring.damping,
+                    })
+                })
+                .collect(),
+            ropes: self
+                .ropes
+                .iter()
+                .filter_map(|rope| {
+                    Some(Rope {
+                        anchor: rope.anchor.unlink(&circle_indices)?,
+                        anchor_length: rope.anchor_length,
+                        circles: rope
+                            .circles
+                            .iter()
+                            .map(|id| circle_indices.get(id).copied())
+                            .collect::<Option<_>>()?,
+                        segment_length: rope.segment_length,
+                    })
+                })
+                .collect(),
+            joints: self
+                .joints
+                .iter()
+                .filter_map(|joint| {
+                    Some(Joint {
+                        body: *circle_indices.get(&joint.body)?,
+                        anchor: joint.anchor.unlink(&circle_indices)?,
+                        length: joint.length,
+                    })
+                })
+                .collect(),
+            drag_model: self.drag_model,
+        }
+    }
+
+    // Everything that happened since the previous frame. Frames straight
+    // from `Grid::tick` only have the events of that tick, while streamed
+    // frames also have those of any ticks that were skipped over.
+    pub fn get_events(&self) -> &FrameEvents {
+        &self.events
+    }
+
+    pub fn with_events(mut self, events: FrameEvents) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub fn with_frame_interval(mut self, frame_interval: Duration) -> Self {
+        self.timing.frame_interval = frame_interval;
+        self
+    }
+
+    pub fn get_collision_events(&self) -> &[CollisionEvent] {
+        &self.events.collisions
+    }
+
+    pub fn get_removal_events(&self) -> &[RemovalEvent] {
+        &self.events.removals
+    }
+
+    pub fn get_rejection_events(&self) -> &[RejectionEvent] {
+        &self.events.rejections
+    }
+
+    pub fn get_sub_tick_count(&self) -> u32 {
+        self.sub_tick_count
+    }
+
+    pub fn get_rewind_buffered_ticks(&self) -> u32 {
+        self.rewind_buffered_ticks
+    }
+
+    // Number of circle-circle collisions resolved across all subticks.
+    pub fn get_collision_pair_count(&self) -> u32 {
+        self.collision_pair_count
+    }
+
+    pub fn get_total_kinetic_energy(&self) -> f32 {
+        self.circles
+            .iter()
+            .map(|circle| 0.5 * circle.mass() * circle.speed().powi(2))
+            .sum()
+    }
+
+    pub fn get_total_energy(&self) -> f32 {
+        self.total_energy
+    }
+
+    pub fn get_max_speed(&self) -> f32 {
+        self.circles.iter().map(Circle::speed).fold(0.0, f32::max)
+    }
+
+    pub fn get_sleeping_circle_count(&self) -> usize {
+        self.circles.iter().filter(|circle| circle.sleeping).count()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Size")]
+struct SizeDef {
+    width: f32,
+    height: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Point")]
+struct PointDef {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Rectangle")]
+struct RectangleDef {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+pub struct Grid {
+    frame_number: u32,
+    width: f32,
+    height: f32,
+    bodies: Bodies,
+    static_circles: Vec<StaticCircle>,
+    static_rectangles: Vec<StaticRectangle>,
+    static_capsules: Vec<StaticCapsule>,
+    kinematic_rectangles: Vec<KinematicRectangle>,
+    kinematic_spinners: Vec<KinematicSpinner>,
+    portal_pairs: Vec<PortalPair>,
+    sinks: Vec<Sink>,
+    fluid_zones: Vec<FluidZone>,
+    gravity_wells: Vec<GravityWell>,
+    spawners: Vec<Spawner>,
+    springs: Vec<LinkedSpring>,
+    ropes: Vec<LinkedRope>,
+    joints: Vec<LinkedJoint>,
+    config: SimulationConfig,
+    // Kept in sync with `config`, and kept between ticks since a broadphase
+    // can reuse work from the previous one.
+    broadphase: Box<dyn Broadphase>,
+    // Remembers the impulses between circles from one subtick to the next.
+    contact_solver: ContactSolver,
+    // Gravity, drag and wind, kept in sync with `config`.
+    builtin_forces: BuiltinForces,
+    // The drag model of the loaded scene, for circles without their own.
+    drag_model: DragModel,
+    // Registered by the embedding code, applied after the built-in forces.
+    force_generators: Vec<Box<dyn ForceGenerator>>,
+    // Source of all randomness in the simulation, such as spawn velocities.
+    rng: Rng,
+    paused: bool,
+    entities: EntityAllocator,
+    rewind_buffer: RewindBuffer,
+    // A copy of the grid simulated with a different config, that every
+    // message apart from config changes is applied to as well. See
+    // `GridMessage::SetComparisonConfig`.
+    comparison: Option<Box<Grid>>,
+    // Where messages sent through the grid's channel arrive, if it has one.
+    // See `Grid::new_detached`.
+    message_receiver: Option<GridMessageReceiver>,
+}
+
+impl Grid {
+    // An empty grid without a channel to send it messages through, for code
+    // that drives it directly with `Grid::tick` or `Grid::step` instead of
+    // running it in a frame stream. `GridBuilder` sets up anything more.
+    pub fn new_detached(width: f32, height: f32, config: SimulationConfig) -> Self {
+        Self {
+            frame_number: 0,
+            width,
+            height,
+            bodies: Bodies::default(),
+            static_circles: Vec::new(),
+            static_rectangles: Vec::new(),
+            static_capsules: Vec::new(),
+            kinematic_rectangles: Vec::new(),
+            kinematic_spinners: Vec::new(),
+            portal_pairs: Vec::new(),
+            sinks: Vec::new(),
+            fluid_zones: Vec::new(),
+            gravity_wells: Vec::new(),
+            spawners: Vec::new(),
+            springs: Vec::new(),
+            ropes: Vec::new(),
+            joints: Vec::new(),
+            broadphase: config.broadphase.broadphase(),
+            contact_solver: ContactSolver::default(),
+            builtin_forces: BuiltinForces::new(&config, DragModel::default()),
+            drag_model: DragModel::default(),
+            force_generators: Vec::new(),
+            config,
+            rng: Rng::new(RNG_SEED),
+            paused: false,
+            entities: EntityAllocator::default(),
+            rewind_buffer: RewindBuffer::default(),
+            comparison: None,
+            message_receiver: None,
+        }
+    }
+
+    pub fn get_frame_number(&self) -> u32 {
+        self.frame_number
+    }
+
+    pub fn add_force_generator(&mut self, force_generator: Box<dyn ForceGenerator>) {
+        self.force_generators.push(force_generator);
+    }
+
+    // Kinetic energy of the circles, plus the potential energy they have
+    // from the config's gravity and from stretched springs. Gravity wells,
+    // charges and added force generators aren't counted. Collisions and
+    // damping should only ever take energy away, so this growing without
+    // anything pushing the circles means the solver is adding it.
+    pub fn total_energy(&self) -> f32 {
+        let (gravity_x, gravity_y) = self.config.gravity;
+        let bodies = &self.bodies;
+        let circles: f32 = (0..bodies.len())
+            .map(|i| {
+                let mass = bodies.mass(i);
+                let (x_pos, y_pos) = bodies.positions[i];
+                let (x_velocity, y_velocity) = bodies.velocities[i];
+                0.5 * mass * (x_velocity * x_velocity + y_velocity * y_velocity)
+                    - mass * (gravity_x * x_pos + gravity_y * y_pos)
+            })
+            .sum();
+        // A spring changes each end's velocity in proportion to the other's
+        // share of their mass, which works out to a force of the stretch
+        // times their reduced mass.
+        let circle_indices: HashMap<EntityId, usize> = if self.springs.is_empty() {
+            HashMap::new()
+        } else {
+            bodies
+                .ids
+                .iter()
+                .enumerate()
+                .map(|(i, &id)| (id, i))
+                .collect()
+        };
+        let springs: f32 = self
+            .springs
+            .iter()
+            .filter_map(|spring| {
+                let i = *circle_indices.get(&spring.a)?;
+                let j = *circle_indices.get(&spring.b)?;
+                let (a, b) = (bodies.positions[i], bodies.positions[j]);
+                let stretch = (b.0 - a.0).hypot(b.1 - a.1) - spring.rest_length;
+                let (mass_a, mass_b) = (bodies.mass(i), bodies.mass(j));
+                let reduced_mass = mass_a * mass_b / (mass_a + mass_b);
+                Some(0.5 * spring.stiffness * reduced_mass * stretch * stretch)
+            })
+            .sum();
+        circles + springs
+    }
+
+    pub fn query_point(&self, point: Point) -> Vec<EntityId> {
+        let circles = self
+            .bodies
+            .iter()
+            .filter(|circle| {
+                point.distance(Point::new(circle.x_pos, circle.y_pos)) <= circle.radius
+            })
+            .map(|circle| circle.id);
+        let static_circles = self
+            .static_circles
+            .iter()
+            .filter(|static_circle| {
+                point.distance(Point::new(static_circle.x_pos, static_circle.y_pos))
+                    <= static_circle.radius
+            })
+            .map(|static_circle| static_circle.id);
+        let static_rectangles = self
+            .static_rectangles
+            .iter()
+            .filter(|static_rectangle| static_rectangle.bounds().contains(point))
+            .map(|static_rectangle| static_rectangle.id);
+        let static_capsules = self
+            .static_capsules
+            .iter()
+            .filter(|static_capsule| {
+                point.distance(static_capsule.closest_point(point)) <= static_capsule.radius
+            })
+            .map(|static_capsule| static_capsule.id);
+        let kinematic_rectangles = self
+            .kinematic_rectangles
+            .iter()
+            .filter(|kinematic_rectangle| kinematic_rectangle.bounds().contains(point))
+            .map(|kinematic_rectangle| kinematic_rectangle.id);
+        let kinematic_spinners = self
+            .kinematic_spinners
+            .iter()
+            .filter(|kinematic_spinner| {
+                let local_point = kinematic_spinner.world_to_local(point, kinematic_spinner.angle);
+                kinematic_spinner.local_bounds().contains(local_point)
+            })
+            .map(|kinematic_spinner| kinematic_spinner.id);
+
+        circles
+            .chain(static_circles)
+            .chain(static_rectangles)
+            .chain(static_capsules)
+            .chain(kinematic_rectangles)
+            .chain(kinematic_spinners)
+            .collect()
+    }
+
+    pub fn query_aabb(&self, rectangle: Rectangle) -> Vec<EntityId> {
+        let circle_overlaps = |x_pos: f32, y_pos: f32, radius: f32| {
+            let closest = Point::new(
+                clamp(x_pos, rectangle.x, rectangle.x + rectangle.width),
+                clamp(y_pos, rectangle.y, rectangle.y + rectangle.height),
+            );
+            closest.distance(Point::new(x_pos, y_pos)) <= radius
+        };
+
+        let circles = self
+            .bodies
+            .iter()
+            .filter(|circle| circle_overlaps(circle.x_pos, circle.y_pos, circle.radius))
+            .map(|circle| circle.id);
+        let static_circles = self
+            .static_circles
+            .iter()
+            .filter(|static_circle| {
+                circle_overlaps(
+                    static_circle.x_pos,
+                    static_circle.y_pos,
+                    static_circle.radius,
+                )
+            })
+            .map(|static_circle| static_circle.id);
+        let static_rectangles = self
+            .static_rectangles
+            .iter()
+            .filter(|static_rectangle| static_rectangle.bounds().intersects(&rectangle))
+            .map(|static_rectangle| static_rectangle.id);
+        // Treats the capsule's rounded ends as square, which is close enough
+        // for picking.
+        let static_capsules = self
+            .static_capsules
+            .iter()
+            .filter(|static_capsule| {
+                let radius = static_capsule.radius;
+                let expanded_rectangle = Rectangle::new(
+                    Point::new(rectangle.x - radius, rectangle.y - radius),
+                    Size::new(
+                        rectangle.width + 2.0 * radius,
+                        rectangle.height + 2.0 * radius,
+                    ),
+                );
+                let direction = static_capsule.b - static_capsule.a;
+                let length = (direction.x * direction.x + direction.y * direction.y).sqrt();
+                if length <= f32::EPSILON {
+                    return expanded_rectangle.contains(static_capsule.a);
+                }
+                ray_rectangle_distance(
+                    static_capsule.a,
+                    direction * (1.0 / length),
+                    expanded_rectangle,
+                )
+                .is_some_and(|distance| distance <= length)
+            })
+            .map(|static_capsule| static_capsule.id);
+        let kinematic_rectangles = self
+            .kinematic_rectangles
+            .iter()
+            .filter(|kinematic_rectangle| kinematic_rectangle.bounds().intersects(&rectangle))
+            .map(|kinematic_rectangle| kinematic_rectangle.id);
+        // Uses the spinner's axis-aligned bounding box, which is close enough
+        // for picking.
+        let kinematic_spinners = self
+            .kinematic_spinners
+            .iter()
+            .filter(|kinematic_spinner| kinematic_spinner.bounding_box().intersects(&rectangle))
+            .map(|kinematic_spinner| kinematic_spinner.id);
+
+        circles
+            .chain(static_circles)
+            .chain(static_rectangles)
+            .chain(static_capsules)
+            .chain(kinematic_rectangles)
+            .chain(kinematic_spinners)
+            .collect()
+    }
+
+    // Returns the first body hit by a ray cast from `origin` in `direction`.
+    pub fn raycast(&self, origin: Point, direction: Vector) -> Option<Hit> {
+        let length = (direction.x * direction.x + direction.y * direction.y).sqrt();
+        if length <= f32::EPSILON {
+            return None;
+        }
+        let direction = Vector::new(direction.x / length, direction.y / length);
+
+        let circles = self
+            .bodies
+            .iter()
+            .map(|circle| (circle.id, circle.x_pos, circle.y_pos, circle.radius));
+        let static_circles = self.static_circles.iter().map(|static_circle| {
+            (
+                static_circle.id,
+                static_circle.x_pos,
+                static_circle.y_pos,
+                static_circle.radius,
+            )
+        });
+        let circle_hits = circles
+            .chain(static_circles)
+            .filter_map(|(id, x_pos, y_pos, radius)| {
+                ray_circle_distance(origin, direction, Point::new(x_pos, y_pos), radius)
+                    .map(|distance| (id, distance))
+            });
+
+        let static_rectangles = self
+            .static_rectangles
+            .iter()
+            .map(|static_rectangle| (static_rectangle.id, static_rectangle.bounds()));
+        let kinematic_rectangles = self
+            .kinematic_rectangles
+            .iter()
+            .map(|kinematic_rectangle| (kinematic_rectangle.id, kinematic_rectangle.bounds()));
+        let rectangle_hits =
+            static_rectangles
+                .chain(kinematic_rectangles)
+                .filter_map(|(id, bounds)| {
+                    ray_rectangle_distance(origin, direction, bounds).map(|distance| (id, distance))
+                });
+
+        let capsule_hits = self.static_capsules.iter().filter_map(|static_capsule| {
+            ray_capsule_distance(origin, direction, static_capsule)
+                .map(|distance| (static_capsule.id, distance))
+        });
+
+        // Spinners are hit tested in their own frame, where they're axis
+        // aligned. Rotation preserves distances along the ray.
+        let spinner_hits = self
+            .kinematic_spinners
+            .iter()
+            .filter_map(|kinematic_spinner| {
+                let angle = kinematic_spinner.angle;
+                let local_origin = kinematic_spinner.world_to_local(origin, angle);
+                let local_direction = rotate(direction, -angle);
+                ray_rectangle_distance(
+                    local_origin,
+                    local_direction,
+                    kinematic_spinner.local_bounds(),
+                )
+                .map(|distance| (kinematic_spinner.id, distance))
+            });
+
+        circle_hits
+            .chain(rectangle_hits)
+            .chain(capsule_hits)
+            .chain(spinner_hits)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(id, distance)| Hit {
+                id,
+                point: origin + direction * distance,
+                distance,
+            })
+    }
+
+    // Ticks with every message that has been sent to the grid so far,
+    // recording them first if a recorder is given.
+    fn tick_with_pending_messages(&mut self, recorder: &mut Option<Recorder>) -> GridFrame {
+        let messages = self
+            .message_receiver
+            .as_mut()
+            .map(GridMessageReceiver::drain)
+            .unwrap_or_default();
+
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(self.frame_number, &messages);
+        }
+
+        self.tick(messages)
+    }
+
+    // Applies the messages and advances the simulation by one tick, which is
+    // `1 / TICKS_PER_SECOND` seconds times the config's time scale.
+    pub fn tick(&mut self, messages: Vec<GridMessage>) -> GridFrame {
+        self.tick_for(1.0, messages)
+    }
+
+    // Like `tick`, but advances the simulation by `dt` seconds, times the
+    // config's time scale, in one go. A `dt` that isn't positive only applies
+    // the messages. Every rate in the config is per tick, so while steps of a
+    // tick's length match the streamed simulation exactly, longer or shorter
+    // ones only approximate it. The messages are borrowed, so the ones with
+    // replies can't be sent on, and are rejected; use `tick` for those.
+    pub fn step(&mut self, dt: f32, messages: &[GridMessage]) -> GridFrame {
+        let mut rejections = Vec::new();
+        let messages = messages
+            .iter()
+            .filter_map(|message| {
+                let copy = message.try_clone();
+                if copy.is_none() {
+                    warn!("Ignoring a stepped message with a reply.");
+                    rejections.push(RejectionEvent {
+                        problem: "a stepped grid can't send replies".to_string(),
+                    });
+                }
+                copy
+            })
+            .collect();
+        let mut grid_frame = self.tick_for(dt * TICKS_PER_SECOND as f32, messages);
+        grid_frame.events.rejections.extend(rejections);
+        grid_frame
+    }
+
+    // Applies the messages and advances the simulation by `ticks` ticks'
+    // worth of time, which needn't be a whole number.
+    fn tick_for(&mut self, ticks: f32, messages: Vec<GridMessage>) -> GridFrame {
+        let _tick_span = trace_span!("tick", frame = self.frame_number).entered();
+        let tick_start = Instant::now();
+        let applied_message_count = messages.len();
+        let mut step_requested = false;
+
+        let messages_span = debug_span!("handle_messages", count = applied_message_count).entered();
+        if applied_message_count > 0 {
+            debug!("Applying {applied_message_count} messages.");
+        }
+        let mut events = FrameEvents::default();
+        // Bad values from a scene file or a remote client would spread to
+        // everything their body touches, so the whole message is dropped, and
+        // the UI told why. Batches are checked as a whole before they're
+        // unpacked, so that none of a bad one is applied.
+        let messages = messages
+            .into_iter()
+            .filter(|message| match message.validate() {
+                Ok(()) => true,
+                Err(problem) => {
+                    warn!("Ignoring an invalid message: {problem}.");
+                    events.rejections.push(RejectionEvent {
+                        problem: problem.to_string(),
+                    });
+                    false
+                }
+            })
+            .flat_map(GridMessage::unbatch);
+        let mut comparison_messages = Vec::new();
+        for message in messages {
+            if self.comparison.is_some() {
+                comparison_messages.extend(message.for_comparison());
+            }
+
+            match message {
+                GridMessage::AddCircle(circle, id_sender) => {
+                    // Dropping the sender unsent tells whoever's waiting that
+                    // the circle wasn't added.
+                    if self.room_for_circles() == 0 {
+                        debug!("Not adding a circle: the grid is full.");
+                    } else {
+                        let id = self.add_circle(circle);
+                        if let Some(id_sender) = id_sender {
+                            // The sender may not care about the id anymore.
+                            let _ = id_sender.send(id);
+                        }
+                    }
+                }
+                GridMessage::Spawn(mut circle) => {
+                    if self.room_for_circles() == 0 {
+                        debug!("Not spawning a circle: the grid is full.");
+                        continue;
+                    }
+                    self.config.spawn_profile.apply(&mut circle, &mut self.rng);
+                    // The message was checked, but the profile wasn't.
+                    match validation::validate_circle(&circle) {
+                        Ok(()) => {
+                            self.add_circle(circle);
+                        }
+                        Err(problem) => warn!("Not spawning a circle: {problem}."),
+                    }
+                }
+                GridMessage::AddStaticCircle(static_circle) => {
+                    self.add_static_circle(static_circle);
+                }
+                GridMessage::AddStaticRectangle(static_rectangle) => {
+                    self.add_static_rectangle(static_rectangle);
+                }
+                GridMessage::AddStaticCapsule(static_capsule) => {
+                    self.add_static_capsule(static_capsule);
+                }
+                GridMessage::AddKinematicRectangle(kinematic_rectangle) => {
+                    self.add_kinematic_rectangle(kinematic_rectangle);
+                }
+                GridMessage::AddKinematicSpinner(kinematic_spinner) => {
+                    self.add_kinematic_spinner(kinematic_spinner);
+                }
+                GridMessage::AddPortalPair(portal_pair) => {
+                    self.add_portal_pair(portal_pair);
+                }
+                GridMessage::AddSink(sink) => {
+                    self.add_sink(sink);
+                }
+                GridMessage::AddGravityWell(gravity_well) => {
+                    self.add_gravity_well(gravity_well);
+                }
+                GridMessage::AddFluidZone {
+                    rect,
+                    density,
+                    drag,
+                } => {
+                    self.add_fluid_zone(FluidZone::new(rect, density, drag));
+                }
+                GridMessage::AddSpawner(spawner) => {
+                    self.add_spawner(spawner);
+                }
+                GridMessage::AddSoftBody(soft_body) => {
+                    // Half a soft body would be a different shape.
+                    if soft_body.circles.len() > self.room_for_circles() {
+                        debug!("Not adding a soft body: the grid is full.");
+                        continue;
+                    }
+                    self.add_connected_circles(
+                        soft_body.circles,
+                        soft_body.springs,
+                        Vec::new(),
+                        Vec::new(),
+                    );
+                }
+                GridMessage::AddRope {
+                    anchor,
+                    segments,
+                    length,
+                    attached_to,
+                } => {
+                    self.add_rope(anchor, segments, length, attached_to);
+                }
+                GridMessage::AddPinJoint { body, anchor } => {
+                    self.add_joint(body, LinkedAnchor::Point(anchor));
+                }
+                GridMessage::AddRevoluteJoint { a, b } => {
+                    self.add_joint(a, LinkedAnchor::Circle(b));
+                }
+                GridMessage::RemoveSpawner(id) => {
+                    if self.spawners.iter().any(|spawner| spawner.id == id) {
+                        self.remove(id);
+                    }
+                }
+                GridMessage::SetSpawnerEnabled(id, enabled) => {
+                    if let Some(spawner) = self.spawners.iter_mut().find(|spawner| spawner.id == id)
+                    {
+                        spawner.enabled = enabled;
+                    }
+                }
+                GridMessage::AddStaticSegment { a, b, thickness } => {
+                    self.add_static_capsule(StaticCapsule::new(a, b, thickness / 2.0));
+                }
+                GridMessage::Resize(size) => self.resize(size),
+                GridMessage::UpdateConfig(config) => self.update_config(config),
+                GridMessage::SetComparisonConfig(config) => self.set_comparison_config(config),
+                GridMessage::SetGravity(gravity) => {
+                    self.config.gravity = gravity;
+                    self.builtin_forces = BuiltinForces::new(&self.config, self.drag_model);
+                    self.wake_all();
+                }
+                GridMessage::SetTimeScale(time_scale) => {
+                    self.config.time_scale = time_scale;
+                }
+                GridMessage::SetPaused(paused) => self.paused = paused,
+                GridMessage::Step => step_requested = true,
+                GridMessage::StepBack => {
+                    let mut rewind_buffer = std::mem::take(&mut self.rewind_buffer);
+                    if rewind_buffer.step_back(self) {
+                        self.paused = true;
+                    }
+                    self.rewind_buffer = rewind_buffer;
+                }
+                GridMessage::ClearDynamic => {
+                    for &id in &self.bodies.ids {
+                        self.entities.free(id);
+                    }
+                    self.bodies.clear();
+                    self.springs.clear();
+                    self.ropes.clear();
+                    self.joints.clear();
+                }
+                GridMessage::ClearStatic => self.clear_static(),
+                GridMessage::ResetAll => self.load_scene(Scene::default()),
+                GridMessage::LoadScene(scene) => self.load_scene(scene),
+                GridMessage::Remove(id) => self.remove(id),
+                GridMessage::SetVelocity(id, velocity) => {
+                    if let Some(i) = self.bodies.index_of(id) {
+                        self.bodies.velocities[i] = velocity;
+                        self.bodies.wake(i);
+                    }
+                }
+                GridMessage::UpdateBody(id, properties) => self.update_body(id, properties),
+                GridMessage::Explode {
+                    x,
+                    y,
+                    strength,
+                    radius,
+                } => {
+                    self.explode(Point::new(x, y), strength, radius);
+                }
+                GridMessage::SpawnBurst { x, y } => self.spawn_burst(Point::new(x, y)),
+                GridMessage::Query(query, result_sender) => {
+                    let result = match query {
+                        Query::Point(point) => QueryResult::Entities(self.query_point(point)),
+                        Query::Ray { origin, direction } => {
+                            QueryResult::Hit(self.raycast(origin, direction))
+                        }
+                        Query::Aabb(rectangle) => QueryResult::Entities(self.query_aabb(rectangle)),
+                    };
+                    // The sender may not care about the result anymore.
+                    let _ = result_sender.send(result);
+                }
+                GridMessage::Batch(_) => {
+                    unreachable!("batches are unpacked before they're applied")
+                }
+            }
+        }
+        messages_span.exit();
+        // Done even while paused, so that circles left outside by a resize
+        // or added there don't wait for the simulation to be stepped.
+        self.recover_out_of_bounds_circles(&mut events.removals);
+
+        for (details, &position) in self.bodies.details.iter_mut().zip(&self.bodies.positions) {
+            details.previous_position = position;
+        }
+        for kinematic_rectangle in &mut self.kinematic_rectangles {
+            kinematic_rectangle.previous_center = kinematic_rectangle.center();
+        }
+        for kinematic_spinner in &mut self.kinematic_spinners {
+            kinematic_spinner.previous_angle = kinematic_spinner.angle;
+        }
+
+        // While paused, messages are still applied so that the scene can be
+        // edited, but the simulation only advances when stepped.
+        let mut phases = PhaseTimings::default();
+        let stepped = (!self.paused || step_requested) && ticks > 0.0 && ticks.is_finite();
+        let (collision_pair_count, sub_ticks) = if stepped {
+            self.advance(ticks, &mut events, &mut phases)
+        } else {
+            (0, 0)
+        };
+        self.enforce_population_cap(&mut events.removals);
+        trace!(
+            sub_ticks,
+            collision_pair_count,
+            duration = ?tick_start.elapsed(),
+            "Ticked."
+        );
+
+        if stepped {
+            let mut rewind_buffer = std::mem::take(&mut self.rewind_buffer);
+            rewind_buffer.record(self);
+            self.rewind_buffer = rewind_buffer;
+        }
+
+        self.frame_number += 1;
+
+        let snapshot_start = Instant::now();
+        let mut grid_frame = GridFrame {
+            frame_number: self.frame_number,
+            width: self.width,
+            height: self.height,
+            circles: self.bodies.iter().collect(),
+            static_circles: self.static_circles.clone(),
+            static_rectangles: self.static_rectangles.clone(),
+            static_capsules: self.static_capsules.clone(),
+            kinematic_rectangles: self.kinematic_rectangles.clone(),
+            kinematic_spinners: self.kinematic_spinners.clone(),
+            portal_pairs: self.portal_pairs.clone(),
+            sinks: self.sinks.clone(),
+            fluid_zones: self.fluid_zones.clone(),
+            gravity_wells: self.gravity_wells.clone(),
+            spawners: self.spawners.clone(),
+            springs: self.springs.clone(),
+            ropes: self.ropes.clone(),
+            joints: self.joints.clone(),
+            drag_model: self.drag_model,
+            timing: FrameTiming {
+                tick_duration: Duration::ZERO,
+                phases,
+                frame_interval: Duration::ZERO,
+            },
+            applied_message_count,
+            dropped_message_count: self
+                .message_receiver
+                .as_ref()
+                .map_or(0, GridMessageReceiver::get_dropped_count),
+            collision_pair_count,
+            total_energy: self.total_energy(),
+            events,
+            sub_tick_count: sub_ticks,
+            rewind_buffered_ticks: self.rewind_buffer.buffered_ticks(),
+            interpolation: 1.0,
+            comparison: None,
+        };
+        grid_frame.timing.phases.snapshot = snapshot_start.elapsed();
+        grid_frame.timing.tick_duration = tick_start.elapsed();
+
+        // Ticked after this grid's timing is taken, so that it only covers
+        // this grid.
+        grid_frame.comparison = self
+            .comparison
+            .as_mut()
+            .map(|comparison| Box::new(comparison.tick_for(ticks, comparison_messages)));
+        grid_frame
+    }
+
+    // Advances the simulation by `ticks` ticks' worth of time, adding
+    // everything that happens to `events` and how long each phase took to
+    // `phases`. Returns the number of circle-circle collisions resolved and
+    // the number of subticks used.
+    fn advance(
+        &mut self,
+        ticks: f32,
+        events: &mut FrameEvents,
+        phases: &mut PhaseTimings,
+    ) -> (u32, u32) {
+        let first_collision_event = events.collisions.len();
+        let collision_events = &mut events.collisions;
+        let mut collision_pair_count = 0;
+        let restitution = Restitution {
+            coefficient: self.config.elasticity_coefficient,
+            threshold: self.config.restitution_threshold,
+        };
+        let wake_speed_threshold = self.config.sleep_speed_threshold * WAKE_SPEED_MULTIPLIER;
+        let time_scale = self.time_scale() * ticks;
+
+        for (radius, details) in self.bodies.radii.iter_mut().zip(&mut self.bodies.details) {
+            if let Some(LifetimePolicy::ShrinkAndDie { rate }) = details.lifetime {
+                *radius *= (1.0 - rate).powf(time_scale);
+            }
+            details.age = details.age.saturating_add(1);
+
+            details.portal_cooldown = details.portal_cooldown.saturating_sub(1);
+        }
+        self.remove_dead_circles(&mut events.removals);
+
+        self.run_spawners();
+
+        // Springs go with either of their circles. Circles don't change
+        // places in the list until the end of the tick, so the spring ends
+        // can be looked up once.
+        let circle_indices: HashMap<EntityId, usize> = self
+            .bodies
+            .ids
+            .iter()
+            .enumerate()
+            .map(|(i, &id)| (id, i))
+            .collect();
+        let spring_ends: Vec<(usize, usize)> = {
+            self.springs.retain(|spring| {
+                circle_indices.contains_key(&spring.a) && circle_indices.contains_key(&spring.b)
+            });
+            self.springs
+                .iter()
+                .map(|spring| (circle_indices[&spring.a], circle_indices[&spring.b]))
+                .collect()
+        };
+        // A rope that lost a link ends where the link was, and one that lost
+        // the circle it hangs from is gone.
+        let rope_links: Vec<(Option<usize>, Vec<usize>)> = {
+            self.ropes.retain_mut(|rope| {
+                if let Some(missing) = rope
+                    .circles
+                    .iter()
+                    .position(|id| !circle_indices.contains_key(id))
+                {
+                    rope.circles.truncate(missing);
+                }
+                let anchored = match rope.anchor {
+                    LinkedAnchor::Point(_) => true,
+                    LinkedAnchor::Circle(id) => circle_indices.contains_key(&id),
+                };
+                anchored && !rope.circles.is_empty()
+            });
+            self.ropes
+                .iter()
+                .map(|rope| {
+                    let anchor_index = match rope.anchor {
+                        LinkedAnchor::Point(_) => None,
+                        LinkedAnchor::Circle(id) => Some(circle_indices[&id]),
+                    };
+                    let link_indices = rope.circles.iter().map(|id| circle_indices[id]).collect();
+                    (anchor_index, link_indices)
+                })
+                .collect()
+        };
+
+        let joint_ends: Vec<(usize, Option<usize>)> = {
+            self.joints.retain(|joint| {
+                circle_indices.contains_key(&joint.body)
+                    && match joint.anchor {
+                        LinkedAnchor::Point(_) => true,
+                        LinkedAnchor::Circle(id) => circle_indices.contains_key(&id),
+                    }
+            });
+            self.joints
+                .iter()
+                .map(|joint| {
+                    let anchor_index = match joint.anchor {
+                        LinkedAnchor::Point(_) => None,
+                        LinkedAnchor::Circle(id) => Some(circle_indices[&id]),
+                    };
+                    (circle_indices[&joint.body], anchor_index)
+                })
+                .collect()
+        };
+
+        // Kinematic bodies move along their paths at a constant velocity
+        // within each tick, so that circles are pushed along smoothly. They
+        // cover `time_scale` ticks' worth of their paths, but push circles
+        // at their unscaled speed, since that's what circles' velocities are
+        // measured against.
+        let kinematic_motions: Vec<((f32, f32), (f32, f32))> = self
+            .kinematic_rectangles
+            .iter_mut()
+            .map(|kinematic_rectangle| {
+                let start = kinematic_rectangle.center();
+                kinematic_rectangle.elapsed_ticks += time_scale;
+                let end = kinematic_rectangle.center();
+                (start, (end.0 - start.0, end.1 - start.1))
+            })
+            .collect();
+        let spinner_start_angles: Vec<f32> = self
+            .kinematic_spinners
+            .iter_mut()
+            .map(|kinematic_spinner| {
+                let start = kinematic_spinner.angle;
+                kinematic_spinner.angle = (start + kinematic_spinner.angular_velocity * time_scale)
+                    .rem_euclid(std::f32::consts::TAU);
+                start
+            })
+            .collect();
+
+        let sub_ticks = self.required_sub_ticks(time_scale);
+        // Spread the grip over the subticks so that it doesn't depend on how
+        // many there are.
+        let conveyor_grip = 1.0 - (1.0 - CONVEYOR_GRIP).powf(time_scale / sub_ticks as f32);
+
+        let integrator = self.config.integrator.integrator();
+        let position_based = self.config.solver == Solver::PositionBased;
+        let mut start_velocities = Vec::with_capacity(self.bodies.len());
+        let mut start_positions = Vec::with_capacity(self.bodies.len());
+        let mut candidate_pairs = Vec::new();
+
+        for sub_tick in 0..sub_ticks {
+            self.wrap_around();
+
+            start_velocities.clear();
+            start_velocities.extend_from_slice(&self.bodies.velocities);
+            start_positions.clear();
+            start_positions.extend_from_slice(&self.bodies.positions);
+
+            // Apply gravity, drag and any registered forces.
+            let dt = time_scale / sub_ticks as f32;
+            let phase_start = Instant::now();
+            for force_generator in self.builtin_forces.generators() {
+                force_generator.apply(&mut self.bodies, dt);
+            }
+            for force_generator in &mut self.force_generators {
+                force_generator.apply(&mut self.bodies, dt);
+            }
+            for fluid_zone in &self.fluid_zones {
+                for i in 0..self.bodies.len() {
+                    if !self.bodies.sleeping[i] {
+                        self.bodies
+                            .update_body(i, |body| fluid_zone.apply(body, self.config.gravity, dt));
+                    }
+                }
+            }
+            for gravity_well in &self.gravity_wells {
+                for i in 0..self.bodies.len() {
+                    if !self.bodies.sleeping[i] {
+                        self.bodies
+                            .update_body(i, |body| gravity_well.apply(body, dt));
+                    }
+                }
+            }
+            for (k, &(i, j)) in spring_ends.iter().enumerate() {
+                let spring = self.springs[k];
+                self.bodies.update_pair(i, j, |body_a, body_b| {
+                    Self::apply_spring(body_a, body_b, &spring, dt)
+                });
+            }
+            phases.forces += phase_start.elapsed();
+
+            // Move circles with the configured integrator.
+            let bodies = &mut self.bodies;
+            integrator.integrate(
+                &mut bodies.positions,
+                &bodies.velocities,
+                &start_velocities,
+                &bodies.sleeping,
+                dt,
+            );
+
+            // Bounce circles off the walls, applying friction.
+            let boundaries = self.config.boundaries;
+            if !position_based {
+                for (i, (position, velocity)) in bodies
+                    .positions
+                    .iter_mut()
+                    .zip(&mut bodies.velocities)
+                    .enumerate()
+                {
+                    if bodies.sleeping[i] {
+                        continue;
+                    }
+                    let radius = bodies.radii[i];
+                    let restitution = Restitution {
+                        coefficient: bodies.restitutions[i].unwrap_or(restitution.coefficient),
+                        ..restitution
+                    };
+
+                    if boundaries.left == BoundaryMode::Bounce && position.0 - radius < 0.0 {
+                        position.0 = radius;
+                        velocity.0 = -velocity.0 * restitution.coefficient_at(velocity.0.abs());
+                    }
+
+                    if boundaries.right == BoundaryMode::Bounce && position.0 + radius > self.width
+                    {
+                        position.0 = self.width - radius;
+                        velocity.0 = -velocity.0 * restitution.coefficient_at(velocity.0.abs());
+                    }
+
+                    if boundaries.top == BoundaryMode::Bounce && position.1 - radius < 0.0 {
+                        position.1 = radius;
+                        velocity.1 = -velocity.1 * restitution.coefficient_at(velocity.1.abs());
+                    }
+
+                    if boundaries.bottom == BoundaryMode::Bounce
+                        && position.1 + radius > self.height
+                    {
+                        position.1 = self.height - radius;
+                        velocity.1 = -velocity.1 * restitution.coefficient_at(velocity.1.abs());
+                    }
+                }
+            }
+
+            // Find the pairs of circles that might be touching.
+            let phase_start = Instant::now();
+            candidate_pairs.clear();
+            self.broadphase.find_pairs(
+                &self.bodies.positions,
+                &self.bodies.radii,
+                &mut candidate_pairs,
+            );
+            phases.broadphase += phase_start.elapsed();
+
+            // Bounce the candidate pairs off each other, going over them
+            // several times so that pushes can travel through a pile. Each
+            // collision is only counted once.
+            let phase_start = Instant::now();
+            let iterations = self.config.solver_iterations.max(1);
+            let position_correction = self.config.position_correction;
+            if position_based {
+                for iteration in 0..iterations {
+                    self.clamp_to_walls();
+
+                    for &(i, j) in &candidate_pairs {
+                        // Most candidate pairs aren't touching, so check
+                        // straight from the arrays before copying the pair out.
+                        let bodies = &self.bodies;
+                        if bodies.sleeping[i] && bodies.sleeping[j] {
+                            continue;
+                        }
+                        let dx = bodies.positions[j].0 - bodies.positions[i].0;
+                        let dy = bodies.positions[j].1 - bodies.positions[i].1;
+                        if (dx * dx + dy * dy).sqrt() >= bodies.radii[i] + bodies.radii[j] {
+                            continue;
+                        }
+
+                        let contact = self.bodies.update_pair(i, j, |circle_a, circle_b| {
+                            Self::project_apart(
+                                circle_a,
+                                circle_b,
+                                wake_speed_threshold,
+                                position_correction,
+                            )
+                        });
+                        if let Some(contact) = contact.filter(|_| iteration == 0) {
+                            collision_pair_count += 1;
+                            collision_events.extend(
+                                contact
+                                    .into_collision_event(self.bodies.ids[i], self.bodies.ids[j]),
+                            );
+                        }
+                    }
+                }
+            } else {
+                self.contact_solver.find_contacts(
+                    &mut self.bodies,
+                    &candidate_pairs,
+                    restitution,
+                    self.config.friction_coefficient,
+                    wake_speed_threshold,
+                    dt,
+                );
+                self.contact_solver
+                    .solve_velocities(&mut self.bodies, iterations);
+                for _ in 0..iterations {
+                    self.contact_solver
+                        .solve_positions(&mut self.bodies, position_correction);
+                }
+                for (i, j, contact) in self.contact_solver.finish(&self.bodies, dt) {
+                    collision_pair_count += 1;
+                    collision_events.extend(
+                        contact.into_collision_event(self.bodies.ids[i], self.bodies.ids[j]),
+                    );
+                }
+            }
+
+            if position_based {
+                self.clamp_to_walls();
+
+                let bodies = &mut self.bodies;
+                for (i, velocity) in bodies.velocities.iter_mut().enumerate() {
+                    if !bodies.sleeping[i] {
+                        let (position, start_position) = (bodies.positions[i], start_positions[i]);
+                        *velocity = (
+                            (position.0 - start_position.0) / dt,
+                            (position.1 - start_position.1) / dt,
+                        );
+                    }
+                }
+            }
+            phases.narrowphase += phase_start.elapsed();
+
+            // Handle collisions between dynamic circles and static circles
+            let phase_start = Instant::now();
+            self.bodies.update_awake_bodies(|id, circle| {
+                for static_circle in &self.static_circles {
+                    let contact =
+                        Self::circle_static_circle_collision(circle, static_circle, restitution);
+                    collision_events
+                        .extend(contact.and_then(|contact| {
+                            contact.into_collision_event(id, static_circle.id)
+                        }));
+                }
+            });
+
+            // Handle collisions between dynamic circles and static rectangles
+            self.bodies.update_awake_bodies(|id, circle| {
+                for static_rectangle in &self.static_rectangles {
+                    let contact = Self::circle_static_rectangle_collision(
+                        circle,
+                        static_rectangle,
+                        restitution,
+                    );
+                    if let Some(contact) = &contact {
+                        Self::apply_conveyor(
+                            circle,
+                            contact,
+                            static_rectangle.surface_velocity,
+                            conveyor_grip,
+                        );
+                    }
+                    collision_events.extend(
+                        contact.and_then(|contact| {
+                            contact.into_collision_event(id, static_rectangle.id)
+                        }),
+                    );
+                }
+            });
+
+            // Handle collisions between dynamic circles and static capsules
+            self.bodies.update_awake_bodies(|id, circle| {
+                for static_capsule in &self.static_capsules {
+                    let contact =
+                        Self::circle_static_capsule_collision(circle, static_capsule, restitution);
+                    if let Some(contact) = &contact {
+                        Self::apply_conveyor(
+                            circle,
+                            contact,
+                            static_capsule.surface_velocity,
+                            conveyor_grip,
+                        );
+                    }
+                    collision_events.extend(
+                        contact.and_then(|contact| {
+                            contact.into_collision_event(id, static_capsule.id)
+                        }),
+                    );
+                }
+            });
+
+            // Handle collisions between dynamic circles and kinematic
+            // rectangles. Sleeping circles are included, since a kinematic
+            // body can run into them.
+            let sub_tick_progress = (sub_tick + 1) as f32 / sub_ticks as f32;
+            for (kinematic_rectangle, &(start, displacement)) in
+                self.kinematic_rectangles.iter().zip(&kinematic_motions)
+            {
+                let bounds = kinematic_rectangle.bounds_at((
+                    start.0 + displacement.0 * sub_tick_progress,
+                    start.1 + displacement.1 * sub_tick_progress,
+                ));
+                let velocity = (displacement.0 / time_scale, displacement.1 / time_scale);
+                self.bodies.update_all_bodies(|id, circle| {
+                    let contact =
+                        Self::circle_rectangle_collision(circle, bounds, velocity, restitution);
+                    if contact.is_some() {
+                        circle.wake();
+                    }
+                    collision_events.extend(contact.and_then(|contact| {
+                        contact.into_collision_event(id, kinematic_rectangle.id)
+                    }));
+                });
+            }
+
+            // Handle collisions between dynamic circles and kinematic
+            // spinners, including sleeping circles for the same reason.
+            for (kinematic_spinner, &start_angle) in
+                self.kinematic_spinners.iter().zip(&spinner_start_angles)
+            {
+                let angle = start_angle
+                    + kinematic_spinner.angular_velocity * time_scale * sub_tick_progress;
+                self.bodies.update_all_bodies(|id, circle| {
+                    let contact = Self::circle_spinner_collision(
+                        circle,
+                        kinematic_spinner,
+                        angle,
+                        restitution,
+                    );
+                    if contact.is_some() {
+                        circle.wake();
+                    }
+                    collision_events.extend(contact.and_then(|contact| {
+                        contact.into_collision_event(id, kinematic_spinner.id)
+                    }));
+                });
+            }
+            phases.static_collisions += phase_start.elapsed();
+
+            // Pull tethered circles back within reach of their anchors
+            for i in 0..self.bodies.len() {
+                if let Some(tether) = self.bodies.details[i].tether.clone() {
+                    if !self.bodies.sleeping[i] {
+                        self.bodies.update_body(i, |circle| {
+                            limit_distance_to_point(circle, tether.anchor, tether.length)
+                        });
+                    }
+                }
+            }
+
+            // Pull the links of every rope back together, starting from the
+            // anchor end
+            for _ in 0..ROPE_SOLVER_ITERATIONS {
+                for (rope, (anchor_index, link_indices)) in self.ropes.iter().zip(&rope_links) {
+                    match (rope.anchor, anchor_index) {
+                        (LinkedAnchor::Circle(_), Some(anchor_index)) => {
+                            self.bodies.update_pair(
+                                *anchor_index,
+                                link_indices[0],
+                                |anchor_circle, first_link| {
+                                    limit_distance(anchor_circle, first_link, rope.anchor_length)
+                                },
+                            );
+                        }
+                        (LinkedAnchor::Point(point), _) => {
+                            if !self.bodies.sleeping[link_indices[0]] {
+                                self.bodies.update_body(link_indices[0], |first_link| {
+                                    limit_distance_to_point(first_link, point, rope.anchor_length)
+                                });
+                            }
+                        }
+                        (LinkedAnchor::Circle(_), None) => {}
+                    }
+                    for pair in link_indices.windows(2) {
+                        self.bodies.update_pair(pair[0], pair[1], |link_a, link_b| {
+                            limit_distance(link_a, link_b, rope.segment_length)
+                        });
+                    }
+                }
+            }
+
+            // Put jointed circles back at their joint lengths
+            for _ in 0..JOINT_SOLVER_ITERATIONS {
+                for (joint, &(body_index, anchor_index)) in self.joints.iter().zip(&joint_ends) {
+                    match (joint.anchor, anchor_index) {
+                        (LinkedAnchor::Point(point), _) => {
+                            if !self.bodies.sleeping[body_index] {
+                                self.bodies.update_body(body_index, |body| {
+                                    hold_distance_to_point(body, point, joint.length)
+                                });
+                            }
+                        }
+                        (LinkedAnchor::Circle(_), Some(anchor_index)) => {
+                            self.bodies.update_pair(
+                                body_index,
+                                anchor_index,
+                                |body, anchor_circle| {
+                                    hold_distance(body, anchor_circle, joint.length)
+                                },
+                            );
+                        }
+                        (LinkedAnchor::Circle(_), None) => {}
+                    }
+                }
+            }
+        }
+
+        if let Some(impulse_threshold) = self.config.fracture_impulse_threshold {
+            self.fracture_struck_circles(
+                &events.collisions[first_collision_event..],
+                impulse_threshold,
+                &mut events.removals,
+            );
+        }
+        if let Some(max_radius) = self.config.coalesce_max_radius {
+            self.coalesce_colliding_circles(
+                &events.collisions[first_collision_event..],
+                max_radius,
+                &mut events.removals,
+            );
+        }
+        self.teleport_through_portals();
+        self.drain_into_sinks(&mut events.removals);
+        self.update_sleep_states();
+
+        (collision_pair_count, sub_ticks)
+    }
+
+    // Moves every awake circle whose center is inside a portal to the same
+    // spot in the portal it's linked to, turning its velocity by the pair's
+    // rotation on the way.
+    fn teleport_through_portals(&mut self) {
+        let bodies = &mut self.bodies;
+        for i in 0..bodies.len() {
+            if bodies.sleeping[i] || bodies.details[i].portal_cooldown > 0 {
+                continue;
+            }
+
+            let center = Point::new(bodies.positions[i].0, bodies.positions[i].1);
+            let Some((entrance, exit, rotation)) =
+                self.portal_pairs.iter().find_map(|portal_pair| {
+                    if portal_pair.a.contains(center) {
+                        Some((&portal_pair.a, &portal_pair.b, portal_pair.rotation))
+                    } else if portal_pair.b.contains(center) {
+                        Some((&portal_pair.b, &portal_pair.a, -portal_pair.rotation))
+                    } else {
+                        None
+                    }
+                })
+            else {
+                continue;
+            };
+
+            let offset = rotate(center - entrance.center, rotation)
+                * (exit.radius / entrance.radius.max(f32::EPSILON));
+            let velocity = rotate(
+                Vector::new(bodies.velocities[i].0, bodies.velocities[i].1),
+                rotation,
+            );
+            bodies.positions[i] = (exit.center.x + offset.x, exit.center.y + offset.y);
+            bodies.velocities[i] = (velocity.x, velocity.y);
+            // Don't draw the circle streaking across the screen.
+            bodies.details[i].previous_position = bodies.positions[i];
+            bodies.details[i].portal_cooldown = PORTAL_COOLDOWN_TICKS;
+        }
+    }
+
+    // Adds a circle for every enabled spawner that's due, unless it already
+    // has as many circles alive as it's allowed.
+    fn run_spawners(&mut self) {
+        let mut spawned_circles = Vec::new();
+        for spawner in self.spawners.iter_mut().filter(|spawner| spawner.enabled) {
+            if spawner.ticks_until_spawn > 0 {
+                spawner.ticks_until_spawn -= 1;
+                continue;
+            }
+            spawner.ticks_until_spawn = spawner.interval.saturating_sub(1);
+
+            if let Some(max_alive) = spawner.max_alive {
+                let alive = self
+                    .bodies
+                    .details
+                    .iter()
+                    .filter(|details| details.spawner == Some(spawner.id))
+                    .count();
+                if alive >= max_alive as usize {
+                    continue;
+                }
+            }
+
+            let mut circle = Circle::new(
+                spawner.position.x,
+                spawner.position.y,
+                spawner.radius,
+                spawner.velocity.sample(&mut self.rng),
+            );
+            circle.spawner = Some(spawner.id);
+            circle.lifetime = spawner.lifetime;
+            self.config.spawn_profile.apply(&mut circle, &mut self.rng);
+            // The spawner was checked when it was added, but the profile
+            // wasn't.
+            match validation::validate_circle(&circle) {
+                Ok(()) => spawned_circles.push(circle),
+                Err(problem) => warn!("Not spawning a circle: {problem}."),
+            }
+        }
+
+        let room = self.room_for_circles();
+        for circle in spawned_circles.into_iter().take(room) {
+            self.add_circle(circle);
+        }
+    }
+
+    // Removes every circle that its lifetime policy says is done, and every
+    // circle that has left the world through an edge that despawns circles.
+    fn remove_dead_circles(&mut self, removal_events: &mut Vec<RemovalEvent>) {
+        let min_radius_size = self.config.min_radius_size;
+        let boundaries = self.config.boundaries;
+        let (width, height) = (self.width, self.height);
+        let entities = &mut self.entities;
+        self.bodies.retain(|circle| {
+            let despawn =
+                |mode: BoundaryMode, past_edge: bool| mode == BoundaryMode::Despawn && past_edge;
+            let despawned = despawn(boundaries.left, circle.x_pos + circle.radius < 0.0)
+                || despawn(boundaries.right, circle.x_pos - circle.radius > width)
+                || despawn(boundaries.top, circle.y_pos + circle.radius < 0.0)
+                || despawn(boundaries.bottom, circle.y_pos - circle.radius > height);
+
+            let cause = match circle.lifetime {
+                _ if despawned => RemovalCause::Offscreen,
+                Some(LifetimePolicy::ShrinkAndDie { .. }) if circle.radius < min_radius_size => {
+                    RemovalCause::Expired
+                }
+                Some(LifetimePolicy::Ttl { frames }) if circle.age >= frames => {
+                    RemovalCause::Expired
+                }
+                Some(LifetimePolicy::DespawnOffscreen)
+                    if circle.x_pos + circle.radius < 0.0
+                        || circle.x_pos - circle.radius > width
+                        || circle.y_pos + circle.radius < 0.0
+                        || circle.y_pos - circle.radius > height =>
+                {
+                    RemovalCause::Offscreen
+                }
+                _ => return true,
+            };
+
+            entities.free(circle.id);
+            removal_events.push(RemovalEvent {
+                id: circle.id,
+                point: (circle.x_pos, circle.y_pos),
+                cause,
+            });
+            false
+        });
+    }
+
+    // Clamps or removes circles that are entirely outside the world, past an
+    // edge that doesn't wrap, as `SimulationConfig::out_of_bounds` says.
+    fn recover_out_of_bounds_circles(&mut self, removal_events: &mut Vec<RemovalEvent>) {
+        let boundaries = self.config.boundaries;
+        let (width, height) = (self.width, self.height);
+        let past = |mode: BoundaryMode, past_edge: bool| mode != BoundaryMode::Wrap && past_edge;
+        match self.config.out_of_bounds {
+            OutOfBoundsPolicy::Keep => {}
+            OutOfBoundsPolicy::Clamp => self.bodies.update_all_bodies(|_, circle| {
+                let (x_pos, y_pos) = (circle.x_pos, circle.y_pos);
+                if past(boundaries.left, circle.x_pos + circle.radius < 0.0) {
+                    circle.x_pos = circle.radius;
+                    circle.velocity.0 = circle.velocity.0.max(0.0);
+                } else if past(boundaries.right, circle.x_pos - circle.radius > width) {
+                    circle.x_pos = width - circle.radius;
+                    circle.velocity.0 = circle.velocity.0.min(0.0);
+                }
+                if past(boundaries.top, circle.y_pos + circle.radius < 0.0) {
+                    circle.y_pos = circle.radius;
+                    circle.velocity.1 = circle.velocity.1.max(0.0);
+                } else if past(boundaries.bottom, circle.y_pos - circle.radius > height) {
+                    circle.y_pos = height - circle.radius;
+                    circle.velocity.1 = circle.velocity.1.min(0.0);
+                }
+                if (circle.x_pos, circle.y_pos) != (x_pos, y_pos) {
+                    circle.wake();
+                }
+            }),
+            OutOfBoundsPolicy::Despawn => {
+                let entities = &mut self.entities;
+                self.bodies.retain(|circle| {
+                    let outside = past(boundaries.left, circle.x_pos + circle.radius < 0.0)
+                        || past(boundaries.right, circle.x_pos - circle.radius > width)
+                        || past(boundaries.top, circle.y_pos + circle.radius < 0.0)
+                        || past(boundaries.bottom, circle.y_pos - circle.radius > height);
+                    if outside {
+                        entities.free(circle.id);
+                        removal_events.push(RemovalEvent {
+                            id: circle.id,
+                            point: (circle.x_pos, circle.y_pos),
+                            cause: RemovalCause::Offscreen,
+                        });
+                    }
+                    !outside
+                });
+            }
+        }
+    }
+
+    // Removes circles until there are no more than `max_circles`, picking
+    // which ones to remove with the eviction policy. With `RejectNew`,
+    // circles were turned away as they were added instead.
+    fn enforce_population_cap(&mut self, removal_events: &mut Vec<RemovalEvent>) {
+        let Some(max_circles) = self.config.max_circles else {
+            return;
+        };
+        let excess = self.bodies.len().saturating_sub(max_circles);
+        if excess == 0 {
+            return;
+        }
+
+        // Circles are kept in the order they were added, so later ones are
+        // newer. The sorts are stable, which breaks ties the same way.
+        let mut eviction_order: Vec<usize> = (0..self.bodies.len()).collect();
+        match self.config.eviction_policy {
+            EvictionPolicy::OldestFirst => {
+                eviction_order.sort_by_key(|&i| std::cmp::Reverse(self.bodies.details[i].age));
+            }
+            EvictionPolicy::SmallestFirst => {
+                let radii = &self.bodies.radii;
+                eviction_order.sort_by(|&i, &j| radii[i].total_cmp(&radii[j]));
+            }
+            EvictionPolicy::RejectNew => return,
+        }
+
+        let mut evicted = vec![false; self.bodies.len()];
+        for &i in &eviction_order[..excess] {
+            evicted[i] = true;
+        }
+
+        let entities = &mut self.entities;
+        let mut evicted = evicted.into_iter();
+        self.bodies.retain(|circle| {
+            if !evicted.next().unwrap_or(false) {
+                return true;
+            }
+            entities.free(circle.id);
+            removal_events.push(RemovalEvent {
+                id: circle.id,
+                point: (circle.x_pos, circle.y_pos),
+                cause: RemovalCause::Evicted,
+            });
+            false
+        });
+    }
+
+    // Removes every circle whose center is inside a sink.
+    fn drain_into_sinks(&mut self, removal_events: &mut Vec<RemovalEvent>) {
+        let sinks = &self.sinks;
+        let entities = &mut self.entities;
+        self.bodies.retain(|circle| {
+            let center = Point::new(circle.x_pos, circle.y_pos);
+            let Some(sink) = sinks.iter().find(|sink| sink.bounds().contains(center)) else {
+                return true;
+            };
+
+            entities.free(circle.id);
+            removal_events.push(RemovalEvent {
+                id: circle.id,
+                point: (circle.x_pos, circle.y_pos),
+                cause: RemovalCause::Sink(sink.id),
+            });
+            false
+        });
+    }
+
+    // Breaks every circle that was in a collision with at least
+    // `impulse_threshold` of impulse into `fracture_pieces` smaller circles of
+    // the same total area, each moving with the original's velocity so that
+    // momentum is conserved. Circles whose pieces would be smaller than
+    // `min_radius_size` stay whole, which stops fragments from breaking up
+    // forever.
+    fn fracture_struck_circles(
+        &mut self,
+        collisions: &[CollisionEvent],
+        impulse_threshold: f32,
+        removal_events: &mut Vec<RemovalEvent>,
+    ) {
+        let piece_count = self.config.fracture_pieces.max(2);
+        let piece_scale = (piece_count as f32).sqrt().recip();
+
+        let struck_ids: HashSet<EntityId> = collisions
+            .iter()
+            .filter(|collision| collision.impulse >= impulse_threshold)
+            .flat_map(|collision| [collision.a, collision.b])
+            .collect();
+        if struck_ids.is_empty() {
+            return;
+        }
+
+        let mut pieces = Vec::new();
+        // A fracture adds one circle fewer than it has pieces, since the
+        // original goes. Circles there isn't room to fracture stay whole.
+        let mut room = self.room_for_circles();
+        let added_per_fracture = piece_count as usize - 1;
+        let entities = &mut self.entities;
+        let rng = &mut self.rng;
+        let min_radius = self.config.min_radius_size;
+        self.bodies.retain(|circle| {
+            let piece_radius = circle.radius * piece_scale;
+            if piece_radius < min_radius
+                || !struck_ids.contains(&circle.id)
+                || room < added_per_fracture
+            {
+                return true;
+            }
+            room -= added_per_fracture;
+
+            // Lay the pieces out in a ring inside the original, starting at a
+            // random angle so that fractures don't all look the same.
+            let start_angle = rng.range(0.0, std::f32::consts::TAU);
+            let ring_radius = circle.radius - piece_radius;
+            for k in 0..piece_count {
+                let angle = start_angle + std::f32::consts::TAU * k as f32 / piece_count as f32;
+                let offset = (ring_radius * angle.cos(), ring_radius * angle.sin());
+                let mut piece = Circle::new(
+                    circle.x_pos + offset.0,
+                    circle.y_pos + offset.1,
+                    piece_radius,
+                    circle.velocity,
+                );
+                piece.density = circle.density;
+                piece.charge = circle.charge / piece_count as f32;
+                piece.drag_model = circle.drag_model;
+                piece.friction = circle.friction;
+                piece.restitution = circle.restitution;
+                piece.color = circle.color;
+                piece.style = circle.style;
+                piece.lifetime = circle.lifetime;
+                piece.age = circle.age;
+                piece.previous_position = (
+                    circle.previous_position.0 + offset.0,
+                    circle.previous_position.1 + offset.1,
+                );
+                pieces.push(piece);
+            }
+
+            entities.free(circle.id);
+            removal_events.push(RemovalEvent {
+                id: circle.id,
+                point: (circle.x_pos, circle.y_pos),
+                cause: RemovalCause::Fractured,
+            });
+            false
+        });
+
+        for piece in pieces {
+            self.add_circle(piece);
+        }
+    }
+
+    // Merges the two circles of every circle-circle collision in `collisions`
+    // into one, as long as the merged circle would be no bigger than
+    // `max_radius`. The heavier circle survives, taking on the pair's combined
+    // area, mass and momentum, and the other is removed. A circle merges at
+    // most once per tick, and any springs, ropes or joints on the removed
+    // circle are dropped with it.
+    fn coalesce_colliding_circles(
+        &mut self,
+        collisions: &[CollisionEvent],
+        max_radius: f32,
+        removal_events: &mut Vec<RemovalEvent>,
+    ) {
+        if collisions.is_empty() {
+            return;
+        }
+
+        let circle_indices: HashMap<EntityId, usize> = self
+            .bodies
+            .ids
+            .iter()
+            .enumerate()
+            .map(|(i, &id)| (id, i))
+            .collect();
+        let mut merged = vec![false; self.bodies.len()];
+        let mut absorbed_circles = vec![false; self.bodies.len()];
+
+        for collision in collisions {
+            let (Some(&i), Some(&j)) = (
+                circle_indices.get(&collision.a),
+                circle_indices.get(&collision.b),
+            ) else {
+                continue;
+            };
+            if i == j || merged[i] || merged[j] {
+                continue;
+            }
+
+            let (a, b) = (self.bodies.get(i), self.bodies.get(j));
+            if a.radius.hypot(b.radius) > max_radius {
+                continue;
+            }
+            let (mut survivor, absorbed, survivor_index, absorbed_index) = if a.mass() >= b.mass() {
+                (a, b, i, j)
+            } else {
+                (b, a, j, i)
+            };
+            survivor.absorb(&absorbed);
+            self.bodies.set(survivor_index, survivor.clone());
+
+            merged[i] = true;
+            merged[j] = true;
+            removal_events.push(RemovalEvent {
+                id: absorbed.id,
+                point: (absorbed.x_pos, absorbed.y_pos),
+                cause: RemovalCause::Merged(survivor.id),
+            });
+            absorbed_circles[absorbed_index] = true;
+        }
+
+        let entities = &mut self.entities;
+        let mut absorbed_circles = absorbed_circles.into_iter();
+        self.bodies.retain(|circle| {
+            if !absorbed_circles.next().unwrap_or(false) {
+                return true;
+            }
+            entities.free(circle.id);
+            false
+        });
+    }
+
+    fn time_scale(&self) -> f32 {
+        self.config.time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE)
+    }
+
+    // Picks just enough subticks that no circle moves more than a fraction of
+    // the smallest circle's radius per subtick, so that fast circles don't
+    // tunnel through each other while slow scenes stay cheap.
+    fn required_sub_ticks(&self, time_scale: f32) -> u32 {
+        let min_sub_ticks = self.config.min_subticks_per_frame.max(1);
+        let max_sub_ticks = self.config.max_subticks_per_frame.max(min_sub_ticks);
+
+        let bodies = &self.bodies;
+        let (max_speed, min_radius) = (0..bodies.len()).filter(|&i| !bodies.sleeping[i]).fold(
+            (0.0, f32::INFINITY),
+            |(max_speed, min_radius), i| {
+                let (vx, vy) = bodies.velocities[i];
+                (
+                    f32::max(max_speed, (vx * vx + vy * vy).sqrt()),
+                    f32::min(min_radius, bodies.radii[i]),
+                )
+            },
+        );
+
+        if !min_radius.is_finite() {
+            return min_sub_ticks;
+        }
+
+        // Gravity is applied before moving, so account for this frame's worth.
+        // A scaled tick covers that much more ground.
+        let (gravity_x, gravity_y) = self.config.gravity;
+        let max_travel = (max_speed + (gravity_x.powi(2) + gravity_y.powi(2)).sqrt()) * time_scale;
+        let sub_ticks = (max_travel / (min_radius * MAX_SUBTICK_TRAVEL_FRACTION)).ceil();
+
+        (sub_ticks as u32).clamp(min_sub_ticks, max_sub_ticks)
+    }
+
+    // Puts circles to sleep once they've been nearly still for long enough.
+    // Nothing sleeps while any circle is shrinking, since circles would be
+    // left floating as the circles supporting them shrink away.
+    fn update_sleep_states(&mut self) {
+        let sleep_speed_threshold = self.config.sleep_speed_threshold;
+        let sleep_frames = self.config.sleep_frames.max(1);
+
+        if sleep_speed_threshold <= 0.0
+            || self.bodies.details.iter().any(|details| {
+                matches!(details.lifetime, Some(LifetimePolicy::ShrinkAndDie { rate }) if rate > 0.0)
+            })
+        {
+            return;
+        }
+
+        let bodies = &mut self.bodies;
+        for (i, details) in bodies.details.iter_mut().enumerate() {
+            if bodies.sleeping[i] {
+                continue;
+            }
+            let velocity = &mut bodies.velocities[i];
+
+            // Circles in a resting pile jitter back and forth, so the check
+            // uses a smoothed velocity in which the jitter cancels out.
+            details.average_velocity.0 +=
+                (velocity.0 - details.average_velocity.0) * VELOCITY_SMOOTHING;
+            details.average_velocity.1 +=
+                (velocity.1 - details.average_velocity.1) * VELOCITY_SMOOTHING;
+            let average_speed =
+                (details.average_velocity.0.powi(2) + details.average_velocity.1.powi(2)).sqrt();
+
+            if average_speed < sleep_speed_threshold {
+                details.low_speed_frames += 1;
+                if details.low_speed_frames >= sleep_frames {
+                    bodies.sleeping[i] = true;
+                    *velocity = (0.0, 0.0);
+                    details.average_velocity = (0.0, 0.0);
+                }
+            } else {
+                details.low_speed_frames = 0;
+            }
+        }
+    }
+
+    fn explode(&mut self, center: Point, strength: f32, radius: f32) {
+        if radius <= 0.0 {
+            return;
+        }
+
+        self.bodies.update_all_bodies(|_, circle| {
+            let dx = circle.x_pos - center.x;
+            let dy = circle.y_pos - center.y;
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance >= radius {
+                return;
+            }
+
+            // Circles right at the center are blown straight up.
+            let (nx, ny) = if distance > 1e-6 {
+                (dx / distance, dy / distance)
+            } else {
+                (0.0, -1.0)
+            };
+            let impulse = strength * (1.0 - distance / radius);
+            let inverse_mass = circle.inverse_mass();
+            circle.velocity.0 += nx * impulse * inverse_mass;
+            circle.velocity.1 += ny * impulse * inverse_mass;
+            circle.wake();
+        });
+    }
+
+    // Adds the config's burst of circles around `center`, randomized by its
+    // spawn profile. Randomness comes from the grid's own generator, so a
+    // recorded burst plays back the same.
+    fn spawn_burst(&mut self, center: Point) {
+        let burst = self.config.spawn_burst.clone();
+        let circles: Vec<_> = (0..burst.count)
+            .map(|_| {
+                let distance = burst.spread * self.rng.next_f32().sqrt();
+                let (sin, cos) = self.rng.range(0.0, std::f32::consts::TAU).sin_cos();
+                let mut circle = Circle::new(
+                    center.x + distance * cos,
+                    center.y + distance * sin,
+                    self.rng.range(burst.min_radius, burst.max_radius),
+                    burst.velocity.sample(&mut self.rng),
+                );
+                self.config.spawn_profile.apply(&mut circle, &mut self.rng);
+                circle
+            })
+            .collect();
+
+        // The message was checked, but the config it draws on wasn't.
+        if let Err(problem) = circles.iter().try_for_each(validation::validate_circle) {
+            warn!("Not spawning a burst: {problem}.");
+            return;
+        }
+        let room = self.room_for_circles();
+        for circle in circles.into_iter().take(room) {
+            self.add_circle(circle);
+        }
+    }
+
+    // How many more circles there's room for, which is only limited when
+    // `max_circles` is set and the eviction policy turns new circles away.
+    // The other policies make room once the tick is done instead.
+    fn room_for_circles(&self) -> usize {
+        match (self.config.max_circles, self.config.eviction_policy) {
+            (Some(max_circles), EvictionPolicy::RejectNew) => {
+                max_circles.saturating_sub(self.bodies.len())
+            }
+            _ => usize::MAX,
+        }
+    }
+
+    fn add_circle(&mut self, mut circle: Circle) -> EntityId {
+        let id = self.entities.allocate();
+        circle.id = id;
+        circle.lifetime.get_or_insert(self.config.lifetime);
+        self.bodies.push(circle);
+        id
+    }
+
+    fn add_static_circle(&mut self, mut static_circle: StaticCircle) {
+        static_circle.id = self.entities.allocate();
+        self.static_circles.push(static_circle);
+    }
+
+    fn add_static_rectangle(&mut self, mut static_rectangle: StaticRectangle) {
+        static_rectangle.id = self.entities.allocate();
+        self.static_rectangles.push(static_rectangle);
+    }
+
+    fn add_static_capsule(&mut self, mut static_capsule: StaticCapsule) {
+        static_capsule.id = self.entities.allocate();
+        self.static_capsules.push(static_capsule);
+    }
+
+    fn add_kinematic_rectangle(&mut self, mut kinematic_rectangle: KinematicRectangle) {
+        kinematic_rectangle.id = self.entities.allocate();
+        kinematic_rectangle.previous_center = kinematic_rectangle.center();
+        self.kinematic_rectangles.push(kinematic_rectangle);
+    }
+
+    fn add_kinematic_spinner(&mut self, mut kinematic_spinner: KinematicSpinner) {
+        kinematic_spinner.id = self.entities.allocate();
+        kinematic_spinner.previous_angle = kinematic_spinner.angle;
+        self.kinematic_spinners.push(kinematic_spinner);
+    }
+
+    fn add_portal_pair(&mut self, mut portal_pair: PortalPair) {
+        portal_pair.id = self.entities.allocate();
+        self.portal_pairs.push(portal_pair);
+    }
+
+    fn add_sink(&mut self, mut sink: Sink) {
+        sink.id = self.entities.allocate();
+        self.sinks.push(sink);
+    }
+
+    fn add_fluid_zone(&mut self, mut fluid_zone: FluidZone) {
+        fluid_zone.id = self.entities.allocate();
+        self.fluid_zones.push(fluid_zone);
+    }
+
+    fn add_gravity_well(&mut self, mut gravity_well: GravityWell) {
+        gravity_well.id = self.entities.allocate();
+        self.gravity_wells.push(gravity_well);
+    }
+
+    fn add_spawner(&mut self, mut spawner: Spawner) {
+        spawner.id = self.entities.allocate();
+        self.spawners.push(spawner);
+    }
+
+    // Replaces every body in the grid with the contents of the scene.
+    fn load_scene(&mut self, scene: Scene) {
+        self.bodies.clear();
+        self.static_circles.clear();
+        self.static_rectangles.clear();
+        self.static_capsules.clear();
+        self.kinematic_rectangles.clear();
+        self.kinematic_spinners.clear();
+        self.portal_pairs.clear();
+        self.sinks.clear();
+        self.fluid_zones.clear();
+        self.gravity_wells.clear();
+        self.spawners.clear();
+        self.springs.clear();
+        self.ropes.clear();
+        self.joints.clear();
+        // Ids of the old bodies, held by scripts or viewers, mustn't refer to
+        // the new ones.
+        self.entities.free_all();
+        self.drag_model = scene.drag_model;
+        self.builtin_forces = BuiltinForces::new(&self.config, self.drag_model);
+
+        self.add_connected_circles(scene.circles, scene.springs, scene.ropes, scene.joints);
+        for static_circle in scene.static_circles {
+            self.add_static_circle(static_circle);
+        }
+        for static_rectangle in scene.static_rectangles {
+            self.add_static_rectangle(static_rectangle);
+        }
+        for static_capsule in scene.static_capsules {
+            self.add_static_capsule(static_capsule);
+        }
+        for kinematic_rectangle in scene.kinematic_rectangles {
+            self.add_kinematic_rectangle(kinematic_rectangle);
+        }
+        for kinematic_spinner in scene.kinematic_spinners {
+            self.add_kinematic_spinner(kinematic_spinner);
+        }
+        for portal_pair in scene.portal_pairs {
+            self.add_portal_pair(portal_pair);
+        }
+        for sink in scene.sinks {
+            self.add_sink(sink);
+        }
+        for fluid_zone in scene.fluid_zones {
+            self.add_fluid_zone(fluid_zone);
+        }
+        for gravity_well in scene.gravity_wells {
+            self.add_gravity_well(gravity_well);
+        }
+        for spawner in scene.spawners {
+            self.add_spawner(spawner);
+        }
+    }
+
+    // Adds the circles and links up the springs, ropes and joints between
+    // them. Any that refer to anything but the circles are skipped.
+    fn add_connected_circles(
+        &mut self,
+        circles: Vec<Circle>,
+        springs: Vec<Spring>,
+        ropes: Vec<Rope>,
+        joints: Vec<Joint>,
+    ) {
+        let room = self.room_for_circles();
+        let ids: Vec<EntityId> = circles
+            .into_iter()
+            .take(room)
+            .map(|circle| self.add_circle(circle))
+            .collect();
+
+        for spring in springs {
+            if let (Some(&a), Some(&b)) = (ids.get(spring.a), ids.get(spring.b)) {
+                if a != b {
+                    self.springs.push(LinkedSpring {
+                        a,
+                        b,
+                        rest_length: spring.rest_length,
+                        stiffness: spring.stiffness,
+                        damping: spring.damping,
+                    });
+                }
+            }
+        }
+
+        for rope in ropes {
+            let anchor = rope.anchor.link(&ids);
+            let links: Option<Vec<EntityId>> = rope
+                .circles
+                .iter()
+                .map(|&index| ids.get(index).copied())
+                .collect();
+            if let (Some(anchor), Some(links)) = (anchor, links) {
+                if !links.is_empty() {
+                    self.ropes.push(LinkedRope {
+                        anchor,
+                        anchor_length: rope.anchor_length,
+                        circles: links,
+                        segment_length: rope.segment_length,
+                    });
+                }
+            }
+        }
+
+        for joint in joints {
+            if let (Some(&body), Some(anchor)) = (ids.get(joint.body), joint.anchor.link(&ids)) {
+                if LinkedAnchor::Circle(body) != anchor {
+                    self.joints.push(LinkedJoint {
+                        body,
+                        anchor,
+                        length: joint.length,
+                    });
+                }
+            }
+        }
+    }
+
+    // Joins the circle to the anchor at their current distance. Joints to
+    // circles that aren't in the grid are ignored.
+    fn add_joint(&mut self, body: EntityId, anchor: LinkedAnchor) {
+        let position_of = |id: EntityId| {
+            self.bodies.index_of(id).map(|i| {
+                let (x_pos, y_pos) = self.bodies.positions[i];
+                Point::new(x_pos, y_pos)
+            })
+        };
+
+        let Some(body_position) = position_of(body) else {
+            return;
+        };
+        let anchor_position = match anchor {
+            LinkedAnchor::Point(point) => Some(point),
+            LinkedAnchor::Circle(id) => position_of(id),
+        };
+        let Some(anchor_position) = anchor_position else {
+            return;
+        };
+
+        self.joints.push(LinkedJoint {
+            body,
+            anchor,
+            length: body_position.distance(anchor_position),
+        });
+    }
+
+    fn add_rope(
+        &mut self,
+        anchor: Point,
+        segments: u32,
+        length: f32,
+        attached_to: Option<EntityId>,
+    ) {
+        if segments == 0 || length <= 0.0 {
+            return;
+        }
+        if segments as usize > self.room_for_circles() {
+            debug!("Not adding a rope: the grid is full.");
+            return;
+        }
+        let segment_length = length / segments as f32;
+
+        // A rope hanging from a circle starts at its edge rather than its
+        // center, so that the first link doesn't sit inside it.
+        let (top, anchor, anchor_length) = match attached_to {
+            Some(id) => {
+                let Some(circle) = self.bodies.index_of(id).map(|i| self.bodies.get(i)) else {
+                    return;
+                };
+                (
+                    Point::new(circle.x_pos, circle.y_pos),
+                    LinkedAnchor::Circle(id),
+                    circle.radius + segment_length,
+                )
+            }
+            None => (anchor, LinkedAnchor::Point(anchor), segment_length),
+        };
+
+        let link_radius = segment_length * ROPE_LINK_RADIUS_FRACTION;
+        let links = (0..segments)
+            .map(|i| {
+                self.add_circle(Circle::new(
+                    top.x,
+                    top.y + anchor_length + i as f32 * segment_length,
+                    link_radius,
+                    (0.0, 0.0),
+                ))
+            })
+            .collect();
+
+        self.ropes.push(LinkedRope {
+            anchor,
+            anchor_length,
+            circles: links,
+            segment_length,
+        });
+    }
+
+    fn remove(&mut self, id: EntityId) {
+        if let Some(i) = self.bodies.index_of(id) {
+            self.bodies.remove(i);
+        } else if let Some(i) = self
+            .static_circles
+            .iter()
+            .position(|static_circle| static_circle.id == id)
+        {
+            self.static_circles.remove(i);
+            // Circles resting on the removed body need to start falling.
+            self.wake_all();
+        } else if let Some(i) = self
+            .static_rectangles
+            .iter()
+            .position(|static_rectangle| static_rectangle.id == id)
+        {
+            self.static_rectangles.remove(i);
+            self.wake_all();
+        } else if let Some(i) = self
+            .static_capsules
+            .iter()
+            .position(|static_capsule| static_capsule.id == id)
+        {
+            self.static_capsules.remove(i);
+            self.wake_all();
+        } else if let Some(i) = self
+            .kinematic_rectangles
+            .iter()
+            .position(|kinematic_rectangle| kinematic_rectangle.id == id)
+        {
+            self.kinematic_rectangles.remove(i);
+            self.wake_all();
+        } else if let Some(i) = self
+            .kinematic_spinners
+            .iter()
+            .position(|kinematic_spinner| kinematic_spinner.id == id)
+        {
+            self.kinematic_spinners.remove(i);
+            self.wake_all();
+        } else if let Some(i) = self
+            .portal_pairs
+            .iter()
+            .position(|portal_pair| portal_pair.id == id)
+        {
+            self.portal_pairs.remove(i);
+        } else if let Some(i) = self.sinks.iter().position(|sink| sink.id == id) {
+            self.sinks.remove(i);
+        } else if let Some(i) = self
+            .fluid_zones
+            .iter()
+            .position(|fluid_zone| fluid_zone.id == id)
+        {
+            self.fluid_zones.remove(i);
+        } else if let Some(i) = self
+            .gravity_wells
+            .iter()
+            .position(|gravity_well| gravity_well.id == id)
+        {
+            self.gravity_wells.remove(i);
+        } else if let Some(i) = self.spawners.iter().position(|spawner| spawner.id == id) {
+            self.spawners.remove(i);
+        } else {
+            return;
+        }
+
+        self.entities.free(id);
+    }
+
+    fn clear_static(&mut self) {
+        let ids = self
+            .static_circles
+            .drain(..)
+            .map(|static_circle| static_circle.id)
+            .chain(
+                self.static_rectangles
+                    .drain(..)
+                    .map(|static_rectangle| static_rectangle.id),
+            )
+            .chain(
+                self.static_capsules
+                    .drain(..)
+                    .map(|static_capsule| static_capsule.id),
+            )
+            .chain(
+                self.kinematic_rectangles
+                    .drain(..)
+                    .map(|kinematic_rectangle| kinematic_rectangle.id),
+            )
+            .chain(
+                self.kinematic_spinners
+                    .drain(..)
+                    .map(|kinematic_spinner| kinematic_spinner.id),
+            )
+            .chain(
+                self.portal_pairs
+                    .drain(..)
+                    .map(|portal_pair| portal_pair.id),
+            )
+            .chain(self.sinks.drain(..).map(|sink| sink.id))
+            .chain(self.fluid_zones.drain(..).map(|fluid_zone| fluid_zone.id))
+            .chain(
+                self.gravity_wells
+                    .drain(..)
+                    .map(|gravity_well| gravity_well.id),
+            )
+            .chain(self.spawners.drain(..).map(|spawner| spawner.id))
+            .collect::<Vec<_>>();
+        for id in ids {
+            self.entities.free(id);
+        }
+        self.wake_all();
+    }
+
+    fn update_body(&mut self, id: EntityId, properties: BodyProperties) {
+        match properties {
+            BodyProperties::Circle {
+                x_pos,
+                y_pos,
+                radius,
+                mass,
+                restitution,
+                color,
+            } => {
+                let Some(i) = self.bodies.index_of(id) else {
+                    return;
+                };
+                let mut circle = self.bodies.get(i);
+                circle.x_pos = x_pos;
+                circle.y_pos = y_pos;
+                // So that it isn't drawn sliding over from where it was.
+                circle.previous_position = (x_pos, y_pos);
+                circle.radius = radius;
+                circle.density = mass / (radius * radius).max(f32::EPSILON);
+                circle.restitution = restitution;
+                circle.color = color;
+                circle.wake();
+                self.bodies.set(i, circle);
+            }
+            BodyProperties::StaticCircle {
+                x_pos,
+                y_pos,
+                radius,
+            } => {
+                let Some(static_circle) = self
+                    .static_circles
+                    .iter_mut()
+                    .find(|static_circle| static_circle.id == id)
+                else {
+                    return;
+                };
+                static_circle.x_pos = x_pos;
+                static_circle.y_pos = y_pos;
+                static_circle.radius = radius;
+                // Circles resting on it may need to fall or be pushed out.
+                self.wake_all();
+            }
+            BodyProperties::StaticRectangle {
+                x_pos,
+                y_pos,
+                width,
+                height,
+            } => {
+                let Some(static_rectangle) = self
+                    .static_rectangles
+                    .iter_mut()
+                    .find(|static_rectangle| static_rectangle.id == id)
+                else {
+                    return;
+                };
+                static_rectangle.x_pos = x_pos;
+                static_rectangle.y_pos = y_pos;
+                static_rectangle.width = width;
+                static_rectangle.height = height;
+                self.wake_all();
+            }
+        }
+    }
+
+    fn update_config(&mut self, config: SimulationConfig) {
+        if config.broadphase != self.config.broadphase {
+            self.broadphase = config.broadphase.broadphase();
+        }
+        self.builtin_forces = BuiltinForces::new(&config, self.drag_model);
+        if let Some(message_receiver) = self.message_receiver.as_mut() {
+            message_receiver.configure(
+                config.message_queue_capacity,
+                config.message_overflow_policy,
+            );
+        }
+        self.config = config;
+        self.wake_all();
+    }
+
+    fn set_comparison_config(&mut self, config: Option<SimulationConfig>) {
+        let Some(config) = config else {
+            self.comparison = None;
+            return;
+        };
+
+        if let Some(comparison) = self.comparison.as_mut() {
+            comparison.update_config(config);
+            return;
+        }
+
+        let mut comparison = Grid::new_detached(self.width, self.height, config);
+        rewind::copy_state(self, &mut comparison);
+        comparison.frame_number = self.frame_number;
+        comparison.paused = self.paused;
+        self.comparison = Some(Box::new(comparison));
+    }
+
+    fn wake_all(&mut self) {
+        for i in 0..self.bodies.len() {
+            self.bodies.wake(i);
+        }
+    }
+
+    // Moves a pair of overlapping circles apart in proportion to their masses,
+    // without touching their velocities. A sleeping circle only gives way to
+    // a circle moving fast enough to wake it. `position_correction` is the
+    // fraction of the overlap that's removed.
+    fn project_apart(
+        circle_a: &mut Body,
+        circle_b: &mut Body,
+        wake_speed_threshold: f32,
+        position_correction: f32,
+    ) -> Option<Contact> {
+        if circle_a.sleeping && circle_b.sleeping {
+            return None;
+        }
+
+        let dx = circle_b.x_pos - circle_a.x_pos;
+        let dy = circle_b.y_pos - circle_a.y_pos;
+        let distance = (dx * dx + dy * dy).sqrt();
+        let min_distance = circle_a.radius + circle_b.radius;
+
+        if min_distance <= distance {
+            return None;
+        }
+
+        // Circles at the same position are pushed apart along an arbitrary axis.
+        let (nx, ny) = if distance > 1e-8 {
+            (dx / distance, dy / distance)
+        } else {
+            (1.0, 0.0)
+        };
+
+        let (speed_a, speed_b) = (circle_a.speed(), circle_b.speed());
+        if circle_a.sleeping && speed_b >= wake_speed_threshold {
+            circle_a.wake();
+        }
+        if circle_b.sleeping && speed_a >= wake_speed_threshold {
+            circle_b.wake();
+        }
+
+        // Circles that are still asleep don't move at all.
+        let inverse_mass = |circle: &Body| {
+            if circle.sleeping {
+                0.0
+            } else {
+                circle.inverse_mass()
+            }
+        };
+        let w_a = inverse_mass(circle_a);
+        let w_b = inverse_mass(circle_b);
+
+        let overlap = (min_distance - distance) * position_correction;
+        circle_a.x_pos -= overlap * nx * w_a / (w_a + w_b);
+        circle_a.y_pos -= overlap * ny * w_a / (w_a + w_b);
+        circle_b.x_pos += overlap * nx * w_b / (w_a + w_b);
+        circle_b.y_pos += overlap * ny * w_b / (w_a + w_b);
+
+        // The collision is perfectly inelastic, so the pair's closing speed is
+        // lost, which changes `a`'s momentum by the reduced mass times that.
+        let closing_speed = (circle_a.velocity.0 - circle_b.velocity.0) * nx
+            + (circle_a.velocity.1 - circle_b.velocity.1) * ny;
+
+        Some(Contact {
+            point: (
+                circle_a.x_pos + nx * circle_a.radius,
+                circle_a.y_pos + ny * circle_a.radius,
+            ),
+            normal: (-nx, -ny),
+            impulse: f32::max(closing_speed, 0.0) / (w_a + w_b),
+        })
+    }
+
+    // Keeps every awake circle inside the edges that are walls without
+    // changing velocities, for the position-based solver.
+    fn clamp_to_walls(&mut self) {
+        let boundaries = self.config.boundaries;
+        let bodies = &mut self.bodies;
+        for (i, position) in bodies.positions.iter_mut().enumerate() {
+            if bodies.sleeping[i] {
+                continue;
+            }
+            let radius = bodies.radii[i];
+            if boundaries.left == BoundaryMode::Bounce {
+                position.0 = position.0.max(radius);
+            }
+            if boundaries.right == BoundaryMode::Bounce {
+                position.0 = position.0.min(self.width - radius);
+            }
+            if boundaries.top == BoundaryMode::Bounce {
+                position.1 = position.1.max(radius);
+            }
+            if boundaries.bottom == BoundaryMode::Bounce {
+                position.1 = position.1.min(self.height - radius);
+            }
+        }
+    }
+
+    // Moves circles whose centers have crossed a wrapping edge to the other
+    // side of the world. Their previous positions move with them, so that
+    // they aren't drawn sweeping across the whole world on the way.
+    fn wrap_around(&mut self) {
+        let boundaries = self.config.boundaries;
+        let (width, height) = (self.width, self.height);
+        let bodies = &mut self.bodies;
+        for (position, details) in bodies.positions.iter_mut().zip(&mut bodies.details) {
+            let mut offset = (0.0, 0.0);
+            if boundaries.left == BoundaryMode::Wrap && position.0 < 0.0 {
+                offset.0 = width;
+            } else if boundaries.right == BoundaryMode::Wrap && position.0 > width {
+                offset.0 = -width;
+            }
+            if boundaries.top == BoundaryMode::Wrap && position.1 < 0.0 {
+                offset.1 = height;
+            } else if boundaries.bottom == BoundaryMode::Wrap && position.1 > height {
+                offset.1 = -height;
+            }
+
+            position.0 += offset.0;
+            position.1 += offset.1;
+            details.previous_position.0 += offset.0;
+            details.previous_position.1 += offset.1;
+        }
+    }
+
+    fn circle_static_circle_collision(
+        circle: &mut Body,
+        static_circle: &StaticCircle,
+        restitution: Restitution,
+    ) -> Option<Contact> {
+        let restitution = restitution.for_body(circle);
+        let dx = circle.x_pos - static_circle.x_pos;
+        let dy = circle.y_pos - static_circle.y_pos;
+        let distance = (dx * dx + dy * dy).sqrt();
+        let min_distance = circle.radius + static_circle.radius;
+
+        if distance < min_distance {
+            // Straight up if the centers are on top of each other
+            let (nx, ny) = if distance > 1e-8 {
+                (dx / distance, dy / distance)
+            } else {
+                (0.0, -1.0)
+            };
+
+            // Project circle out of collision
+            let overlap = min_distance - distance;
+            circle.x_pos += overlap * nx;
+            circle.y_pos += overlap * ny;
+
+            // Reflect velocity
+            let v_dot_n = circle.velocity.0 * nx + circle.velocity.1 * ny;
+            let reflection_factor = restitution.reflection_factor(v_dot_n);
+            circle.velocity.0 -= reflection_factor * v_dot_n * nx;
+            circle.velocity.1 -= reflection_factor * v_dot_n * ny;
+
+            Some(Contact {
+                point: (
+                    static_circle.x_pos + nx * static_circle.radius,
+                    static_circle.y_pos + ny * static_circle.radius,
+                ),
+                normal: (nx, ny),
+                impulse: circle.mass() * reflection_factor * f32::max(-v_dot_n, 0.0),
+            })
+        } else {
+            None
+        }
+    }
+
+    // Pushes or pulls the circles towards the spring's rest length, sharing
+    // the change in velocity out by mass. A spring keeps both of its circles
+    // awake as long as either of them is.
+    fn apply_spring(circle_a: &mut Body, circle_b: &mut Body, spring: &LinkedSpring, dt: f32) {
+        if circle_a.sleeping && circle_b.sleeping {
+            return;
+        }
+        circle_a.wake();
+        circle_b.wake();
+
+        let dx = circle_b.x_pos - circle_a.x_pos;
+        let dy = circle_b.y_pos - circle_a.y_pos;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance <= 1e-8 {
+            return;
+        }
+        let (nx, ny) = (dx / distance, dy / distance);
+
+        let stretch = distance - spring.rest_length;
+        let separating_speed = (circle_b.velocity.0 - circle_a.velocity.0) * nx
+            + (circle_b.velocity.1 - circle_a.velocity.1) * ny;
+        let closing = (spring.stiffness * stretch + spring.damping * separating_speed) * dt;
+
+        let w_a = circle_a.inverse_mass();
+        let w_b = circle_b.inverse_mass();
+        let share_a = w_a / (w_a + w_b);
+        let share_b = w_b / (w_a + w_b);
+
+        circle_a.velocity.0 += closing * nx * share_a;
+        circle_a.velocity.1 += closing * ny * share_a;
+        circle_b.velocity.0 -= closing * nx * share_b;
+        circle_b.velocity.1 -= closing * ny * share_b;
+    }
+
+    // Pulls the circle's velocity along the surface towards the surface's own
+    // velocity along it.
+    fn apply_conveyor(
+        circle: &mut Body,
+        contact: &Contact,
+        surface_velocity: (f32, f32),
+        grip: f32,
+    ) {
+        if surface_velocity == (0.0, 0.0) {
+            return;
+        }
+
+        let (nx, ny) = contact.normal;
+        let relative_velocity = (
+            circle.velocity.0 - surface_velocity.0,
+            circle.velocity.1 - surface_velocity.1,
+        );
+        let v_dot_n = relative_velocity.0 * nx + relative_velocity.1 * ny;
+        circle.velocity.0 -= (relative_velocity.0 - v_dot_n * nx) * grip;
+        circle.velocity.1 -= (relative_velocity.1 - v_dot_n * ny) * grip;
+    }
+
+    fn circle_static_capsule_collision(
+        circle: &mut Body,
+        capsule: &StaticCapsule,
+        restitution: Restitution,
+    ) -> Option<Contact> {
+        let restitution = restitution.for_body(circle);
+        let center = Point::new(circle.x_pos, circle.y_pos);
+        let closest = capsule.closest_point(center);
+
+        let dx = center.x - closest.x;
+        let dy = center.y - closest.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        let min_distance = circle.radius + capsule.radius;
+
+        if distance >= min_distance {
+            return None;
+        }
+
+        // Avoid division by zero
+        let (nx, ny) = if distance > 1e-8 {
+            (dx / distance, dy / distance)
+        } else {
+            // Circle center is on the capsule's center line; push it out
+            // along the line's normal
+            let direction = capsule.b - capsule.a;
+            let length = (direction.x * direction.x + direction.y * direction.y).sqrt();
+            if length > 1e-8 {
+                (-direction.y / length, direction.x / length)
+            } else {
+                (0.0, -1.0)
+            }
+        };
+
+        // Project circle out of collision
+        let overlap = min_distance - distance;
+        circle.x_pos += overlap * nx;
+        circle.y_pos += overlap * ny;
+
+        // Reflect velocity
+        let v_dot_n = circle.velocity.0 * nx + circle.velocity.1 * ny;
+        let reflection_factor = restitution.reflection_factor(v_dot_n);
+        circle.velocity.0 -= reflection_factor * v_dot_n * nx;
+        circle.velocity.1 -= reflection_factor * v_dot_n * ny;
+
+        Some(Contact {
+            point: (closest.x, closest.y),
+            normal: (nx, ny),
+            impulse: circle.mass() * reflection_factor * f32::max(-v_dot_n, 0.0),
+        })
+    }
+
+    fn circle_static_rectangle_collision(
+        circle: &mut Body,
+        rect: &StaticRectangle,
+        restitution: Restitution,
+    ) -> Option<Contact> {
+        // One-way platforms only catch circles that are falling and whose
+        // center is still above the top surface
+        if rect.one_way && (circle.velocity.1 < 0.0 || circle.y_pos > rect.y_pos) {
+            return None;
+        }
+
+        Self::circle_rectangle_collision(circle, rect.bounds(), (0.0, 0.0), restitution)
+    }
+
+    // Bounces the circle off the spinner as it is at `angle`, taking the
+    // speed of the spinner's surface at the contact point into account.
+    fn circle_spinner_collision(
+        circle: &mut Body,
+        spinner: &KinematicSpinner,
+        angle: f32,
+        restitution: Restitution,
+    ) -> Option<Contact> {
+        let restitution = restitution.for_body(circle);
+        // Work in the spinner's frame, where it's an axis-aligned rectangle
+        // centered on the origin
+        let center = spinner.world_to_local(Point::new(circle.x_pos, circle.y_pos), angle);
+        let half_length = spinner.length / 2.0;
+        let half_thickness = spinner.thickness / 2.0;
+        let mut closest = Point::new(
+            clamp(center.x, -half_length, half_length),
+            clamp(center.y, -half_thickness, half_thickness),
+        );
+
+        let dx = center.x - closest.x;
+        let dy = center.y - closest.y;
+        let distance_squared = dx * dx + dy * dy;
+        if distance_squared >= circle.radius * circle.radius {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let (local_normal, overlap) = if distance > 1e-8 {
+            ((dx / distance, dy / distance), circle.radius - distance)
+        } else {
+            // The spinner swept over the circle's center; push it out through
+            // the nearest side
+            let penetration_x = half_length - center.x.abs();
+            let penetration_y = half_thickness - center.y.abs();
+            if penetration_x < penetration_y {
+                closest.x = half_length.copysign(center.x);
+                (
+                    (1.0_f32.copysign(center.x), 0.0),
+                    circle.radius + penetration_x,
+                )
+            } else {
+                closest.y = half_thickness.copysign(center.y);
+                (
+                    (0.0, 1.0_f32.copysign(center.y)),
+                    circle.radius + penetration_y,
+                )
+            }
+        };
+
+        let normal = rotate(Vector::new(local_normal.0, local_normal.1), angle);
+        let contact_point = spinner.local_to_world(closest, angle);
+
+        // Project circle out of collision
+        circle.x_pos += overlap * normal.x;
+        circle.y_pos += overlap * normal.y;
+
+        // Reflect velocity relative to the surface, which moves at right
+        // angles to the arm from the pivot
+        let arm = contact_point - spinner.pivot;
+        let surface_velocity = (
+            -spinner.angular_velocity * arm.y,
+            spinner.angular_velocity * arm.x,
+        );
+        let v_dot_n = (circle.velocity.0 - surface_velocity.0) * normal.x
+            + (circle.velocity.1 - surface_velocity.1) * normal.y;
+        let reflection_factor = restitution.reflection_factor(v_dot_n);
+        if v_dot_n < 0.0 {
+            circle.velocity.0 -= reflection_factor * v_dot_n * normal.x;
+            circle.velocity.1 -= reflection_factor * v_dot_n * normal.y;
+        }
+
+        Some(Contact {
+            point: (contact_point.x, contact_point.y),
+            normal: (normal.x, normal.y),
+            impulse: circle.mass() * reflection_factor * f32::max(-v_dot_n, 0.0),
+        })
+    }
+
+    // Bounces the circle off a rectangle whose surface is moving at
+    // `surface_velocity`.
+    fn circle_rectangle_collision(
+        circle: &mut Body,
+        rect: Rectangle,
+        surface_velocity: (f32, f32),
+        restitution: Restitution,
+    ) -> Option<Contact> {
+        let restitution = restitution.for_body(circle);
+        // Find the closest point to the circle within the rectangle
+        let closest_x = clamp(circle.x_pos, rect.x, rect.x + rect.width);
+        let closest_y = clamp(circle.y_pos, rect.y, rect.y + rect.height);
+
+        let dx = circle.x_pos - closest_x;
+        let dy = circle.y_pos - closest_y;
+
+        let distance_squared = dx * dx + dy * dy;
+
+        if distance_squared < circle.radius * circle.radius {
+            let distance = distance_squared.sqrt();
+
+            let ((nx, ny), overlap) = if distance > 1e-8 {
+                ((dx / distance, dy / distance), circle.radius - distance)
+            } else {
+                // Circle center is inside rectangle; push it out through the
+                // nearest side
+                let center_x = circle.x_pos - (rect.x + rect.width / 2.0);
+                let center_y = circle.y_pos - (rect.y + rect.height / 2.0);
+                let penetration_x = rect.width / 2.0 - center_x.abs();
+                let penetration_y = rect.height / 2.0 - center_y.abs();
+                if penetration_x < penetration_y {
+                    (
+                        (1.0_f32.copysign(center_x), 0.0),
+                        circle.radius + penetration_x,
+                    )
+                } else {
+                    (
+                        (0.0, 1.0_f32.copysign(center_y)),
+                        circle.radius + penetration_y,
+                    )
+                }
+            };
+
+            // Project circle out of collision
+            circle.x_pos += overlap * nx;
+            circle.y_pos += overlap * ny;
+
+            // Reflect velocity relative to the surface
+            let v_dot_n = (circle.velocity.0 - surface_velocity.0) * nx
+                + (circle.velocity.1 - surface_velocity.1) * ny;
+            let reflection_factor = restitution.reflection_factor(v_dot_n);
+            circle.velocity.0 -= reflection_factor * v_dot_n * nx;
+            circle.velocity.1 -= reflection_factor * v_dot_n * ny;
+
+            Some(Contact {
+                point: (closest_x, closest_y),
+                normal: (nx, ny),
+                impulse: circle.mass() * reflection_factor * f32::max(-v_dot_n, 0.0),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Circle {
+    pub x_pos: f32,
+    pub y_pos: f32,
+    pub radius: f32,
+    pub velocity: (f32, f32),
+    // Mass per unit of area, so a circle's mass is `density * radius^2`.
+    // Drag and wind push denser circles around less.
+    #[serde(default = "default_density")]
+    pub density: f32,
+    // Electric charge. Charged circles push and pull each other, and are
+    // colored by the sign of their charge.
+    #[serde(default)]
+    pub charge: f32,
+    // Overrides the scene's drag model for this circle.
+    #[serde(default)]
+    pub drag_model: Option<DragModel>,
+    // Overrides the config's friction coefficient for this circle.
+    #[serde(default)]
+    pub friction: Option<f32>,
+    // Overrides the config's elasticity coefficient for this circle. Two
+    // circles hitting each other bounce as much as the bouncier one would.
+    #[serde(default)]
+    pub restitution: Option<f32>,
+    // Red, green and blue from 0 to 1. Overrides the solid color the circle
+    // would otherwise be drawn in, but not the heat map.
+    #[serde(default)]
+    pub color: Option<(f32, f32, f32)>,
+    // How the circle is filled in, if not the usual radial gradient.
+    #[serde(default)]
+    pub style: Option<FillStyle>,
+    // When the circle is removed. Circles added without one get the config's
+    // default.
+    #[serde(default)]
+    pub lifetime: Option<LifetimePolicy>,
+    // Number of ticks since the circle was added. Saved so that circles
+    // keep their time to live, and sent to viewers so that they can animate
+    // circles in and out.
+    #[serde(default)]
+    age: u32,
+    // Number of consecutive frames this circle's smoothed velocity has been
+    // slower than the sleep threshold.
+    #[serde(skip)]
+    low_speed_frames: u32,
+    #[serde(skip)]
+    average_velocity: (f32, f32),
+    #[serde(skip)]
+    sleeping: bool,
+    // Position at the start of the latest tick, used to interpolate between
+    // ticks when rendering.
+    #[serde(skip)]
+    previous_position: (f32, f32),
+    // Ticks left until the circle can go through a portal again.
+    #[serde(skip)]
+    portal_cooldown: u32,
+    // The spawner that added the circle, if any.
+    #[serde(skip)]
+    spawner: Option<EntityId>,
+    #[serde(default)]
+    pub tether: Option<Tether>,
+    // Assigned when the circle is added to a grid, replacing any it came
+    // with. It's sent with frames all the same, since viewers find the
+    // circles that springs, ropes and joints link by their ids.
+    #[serde(default)]
+    id: EntityId,
+}
+
+impl Circle {
+    pub fn new(x_pos: f32, y_pos: f32, radius: f32, velocity: (f32, f32)) -> Self {
+        Self {
+            x_pos,
+            y_pos,
+            radius,
+            velocity,
+            density: 1.0,
+            charge: 0.0,
+            drag_model: None,
+            friction: None,
+            restitution: None,
+            color: None,
+            style: None,
+            lifetime: None,
+            age: 0,
+            low_speed_frames: 0,
+            average_velocity: (0.0, 0.0),
+            sleeping: false,
+            previous_position: (x_pos, y_pos),
+            portal_cooldown: 0,
+            spawner: None,
+            tether: None,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    // Ticks since the circle was added.
+    pub fn get_age(&self) -> u32 {
+        self.age
+    }
+
+    pub fn mass(&self) -> f32 {
+        self.density * self.radius * self.radius
+    }
+
+    pub fn interpolated_position(&self, interpolation: f32) -> Point {
+        let (previous_x, previous_y) = self.previous_position;
+        Point::new(
+            previous_x + (self.x_pos - previous_x) * interpolation,
+            previous_y + (self.y_pos - previous_y) * interpolation,
+        )
+    }
+
+    fn wake(&mut self) {
+        self.low_speed_frames = 0;
+        self.sleeping = false;
+    }
+
+    // Takes on `other`'s area, mass and charge, moving to the pair's center of mass
+    // with the velocity that conserves their momentum.
+    fn absorb(&mut self, other: &Circle) {
+        let mass = self.mass();
+        let other_mass = other.mass();
+        let total_mass = mass + other_mass;
+        let weighted = |value: f32, other_value: f32| {
+            if total_mass > 0.0 {
+                (value * mass + other_value * other_mass) / total_mass
+            } else {
+                (value + other_value) / 2.0
+            }
+        };
+
+        self.x_pos = weighted(self.x_pos, other.x_pos);
+        self.y_pos = weighted(self.y_pos, other.y_pos);
+        self.previous_position = (
+            weighted(self.previous_position.0, other.previous_position.0),
+            weighted(self.previous_position.1, other.previous_position.1),
+        );
+        self.velocity = (
+            weighted(self.velocity.0, other.velocity.0),
+            weighted(self.velocity.1, other.velocity.1),
+        );
+        self.average_velocity = self.velocity;
+        self.radius = self.radius.hypot(other.radius);
+        self.density = total_mass / (self.radius * self.radius);
+        self.charge += other.charge;
+        self.wake();
+    }
+
+    pub fn speed(&self) -> f32 {
+        (self.velocity.0.powi(2) + self.velocity.1.powi(2)).sqrt()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticCircle {
+    pub x_pos: f32,
+    pub y_pos: f32,
+    pub radius: f32,
+    // How the circle is filled in, if not solid.
+    #[serde(default)]
+    pub style: Option<FillStyle>,
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl StaticCircle {
+    pub fn new(x_pos: f32, y_pos: f32, radius: f32) -> Self {
+        Self {
+            x_pos,
+            y_pos,
+            radius,
+            style: None,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticRectangle {
+    pub x_pos: f32,
+    pub y_pos: f32,
+    pub width: f32,
+    pub height: f32,
+    // Only stops circles falling onto its top surface; anything else passes
+    // straight through.
+    #[serde(default)]
+    pub one_way: bool,
+    // Speed at which the surface moves like a conveyor belt, in pixels per
+    // tick. Only the part along the surface at the contact point matters.
+    #[serde(default)]
+    pub surface_velocity: (f32, f32),
+    // How the rectangle is filled in, if not solid.
+    #[serde(default)]
+    pub style: Option<FillStyle>,
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl StaticRectangle {
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(self.x_pos, self.y_pos),
+            Size::new(self.width, self.height),
+        )
+    }
+
+    pub fn new(x_pos: f32, y_pos: f32, width: f32, height: f32) -> Self {
+        Self {
+            x_pos,
+            y_pos,
+            width,
+            height,
+            one_way: false,
+            surface_velocity: (0.0, 0.0),
+            style: None,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+}
+
+// How a kinematic body moves. Periods are in ticks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum KinematicPath {
+    // Back and forth between `from` and `to`, at a constant speed.
+    Linear {
+        from: (f32, f32),
+        to: (f32, f32),
+        period: f32,
+    },
+    // Around `center`, clockwise on screen, starting to its right.
+    Orbit {
+        center: (f32, f32),
+        radius: f32,
+        period: f32,
+    },
+}
+
+impl KinematicPath {
+    fn position_at(&self, ticks: f32) -> (f32, f32) {
+        match *self {
+            KinematicPath::Linear { from, to, period } => {
+                if period <= 0.0 {
+                    return from;
+                }
+                let phase = (ticks / period).fract();
+                let t = 1.0 - (2.0 * phase - 1.0).abs();
+                (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+            }
+            KinematicPath::Orbit {
+                center,
+                radius,
+                period,
+            } => {
+                if period <= 0.0 {
+                    return (center.0 + radius, center.1);
+                }
+                let angle = std::f32::consts::TAU * (ticks / period).fract();
+                (
+                    center.0 + radius * angle.cos(),
+                    center.1 + radius * angle.sin(),
+                )
+            }
+        }
+    }
+}
+
+// A rectangle that follows its path regardless of what it runs into, pushing
+// circles along with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KinematicRectangle {
+    pub width: f32,
+    pub height: f32,
+    // Where the center of the rectangle goes.
+    pub path: KinematicPath,
+    // How the rectangle is filled in, if not outlined.
+    #[serde(default)]
+    pub style: Option<FillStyle>,
+    // Ticks simulated since the body was added, scaled by the time scale
+    // they were simulated at, which determines where along its path it is.
+    #[serde(default)]
+    elapsed_ticks: f32,
+    // Center at the start of the latest tick, used to interpolate between
+    // ticks when rendering.
+    #[serde(skip)]
+    previous_center: (f32, f32),
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl KinematicRectangle {
+    pub fn new(width: f32, height: f32, path: KinematicPath) -> Self {
+        Self {
+            width,
+            height,
+            path,
+            style: None,
+            elapsed_ticks: 0.0,
+            previous_center: (0.0, 0.0),
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+
+    fn center(&self) -> (f32, f32) {
+        self.path.position_at(self.elapsed_ticks)
+    }
+
+    fn bounds_at(&self, center: (f32, f32)) -> Rectangle {
+        Rectangle::new(
+            Point::new(center.0 - self.width / 2.0, center.1 - self.height / 2.0),
+            Size::new(self.width, self.height),
+        )
+    }
+
+    fn bounds(&self) -> Rectangle {
+        self.bounds_at(self.center())
+    }
+
+    pub fn interpolated_bounds(&self, interpolation: f32) -> Rectangle {
+        let (previous_x, previous_y) = self.previous_center;
+        let (x_pos, y_pos) = self.center();
+        self.bounds_at((
+            previous_x + (x_pos - previous_x) * interpolation,
+            previous_y + (y_pos - previous_y) * interpolation,
+        ))
+    }
+}
+
+// A paddle that rotates about its center at a constant rate, batting away
+// anything in its way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KinematicSpinner {
+    #[serde(with = "PointDef")]
+    pub pivot: Point,
+    pub length: f32,
+    pub thickness: f32,
+    // Radians per tick. Positive values turn clockwise on screen.
+    pub angular_velocity: f32,
+    // Radians from horizontal.
+    #[serde(default)]
+    pub angle: f32,
+    // Angle at the start of the latest tick, used to interpolate between
+    // ticks when rendering.
+    #[serde(skip)]
+    previous_angle: f32,
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl KinematicSpinner {
+    pub fn new(pivot: Point, length: f32, thickness: f32, angular_velocity: f32) -> Self {
+        Self {
+            pivot,
+            length,
+            thickness,
+            angular_velocity,
+            angle: 0.0,
+            previous_angle: 0.0,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+
+    // Converts a point into the frame of the spinner at `angle`, where it's
+    // centered on the origin and lies along the x axis.
+    fn world_to_local(&self, point: Point, angle: f32) -> Point {
+        let local = rotate(point - self.pivot, -angle);
+        Point::new(local.x, local.y)
+    }
+
+    fn local_to_world(&self, point: Point, angle: f32) -> Point {
+        self.pivot + rotate(Vector::new(point.x, point.y), angle)
+    }
+
+    // The spinner in its own frame.
+    pub fn local_bounds(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(-self.length / 2.0, -self.thickness / 2.0),
+            Size::new(self.length, self.thickness),
+        )
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let (sin, cos) = self.angle.sin_cos();
+        let half_width = (cos * self.length).abs() / 2.0 + (sin * self.thickness).abs() / 2.0;
+        let half_height = (sin * self.length).abs() / 2.0 + (cos * self.thickness).abs() / 2.0;
+        Rectangle::new(
+            Point::new(self.pivot.x - half_width, self.pivot.y - half_height),
+            Size::new(2.0 * half_width, 2.0 * half_height),
+        )
+    }
+
+    pub fn interpolated_angle(&self, interpolation: f32) -> f32 {
+        // The angle wraps around, so take the short way from the previous one.
+        let turned = (self.angle - self.previous_angle + std::f32::consts::PI)
+            .rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        self.previous_angle + turned * interpolation
+    }
+}
+
+// One end of a portal pair. Circles whose center enters it come out of the
+// other end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Portal {
+    #[serde(with = "PointDef")]
+    pub center: Point,
+    pub radius: f32,
+}
+
+impl Portal {
+    fn contains(&self, point: Point) -> bool {
+        point.distance(self.center) < self.radius
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortalPair {
+    pub a: Portal,
+    pub b: Portal,
+    // Radians that velocities are turned by going from `a` to `b`, clockwise
+    // on screen. Going from `b` to `a` turns them back.
+    #[serde(default)]
+    pub rotation: f32,
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl PortalPair {
+    pub fn new(a: Portal, b: Portal, rotation: f32) -> Self {
+        Self {
+            a,
+            b,
+            rotation,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+}
+
+// A spring between two circles, given by their positions in a list of circles
+// such as `Scene::circles` or `SoftBody::circles`. `stiffness` is how much
+// closing speed the spring adds per tick for each unit it's stretched, and
+// `damping` how much of the circles' speed apart it takes away per tick.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spring {
+    pub a: usize,
+    pub b: usize,
+    pub rest_length: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+// A spring once its circles are in the grid.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LinkedSpring {
+    pub a: EntityId,
+    pub b: EntityId,
+    rest_length: f32,
+    stiffness: f32,
+    damping: f32,
+}
+
+// Circles held together by springs into one squishy object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftBody {
+    pub circles: Vec<Circle>,
+    pub springs: Vec<Spring>,
+}
+
+impl SoftBody {
+    // A ring of `particle_count` circles around a center circle, with springs
+    // between neighbours, between every other circle and out to the center.
+    pub fn ring(center: Point, radius: f32, particle_count: usize, particle_radius: f32) -> Self {
+        let mut soft_body = Self {
+            circles: vec![Circle::new(center.x, center.y, particle_radius, (0.0, 0.0))],
+            springs: Vec::new(),
+        };
+
+        for i in 0..particle_count {
+            let angle = std::f32::consts::TAU * i as f32 / particle_count as f32;
+            soft_body.circles.push(Circle::new(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+                particle_radius,
+                (0.0, 0.0),
+            ));
+        }
+
+        for i in 0..particle_count {
+            let ring_index = |offset: usize| 1 + (i + offset) % particle_count;
+            soft_body.connect(0, ring_index(0));
+            soft_body.connect(ring_index(0), ring_index(1));
+            if particle_count > 4 {
+                soft_body.connect(ring_index(0), ring_index(2));
+            }
+        }
+
+        soft_body
+    }
+
+    // A grid of circles `spacing` apart with its top left circle at
+    // `origin`, braced with springs along the rows, columns and diagonals.
+    pub fn lattice(
+        origin: Point,
+        columns: usize,
+        rows: usize,
+        spacing: f32,
+        particle_radius: f32,
+    ) -> Self {
+        let mut soft_body = Self {
+            circles: Vec::new(),
+            springs: Vec::new(),
+        };
+
+        for row in 0..rows {
+            for column in 0..columns {
+                soft_body.circles.push(Circle::new(
+                    origin.x + column as f32 * spacing,
+                    origin.y + row as f32 * spacing,
+                    particle_radius,
+                    (0.0, 0.0),
+                ));
+            }
+        }
+
+        let index = |column: usize, row: usize| row * columns + column;
+        for row in 0..rows {
+            for column in 0..columns {
+                if column + 1 < columns {
+                    soft_body.connect(index(column, row), index(column + 1, row));
+                }
+                if row + 1 < rows {
+                    soft_body.connect(index(column, row), index(column, row + 1));
+                }
+                if column + 1 < columns && row + 1 < rows {
+                    soft_body.connect(index(column, row), index(column + 1, row + 1));
+                    soft_body.connect(index(column + 1, row), index(column, row + 1));
+                }
+            }
+        }
+
+        soft_body
+    }
+
+    pub fn with_velocity(mut self, velocity: (f32, f32)) -> Self {
+        for circle in &mut self.circles {
+            circle.velocity = velocity;
+        }
+        self
+    }
+
+    pub fn with_spring_constants(mut self, stiffness: f32, damping: f32) -> Self {
+        for spring in &mut self.springs {
+            spring.stiffness = stiffness;
+            spring.damping = damping;
+        }
+        self
+    }
+
+    // Adds a spring that's at rest at the circles' current distance.
+    fn connect(&mut self, a: usize, b: usize) {
+        let (circle_a, circle_b) = (&self.circles[a], &self.circles[b]);
+        self.springs.push(Spring {
+            a,
+            b,
+            rest_length: Point::new(circle_a.x_pos, circle_a.y_pos)
+                .distance(Point::new(circle_b.x_pos, circle_b.y_pos)),
+            stiffness: SOFT_BODY_STIFFNESS,
+            damping: SOFT_BODY_DAMPING,
+        });
+    }
+}
+
+// A chain of circles hanging from an anchor, each link no further than
+// `segment_length` from the one before it. Like springs, the circles are given
+// by their positions in a list of circles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rope {
+    pub anchor: Anchor,
+    // Furthest the first link can be from the anchor.
+    pub anchor_length: f32,
+    pub circles: Vec<usize>,
+    pub segment_length: f32,
+}
+
+// What a rope or joint is attached to: a fixed point, or a circle given by
+// its position in a list of circles.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Anchor {
+    Point(#[serde(with = "PointDef")] Point),
+    Circle(usize),
+}
+
+impl Anchor {
+    // Looks the circle up among the ids of the list's circles.
+    fn link(self, ids: &[EntityId]) -> Option<LinkedAnchor> {
+        match self {
+            Anchor::Point(point) => Some(LinkedAnchor::Point(point)),
+            Anchor::Circle(index) => ids.get(index).copied().map(LinkedAnchor::Circle),
+        }
+    }
+}
+
+// A rope once its circles are in the grid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedRope {
+    pub anchor: LinkedAnchor,
+    anchor_length: f32,
+    pub circles: Vec<EntityId>,
+    segment_length: f32,
+}
+
+// An anchor once its circle, if it has one, is in the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LinkedAnchor {
+    Point(#[serde(with = "PointDef")] Point),
+    Circle(EntityId),
+}
+
+impl LinkedAnchor {
+    fn unlink(self, circle_indices: &HashMap<EntityId, usize>) -> Option<Anchor> {
+        match self {
+            LinkedAnchor::Point(point) => Some(Anchor::Point(point)),
+            LinkedAnchor::Circle(id) => circle_indices.get(&id).copied().map(Anchor::Circle),
+        }
+    }
+}
+
+// Holds a circle at exactly `length` from its anchor. Circles don't turn, so
+// this is all a pin or revolute joint between them needs to do.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Joint {
+    pub body: usize,
+    pub anchor: Anchor,
+    pub length: f32,
+}
+
+// A joint once its circles are in the grid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedJoint {
+    pub body: EntityId,
+    pub anchor: LinkedAnchor,
+    length: f32,
+}
+
+// Which circles make way when the grid has more than `max_circles`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionPolicy {
+    // The circles that have been in the grid the longest.
+    #[default]
+    OldestFirst,
+    SmallestFirst,
+    // None. Circles that would go over the limit aren't added, so the grid
+    // stays as it is. Lowering the limit leaves the circles already there.
+    RejectNew,
+}
+
+// What happens to circles found entirely outside the world, past an edge
+// they wouldn't wrap around from. They can end up there by being added
+// there, by leaving through an open edge, or by the world shrinking around
+// them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutOfBoundsPolicy {
+    // They're left to carry on, and may never come back.
+    #[default]
+    Keep,
+    // They're moved back to just inside the edge, and stop heading out.
+    Clamp,
+    // They're removed, as if the edge despawned circles.
+    Despawn,
+}
+
+// When a circle is taken out of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LifetimePolicy {
+    // Stays until something else removes it.
+    Immortal,
+    // Loses `rate` of its radius every tick, and is removed once it's smaller
+    // than `min_radius_size`.
+    ShrinkAndDie { rate: f32 },
+    // Removed after this many ticks.
+    Ttl { frames: u32 },
+    // Removed once it's entirely outside the world.
+    DespawnOffscreen,
+}
+
+// Keeps a circle within `length` of `anchor`, like a pendulum on a string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tether {
+    #[serde(with = "PointDef")]
+    pub anchor: Point,
+    pub length: f32,
+}
+
+// Adds a circle every `interval` ticks while enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spawner {
+    #[serde(with = "PointDef")]
+    pub position: Point,
+    // Radius of the circles that are added.
+    pub radius: f32,
+    pub velocity: VelocityDistribution,
+    pub interval: u32,
+    // Stops adding circles while this many of the ones it added are still in
+    // the grid.
+    #[serde(default)]
+    pub max_alive: Option<u32>,
+    // Given to every circle that's added, instead of the config's default.
+    #[serde(default)]
+    pub lifetime: Option<LifetimePolicy>,
+    pub enabled: bool,
+    #[serde(default)]
+    ticks_until_spawn: u32,
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl Spawner {
+    pub fn new(
+        position: Point,
+        radius: f32,
+        velocity: VelocityDistribution,
+        interval: u32,
+    ) -> Self {
+        Self {
+            position,
+            radius,
+            velocity,
+            interval,
+            max_alive: None,
+            lifetime: None,
+            enabled: true,
+            ticks_until_spawn: 0,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+}
+
+// The velocities that spawned circles start with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum VelocityDistribution {
+    Fixed((f32, f32)),
+    // A speed and a direction picked uniformly from the given ranges. Angles
+    // are in radians, clockwise on screen from the positive x axis.
+    Uniform {
+        min_speed: f32,
+        max_speed: f32,
+        min_angle: f32,
+        max_angle: f32,
+    },
+}
+
+impl VelocityDistribution {
+    fn sample(&self, rng: &mut Rng) -> (f32, f32) {
+        match *self {
+            VelocityDistribution::Fixed(velocity) => velocity,
+            VelocityDistribution::Uniform {
+                min_speed,
+                max_speed,
+                min_angle,
+                max_angle,
+            } => {
+                let speed = rng.range(min_speed, max_speed);
+                let (sin, cos) = rng.range(min_angle, max_angle).sin_cos();
+                (speed * cos, speed * sin)
+            }
+        }
+    }
+}
+
+// What `GridMessage::SpawnBurst` adds: `count` circles with radii from
+// `min_radius` to `max_radius`, scattered within `spread` of where it's aimed,
+// each moving as `velocity` says.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpawnBurst {
+    pub count: u32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    pub spread: f32,
+    pub velocity: VelocityDistribution,
+}
+
+impl Default for SpawnBurst {
+    fn default() -> Self {
+        Self {
+            count: 100,
+            min_radius: 3.0,
+            max_radius: 8.0,
+            spread: 40.0,
+            // Fanned out upwards.
+            velocity: VelocityDistribution::Uniform {
+                min_speed: 4.0,
+                max_speed: 10.0,
+                min_angle: -std::f32::consts::PI,
+                max_angle: 0.0,
+            },
+        }
+    }
+}
+
+// A number picked at random for each spawned circle.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Distribution {
+    Fixed(f32),
+    // Uniformly from `min` up to `max`.
+    Uniform { min: f32, max: f32 },
+    // Bell-shaped around `mean`, with about two thirds of the numbers within
+    // `std_dev` of it, but never below `MIN_NORMAL_SAMPLE`.
+    Normal { mean: f32, std_dev: f32 },
+}
+
+impl Distribution {
+    fn sample(self, rng: &mut Rng) -> f32 {
+        match self {
+            Distribution::Fixed(value) => value,
+            Distribution::Uniform { min, max } => rng.range(min, max),
+            // The Box-Muller transform. `1 - next_f32()` is never zero, so
+            // its log is finite.
+            Distribution::Normal { mean, std_dev } => {
+                let magnitude = (-2.0 * (1.0 - rng.next_f32()).ln()).sqrt();
+                let angle = rng.range(0.0, std::f32::consts::TAU);
+                (mean + std_dev * magnitude * angle.cos()).max(MIN_NORMAL_SAMPLE)
+            }
+        }
+    }
+
+    // The smallest number that can be sampled, or for normal distributions,
+    // that's at all likely to be.
+    pub fn smallest(self) -> f32 {
+        match self {
+            Distribution::Fixed(value) => value,
+            Distribution::Uniform { min, max } => min.min(max),
+            Distribution::Normal { mean, std_dev } => {
+                (mean - 3.0 * std_dev.abs()).max(MIN_NORMAL_SAMPLE)
+            }
+        }
+    }
+
+    // The largest number that can be sampled, or for normal distributions,
+    // that's at all likely to be.
+    pub fn largest(self) -> f32 {
+        match self {
+            Distribution::Fixed(value) => value,
+            Distribution::Uniform { min, max } => min.max(max),
+            Distribution::Normal { mean, std_dev } => {
+                (mean + 3.0 * std_dev.abs()).max(MIN_NORMAL_SAMPLE)
+            }
+        }
+    }
+}
+
+// How every spawned circle is randomized, whether it comes from the brush,
+// a spawner or a burst. Sizes and speeds are scaled rather than replaced, so
+// that each of those keeps its own, only varied. The defaults change nothing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpawnProfile {
+    // What each circle's radius is multiplied by.
+    pub radius_scale: Distribution,
+    // What each circle's speed is multiplied by. Its direction stays the
+    // same.
+    pub speed_scale: Distribution,
+    // Red, green and blue from 0 to 1. Each circle is given one of them,
+    // picked at random. With none, circles are drawn in the palette's color.
+    pub colors: Vec<(f32, f32, f32)>,
+    // Each circle's mass per unit of area.
+    pub density: Distribution,
+}
+
+impl Default for SpawnProfile {
+    fn default() -> Self {
+        Self {
+            radius_scale: Distribution::Fixed(1.0),
+            speed_scale: Distribution::Fixed(1.0),
+            colors: Vec::new(),
+            density: Distribution::Fixed(1.0),
+        }
+    }
+}
+
+impl SpawnProfile {
+    // Doesn't touch `rng` for fixed numbers or without colors, so that the
+    // default profile leaves the grid's randomness as it was.
+    fn apply(&self, circle: &mut Circle, rng: &mut Rng) {
+        circle.radius *= self.radius_scale.sample(rng);
+        let speed_scale = self.speed_scale.sample(rng);
+        circle.velocity = (
+            circle.velocity.0 * speed_scale,
+            circle.velocity.1 * speed_scale,
+        );
+        if !self.colors.is_empty() {
+            let index = (rng.next_u64() % self.colors.len() as u64) as usize;
+            circle.color = Some(self.colors[index]);
+        }
+        circle.density = self.density.sample(rng);
+    }
+}
+
+// A region that swallows any circle whose center enters it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sink {
+    pub x_pos: f32,
+    pub y_pos: f32,
+    pub width: f32,
+    pub height: f32,
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl Sink {
+    fn bounds(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(self.x_pos, self.y_pos),
+            Size::new(self.width, self.height),
+        )
+    }
+
+    pub fn new(x_pos: f32, y_pos: f32, width: f32, height: f32) -> Self {
+        Self {
+            x_pos,
+            y_pos,
+            width,
+            height,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+}
+
+// A body of fluid that pushes circles against gravity in proportion to how
+// much of them is submerged and how dense the fluid is compared to them, so
+// circles lighter than the fluid float and heavier ones sink. Submerged
+// circles are also slowed by `drag` of their velocity per tick, scaled the
+// same way as air drag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FluidZone {
+    pub x_pos: f32,
+    pub y_pos: f32,
+    pub width: f32,
+    pub height: f32,
+    pub density: f32,
+    pub drag: f32,
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl FluidZone {
+    pub fn new(rect: Rectangle, density: f32, drag: f32) -> Self {
+        Self {
+            x_pos: rect.x,
+            y_pos: rect.y,
+            width: rect.width,
+            height: rect.height,
+            density,
+            drag,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+
+    // How much of the circle's area is inside the zone, from 0 to 1. The
+    // depth is measured exactly, while the sides are approximated by how much
+    // of the circle's width overlaps the zone.
+    fn submerged_fraction(&self, circle: &Body) -> f32 {
+        let radius = circle.radius;
+        let overlap = (circle.x_pos + radius).min(self.x_pos + self.width)
+            - (circle.x_pos - radius).max(self.x_pos);
+        if overlap <= 0.0 {
+            return 0.0;
+        }
+
+        let bottom = circle.y_pos + radius;
+        let below_surface = circle_fraction_below(radius, bottom - self.y_pos);
+        let below_floor = circle_fraction_below(radius, bottom - (self.y_pos + self.height));
+        (below_surface - below_floor) * (overlap / (2.0 * radius)).min(1.0)
+    }
+
+    fn apply(&self, circle: &mut Body, gravity: (f32, f32), dt: f32) {
+        let submerged = self.submerged_fraction(circle);
+        if submerged <= 0.0 {
+            return;
+        }
+
+        let circle_density = circle.density.max(f32::EPSILON);
+        let lift = self.density / circle_density * submerged * dt;
+        circle.velocity.0 -= gravity.0 * lift;
+        circle.velocity.1 -= gravity.1 * lift;
+
+        let retained = (1.0 - self.drag * submerged / circle_density)
+            .max(0.0)
+            .powf(dt);
+        circle.velocity.0 *= retained;
+        circle.velocity.1 *= retained;
+    }
+}
+
+// The fraction of a circle's area that's within `depth` of its lowest point.
+fn circle_fraction_below(radius: f32, depth: f32) -> f32 {
+    let depth = depth.clamp(0.0, 2.0 * radius);
+    let offset = radius - depth;
+    let segment_area = radius * radius * (offset / radius).clamp(-1.0, 1.0).acos()
+        - offset * (2.0 * radius * depth - depth * depth).max(0.0).sqrt();
+    segment_area / (std::f32::consts::PI * radius * radius)
+}
+
+// Pulls every circle within `radius` of it towards its center. The pull is
+// `strength` units per tick per tick at the center, falling off linearly to
+// nothing at `radius`. A negative strength pushes circles away instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GravityWell {
+    pub x_pos: f32,
+    pub y_pos: f32,
+    pub strength: f32,
+    pub radius: f32,
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl GravityWell {
+    pub fn new(x_pos: f32, y_pos: f32, strength: f32, radius: f32) -> Self {
+        Self {
+            x_pos,
+            y_pos,
+            strength,
+            radius,
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+
+    fn apply(&self, circle: &mut Body, dt: f32) {
+        let dx = self.x_pos - circle.x_pos;
+        let dy = self.y_pos - circle.y_pos;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance >= self.radius || distance <= 1e-6 {
+            return;
+        }
+
+        let pull = self.strength * (1.0 - distance / self.radius) * dt / distance;
+        circle.velocity.0 += dx * pull;
+        circle.velocity.1 += dy * pull;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+// Every point within `radius` of the segment from `a` to `b`.
+pub struct StaticCapsule {
+    #[serde(with = "PointDef")]
+    pub a: Point,
+    #[serde(with = "PointDef")]
+    pub b: Point,
+    pub radius: f32,
+    // Speed at which the surface moves like a conveyor belt, in pixels per
+    // tick. Only the part along the capsule's axis matters on its sides.
+    #[serde(default)]
+    pub surface_velocity: (f32, f32),
+    // Assigned when the body is added to a grid.
+    #[serde(skip)]
+    id: EntityId,
+}
+
+impl StaticCapsule {
+    pub fn new(a: Point, b: Point, radius: f32) -> Self {
+        Self {
+            a,
+            b,
+            radius,
+            surface_velocity: (0.0, 0.0),
+            id: EntityId::default(),
+        }
+    }
+
+    pub fn get_id(&self) -> EntityId {
+        self.id
+    }
+
+    // The point on the center line closest to `point`.
+    fn closest_point(&self, point: Point) -> Point {
+        let axis = self.b - self.a;
+        let length_squared = axis.x * axis.x + axis.y * axis.y;
+        if length_squared <= f32::EPSILON {
+            return self.a;
+        }
+
+        let relative_point = point - self.a;
+        let t = ((relative_point.x * axis.x + relative_point.y * axis.y) / length_squared)
+            .clamp(0.0, 1.0);
+        self.a + axis * t
+    }
+
+    pub fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(
+                self.a.x.min(self.b.x) - self.radius,
+                self.a.y.min(self.b.y) - self.radius,
+            ),
+            Size::new(
+                (self.a.x - self.b.x).abs() + 2.0 * self.radius,
+                (self.a.y - self.b.y).abs() + 2.0 * self.radius,
+            ),
+        )
+    }
+}
+
+fn default_density() -> f32 {
+    1.0
+}
+
+// Tethers and rope links are slack, so they only act on circles that have
+// moved past their length, and only stop them from moving further away.
+fn limit_distance_to_point(circle: &mut Body, anchor: Point, length: f32) {
+    let dx = circle.x_pos - anchor.x;
+    let dy = circle.y_pos - anchor.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance <= length || distance <= 1e-8 {
+        return;
+    }
+
+    let (nx, ny) = (dx / distance, dy / distance);
+    circle.x_pos = anchor.x + nx * length;
+    circle.y_pos = anchor.y + ny * length;
+
+    let v_dot_n = circle.velocity.0 * nx + circle.velocity.1 * ny;
+    if v_dot_n > 0.0 {
+        circle.velocity.0 -= v_dot_n * nx;
+        circle.velocity.1 -= v_dot_n * ny;
+    }
+}
+
+// Unlike the slack limits, joints push back as well as pull, and take away
+// all of the circle's speed towards or away from the anchor.
+fn hold_distance_to_point(circle: &mut Body, anchor: Point, length: f32) {
+    let dx = circle.x_pos - anchor.x;
+    let dy = circle.y_pos - anchor.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance <= 1e-8 {
+        // Pinned right at the anchor, so the circle can't go anywhere.
+        if length <= 1e-8 {
+            circle.velocity = (0.0, 0.0);
+        }
+        return;
+    }
+
+    let (nx, ny) = (dx / distance, dy / distance);
+    circle.x_pos = anchor.x + nx * length;
+    circle.y_pos = anchor.y + ny * length;
+
+    let v_dot_n = circle.velocity.0 * nx + circle.velocity.1 * ny;
+    circle.velocity.0 -= v_dot_n * nx;
+    circle.velocity.1 -= v_dot_n * ny;
+}
+
+fn hold_distance(circle_a: &mut Body, circle_b: &mut Body, length: f32) {
+    if circle_a.sleeping && circle_b.sleeping {
+        return;
+    }
+    circle_a.wake();
+    circle_b.wake();
+
+    let dx = circle_b.x_pos - circle_a.x_pos;
+    let dy = circle_b.y_pos - circle_a.y_pos;
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance <= 1e-8 {
+        return;
+    }
+
+    let (nx, ny) = (dx / distance, dy / distance);
+    let w_a = circle_a.inverse_mass();
+    let w_b = circle_b.inverse_mass();
+    let share_a = w_a / (w_a + w_b);
+    let share_b = w_b / (w_a + w_b);
+
+    let stretch = distance - length;
+    circle_a.x_pos += stretch * nx * share_a;
+    circle_a.y_pos += stretch * ny * share_a;
+    circle_b.x_pos -= stretch * nx * share_b;
+    circle_b.y_pos -= stretch * ny * share_b;
+
+    let separating_speed = (circle_b.velocity.0 - circle_a.velocity.0) * nx
+        + (circle_b.velocity.1 - circle_a.velocity.1) * ny;
+    circle_a.velocity.0 += separating_speed * nx * share_a;
+    circle_a.velocity.1 += separating_speed * ny * share_a;
+    circle_b.velocity.0 -= separating_speed * nx * share_b;
+    circle_b.velocity.1 -= separating_speed * ny * share_b;
+}
+
+// Like `limit_distance_to_point`, but both circles give way, in proportion to
+// their masses. Stretching the link wakes both of them.
+fn limit_distance(circle_a: &mut Body, circle_b: &mut Body, length: f32) {
+    if circle_a.sleeping && circle_b.sleeping {
+        return;
+    }
+
+    let dx = circle_b.x_pos - circle_a.x_pos;
+    let dy = circle_b.y_pos - circle_a.y_pos;
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance <= length || distance <= 1e-8 {
+        return;
+    }
+    circle_a.wake();
+    circle_b.wake();
+
+    let (nx, ny) = (dx / distance, dy / distance);
+    let w_a = circle_a.inverse_mass();
+    let w_b = circle_b.inverse_mass();
+    let share_a = w_a / (w_a + w_b);
+    let share_b = w_b / (w_a + w_b);
+
+    let stretch = distance - length;
+    circle_a.x_pos += stretch * nx * share_a;
+    circle_a.y_pos += stretch * ny * share_a;
+    circle_b.x_pos -= stretch * nx * share_b;
+    circle_b.y_pos -= stretch * ny * share_b;
+
+    let separating_speed = (circle_b.velocity.0 - circle_a.velocity.0) * nx
+        + (circle_b.velocity.1 - circle_a.velocity.1) * ny;
+    if separating_speed > 0.0 {
+        circle_a.velocity.0 += separating_speed * nx * share_a;
+        circle_a.velocity.1 += separating_speed * ny * share_a;
+        circle_b.velocity.0 -= separating_speed * nx * share_b;
+        circle_b.velocity.1 -= separating_speed * ny * share_b;
+    }
+}
+
+// Rotates the vector by `angle` radians, clockwise on screen.
+fn rotate(vector: Vector, angle: f32) -> Vector {
+    let (sin, cos) = angle.sin_cos();
+    Vector::new(
+        vector.x * cos - vector.y * sin,
+        vector.x * sin + vector.y * cos,
+    )
+}
+
+// Distance along a ray with a normalized `direction` to where it enters the
+// circle, or zero if it starts inside it.
+fn ray_circle_distance(
+    origin: Point,
+    direction: Vector,
+    center: Point,
+    radius: f32,
+) -> Option<f32> {
+    let to_center = center - origin;
+    let projection = to_center.x * direction.x + to_center.y * direction.y;
+    let closest_distance_squared =
+        to_center.x * to_center.x + to_center.y * to_center.y - projection * projection;
+    let radius_squared = radius * radius;
+    if closest_distance_squared > radius_squared {
+        return None;
+    }
+
+    let half_chord = (radius_squared - closest_distance_squared).sqrt();
+    if projection + half_chord < 0.0 {
+        // The circle is behind the ray.
+        None
+    } else {
+        Some(f32::max(projection - half_chord, 0.0))
+    }
+}
+
+// Distance along a ray with a normalized `direction` to where it enters the
+// capsule's outline, or zero if it starts inside it.
+fn ray_capsule_distance(origin: Point, direction: Vector, capsule: &StaticCapsule) -> Option<f32> {
+    let end_hits = [
+        ray_circle_distance(origin, direction, capsule.a, capsule.radius),
+        ray_circle_distance(origin, direction, capsule.b, capsule.radius),
+    ];
+
+    let axis = capsule.b - capsule.a;
+    let length = (axis.x * axis.x + axis.y * axis.y).sqrt();
+    if length <= f32::EPSILON {
+        return end_hits.into_iter().flatten().min_by(f32::total_cmp);
+    }
+
+    // Work in the capsule's frame, where it runs along the x axis from 0 to
+    // `length` and its straight sides are at y = ±`radius`.
+    let (ux, uy) = (axis.x / length, axis.y / length);
+    let relative_origin = origin - capsule.a;
+    let local_x = relative_origin.x * ux + relative_origin.y * uy;
+    let local_y = relative_origin.y * ux - relative_origin.x * uy;
+    let local_dx = direction.x * ux + direction.y * uy;
+    let local_dy = direction.y * ux - direction.x * uy;
+
+    let side_hit = if local_y.abs() <= capsule.radius {
+        (0.0..=length).contains(&local_x).then_some(0.0)
+    } else if local_dy.abs() > f32::EPSILON {
+        let side_y = capsule.radius.copysign(local_y);
+        let distance = (side_y - local_y) / local_dy;
+        (distance >= 0.0 && (0.0..=length).contains(&(local_x + local_dx * distance)))
+            .then_some(distance)
+    } else {
+        None
+    };
+
+    end_hits
+        .into_iter()
+        .chain([side_hit])
+        .flatten()
+        .min_by(f32::total_cmp)
+}
+
+// Distance along a ray to where it enters the rectangle, or zero if it starts
+// inside it.
+fn ray_rectangle_distance(origin: Point, direction: Vector, rectangle: Rectangle) -> Option<f32> {
+    let mut entry: f32 = 0.0;
+    let mut exit = f32::INFINITY;
+
+    for (origin, direction, min, max) in [
+        (
+            origin.x,
+            direction.x,
+            rectangle.x,
+            rectangle.x + rectangle.width,
+        ),
+        (
+            origin.y,
+            direction.y,
+            rectangle.y,
+            rectangle.y + rectangle.height,
+        ),
+    ] {
+        if direction.abs() <= f32::EPSILON {
+            if origin < min || origin > max {
+                return None;
+            }
+            continue;
+        }
+
+        let near = (min - origin) / direction;
+        let far = (max - origin) / direction;
+        entry = entry.max(near.min(far));
+        exit = exit.min(near.max(far));
+    }
+
+    (entry <= exit).then_some(entry)
+}
+
+fn clamp(value: f32, min: f32, max: f32) -> f32 {
+    if value < min {
+        min
+    } else if value > max {
+        max
+    } else {
+        value
+    }
+}
@@ -0,0 +1,226 @@
+use iced::{Point, Size};
+use serde::{Deserialize, Serialize};
+
+use super::{Grid, KinematicPath, LinkedAnchor};
+
+// What happens to the bodies in the world when it's resized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResizeContents {
+    // Bodies stay where they are, and only the walls move.
+    #[default]
+    Fixed,
+    // Positions are scaled along each axis with the world, and sizes by the
+    // geometric mean of the two, so that circles stay round.
+    Scale,
+    // Bodies keep their size and their distance to the nearest edge along
+    // each axis, so ones in the right or bottom half move with those edges.
+    Anchor,
+}
+
+// Where a point in the world ends up after a resize.
+enum Remap {
+    Scale { x: f32, y: f32 },
+    Anchor { old: Size, new: Size },
+}
+
+impl Remap {
+    fn point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        match *self {
+            Remap::Scale {
+                x: x_scale,
+                y: y_scale,
+            } => (x * x_scale, y * y_scale),
+            Remap::Anchor { old, new } => (
+                if x > old.width / 2.0 {
+                    x + new.width - old.width
+                } else {
+                    x
+                },
+                if y > old.height / 2.0 {
+                    y + new.height - old.height
+                } else {
+                    y
+                },
+            ),
+        }
+    }
+
+    // How much widths and heights are scaled by.
+    fn axes(&self) -> (f32, f32) {
+        match *self {
+            Remap::Scale { x, y } => (x, y),
+            Remap::Anchor { .. } => (1.0, 1.0),
+        }
+    }
+
+    // How much radii and other lengths that aren't along an axis are scaled
+    // by.
+    fn length(&self) -> f32 {
+        match *self {
+            Remap::Scale { x, y } => (x * y).sqrt(),
+            Remap::Anchor { .. } => 1.0,
+        }
+    }
+
+    // Where a point on a body centered at `center` ends up, so that the body
+    // moves as a whole instead of being pulled apart by `Anchor`.
+    fn around(&self, center: (f32, f32), (x, y): (f32, f32)) -> (f32, f32) {
+        let new_center = self.point(center);
+        let (x_scale, y_scale) = self.axes();
+        (
+            new_center.0 + (x - center.0) * x_scale,
+            new_center.1 + (y - center.1) * y_scale,
+        )
+    }
+
+    // The new top left corner and size of a rectangle.
+    fn rectangle(&self, position: (f32, f32), size: (f32, f32)) -> ((f32, f32), (f32, f32)) {
+        let center = (position.0 + size.0 / 2.0, position.1 + size.1 / 2.0);
+        let (x_scale, y_scale) = self.axes();
+        (
+            self.around(center, position),
+            (size.0 * x_scale, size.1 * y_scale),
+        )
+    }
+
+    fn point_of(&self, point: Point) -> Point {
+        let (x, y) = self.point((point.x, point.y));
+        Point::new(x, y)
+    }
+}
+
+impl Grid {
+    // Resizes the world, and moves what's in it according to
+    // `SimulationConfig::resize_contents`.
+    pub(super) fn resize(&mut self, size: Size) {
+        let old = Size::new(self.width, self.height);
+        self.width = size.width;
+        self.height = size.height;
+
+        // A grid made with no size has nothing to scale from.
+        if old.width > 0.0 && old.height > 0.0 {
+            match self.config.resize_contents {
+                ResizeContents::Fixed => {}
+                ResizeContents::Scale => self.remap(Remap::Scale {
+                    x: size.width / old.width,
+                    y: size.height / old.height,
+                }),
+                ResizeContents::Anchor => self.remap(Remap::Anchor { old, new: size }),
+            }
+        }
+        self.wake_all();
+    }
+
+    fn remap(&mut self, remap: Remap) {
+        let length = remap.length();
+        let (x_scale, y_scale) = remap.axes();
+
+        let bodies = &mut self.bodies;
+        for ((position, radius), details) in bodies
+            .positions
+            .iter_mut()
+            .zip(&mut bodies.radii)
+            .zip(&mut bodies.details)
+        {
+            details.previous_position = remap.around(*position, details.previous_position);
+            *position = remap.point(*position);
+            *radius *= length;
+            if let Some(tether) = &mut details.tether {
+                tether.anchor = remap.point_of(tether.anchor);
+                tether.length *= length;
+            }
+        }
+
+        for static_circle in &mut self.static_circles {
+            (static_circle.x_pos, static_circle.y_pos) =
+                remap.point((static_circle.x_pos, static_circle.y_pos));
+            static_circle.radius *= length;
+        }
+        for static_rectangle in &mut self.static_rectangles {
+            (
+                (static_rectangle.x_pos, static_rectangle.y_pos),
+                (static_rectangle.width, static_rectangle.height),
+            ) = remap.rectangle(
+                (static_rectangle.x_pos, static_rectangle.y_pos),
+                (static_rectangle.width, static_rectangle.height),
+            );
+        }
+        for static_capsule in &mut self.static_capsules {
+            let center = (
+                (static_capsule.a.x + static_capsule.b.x) / 2.0,
+                (static_capsule.a.y + static_capsule.b.y) / 2.0,
+            );
+            for end in [&mut static_capsule.a, &mut static_capsule.b] {
+                let (x, y) = remap.around(center, (end.x, end.y));
+                *end = Point::new(x, y);
+            }
+            static_capsule.radius *= length;
+        }
+        for kinematic_rectangle in &mut self.kinematic_rectangles {
+            kinematic_rectangle.width *= x_scale;
+            kinematic_rectangle.height *= y_scale;
+            match &mut kinematic_rectangle.path {
+                KinematicPath::Linear { from, to, .. } => {
+                    *from = remap.point(*from);
+                    *to = remap.point(*to);
+                }
+                KinematicPath::Orbit { center, radius, .. } => {
+                    *center = remap.point(*center);
+                    *radius *= length;
+                }
+            }
+            // It's drawn where it is now, not swept across from where it was.
+            kinematic_rectangle.previous_center = kinematic_rectangle.center();
+        }
+        for kinematic_spinner in &mut self.kinematic_spinners {
+            kinematic_spinner.pivot = remap.point_of(kinematic_spinner.pivot);
+            kinematic_spinner.length *= length;
+            kinematic_spinner.thickness *= length;
+        }
+        for portal_pair in &mut self.portal_pairs {
+            for portal in [&mut portal_pair.a, &mut portal_pair.b] {
+                portal.center = remap.point_of(portal.center);
+                portal.radius *= length;
+            }
+        }
+        for sink in &mut self.sinks {
+            ((sink.x_pos, sink.y_pos), (sink.width, sink.height)) =
+                remap.rectangle((sink.x_pos, sink.y_pos), (sink.width, sink.height));
+        }
+        for fluid_zone in &mut self.fluid_zones {
+            (
+                (fluid_zone.x_pos, fluid_zone.y_pos),
+                (fluid_zone.width, fluid_zone.height),
+            ) = remap.rectangle(
+                (fluid_zone.x_pos, fluid_zone.y_pos),
+                (fluid_zone.width, fluid_zone.height),
+            );
+        }
+        for gravity_well in &mut self.gravity_wells {
+            (gravity_well.x_pos, gravity_well.y_pos) =
+                remap.point((gravity_well.x_pos, gravity_well.y_pos));
+            gravity_well.radius *= length;
+        }
+        for spawner in &mut self.spawners {
+            spawner.position = remap.point_of(spawner.position);
+            spawner.radius *= length;
+        }
+
+        for spring in &mut self.springs {
+            spring.rest_length *= length;
+        }
+        for rope in &mut self.ropes {
+            rope.anchor_length *= length;
+            rope.segment_length *= length;
+            if let LinkedAnchor::Point(point) = &mut rope.anchor {
+                *point = remap.point_of(*point);
+            }
+        }
+        for joint in &mut self.joints {
+            joint.length *= length;
+            if let LinkedAnchor::Point(point) = &mut joint.anchor {
+                *point = remap.point_of(*point);
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+
+use super::{
+    Bodies, BuiltinForces, ContactSolver, DragModel, EntityAllocator, FluidZone, GravityWell, Grid,
+    KinematicRectangle, KinematicSpinner, LinkedJoint, LinkedRope, LinkedSpring, PortalPair, Sink,
+    Spawner, StaticCapsule, StaticCircle, StaticRectangle, TICKS_PER_SECOND,
+};
+use crate::random::Rng;
+
+// Ticks between keyframes. Stepping back goes a keyframe at a time, so this
+// trades how finely the simulation can be rewound for how much memory the
+// keyframes take.
+const KEYFRAME_INTERVAL: u32 = 6;
+
+// Everything about the grid that changes as it's simulated, as of the end of
+// one tick. The config, size, force generators and message receiver belong
+// to whoever is running the grid, so rewinding leaves them alone. So does the
+// frame number, which keeps counting up so that recordings stay in order. The
+// broadphase is rebuilt rather than saved, since it only keeps state to speed
+// itself up.
+#[derive(Clone)]
+struct Keyframe {
+    // `RewindBuffer::tick` when the keyframe was taken.
+    tick: u32,
+    bodies: Bodies,
+    static_circles: Vec<StaticCircle>,
+    static_rectangles: Vec<StaticRectangle>,
+    static_capsules: Vec<StaticCapsule>,
+    kinematic_rectangles: Vec<KinematicRectangle>,
+    kinematic_spinners: Vec<KinematicSpinner>,
+    portal_pairs: Vec<PortalPair>,
+    sinks: Vec<Sink>,
+    fluid_zones: Vec<FluidZone>,
+    gravity_wells: Vec<GravityWell>,
+    spawners: Vec<Spawner>,
+    springs: Vec<LinkedSpring>,
+    ropes: Vec<LinkedRope>,
+    joints: Vec<LinkedJoint>,
+    contact_solver: ContactSolver,
+    drag_model: DragModel,
+    rng: Rng,
+    entities: EntityAllocator,
+}
+
+// Keyframes of the grid over the last `rewind_seconds`, oldest first, so that
+// it can be stepped back through them. Simulating forward again from a
+// keyframe starts a new history, so the keyframes after it are dropped.
+#[derive(Default)]
+pub(super) struct RewindBuffer {
+    keyframes: VecDeque<Keyframe>,
+    // Ticks simulated so far along the current history. Paused ticks don't
+    // count, and stepping back winds it back.
+    tick: u32,
+}
+
+impl RewindBuffer {
+    // Counts a simulated tick, and keeps a keyframe of `grid` as it is after
+    // it if one is due, dropping any that have aged out.
+    pub(super) fn record(&mut self, grid: &Grid) {
+        self.tick += 1;
+
+        let capacity = Self::capacity(grid.config.rewind_seconds);
+        if capacity == 0 {
+            self.keyframes.clear();
+            return;
+        }
+        if !self.tick.is_multiple_of(KEYFRAME_INTERVAL) {
+            return;
+        }
+
+        self.drop_rewound_keyframes();
+        while self.keyframes.len() >= capacity {
+            self.keyframes.pop_front();
+        }
+        self.keyframes.push_back(Keyframe::of(grid, self.tick));
+    }
+
+    // Puts `grid` back to the latest keyframe before the current tick, if
+    // there is one. Returns whether it did.
+    pub(super) fn step_back(&mut self, grid: &mut Grid) -> bool {
+        self.drop_rewound_keyframes();
+
+        // The keyframe is kept so that simulating forward from it, and
+        // stepping back to it again, both still work.
+        match self.keyframes.back() {
+            Some(keyframe) => {
+                self.tick = keyframe.tick;
+                keyframe.clone().restore(grid);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // How many ticks back the grid can be rewound.
+    pub(super) fn buffered_ticks(&self) -> u32 {
+        self.keyframes
+            .front()
+            .map_or(0, |oldest| self.tick - oldest.tick)
+    }
+
+    // Drops the keyframes at or after the current tick, which are from a
+    // history that has since been rewound.
+    fn drop_rewound_keyframes(&mut self) {
+        while self
+            .keyframes
+            .back()
+            .is_some_and(|keyframe| keyframe.tick >= self.tick)
+        {
+            self.keyframes.pop_back();
+        }
+    }
+
+    fn capacity(rewind_seconds: f32) -> usize {
+        let ticks = rewind_seconds.max(0.0) * TICKS_PER_SECOND as f32;
+        (ticks / KEYFRAME_INTERVAL as f32).ceil() as usize
+    }
+}
+
+// Copies everything a keyframe would keep from one grid to another.
+pub(super) fn copy_state(from: &Grid, to: &mut Grid) {
+    Keyframe::of(from, 0).restore(to);
+}
+
+impl Keyframe {
+    fn of(grid: &Grid, tick: u32) -> Self {
+        Self {
+            tick,
+            bodies: grid.bodies.clone(),
+            static_circles: grid.static_circles.clone(),
+            static_rectangles: grid.static_rectangles.clone(),
+            static_capsules: grid.static_capsules.clone(),
+            kinematic_rectangles: grid.kinematic_rectangles.clone(),
+            kinematic_spinners: grid.kinematic_spinners.clone(),
+            portal_pairs: grid.portal_pairs.clone(),
+            sinks: grid.sinks.clone(),
+            fluid_zones: grid.fluid_zones.clone(),
+            gravity_wells: grid.gravity_wells.clone(),
+            spawners: grid.spawners.clone(),
+            springs: grid.springs.clone(),
+            ropes: grid.ropes.clone(),
+            joints: grid.joints.clone(),
+            contact_solver: grid.contact_solver.clone(),
+            drag_model: grid.drag_model,
+            rng: grid.rng.clone(),
+            entities: grid.entities.clone(),
+        }
+    }
+
+    fn restore(self, grid: &mut Grid) {
+        grid.bodies = self.bodies;
+        grid.static_circles = self.static_circles;
+        grid.static_rectangles = self.static_rectangles;
+        grid.static_capsules = self.static_capsules;
+        grid.kinematic_rectangles = self.kinematic_rectangles;
+        grid.kinematic_spinners = self.kinematic_spinners;
+        grid.portal_pairs = self.portal_pairs;
+        grid.sinks = self.sinks;
+        grid.fluid_zones = self.fluid_zones;
+        grid.gravity_wells = self.gravity_wells;
+        grid.spawners = self.spawners;
+        grid.springs = self.springs;
+        grid.ropes = self.ropes;
+        grid.joints = self.joints;
+        grid.contact_solver = self.contact_solver;
+        grid.drag_model = self.drag_model;
+        grid.rng = self.rng;
+        grid.entities = self.entities;
+        grid.broadphase = grid.config.broadphase.broadphase();
+        grid.builtin_forces = BuiltinForces::new(&grid.config, grid.drag_model);
+    }
+}
@@ -0,0 +1,104 @@
+// Checks that a built grid starts out with everything the builder was given.
+use super::{HEIGHT, WIDTH};
+use crate::broadphase::BroadphaseKind;
+use crate::config::SimulationConfig;
+use crate::forces::Gravity;
+use crate::integrator::IntegratorKind;
+use crate::physics::{
+    Boundaries, BoundaryMode, Circle, GridBuilder, GridMessage, OverflowPolicy, StaticCircle,
+    TICKS_PER_SECOND,
+};
+use crate::scene::Scene;
+
+#[test]
+fn built_grid_has_the_given_config() {
+    let boundaries = Boundaries {
+        left: BoundaryMode::Wrap,
+        right: BoundaryMode::Wrap,
+        ..Boundaries::default()
+    };
+    let grid = GridBuilder::new(WIDTH, HEIGHT)
+        .with_config(SimulationConfig {
+            time_scale: 2.0,
+            ..SimulationConfig::default()
+        })
+        .with_gravity((0.0, 0.5))
+        .with_boundaries(boundaries)
+        .with_broadphase(BroadphaseKind::SweepAndPrune)
+        .with_integrator(IntegratorKind::Verlet)
+        .build_detached();
+
+    assert_eq!(grid.config.time_scale, 2.0);
+    assert_eq!(grid.config.gravity, (0.0, 0.5));
+    assert_eq!(grid.config.boundaries, boundaries);
+    assert_eq!(grid.config.broadphase, BroadphaseKind::SweepAndPrune);
+    assert_eq!(grid.config.integrator, IntegratorKind::Verlet);
+}
+
+#[test]
+fn built_grid_starts_with_the_scene() {
+    let mut grid = GridBuilder::new(WIDTH, HEIGHT)
+        .with_scene(scene(5.0))
+        .build_detached();
+    assert_eq!(grid.get_frame_number(), 0);
+
+    let grid_frame = grid.tick(Vec::new());
+    assert_eq!(grid_frame.circles.len(), 1);
+    assert_eq!(grid_frame.static_circles.len(), 1);
+    assert_eq!(grid_frame.get_applied_message_count(), 0);
+}
+
+#[test]
+fn invalid_scene_is_left_out() {
+    let mut grid = GridBuilder::new(WIDTH, HEIGHT)
+        .with_scene(scene(f32::NAN))
+        .build_detached();
+
+    let grid_frame = grid.tick(Vec::new());
+    assert!(grid_frame.circles.is_empty());
+    assert!(grid_frame.static_circles.is_empty());
+}
+
+#[test]
+fn built_grid_applies_the_force_generators() {
+    let mut grid = GridBuilder::new(WIDTH, HEIGHT)
+        .with_config(SimulationConfig {
+            gravity: (0.0, 0.0),
+            air_density: 0.0,
+            ..SimulationConfig::default()
+        })
+        .with_force_generator(Box::new(Gravity {
+            acceleration: (0.0, 1.0),
+        }))
+        .with_scene(scene(5.0))
+        .build_detached();
+
+    let grid_frame = grid.step(1.0 / TICKS_PER_SECOND as f32, &[]);
+    assert!(grid_frame.circles[0].velocity.1 > 0.0);
+}
+
+#[test]
+fn built_channel_has_the_given_capacity() {
+    let (mut grid, grid_message_sender) = GridBuilder::new(WIDTH, HEIGHT)
+        .with_channel_capacity(2)
+        .with_overflow_policy(OverflowPolicy::DropNewest)
+        .build();
+
+    let circle = || GridMessage::AddCircle(Circle::new(100.0, 100.0, 5.0, (0.0, 0.0)), None);
+    for _ in 0..2 {
+        assert!(grid_message_sender.send(circle()).is_ok());
+    }
+    assert!(grid_message_sender.send(circle()).is_err());
+
+    let grid_frame = grid.tick_with_pending_messages(&mut None);
+    assert_eq!(grid_frame.get_applied_message_count(), 2);
+    assert_eq!(grid_frame.get_dropped_message_count(), 1);
+}
+
+fn scene(radius: f32) -> Scene {
+    Scene {
+        circles: vec![Circle::new(100.0, 100.0, radius, (0.0, 0.0))],
+        static_circles: vec![StaticCircle::new(400.0, 300.0, 20.0)],
+        ..Scene::default()
+    }
+}
@@ -0,0 +1,111 @@
+// Checks what each overflow policy does with messages sent to a full queue.
+use iced::Size;
+
+use std::time::Duration;
+
+use crate::physics::channel::{grid_message_queue, SendError};
+use crate::physics::{Circle, GridMessage, OverflowPolicy};
+
+const CAPACITY: usize = 3;
+
+#[test]
+fn drop_newest_keeps_the_oldest_messages() {
+    let (sender, mut receiver) = grid_message_queue(CAPACITY, OverflowPolicy::DropNewest);
+    for radius in 1..=5 {
+        let result = sender.send(circle(radius as f32));
+        assert_eq!(result.is_ok(), radius <= CAPACITY);
+    }
+
+    assert_eq!(radii(receiver.drain()), [1.0, 2.0, 3.0]);
+    assert_eq!(receiver.get_dropped_count(), 2);
+}
+
+#[test]
+fn drop_oldest_keeps_the_newest_messages() {
+    let (sender, mut receiver) = grid_message_queue(CAPACITY, OverflowPolicy::DropOldest);
+    for radius in 1..=5 {
+        assert!(sender.send(circle(radius as f32)).is_ok());
+    }
+
+    assert_eq!(radii(receiver.drain()), [3.0, 4.0, 5.0]);
+    assert_eq!(receiver.get_dropped_count(), 2);
+}
+
+#[test]
+fn block_waits_for_the_queue_to_be_drained() {
+    let (sender, mut receiver) = grid_message_queue(CAPACITY, OverflowPolicy::Block);
+    let sending_thread = std::thread::spawn(move || {
+        for radius in 1..=5 {
+            sender.send(circle(radius as f32)).unwrap();
+        }
+    });
+
+    let mut received = Vec::new();
+    while received.len() < 5 {
+        received.extend(radii(receiver.drain()));
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    sending_thread.join().unwrap();
+
+    assert_eq!(received, [1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(receiver.get_dropped_count(), 0);
+}
+
+#[test]
+fn control_messages_get_through_a_full_queue() {
+    let (sender, mut receiver) = grid_message_queue(CAPACITY, OverflowPolicy::DropOldest);
+    assert!(sender.send(GridMessage::SetPaused(true)).is_ok());
+    for radius in 1..=5 {
+        assert!(sender.send(circle(radius as f32)).is_ok());
+    }
+    assert!(sender.send(GridMessage::ClearDynamic).is_ok());
+
+    let messages = receiver.drain();
+    assert!(matches!(messages[0], GridMessage::SetPaused(true)));
+    assert!(matches!(messages[4], GridMessage::ClearDynamic));
+    assert_eq!(
+        radii(messages.into_iter().skip(1).take(3).collect()),
+        [3.0, 4.0, 5.0]
+    );
+}
+
+#[test]
+fn only_the_last_resize_is_kept() {
+    let (sender, mut receiver) = grid_message_queue(CAPACITY, OverflowPolicy::DropNewest);
+    for width in 1..=10 {
+        sender
+            .send(GridMessage::Resize(Size::new(width as f32 * 100.0, 400.0)))
+            .unwrap();
+        if width == 5 {
+            sender.send(circle(1.0)).unwrap();
+        }
+    }
+
+    let messages = receiver.drain();
+    assert_eq!(messages.len(), 2);
+    assert!(matches!(messages[0], GridMessage::AddCircle(_, _)));
+    assert!(matches!(messages[1], GridMessage::Resize(size) if size == Size::new(1000.0, 400.0)));
+    assert_eq!(receiver.get_dropped_count(), 0);
+}
+
+#[test]
+fn sending_fails_once_the_grid_is_gone() {
+    let (sender, receiver) = grid_message_queue(CAPACITY, OverflowPolicy::Block);
+    drop(receiver);
+
+    assert_eq!(sender.send(circle(1.0)), Err(SendError::Disconnected));
+}
+
+fn circle(radius: f32) -> GridMessage {
+    GridMessage::AddCircle(Circle::new(0.0, 0.0, radius, (0.0, 0.0)), None)
+}
+
+fn radii(messages: Vec<GridMessage>) -> Vec<f32> {
+    messages
+        .into_iter()
+        .map(|message| match message {
+            GridMessage::AddCircle(circle, _) => circle.radius,
+            _ => panic!("expected only circles"),
+        })
+        .collect()
+}
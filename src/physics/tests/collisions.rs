@@ -0,0 +1,319 @@
+// Checks invariants of the collision code on generated cases: nothing comes
+// out NaN, bodies end up no longer overlapping, pairs of circles keep their
+// momentum, and which circle of a pair comes first makes no difference.
+// Failing cases are shrunk to the simplest one that still fails, and saved
+// under `proptest-regressions` so that they're tried first from then on.
+use iced::Point;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+use crate::physics::bodies::{Bodies, Body};
+use crate::physics::contacts::ContactSolver;
+use crate::physics::{Circle, Grid, Restitution, StaticCapsule, StaticCircle, StaticRectangle};
+
+const CASES: u32 = 2000;
+// Lengths and speeds within this much of each other, relative to their size
+// for big ones, are equal.
+const TOLERANCE: f32 = 1e-3;
+const SOLVER_ITERATIONS: u32 = 8;
+const DT: f32 = 0.25;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn contact_solver_keeps_invariants(
+        (a, b) in overlapping_pair(),
+        restitution in restitution(),
+        friction in 0.0f32..1.0,
+    ) {
+        let [a_after, b_after] = solve_pair(&a, &b, restitution, friction);
+        check_finite(&[&a_after, &b_after])?;
+        check_separated(&a_after, &b_after)?;
+
+        let momentum_before = momentum(&[&a, &b]);
+        let momentum_after = momentum(&[&a_after, &b_after]);
+        prop_assert!(
+            close(momentum_before.0, momentum_after.0)
+                && close(momentum_before.1, momentum_after.1),
+            "momentum went from {:?} to {:?}",
+            momentum_before,
+            momentum_after
+        );
+
+        let [b_swapped, a_swapped] = solve_pair(&b, &a, restitution, friction);
+        check_same(&a_after, &a_swapped)?;
+        check_same(&b_after, &b_swapped)?;
+    }
+
+    #[test]
+    fn project_apart_keeps_invariants((a, b) in overlapping_pair()) {
+        let [a_after, b_after] = project_pair(&a, &b);
+        check_finite(&[&a_after, &b_after])?;
+        check_separated(&a_after, &b_after)?;
+
+        let [b_swapped, a_swapped] = project_pair(&b, &a);
+        check_same(&a_after, &a_swapped)?;
+        check_same(&b_after, &b_swapped)?;
+    }
+
+    #[test]
+    fn static_circle_collisions_keep_invariants(
+        radius in 1.0f32..50.0,
+        circle in circle(),
+        offset in offset(),
+        restitution in restitution(),
+    ) {
+        let static_circle = StaticCircle::new(0.0, 0.0, radius);
+        let mut body = place_near(circle, offset, Point::ORIGIN, static_circle.radius);
+
+        Grid::circle_static_circle_collision(&mut body, &static_circle, restitution);
+        check_finite_body(&body)?;
+        let distance = body.x_pos.hypot(body.y_pos);
+        prop_assert!(
+            distance >= body.radius + static_circle.radius - TOLERANCE,
+            "circle still overlaps the static circle, {} apart",
+            distance
+        );
+    }
+
+    #[test]
+    fn static_capsule_collisions_keep_invariants(
+        a in point(-50.0..50.0),
+        b in point(-50.0..50.0),
+        radius in 1.0f32..20.0,
+        circle in circle(),
+        offset in offset(),
+        restitution in restitution(),
+    ) {
+        let capsule = StaticCapsule::new(a, b, radius);
+        let mut body = place_near(circle, offset, capsule.a, capsule.radius + 50.0);
+
+        Grid::circle_static_capsule_collision(&mut body, &capsule, restitution);
+        check_finite_body(&body)?;
+        let center = Point::new(body.x_pos, body.y_pos);
+        let distance = center.distance(capsule.closest_point(center));
+        prop_assert!(
+            distance >= body.radius + capsule.radius - TOLERANCE,
+            "circle still overlaps the capsule, {} from its center line",
+            distance
+        );
+    }
+
+    #[test]
+    fn static_rectangle_collisions_keep_invariants(
+        width in 1.0f32..100.0,
+        height in 1.0f32..100.0,
+        circle in circle(),
+        offset in offset(),
+        restitution in restitution(),
+    ) {
+        let rectangle = StaticRectangle::new(0.0, 0.0, width, height);
+        let center = Point::new(rectangle.width / 2.0, rectangle.height / 2.0);
+        let mut body = place_near(circle, offset, center, rectangle.width.max(rectangle.height));
+
+        Grid::circle_static_rectangle_collision(&mut body, &rectangle, restitution);
+        check_finite_body(&body)?;
+        let bounds = rectangle.bounds();
+        let closest = Point::new(
+            body.x_pos.clamp(bounds.x, bounds.x + bounds.width),
+            body.y_pos.clamp(bounds.y, bounds.y + bounds.height),
+        );
+        let distance = Point::new(body.x_pos, body.y_pos).distance(closest);
+        prop_assert!(
+            distance >= body.radius - TOLERANCE,
+            "circle still overlaps the rectangle, {} from it",
+            distance
+        );
+    }
+}
+
+#[test]
+fn coincident_circles_are_pushed_apart() -> Result<(), TestCaseError> {
+    let a = Circle::new(10.0, 10.0, 5.0, (1.0, 0.0));
+    let b = Circle::new(10.0, 10.0, 3.0, (-1.0, 0.0));
+    let restitution = Restitution {
+        coefficient: 0.5,
+        threshold: 1.0,
+    };
+
+    let [a_after, b_after] = solve_pair(&a, &b, restitution, 0.2);
+    check_finite(&[&a_after, &b_after])?;
+    check_separated(&a_after, &b_after)?;
+
+    let [a_after, b_after] = project_pair(&a, &b);
+    check_finite(&[&a_after, &b_after])?;
+    check_separated(&a_after, &b_after)?;
+
+    let mut bodies = Bodies::default();
+    bodies.push(a);
+    let mut body = bodies.body(0);
+    let static_circle = StaticCircle::new(10.0, 10.0, 3.0);
+    Grid::circle_static_circle_collision(&mut body, &static_circle, restitution);
+    check_finite_body(&body)?;
+    let distance = (body.x_pos - 10.0).hypot(body.y_pos - 10.0);
+    prop_assert!(
+        distance >= body.radius + static_circle.radius - TOLERANCE,
+        "circle still overlaps the static circle, {} apart",
+        distance
+    );
+    Ok(())
+}
+
+prop_compose! {
+    // A circle at the origin, moving every which way.
+    fn circle()(
+        radius in 1.0f32..20.0,
+        velocity in (-20.0f32..20.0, -20.0f32..20.0),
+        density in 0.1f32..10.0,
+        restitution in 0.0f32..1.0,
+    ) -> Circle {
+        let mut circle = Circle::new(0.0, 0.0, radius, velocity);
+        circle.density = density;
+        circle.restitution = Some(restitution);
+        circle
+    }
+}
+
+prop_compose! {
+    // Two circles that overlap by anything from barely at all to almost
+    // entirely, with the same restitution.
+    fn overlapping_pair()(
+        a in circle(),
+        b in circle(),
+        angle in 0.0f32..std::f32::consts::TAU,
+        overlap in 0.01f32..0.99,
+    ) -> (Circle, Circle) {
+        let distance = overlap * (a.radius + b.radius);
+        let b = Circle {
+            x_pos: angle.cos() * distance,
+            y_pos: angle.sin() * distance,
+            restitution: a.restitution,
+            ..b
+        };
+        (a, b)
+    }
+}
+
+prop_compose! {
+    fn restitution()(coefficient in 0.0f32..1.0, threshold in 0.0f32..2.0) -> Restitution {
+        Restitution {
+            coefficient,
+            threshold,
+        }
+    }
+}
+
+fn point(range: std::ops::Range<f32>) -> impl Strategy<Value = Point> {
+    (range.clone(), range).prop_map(|(x, y)| Point::new(x, y))
+}
+
+// Where to put a circle with `place_near`, from one corner of the area to the
+// other.
+fn offset() -> impl Strategy<Value = (f32, f32)> {
+    (-1.0f32..1.0, -1.0f32..1.0)
+}
+
+// The circle moved to somewhere within `reach` of `center`, plus its own
+// radius.
+fn place_near(circle: Circle, offset: (f32, f32), center: Point, reach: f32) -> Body {
+    let reach = reach + circle.radius;
+    let mut bodies = Bodies::default();
+    bodies.push(Circle {
+        x_pos: center.x + offset.0 * reach,
+        y_pos: center.y + offset.1 * reach,
+        ..circle
+    });
+    bodies.body(0)
+}
+
+// The pair after a subtick of the impulse solver, in the order given.
+fn solve_pair(a: &Circle, b: &Circle, restitution: Restitution, friction: f32) -> [Circle; 2] {
+    let mut bodies = Bodies::default();
+    bodies.push(a.clone());
+    bodies.push(b.clone());
+
+    let mut contact_solver = ContactSolver::default();
+    contact_solver.find_contacts(&mut bodies, &[(0, 1)], restitution, friction, f32::MAX, DT);
+    contact_solver.solve_velocities(&mut bodies, SOLVER_ITERATIONS);
+    for _ in 0..SOLVER_ITERATIONS {
+        contact_solver.solve_positions(&mut bodies, 1.0);
+    }
+    [bodies.get(0), bodies.get(1)]
+}
+
+// The pair after being projected apart, in the order given.
+fn project_pair(a: &Circle, b: &Circle) -> [Circle; 2] {
+    let mut bodies = Bodies::default();
+    bodies.push(a.clone());
+    bodies.push(b.clone());
+    bodies.update_pair(0, 1, |body_a, body_b| {
+        Grid::project_apart(body_a, body_b, f32::MAX, 1.0);
+    });
+    [bodies.get(0), bodies.get(1)]
+}
+
+fn momentum(circles: &[&Circle]) -> (f32, f32) {
+    circles.iter().fold((0.0, 0.0), |(x, y), circle| {
+        let mass = circle.density * circle.radius * circle.radius;
+        (x + mass * circle.velocity.0, y + mass * circle.velocity.1)
+    })
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() <= TOLERANCE * a.abs().max(b.abs()).max(1.0)
+}
+
+fn check_finite(circles: &[&Circle]) -> Result<(), TestCaseError> {
+    for circle in circles {
+        prop_assert!(
+            [
+                circle.x_pos,
+                circle.y_pos,
+                circle.velocity.0,
+                circle.velocity.1
+            ]
+            .iter()
+            .all(|value| value.is_finite()),
+            "circle isn't finite: {:?}",
+            circle
+        );
+    }
+    Ok(())
+}
+
+fn check_finite_body(body: &Body) -> Result<(), TestCaseError> {
+    prop_assert!(
+        [body.x_pos, body.y_pos, body.velocity.0, body.velocity.1]
+            .iter()
+            .all(|value| value.is_finite()),
+        "circle isn't finite: {:?}",
+        body
+    );
+    Ok(())
+}
+
+fn check_separated(a: &Circle, b: &Circle) -> Result<(), TestCaseError> {
+    let distance = (b.x_pos - a.x_pos).hypot(b.y_pos - a.y_pos);
+    prop_assert!(
+        distance >= a.radius + b.radius - TOLERANCE,
+        "circles still overlap, {} apart with radii {} and {}",
+        distance,
+        a.radius,
+        b.radius
+    );
+    Ok(())
+}
+
+fn check_same(expected: &Circle, actual: &Circle) -> Result<(), TestCaseError> {
+    prop_assert!(
+        close(expected.x_pos, actual.x_pos)
+            && close(expected.y_pos, actual.y_pos)
+            && close(expected.velocity.0, actual.velocity.0)
+            && close(expected.velocity.1, actual.velocity.1),
+        "swapping the circles changed the result from {:?} to {:?}",
+        expected,
+        actual
+    );
+    Ok(())
+}
@@ -0,0 +1,108 @@
+// Runs closed scenes, with no gravity, drag or anything else to push the
+// circles around, and checks that their total energy never grows. With
+// collisions that lose some energy, the only way it could is the solver
+// adding it, which left alone ends with circles flying apart.
+use crate::config::SimulationConfig;
+use crate::integrator::IntegratorKind;
+use crate::physics::{
+    Circle, Grid, GridMessage, LifetimePolicy, Solver, StaticCircle, StaticRectangle,
+};
+use crate::random::Rng;
+use crate::scene::Scene;
+
+const WIDTH: f32 = 400.0;
+const HEIGHT: f32 = 300.0;
+const TICK_COUNT: u32 = 3000;
+const CIRCLE_COUNT: usize = 150;
+const SEED: u64 = 0xe4e7;
+// How much energy can grow in a tick, as a fraction of what the scene
+// started with. A few solver passes don't quite settle a crowd of circles
+// hitting each other at once, and the position based solver's velocities
+// come from how far it moved the circles, so either can add a tiny amount
+// now and then. A solver blowing up adds far more, and keeps adding it.
+const TOLERANCE: f32 = 1e-4;
+
+#[test]
+fn impulse_solver_never_adds_energy() {
+    check_energy_never_grows(Solver::Impulse, IntegratorKind::SemiImplicitEuler);
+}
+
+#[test]
+fn position_based_solver_never_adds_energy() {
+    check_energy_never_grows(Solver::PositionBased, IntegratorKind::SemiImplicitEuler);
+}
+
+#[test]
+fn verlet_integrator_never_adds_energy() {
+    check_energy_never_grows(Solver::Impulse, IntegratorKind::Verlet);
+}
+
+#[test]
+fn explicit_euler_integrator_never_adds_energy() {
+    check_energy_never_grows(Solver::Impulse, IntegratorKind::ExplicitEuler);
+}
+
+fn check_energy_never_grows(solver: Solver, integrator: IntegratorKind) {
+    let config = SimulationConfig {
+        elasticity_coefficient: 0.8,
+        air_density: 0.0,
+        lifetime: LifetimePolicy::Immortal,
+        gravity: (0.0, 0.0),
+        wind: (0.0, 0.0),
+        integrator,
+        solver,
+        rewind_seconds: 0.0,
+        ..SimulationConfig::default()
+    };
+    let mut grid = Grid::new_detached(WIDTH, HEIGHT, config);
+    grid.tick(vec![GridMessage::LoadScene(closed_scene())]);
+
+    let starting_energy = grid.total_energy();
+    assert!(starting_energy > 0.0, "the scene starts without any energy");
+    let mut energy = starting_energy;
+    for tick in 1..=TICK_COUNT {
+        grid.tick(Vec::new());
+        let next_energy = grid.total_energy();
+        assert!(
+            next_energy.is_finite()
+                && next_energy <= energy + starting_energy * TOLERANCE
+                && next_energy <= starting_energy,
+            "{solver:?} with {integrator:?}: energy went from {energy} to {next_energy} on tick {tick}, having started at {starting_energy}"
+        );
+        energy = next_energy;
+    }
+}
+
+// Circles of different sizes and densities moving every which way, among a
+// few pegs and a block, in a box they can't leave.
+fn closed_scene() -> Scene {
+    let mut rng = Rng::new(SEED);
+    let mut scene = Scene::default();
+
+    let spacing = 20.0;
+    let columns = (WIDTH / spacing) as usize;
+    scene.circles = (0..CIRCLE_COUNT)
+        .map(|i| {
+            let mut circle = Circle::new(
+                ((i % columns) as f32 + 0.5) * spacing,
+                ((i / columns) as f32 + 0.5) * spacing,
+                rng.range(3.0, 9.0),
+                (rng.range(-5.0, 5.0), rng.range(-5.0, 5.0)),
+            );
+            circle.density = rng.range(0.5, 2.0);
+            circle
+        })
+        .collect();
+
+    scene.static_circles = vec![
+        StaticCircle::new(WIDTH * 0.25, HEIGHT * 0.75, 15.0),
+        StaticCircle::new(WIDTH * 0.75, HEIGHT * 0.75, 15.0),
+    ];
+    scene.static_rectangles = vec![StaticRectangle::new(
+        WIDTH * 0.45,
+        HEIGHT * 0.6,
+        WIDTH * 0.1,
+        HEIGHT * 0.1,
+    )];
+    scene
+}
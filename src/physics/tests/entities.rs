@@ -0,0 +1,25 @@
+// Checks that ids stop referring to anything once their bodies are gone, so
+// that an id held on to by a script or a viewer never picks out another body.
+use super::grid_with;
+use crate::config::SimulationConfig;
+use crate::physics::{Circle, GridMessage};
+use crate::scene::Scene;
+
+#[test]
+fn ids_from_before_a_scene_load_are_stale() {
+    let mut grid = grid_with(SimulationConfig::default());
+    let old_frame = grid.tick(vec![GridMessage::LoadScene(scene())]);
+    let new_frame = grid.tick(vec![GridMessage::LoadScene(scene())]);
+
+    assert_eq!(new_frame.circles.len(), 1);
+    let old_id = old_frame.circles[0].get_id();
+    assert_ne!(new_frame.circles[0].get_id(), old_id);
+    assert!(new_frame.get_circle(old_id).is_none());
+}
+
+fn scene() -> Scene {
+    Scene {
+        circles: vec![Circle::new(100.0, 100.0, 5.0, (0.0, 0.0))],
+        ..Scene::default()
+    }
+}
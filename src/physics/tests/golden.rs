@@ -0,0 +1,133 @@
+// Runs each preset for a fixed number of frames and compares the last frame
+// against a checked-in golden copy, to catch changes to how the simulation
+// behaves that weren't meant to happen. After a change that was meant to,
+// run the tests with `UPDATE_GOLDEN=1` to write new golden frames, and check
+// the differences in them before committing.
+use serde_json::Value;
+
+use std::path::PathBuf;
+
+use super::{grid_with, HEIGHT, WIDTH};
+use crate::config::SimulationConfig;
+use crate::physics::{GridFrame, GridMessage};
+use crate::scene::presets::Preset;
+
+const FRAME_COUNT: u32 = 120;
+// Numbers within this much of the golden ones, relative to their size for
+// big ones, match. Tight enough to catch a real change, while leaving room
+// for floating point to round differently across platforms.
+const TOLERANCE: f64 = 1e-3;
+// How long each part of the tick took, which changes from run to run.
+const IGNORED_FIELDS: [&str; 1] = ["timing"];
+
+#[test]
+fn rounded_square_matches_golden_frame() {
+    check_golden_frame(Preset::RoundedSquare, "rounded_square");
+}
+
+#[test]
+fn plinko_matches_golden_frame() {
+    check_golden_frame(Preset::Plinko, "plinko");
+}
+
+#[test]
+fn hourglass_matches_golden_frame() {
+    check_golden_frame(Preset::Hourglass, "hourglass");
+}
+
+#[test]
+fn newtons_cradle_matches_golden_frame() {
+    check_golden_frame(Preset::NewtonsCradle, "newtons_cradle");
+}
+
+fn check_golden_frame(preset: Preset, name: &str) {
+    let mut actual = serde_json::to_value(run_preset(preset)).unwrap();
+    strip_ignored_fields(&mut actual);
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/physics/tests/golden")
+        .join(format!("{name}.json"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let json = serde_json::to_string_pretty(&actual).unwrap();
+        std::fs::write(&path, json + "\n").unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "failed to read {}: {err}. Run with UPDATE_GOLDEN=1 to create it.",
+            path.display()
+        )
+    });
+    let expected: Value = serde_json::from_str(&golden).unwrap();
+    if let Err(mismatch) = compare(&expected, &actual, "frame") {
+        panic!("{preset} no longer matches {}: {mismatch}", path.display());
+    }
+}
+
+// The preset's frame after `FRAME_COUNT` ticks, with the default config.
+fn run_preset(preset: Preset) -> GridFrame {
+    let mut grid = grid_with(SimulationConfig::default());
+    let mut grid_frame = grid.tick(vec![GridMessage::LoadScene(preset.build(WIDTH, HEIGHT))]);
+    for _ in 1..FRAME_COUNT {
+        grid_frame = grid.tick(Vec::new());
+    }
+    grid_frame
+}
+
+fn strip_ignored_fields(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for field in IGNORED_FIELDS {
+                fields.remove(field);
+            }
+            fields.values_mut().for_each(strip_ignored_fields);
+        }
+        Value::Array(values) => values.iter_mut().for_each(strip_ignored_fields),
+        _ => {}
+    }
+}
+
+// The first place `actual` differs from `expected`, if it does. `path` is
+// where in the frame they are, for the error.
+fn compare(expected: &Value, actual: &Value, path: &str) -> Result<(), String> {
+    match (expected, actual) {
+        (Value::Number(expected), Value::Number(actual)) => {
+            let (expected, actual) = (expected.as_f64().unwrap(), actual.as_f64().unwrap());
+            if (expected - actual).abs() <= TOLERANCE * expected.abs().max(1.0) {
+                Ok(())
+            } else {
+                Err(format!("{path} is {actual}, not {expected}"))
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() != actual.len() {
+                return Err(format!(
+                    "{path} has {} entries, not {}",
+                    actual.len(),
+                    expected.len()
+                ));
+            }
+            expected
+                .iter()
+                .zip(actual)
+                .enumerate()
+                .try_for_each(|(i, (expected, actual))| {
+                    compare(expected, actual, &format!("{path}[{i}]"))
+                })
+        }
+        (Value::Object(expected), Value::Object(actual)) => {
+            if let Some(key) = actual.keys().find(|key| !expected.contains_key(*key)) {
+                return Err(format!("{path}.{key} is new"));
+            }
+            expected.iter().try_for_each(|(key, expected)| {
+                let actual = actual
+                    .get(key)
+                    .ok_or_else(|| format!("{path}.{key} is missing"))?;
+                compare(expected, actual, &format!("{path}.{key}"))
+            })
+        }
+        _ if expected == actual => Ok(()),
+        _ => Err(format!("{path} is {actual}, not {expected}")),
+    }
+}
@@ -0,0 +1,26 @@
+mod builder;
+mod channel;
+mod collisions;
+mod energy;
+mod entities;
+mod golden;
+mod population_cap;
+mod remote_frames;
+mod resize;
+mod spawn_burst;
+mod spawn_profile;
+mod step;
+mod validation;
+mod wind;
+
+use crate::config::SimulationConfig;
+use crate::physics::Grid;
+
+// The size of the world in most tests, the same as the app's window.
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 480.0;
+
+// A detached grid of the usual size.
+fn grid_with(config: SimulationConfig) -> Grid {
+    Grid::new_detached(WIDTH, HEIGHT, config)
+}
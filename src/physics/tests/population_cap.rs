@@ -0,0 +1,71 @@
+// Checks that `RejectNew` turns circles away as they're added, rather than
+// letting them in and evicting them once the tick is done.
+use futures::channel::oneshot;
+
+use super::grid_with;
+use crate::config::SimulationConfig;
+use crate::physics::{Circle, EvictionPolicy, GridMessage, RemovalCause};
+
+#[test]
+fn full_grids_turn_new_circles_away() {
+    let mut grid = grid_with(SimulationConfig {
+        max_circles: Some(2),
+        eviction_policy: EvictionPolicy::RejectNew,
+        ..SimulationConfig::default()
+    });
+    let (id_sender, mut id_receiver) = oneshot::channel();
+    let grid_frame = grid.tick(vec![
+        GridMessage::AddCircle(circle(100.0), None),
+        GridMessage::AddCircle(circle(200.0), None),
+        GridMessage::AddCircle(circle(300.0), Some(id_sender)),
+        GridMessage::SpawnBurst { x: 400.0, y: 100.0 },
+    ]);
+
+    assert_eq!(grid_frame.circles.len(), 2);
+    assert!(id_receiver.try_recv().is_err());
+    assert!(grid_frame
+        .get_removal_events()
+        .iter()
+        .all(|removal| removal.cause != RemovalCause::Evicted));
+}
+
+#[test]
+fn bursts_fill_what_room_is_left() {
+    let mut grid = grid_with(SimulationConfig {
+        max_circles: Some(10),
+        eviction_policy: EvictionPolicy::RejectNew,
+        ..SimulationConfig::default()
+    });
+    let grid_frame = grid.tick(vec![
+        GridMessage::AddCircle(circle(100.0), None),
+        GridMessage::SpawnBurst { x: 400.0, y: 240.0 },
+    ]);
+
+    assert_eq!(grid_frame.circles.len(), 10);
+    assert!(grid_frame.get_removal_events().is_empty());
+}
+
+#[test]
+fn other_policies_still_evict_after_the_tick() {
+    let mut grid = grid_with(SimulationConfig {
+        max_circles: Some(2),
+        eviction_policy: EvictionPolicy::OldestFirst,
+        ..SimulationConfig::default()
+    });
+    let grid_frame = grid.tick(vec![
+        GridMessage::AddCircle(circle(100.0), None),
+        GridMessage::AddCircle(circle(200.0), None),
+        GridMessage::AddCircle(circle(300.0), None),
+    ]);
+
+    assert_eq!(grid_frame.circles.len(), 2);
+    assert_eq!(grid_frame.get_removal_events().len(), 1);
+    assert_eq!(
+        grid_frame.get_removal_events()[0].cause,
+        RemovalCause::Evicted
+    );
+}
+
+fn circle(x_pos: f32) -> Circle {
+    Circle::new(x_pos, 100.0, 5.0, (0.0, 0.0))
+}
@@ -0,0 +1,33 @@
+// Checks that frames still draw the same after the trip to a `--connect`
+// viewer, which gets them serialized with `bincode`.
+use iced::Point;
+
+use super::{grid_with, HEIGHT, WIDTH};
+use crate::config::SimulationConfig;
+use crate::physics::{GridFrame, GridMessage, SoftBody};
+
+#[test]
+fn springs_still_link_their_circles() {
+    let mut grid = grid_with(SimulationConfig::default());
+    let sent = grid.tick(vec![GridMessage::AddSoftBody(SoftBody::ring(
+        Point::new(WIDTH / 2.0, HEIGHT / 2.0),
+        40.0,
+        6,
+        5.0,
+    ))]);
+    let received: GridFrame = bincode::deserialize(&bincode::serialize(&sent).unwrap()).unwrap();
+
+    assert!(!received.springs.is_empty());
+    for spring in &received.springs {
+        for id in [spring.a, spring.b] {
+            let sent_circle = sent.get_circle(id).unwrap();
+            let received_circle = received
+                .get_circle(id)
+                .unwrap_or_else(|| panic!("no circle has the spring's id {id:?}"));
+            assert_eq!(
+                (received_circle.x_pos, received_circle.y_pos),
+                (sent_circle.x_pos, sent_circle.y_pos)
+            );
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Checks what resizing the world does to the bodies in it with each
+// `ResizeContents`, and what happens to circles left outside of it with each
+// `OutOfBoundsPolicy`. Steps of no time are used so that nothing moves apart
+// from the resize.
+use iced::Size;
+
+use super::{grid_with, HEIGHT, WIDTH};
+use crate::config::SimulationConfig;
+use crate::physics::{
+    Circle, GridFrame, GridMessage, OutOfBoundsPolicy, RemovalCause, ResizeContents,
+    StaticRectangle,
+};
+
+#[test]
+fn fixed_contents_stay_where_they_are() {
+    let grid_frame = shrink_by_half(ResizeContents::Fixed);
+
+    assert_eq!(circle(&grid_frame), (600.0, 400.0, 20.0));
+    assert_eq!(rectangle(&grid_frame), (100.0, 440.0, 600.0, 40.0));
+}
+
+#[test]
+fn scaled_contents_shrink_with_the_world() {
+    let grid_frame = shrink_by_half(ResizeContents::Scale);
+
+    assert_eq!(circle(&grid_frame), (300.0, 200.0, 10.0));
+    assert_eq!(rectangle(&grid_frame), (50.0, 220.0, 300.0, 20.0));
+}
+
+#[test]
+fn anchored_contents_keep_their_distance_to_the_nearest_edge() {
+    let grid_frame = shrink_by_half(ResizeContents::Anchor);
+
+    assert_eq!(circle(&grid_frame), (200.0, 160.0, 20.0));
+    assert_eq!(rectangle(&grid_frame), (100.0, 200.0, 600.0, 40.0));
+}
+
+#[test]
+fn circles_left_outside_are_clamped_back_in() {
+    let grid_frame = shrink_by_half_with(ResizeContents::Fixed, OutOfBoundsPolicy::Clamp);
+
+    assert_eq!(circle(&grid_frame), (380.0, 220.0, 20.0));
+}
+
+#[test]
+fn circles_left_outside_are_despawned() {
+    let grid_frame = shrink_by_half_with(ResizeContents::Fixed, OutOfBoundsPolicy::Despawn);
+
+    assert!(grid_frame.circles.is_empty());
+    let removals = grid_frame.get_removal_events();
+    assert_eq!(removals.len(), 1);
+    assert_eq!(removals[0].cause, RemovalCause::Offscreen);
+}
+
+#[test]
+fn circles_added_outside_are_recovered_while_paused() {
+    let config = SimulationConfig {
+        out_of_bounds: OutOfBoundsPolicy::Clamp,
+        ..SimulationConfig::default()
+    };
+    let mut grid = grid_with(config);
+    let grid_frame = grid.tick(vec![
+        GridMessage::SetPaused(true),
+        GridMessage::AddCircle(Circle::new(-100.0, 240.0, 10.0, (-3.0, 1.0)), None),
+    ]);
+
+    let circle = &grid_frame.circles[0];
+    assert_eq!((circle.x_pos, circle.y_pos), (10.0, 240.0));
+    assert_eq!(circle.velocity, (0.0, 1.0));
+}
+
+fn shrink_by_half(resize_contents: ResizeContents) -> GridFrame {
+    shrink_by_half_with(resize_contents, OutOfBoundsPolicy::Keep)
+}
+
+// Resizes a world with a circle in its bottom right quarter and a floor
+// along its bottom to half its size.
+fn shrink_by_half_with(
+    resize_contents: ResizeContents,
+    out_of_bounds: OutOfBoundsPolicy,
+) -> GridFrame {
+    let config = SimulationConfig {
+        resize_contents,
+        out_of_bounds,
+        ..SimulationConfig::default()
+    };
+    let mut grid = grid_with(config);
+    grid.step(
+        0.0,
+        &[
+            GridMessage::AddCircle(Circle::new(600.0, 400.0, 20.0, (0.0, 0.0)), None),
+            GridMessage::AddStaticRectangle(StaticRectangle::new(100.0, 440.0, 600.0, 40.0)),
+        ],
+    );
+    grid.step(
+        0.0,
+        &[GridMessage::Resize(Size::new(WIDTH / 2.0, HEIGHT / 2.0))],
+    )
+}
+
+fn circle(grid_frame: &GridFrame) -> (f32, f32, f32) {
+    let circle = &grid_frame.circles[0];
+    (circle.x_pos, circle.y_pos, circle.radius)
+}
+
+fn rectangle(grid_frame: &GridFrame) -> (f32, f32, f32, f32) {
+    let rectangle = &grid_frame.static_rectangles[0];
+    (
+        rectangle.x_pos,
+        rectangle.y_pos,
+        rectangle.width,
+        rectangle.height,
+    )
+}
@@ -0,0 +1,81 @@
+// Checks that `GridMessage::SpawnBurst` adds the circles the config asks for,
+// and adds the same ones every time, so that bursts replay faithfully.
+use serde_json::Value;
+
+use super::{grid_with, HEIGHT, WIDTH};
+use crate::config::SimulationConfig;
+use crate::physics::{Grid, GridFrame, GridMessage, SpawnBurst, VelocityDistribution};
+
+#[test]
+fn bursts_have_the_configured_circles() {
+    let spawn_burst = SpawnBurst {
+        count: 30,
+        min_radius: 2.0,
+        max_radius: 4.0,
+        spread: 25.0,
+        velocity: VelocityDistribution::Fixed((1.0, -2.0)),
+    };
+    let grid_frame = burst(spawn_burst);
+
+    assert_eq!(grid_frame.circles.len(), 30);
+    for circle in &grid_frame.circles {
+        let distance = (circle.x_pos - 400.0).hypot(circle.y_pos - 240.0);
+        assert!(distance <= 25.0, "{circle:?} is too far out");
+        assert!(
+            (2.0..4.0).contains(&circle.radius),
+            "{circle:?} is the wrong size"
+        );
+        assert_eq!(circle.velocity, (1.0, -2.0));
+    }
+}
+
+#[test]
+fn bursts_are_the_same_every_time() {
+    let circles = |grid_frame: GridFrame| serde_json::to_value(&grid_frame.circles).unwrap();
+    let first: Value = circles(burst(SpawnBurst::default()));
+
+    assert_eq!(first.as_array().unwrap().len(), 100);
+    assert_eq!(circles(burst(SpawnBurst::default())), first);
+}
+
+#[test]
+fn bursts_of_invalid_circles_are_skipped() {
+    let grid_frame = burst(SpawnBurst {
+        min_radius: -5.0,
+        max_radius: -1.0,
+        ..SpawnBurst::default()
+    });
+
+    assert!(grid_frame.circles.is_empty());
+}
+
+// A burst in the middle of an empty world, before anything has moved.
+fn burst(spawn_burst: SpawnBurst) -> GridFrame {
+    let config = SimulationConfig {
+        spawn_burst,
+        ..SimulationConfig::default()
+    };
+    let mut grid = grid_with(config);
+    grid.step(
+        0.0,
+        &[GridMessage::SpawnBurst {
+            x: WIDTH / 2.0,
+            y: HEIGHT / 2.0,
+        }],
+    )
+}
+
+#[test]
+fn crowded_bursts_stay_finite() {
+    let mut grid = Grid::new_detached(60.0, 60.0, SimulationConfig::default());
+    grid.tick(vec![GridMessage::SpawnBurst { x: 30.0, y: 30.0 }]);
+    for _ in 0..120 {
+        let grid_frame = grid.tick(Vec::new());
+        for circle in &grid_frame.circles {
+            assert!(
+                circle.x_pos.is_finite() && circle.velocity.0.is_finite(),
+                "{circle:?} isn't finite"
+            );
+        }
+    }
+}
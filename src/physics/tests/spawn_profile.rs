@@ -0,0 +1,154 @@
+// Checks that the config's `SpawnProfile` randomizes circles that are
+// spawned, by the brush's `GridMessage::Spawn` and by spawners, and leaves
+// circles that are added directly alone.
+use iced::Point;
+
+use super::{grid_with, HEIGHT, WIDTH};
+use crate::config::SimulationConfig;
+use crate::physics::{
+    Circle, Distribution, Grid, GridMessage, SpawnBurst, SpawnProfile, Spawner,
+    VelocityDistribution,
+};
+
+const RED: (f32, f32, f32) = (1.0, 0.0, 0.0);
+const BLUE: (f32, f32, f32) = (0.0, 0.0, 1.0);
+
+#[test]
+fn spawned_circles_are_randomized() {
+    let spawn_profile = SpawnProfile {
+        radius_scale: Distribution::Uniform { min: 0.5, max: 2.0 },
+        speed_scale: Distribution::Fixed(2.0),
+        colors: vec![RED, BLUE],
+        density: Distribution::Normal {
+            mean: 3.0,
+            std_dev: 0.1,
+        },
+    };
+    let messages: Vec<_> = (0..40)
+        .map(|i| GridMessage::Spawn(Circle::new(20.0 * i as f32, 100.0, 4.0, (1.0, -0.5))))
+        .collect();
+    let grid_frame = grid(spawn_profile).step(0.0, &messages);
+
+    assert_eq!(grid_frame.circles.len(), 40);
+    for circle in &grid_frame.circles {
+        assert!(
+            (2.0..8.0).contains(&circle.radius),
+            "{circle:?} is the wrong size"
+        );
+        assert_eq!(circle.velocity, (2.0, -1.0));
+        assert!(circle.color == Some(RED) || circle.color == Some(BLUE));
+        assert!(
+            (2.0..4.0).contains(&circle.density),
+            "{circle:?} is the wrong density"
+        );
+    }
+    let first_radius = grid_frame.circles[0].radius;
+    assert!(grid_frame
+        .circles
+        .iter()
+        .any(|circle| circle.radius != first_radius));
+}
+
+#[test]
+fn spawners_use_the_profile() {
+    let spawn_profile = SpawnProfile {
+        radius_scale: Distribution::Fixed(2.0),
+        colors: vec![RED],
+        ..SpawnProfile::default()
+    };
+    let spawner = Spawner::new(
+        Point::new(WIDTH / 2.0, HEIGHT / 2.0),
+        5.0,
+        VelocityDistribution::Fixed((0.0, 0.0)),
+        1,
+    );
+    let mut grid = grid(spawn_profile);
+    grid.tick(vec![GridMessage::AddSpawner(spawner)]);
+    let grid_frame = grid.tick(Vec::new());
+
+    assert!(!grid_frame.circles.is_empty());
+    for circle in &grid_frame.circles {
+        // Circles shrink a little every tick.
+        assert!(
+            (circle.radius - 10.0).abs() < 0.1,
+            "{circle:?} is the wrong size"
+        );
+        assert_eq!(circle.color, Some(RED));
+    }
+}
+
+#[test]
+fn wide_normal_profiles_still_spawn_whole_bursts() {
+    // Most of the bell reaches below zero, but none of it is sampled.
+    let wide = Distribution::Normal {
+        mean: 1.0,
+        std_dev: 5.0,
+    };
+    let spawn_profile = SpawnProfile {
+        radius_scale: wide,
+        density: wide,
+        ..SpawnProfile::default()
+    };
+    let grid_frame = grid(spawn_profile).step(
+        0.0,
+        &[GridMessage::SpawnBurst {
+            x: WIDTH / 2.0,
+            y: HEIGHT / 2.0,
+        }],
+    );
+
+    assert_eq!(
+        grid_frame.circles.len(),
+        SpawnBurst::default().count as usize
+    );
+    for circle in &grid_frame.circles {
+        assert!(circle.radius > 0.0, "{circle:?} is the wrong size");
+        assert!(circle.density > 0.0, "{circle:?} is the wrong density");
+    }
+}
+
+#[test]
+fn added_circles_are_left_alone() {
+    let spawn_profile = SpawnProfile {
+        radius_scale: Distribution::Fixed(2.0),
+        colors: vec![RED],
+        ..SpawnProfile::default()
+    };
+    let grid_frame = grid(spawn_profile).step(
+        0.0,
+        &[GridMessage::AddCircle(
+            Circle::new(100.0, 100.0, 4.0, (0.0, 0.0)),
+            None,
+        )],
+    );
+
+    assert_eq!(grid_frame.circles[0].radius, 4.0);
+    assert_eq!(grid_frame.circles[0].color, None);
+}
+
+#[test]
+fn circles_the_profile_makes_invalid_are_skipped() {
+    let spawn_profile = SpawnProfile {
+        radius_scale: Distribution::Fixed(-1.0),
+        ..SpawnProfile::default()
+    };
+    let grid_frame = grid(spawn_profile).step(
+        0.0,
+        &[GridMessage::Spawn(Circle::new(
+            100.0,
+            100.0,
+            4.0,
+            (0.0, 0.0),
+        ))],
+    );
+
+    assert!(grid_frame.circles.is_empty());
+}
+
+fn grid(spawn_profile: SpawnProfile) -> Grid {
+    let config = SimulationConfig {
+        spawn_profile,
+        ..SimulationConfig::default()
+    };
+    grid_with(config)
+}
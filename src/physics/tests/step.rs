@@ -0,0 +1,70 @@
+// Checks that driving a detached grid with `Grid::step` does what ticking it
+// does, so that tests, benchmarks and embedders can use either.
+use futures::channel::oneshot;
+use iced::Point;
+use serde_json::Value;
+
+use super::{grid_with, HEIGHT, WIDTH};
+use crate::config::SimulationConfig;
+use crate::physics::{Circle, GridFrame, GridMessage, Query, TICKS_PER_SECOND};
+use crate::scene::presets::Preset;
+
+const FRAME_COUNT: u32 = 60;
+
+#[test]
+fn stepping_by_a_tick_matches_ticking() {
+    let mut ticked = grid_with(SimulationConfig::default());
+    let mut stepped = grid_with(SimulationConfig::default());
+    let tick_seconds = 1.0 / TICKS_PER_SECOND as f32;
+
+    let mut ticked_frame = ticked.tick(vec![load_plinko()]);
+    let mut stepped_frame = stepped.step(tick_seconds, &[load_plinko()]);
+    for _ in 1..FRAME_COUNT {
+        ticked_frame = ticked.tick(Vec::new());
+        stepped_frame = stepped.step(tick_seconds, &[]);
+    }
+
+    assert!(!ticked_frame.circles.is_empty());
+    assert_eq!(stepped_frame.get_frame_number(), FRAME_COUNT);
+    assert_eq!(circles(&stepped_frame), circles(&ticked_frame));
+}
+
+#[test]
+fn stepping_by_no_time_only_applies_messages() {
+    let mut grid = grid_with(SimulationConfig::default());
+    let loaded_frame = grid.step(
+        0.0,
+        &[GridMessage::LoadScene(
+            Preset::NewtonsCradle.build(WIDTH, HEIGHT),
+        )],
+    );
+    let later_frame = grid.step(0.0, &[]);
+
+    assert!(!loaded_frame.circles.is_empty());
+    assert_eq!(circles(&later_frame), circles(&loaded_frame));
+}
+
+#[test]
+fn stepped_messages_with_replies_are_rejected() {
+    let mut grid = grid_with(SimulationConfig::default());
+    let (id_sender, _id_receiver) = oneshot::channel();
+    let (result_sender, _result_receiver) = oneshot::channel();
+    let grid_frame = grid.step(
+        0.0,
+        &[
+            GridMessage::AddCircle(Circle::new(100.0, 100.0, 5.0, (0.0, 0.0)), Some(id_sender)),
+            GridMessage::Query(Query::Point(Point::new(100.0, 100.0)), result_sender),
+        ],
+    );
+
+    assert!(grid_frame.circles.is_empty());
+    assert_eq!(grid_frame.get_rejection_events().len(), 2);
+}
+
+fn load_plinko() -> GridMessage {
+    GridMessage::LoadScene(Preset::Plinko.build(WIDTH, HEIGHT))
+}
+
+fn circles(grid_frame: &GridFrame) -> Value {
+    serde_json::to_value(&grid_frame.circles).unwrap()
+}
@@ -0,0 +1,73 @@
+// Checks that messages with values no sensible caller would send are turned
+// away, and reported, without changing the grid. `fuzz/` throws arbitrary
+// messages at the grid to look for any that get through.
+use iced::Point;
+
+use super::grid_with;
+use crate::config::SimulationConfig;
+use crate::physics::{Circle, GridMessage, Spawner, StaticRectangle, VelocityDistribution};
+use crate::scene::Scene;
+
+#[test]
+fn invalid_messages_are_rejected_and_reported() {
+    let mut grid = grid_with(SimulationConfig::default());
+    let grid_frame = grid.tick(vec![
+        GridMessage::AddCircle(Circle::new(10.0, 10.0, -5.0, (0.0, 0.0)), None),
+        GridMessage::AddStaticRectangle(StaticRectangle::new(f32::NAN, 0.0, 10.0, 10.0)),
+        GridMessage::AddCircle(Circle::new(10.0, 10.0, 5.0, (0.0, 0.0)), None),
+    ]);
+
+    assert_eq!(grid_frame.circles.len(), 1);
+    let problems: Vec<_> = grid_frame
+        .get_rejection_events()
+        .iter()
+        .map(|rejection| rejection.problem.as_str())
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            "the circle's radius is out of range",
+            "the position is out of range"
+        ]
+    );
+}
+
+#[test]
+fn scenes_with_invalid_bodies_are_rejected() {
+    let mut grid = grid_with(SimulationConfig::default());
+    let scene = Scene {
+        circles: vec![Circle::new(10.0, 10.0, 5.0, (0.0, 0.0))],
+        spawners: vec![Spawner::new(
+            Point::new(100.0, 100.0),
+            5.0,
+            VelocityDistribution::Fixed((f32::NAN, 0.0)),
+            10,
+        )],
+        ..Scene::default()
+    };
+    let grid_frame = grid.tick(vec![GridMessage::LoadScene(scene)]);
+
+    assert!(grid_frame.circles.is_empty());
+    assert_eq!(grid_frame.get_rejection_events().len(), 1);
+}
+
+#[test]
+fn batches_are_applied_whole_or_not_at_all() {
+    let mut grid = grid_with(SimulationConfig::default());
+    let circle = |radius| GridMessage::AddCircle(Circle::new(10.0, 10.0, radius, (0.0, 0.0)), None);
+
+    let grid_frame = grid.tick(vec![GridMessage::Batch(vec![
+        circle(5.0),
+        circle(-5.0),
+        circle(5.0),
+    ])]);
+    assert!(grid_frame.circles.is_empty());
+    assert_eq!(grid_frame.get_rejection_events().len(), 1);
+
+    let grid_frame = grid.tick(vec![GridMessage::Batch(vec![
+        circle(5.0),
+        GridMessage::Batch(vec![circle(4.0), circle(3.0)]),
+    ])]);
+    assert_eq!(grid_frame.circles.len(), 3);
+    assert!(grid_frame.get_rejection_events().is_empty());
+}
@@ -0,0 +1,56 @@
+// Checks that the wind drags circles along through their drag model, so that
+// they end up moving with it, never faster, and circles without drag aren't
+// moved at all.
+use super::{grid_with, HEIGHT};
+use crate::config::SimulationConfig;
+use crate::forces::DragModel;
+use crate::physics::{Circle, GridMessage, LifetimePolicy};
+
+const WIND: (f32, f32) = (3.0, 0.0);
+// Long enough to catch up, but not to reach the far wall.
+const TICK_COUNT: u32 = 200;
+
+#[test]
+fn circles_catch_up_with_the_wind() {
+    for drag_model in [DragModel::Linear, DragModel::Quadratic] {
+        let speed = final_speed(drag_model);
+        assert!(
+            (speed - WIND.0).abs() < 0.1,
+            "{drag_model:?} drag left a circle moving at {speed}"
+        );
+    }
+}
+
+#[test]
+fn circles_without_drag_are_left_alone() {
+    assert_eq!(final_speed(DragModel::None), 0.0);
+}
+
+// The speed of a circle that starts out still in the wind, once it's been
+// blown around for a while.
+fn final_speed(drag_model: DragModel) -> f32 {
+    let config = SimulationConfig {
+        gravity: (0.0, 0.0),
+        wind: WIND,
+        air_density: 0.2,
+        sleep_speed_threshold: 0.0,
+        lifetime: LifetimePolicy::Immortal,
+        ..SimulationConfig::default()
+    };
+    let mut grid = grid_with(config);
+    let mut circle = Circle::new(50.0, HEIGHT / 2.0, 2.0, (0.0, 0.0));
+    circle.drag_model = Some(drag_model);
+    grid.tick(vec![GridMessage::AddCircle(circle, None)]);
+
+    let mut speed = 0.0;
+    for _ in 0..TICK_COUNT {
+        let grid_frame = grid.tick(Vec::new());
+        let (vx, vy) = grid_frame.circles[0].velocity;
+        speed = (vx * vx + vy * vy).sqrt();
+        assert!(
+            speed <= WIND.0 + 1e-3,
+            "{drag_model:?} drag overshot the wind"
+        );
+    }
+    speed
+}
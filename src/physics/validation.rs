@@ -0,0 +1,512 @@
+use iced::{Point, Rectangle, Size};
+
+use super::{
+    BodyProperties, Circle, Distribution, FluidZone, GravityWell, GridMessage, KinematicPath,
+    KinematicRectangle, KinematicSpinner, LifetimePolicy, Portal, PortalPair, SpawnBurst,
+    SpawnProfile, Spawner, StaticCapsule, StaticCircle, StaticRectangle, VelocityDistribution,
+};
+use crate::config::SimulationConfig;
+use crate::scene::Scene;
+
+// No position, size or speed in a sensible message comes anywhere near
+// this. Past it, they overflow the integer cell coordinates of the spatial
+// hashes, or take forever to insert into them.
+const MAX_MAGNITUDE: f32 = 1e6;
+// Nor do sizes come anywhere near this. Below it, circles are so light that
+// the slightest push sends them off at an infinite speed.
+const MIN_SIZE: f32 = 1e-3;
+
+impl GridMessage {
+    // Why the message would corrupt the simulation, if it would: NaNs,
+    // infinities, values so big that they may as well be infinite, or sizes
+    // too small to simulate.
+    pub(super) fn validate(&self) -> Result<(), &'static str> {
+        match self {
+            GridMessage::AddCircle(circle, _) | GridMessage::Spawn(circle) => {
+                validate_circle(circle)
+            }
+            GridMessage::AddStaticCircle(static_circle) => validate_static_circle(static_circle),
+            GridMessage::AddStaticRectangle(static_rectangle) => {
+                validate_static_rectangle(static_rectangle)
+            }
+            GridMessage::AddStaticCapsule(static_capsule) => {
+                validate_static_capsule(static_capsule)
+            }
+            GridMessage::AddKinematicRectangle(kinematic_rectangle) => {
+                validate_kinematic_rectangle(kinematic_rectangle)
+            }
+            GridMessage::AddKinematicSpinner(kinematic_spinner) => {
+                validate_kinematic_spinner(kinematic_spinner)
+            }
+            GridMessage::AddPortalPair(portal_pair) => validate_portal_pair(portal_pair),
+            GridMessage::AddSink(sink) => check_rectangle(sink.bounds()),
+            GridMessage::AddGravityWell(gravity_well) => validate_gravity_well(gravity_well),
+            GridMessage::AddFluidZone {
+                rect,
+                density,
+                drag,
+            } => check_fluid(*rect, *density, *drag),
+            GridMessage::AddSpawner(spawner) => validate_spawner(spawner),
+            GridMessage::AddSoftBody(soft_body) => {
+                soft_body.circles.iter().try_for_each(validate_circle)
+            }
+            GridMessage::AddRope { anchor, length, .. } => {
+                check_point(*anchor)?;
+                check_size(*length, "the rope's length is out of range")
+            }
+            GridMessage::AddPinJoint { anchor, .. } => check_point(*anchor),
+            GridMessage::AddRevoluteJoint { a, b } => {
+                if a == b {
+                    Err("a joint can't join a circle to itself")
+                } else {
+                    Ok(())
+                }
+            }
+            GridMessage::AddStaticSegment { a, b, thickness } => {
+                check_point(*a)?;
+                check_point(*b)?;
+                check_size(*thickness, "the segment's thickness is out of range")
+            }
+            GridMessage::Resize(size) => check_world_size(*size),
+            GridMessage::UpdateConfig(config) => validate_config(config),
+            GridMessage::SetComparisonConfig(config) => {
+                config.as_ref().map_or(Ok(()), validate_config)
+            }
+            GridMessage::SetGravity(gravity) => {
+                check_vector(*gravity, "the gravity is out of range")
+            }
+            GridMessage::SetTimeScale(time_scale) => {
+                check_size(*time_scale, "the time scale is out of range")
+            }
+            GridMessage::LoadScene(scene) => validate_scene(scene),
+            GridMessage::SetVelocity(_, velocity) => {
+                check_vector(*velocity, "the velocity is out of range")
+            }
+            GridMessage::UpdateBody(_, properties) => validate_body_properties(properties),
+            GridMessage::Explode {
+                x,
+                y,
+                strength,
+                radius,
+            } => {
+                check_point(Point::new(*x, *y))?;
+                check_number(*strength, "the explosion's strength is out of range")?;
+                check_number(*radius, "the explosion's radius is out of range")
+            }
+            GridMessage::SpawnBurst { x, y } => check_point(Point::new(*x, *y)),
+            GridMessage::Batch(messages) => messages.iter().try_for_each(|message| match message {
+                GridMessage::Query(_, _) => Err("a batch can't have queries in it"),
+                message => message.validate(),
+            }),
+            GridMessage::RemoveSpawner(_)
+            | GridMessage::SetSpawnerEnabled(_, _)
+            | GridMessage::SetPaused(_)
+            | GridMessage::Step
+            | GridMessage::StepBack
+            | GridMessage::ClearDynamic
+            | GridMessage::ClearStatic
+            | GridMessage::ResetAll
+            | GridMessage::Remove(_)
+            | GridMessage::Query(_, _) => Ok(()),
+        }
+    }
+}
+
+pub(super) fn validate_scene(scene: &Scene) -> Result<(), &'static str> {
+    scene.circles.iter().try_for_each(validate_circle)?;
+    scene
+        .static_circles
+        .iter()
+        .try_for_each(validate_static_circle)?;
+    scene
+        .static_rectangles
+        .iter()
+        .try_for_each(validate_static_rectangle)?;
+    scene
+        .static_capsules
+        .iter()
+        .try_for_each(validate_static_capsule)?;
+    scene
+        .kinematic_rectangles
+        .iter()
+        .try_for_each(validate_kinematic_rectangle)?;
+    scene
+        .kinematic_spinners
+        .iter()
+        .try_for_each(validate_kinematic_spinner)?;
+    scene
+        .portal_pairs
+        .iter()
+        .try_for_each(validate_portal_pair)?;
+    scene
+        .sinks
+        .iter()
+        .try_for_each(|sink| check_rectangle(sink.bounds()))?;
+    scene.fluid_zones.iter().try_for_each(validate_fluid_zone)?;
+    scene
+        .gravity_wells
+        .iter()
+        .try_for_each(validate_gravity_well)?;
+    scene.spawners.iter().try_for_each(validate_spawner)
+}
+
+pub(super) fn validate_circle(circle: &Circle) -> Result<(), &'static str> {
+    check_point(Point::new(circle.x_pos, circle.y_pos))?;
+    check_vector(circle.velocity, "the circle's velocity is out of range")?;
+    check_size(circle.radius, "the circle's radius is out of range")?;
+    check_size(circle.density, "the circle's density is out of range")?;
+    check_number(circle.charge, "the circle's charge is out of range")?;
+    if let Some(friction) = circle.friction {
+        check_not_negative(friction, "the circle's friction is out of range")?;
+    }
+    if let Some(restitution) = circle.restitution {
+        check_not_negative(restitution, "the circle's restitution is out of range")?;
+    }
+    Ok(())
+}
+
+fn validate_static_circle(static_circle: &StaticCircle) -> Result<(), &'static str> {
+    check_point(Point::new(static_circle.x_pos, static_circle.y_pos))?;
+    check_size(static_circle.radius, "the circle's radius is out of range")
+}
+
+fn validate_static_rectangle(static_rectangle: &StaticRectangle) -> Result<(), &'static str> {
+    check_rectangle(static_rectangle.bounds())?;
+    check_vector(
+        static_rectangle.surface_velocity,
+        "the rectangle's surface velocity is out of range",
+    )
+}
+
+fn validate_static_capsule(static_capsule: &StaticCapsule) -> Result<(), &'static str> {
+    check_point(static_capsule.a)?;
+    check_point(static_capsule.b)?;
+    check_size(
+        static_capsule.radius,
+        "the capsule's radius is out of range",
+    )?;
+    check_vector(
+        static_capsule.surface_velocity,
+        "the capsule's surface velocity is out of range",
+    )
+}
+
+fn validate_kinematic_rectangle(
+    kinematic_rectangle: &KinematicRectangle,
+) -> Result<(), &'static str> {
+    check_size(
+        kinematic_rectangle.width,
+        "the rectangle's width is out of range",
+    )?;
+    check_size(
+        kinematic_rectangle.height,
+        "the rectangle's height is out of range",
+    )?;
+    match kinematic_rectangle.path {
+        KinematicPath::Linear { from, to, period } => {
+            check_vector(from, "the position is out of range")?;
+            check_vector(to, "the position is out of range")?;
+            check_period(period)
+        }
+        KinematicPath::Orbit {
+            center,
+            radius,
+            period,
+        } => {
+            check_vector(center, "the position is out of range")?;
+            check_number(radius, "the orbit's radius is out of range")?;
+            check_period(period)
+        }
+    }
+}
+
+// Paths with no period stay put, but ones with a tiny period would be passed
+// through so often that no position along them could be worked out.
+fn check_period(period: f32) -> Result<(), &'static str> {
+    let problem = "the path's period is out of range";
+    if period > 0.0 {
+        check_size(period, problem)
+    } else {
+        check_number(period, problem)
+    }
+}
+
+fn validate_kinematic_spinner(kinematic_spinner: &KinematicSpinner) -> Result<(), &'static str> {
+    check_point(kinematic_spinner.pivot)?;
+    check_size(
+        kinematic_spinner.length,
+        "the spinner's length is out of range",
+    )?;
+    check_size(
+        kinematic_spinner.thickness,
+        "the spinner's thickness is out of range",
+    )?;
+    check_number(
+        kinematic_spinner.angular_velocity,
+        "the spinner's angular velocity is out of range",
+    )?;
+    check_number(
+        kinematic_spinner.angle,
+        "the spinner's angle is out of range",
+    )
+}
+
+fn validate_portal_pair(portal_pair: &PortalPair) -> Result<(), &'static str> {
+    validate_portal(&portal_pair.a)?;
+    validate_portal(&portal_pair.b)?;
+    check_number(
+        portal_pair.rotation,
+        "the portals' rotation is out of range",
+    )
+}
+
+fn validate_portal(portal: &Portal) -> Result<(), &'static str> {
+    check_point(portal.center)?;
+    check_size(portal.radius, "the portal's radius is out of range")
+}
+
+fn validate_fluid_zone(fluid_zone: &FluidZone) -> Result<(), &'static str> {
+    check_fluid(
+        Rectangle::new(
+            Point::new(fluid_zone.x_pos, fluid_zone.y_pos),
+            Size::new(fluid_zone.width, fluid_zone.height),
+        ),
+        fluid_zone.density,
+        fluid_zone.drag,
+    )
+}
+
+fn check_fluid(rect: Rectangle, density: f32, drag: f32) -> Result<(), &'static str> {
+    check_rectangle(rect)?;
+    check_number(density, "the fluid's density is out of range")?;
+    check_number(drag, "the fluid's drag is out of range")
+}
+
+fn validate_spawner(spawner: &Spawner) -> Result<(), &'static str> {
+    check_point(spawner.position)?;
+    check_size(spawner.radius, "the spawner's radius is out of range")?;
+    validate_velocity_distribution(&spawner.velocity)?;
+    spawner.lifetime.as_ref().map_or(Ok(()), validate_lifetime)
+}
+
+fn validate_velocity_distribution(velocity: &VelocityDistribution) -> Result<(), &'static str> {
+    match *velocity {
+        VelocityDistribution::Fixed(velocity) => {
+            check_vector(velocity, "the velocity is out of range")
+        }
+        VelocityDistribution::Uniform {
+            min_speed,
+            max_speed,
+            min_angle,
+            max_angle,
+        } => {
+            check_vector((min_speed, max_speed), "the speed is out of range")?;
+            check_vector((min_angle, max_angle), "the angle is out of range")
+        }
+    }
+}
+
+fn validate_lifetime(lifetime: &LifetimePolicy) -> Result<(), &'static str> {
+    match *lifetime {
+        LifetimePolicy::ShrinkAndDie { rate } => {
+            check_number(rate, "the shrink rate is out of range")
+        }
+        LifetimePolicy::Immortal
+        | LifetimePolicy::Ttl { .. }
+        | LifetimePolicy::DespawnOffscreen => Ok(()),
+    }
+}
+
+// Only the parts of the config that the simulation does arithmetic with.
+fn validate_config(config: &SimulationConfig) -> Result<(), &'static str> {
+    check_size(config.time_scale, "the time scale is out of range")?;
+    check_not_negative(
+        config.elasticity_coefficient,
+        "the elasticity coefficient is out of range",
+    )?;
+    check_not_negative(
+        config.restitution_threshold,
+        "the restitution threshold is out of range",
+    )?;
+    check_not_negative(config.air_density, "the air density is out of range")?;
+    validate_lifetime(&config.lifetime)?;
+    check_not_negative(config.min_radius_size, "the minimum radius is out of range")?;
+    check_vector(config.gravity, "the gravity is out of range")?;
+    check_vector(config.wind, "the wind is out of range")?;
+    check_not_negative(
+        config.position_correction,
+        "the position correction is out of range",
+    )?;
+    check_not_negative(
+        config.friction_coefficient,
+        "the friction coefficient is out of range",
+    )?;
+    check_not_negative(
+        config.sleep_speed_threshold,
+        "the sleep speed threshold is out of range",
+    )?;
+    if let Some(coalesce_max_radius) = config.coalesce_max_radius {
+        check_size(coalesce_max_radius, "the coalescing radius is out of range")?;
+    }
+    if let Some(fracture_impulse_threshold) = config.fracture_impulse_threshold {
+        check_not_negative(
+            fracture_impulse_threshold,
+            "the fracture threshold is out of range",
+        )?;
+    }
+    check_number(
+        config.gravity_well_strength,
+        "the gravity well's strength is out of range",
+    )?;
+    check_size(
+        config.gravity_well_radius,
+        "the gravity well's radius is out of range",
+    )?;
+    validate_spawn_burst(&config.spawn_burst)?;
+    validate_spawn_profile(&config.spawn_profile)?;
+    check_number(
+        config.coulomb_constant,
+        "the Coulomb constant is out of range",
+    )?;
+    check_not_negative(
+        config.charge_cutoff_radius,
+        "the charge cutoff radius is out of range",
+    )
+}
+
+fn validate_spawn_burst(spawn_burst: &SpawnBurst) -> Result<(), &'static str> {
+    check_size(spawn_burst.min_radius, "the burst's radius is out of range")?;
+    check_size(spawn_burst.max_radius, "the burst's radius is out of range")?;
+    check_not_negative(spawn_burst.spread, "the burst's spread is out of range")?;
+    validate_velocity_distribution(&spawn_burst.velocity)
+}
+
+fn validate_spawn_profile(spawn_profile: &SpawnProfile) -> Result<(), &'static str> {
+    check_distribution(
+        spawn_profile.radius_scale,
+        "the profile's radius scale is out of range",
+    )?;
+    check_distribution(
+        spawn_profile.speed_scale,
+        "the profile's speed scale is out of range",
+    )?;
+    check_distribution(
+        spawn_profile.density,
+        "the profile's density is out of range",
+    )?;
+    spawn_profile
+        .colors
+        .iter()
+        .flat_map(|&(red, green, blue)| [red, green, blue])
+        .try_for_each(|value| check_number(value, "the profile's colors are out of range"))
+}
+
+fn check_distribution(
+    distribution: Distribution,
+    problem: &'static str,
+) -> Result<(), &'static str> {
+    match distribution {
+        Distribution::Fixed(value) => check_number(value, problem),
+        Distribution::Uniform { min, max } => check_vector((min, max), problem),
+        Distribution::Normal { mean, std_dev } => {
+            check_number(mean, problem)?;
+            check_not_negative(std_dev, problem)
+        }
+    }
+}
+
+fn validate_gravity_well(gravity_well: &GravityWell) -> Result<(), &'static str> {
+    check_point(Point::new(gravity_well.x_pos, gravity_well.y_pos))?;
+    check_number(
+        gravity_well.strength,
+        "the gravity well's strength is out of range",
+    )?;
+    check_size(
+        gravity_well.radius,
+        "the gravity well's radius is out of range",
+    )
+}
+
+fn validate_body_properties(properties: &BodyProperties) -> Result<(), &'static str> {
+    match *properties {
+        BodyProperties::Circle {
+            x_pos,
+            y_pos,
+            radius,
+            mass,
+            restitution,
+            ..
+        } => {
+            check_point(Point::new(x_pos, y_pos))?;
+            check_size(radius, "the circle's radius is out of range")?;
+            check_size(mass, "the circle's mass is out of range")?;
+            if let Some(restitution) = restitution {
+                check_not_negative(restitution, "the circle's restitution is out of range")?;
+            }
+            Ok(())
+        }
+        BodyProperties::StaticCircle {
+            x_pos,
+            y_pos,
+            radius,
+        } => {
+            check_point(Point::new(x_pos, y_pos))?;
+            check_size(radius, "the circle's radius is out of range")
+        }
+        BodyProperties::StaticRectangle {
+            x_pos,
+            y_pos,
+            width,
+            height,
+        } => check_rectangle(Rectangle::new(
+            Point::new(x_pos, y_pos),
+            Size::new(width, height),
+        )),
+    }
+}
+
+fn check_world_size(size: Size) -> Result<(), &'static str> {
+    check_size(size.width, "the world's width is out of range")?;
+    check_size(size.height, "the world's height is out of range")
+}
+
+fn check_rectangle(rectangle: Rectangle) -> Result<(), &'static str> {
+    check_point(rectangle.position())?;
+    check_size(rectangle.width, "the rectangle's width is out of range")?;
+    check_size(rectangle.height, "the rectangle's height is out of range")
+}
+
+fn check_point(point: Point) -> Result<(), &'static str> {
+    check_vector((point.x, point.y), "the position is out of range")
+}
+
+fn check_vector((x, y): (f32, f32), problem: &'static str) -> Result<(), &'static str> {
+    check_number(x, problem)?;
+    check_number(y, problem)
+}
+
+fn check_number(value: f32, problem: &'static str) -> Result<(), &'static str> {
+    if value.is_finite() && value.abs() <= MAX_MAGNITUDE {
+        Ok(())
+    } else {
+        Err(problem)
+    }
+}
+
+fn check_not_negative(value: f32, problem: &'static str) -> Result<(), &'static str> {
+    check_number(value, problem)?;
+    if value >= 0.0 {
+        Ok(())
+    } else {
+        Err(problem)
+    }
+}
+
+fn check_size(value: f32, problem: &'static str) -> Result<(), &'static str> {
+    check_number(value, problem)?;
+    if value >= MIN_SIZE {
+        Ok(())
+    } else {
+        Err(problem)
+    }
+}
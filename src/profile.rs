@@ -0,0 +1,74 @@
+use simulation::config::SimulationConfig;
+
+// Bundles of gravity, drag and bounciness that can be picked while running,
+// on top of whatever the config file says about everything else.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Profile {
+    // The config file's own values.
+    #[default]
+    ConfigFile,
+    // The defaults.
+    Earth,
+    // A sixth of the gravity, and no air to slow anything down.
+    Moon,
+    // Thick drag, weak gravity after buoyancy, and collisions that barely
+    // bounce.
+    Underwater,
+    // No gravity, hardly any drag, and slick, lively balls, like a pool table
+    // without the table.
+    ZeroGPool,
+    // Collisions that lose next to nothing, even slow ones.
+    BouncyCastle,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 6] = [
+        Profile::ConfigFile,
+        Profile::Earth,
+        Profile::Moon,
+        Profile::Underwater,
+        Profile::ZeroGPool,
+        Profile::BouncyCastle,
+    ];
+
+    // The config with the profile's values in place of its own.
+    pub fn apply(self, config: SimulationConfig) -> SimulationConfig {
+        let earth = SimulationConfig::default();
+        let (gravity, air_density, elasticity_coefficient, restitution_threshold, friction) =
+            match self {
+                Profile::ConfigFile => return config,
+                Profile::Earth => (
+                    earth.gravity,
+                    earth.air_density,
+                    earth.elasticity_coefficient,
+                    earth.restitution_threshold,
+                    earth.friction_coefficient,
+                ),
+                Profile::Moon => ((0.0, earth.gravity.1 / 6.0), 0.0, 0.9, 0.5, 0.2),
+                Profile::Underwater => ((0.0, 0.08), 0.08, 0.3, 1.0, 0.1),
+                Profile::ZeroGPool => ((0.0, 0.0), 0.001, 0.95, 0.05, 0.02),
+                Profile::BouncyCastle => (earth.gravity, earth.air_density, 0.99, 0.05, 0.3),
+            };
+        SimulationConfig {
+            gravity,
+            air_density,
+            elasticity_coefficient,
+            restitution_threshold,
+            friction_coefficient: friction,
+            ..config
+        }
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Profile::ConfigFile => "Config file",
+            Profile::Earth => "Earth",
+            Profile::Moon => "Moon",
+            Profile::Underwater => "Underwater",
+            Profile::ZeroGPool => "Zero-G pool",
+            Profile::BouncyCastle => "Bouncy castle",
+        })
+    }
+}
@@ -0,0 +1,184 @@
+use futures::{channel::mpsc, stream::Stream};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::config::SimulationConfig;
+use crate::physics::{FrameEvents, Grid, GridFrame, GridMessage, TICKS_PER_SECOND};
+use crate::time::{frame_interval, FixedTimestep, Interval};
+
+pub const MIN_REPLAY_SPEED: f32 = 1.0 / 16.0;
+pub const MAX_REPLAY_SPEED: f32 = 16.0;
+
+// Everything needed to reconstruct the grid before the first recorded frame.
+// The simulation is deterministic, so replaying the recorded input messages
+// on top of this reproduces the original run exactly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub width: f32,
+    pub height: f32,
+    pub config: SimulationConfig,
+}
+
+pub struct Recording {
+    header: RecordingHeader,
+    // Messages to apply, keyed by the frame number of the tick that applied
+    // them. Frames without any messages aren't stored.
+    frames: VecDeque<(u32, Vec<GridMessage>)>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Parse(ron::error::SpannedError),
+    MissingHeader,
+}
+
+impl std::fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingError::Io(err) => write!(f, "recording I/O error: {err}"),
+            RecordingError::Serialize(err) => write!(f, "failed to serialize recording: {err}"),
+            RecordingError::Parse(err) => write!(f, "failed to parse recording: {err}"),
+            RecordingError::MissingHeader => write!(f, "recording is missing its header"),
+        }
+    }
+}
+
+// Writes the input message log of a running simulation to disk, one line per
+// frame that received any messages.
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, header: &RecordingHeader) -> Result<Self, RecordingError> {
+        let mut writer = BufWriter::new(File::create(path).map_err(RecordingError::Io)?);
+        let header = ron::to_string(header).map_err(RecordingError::Serialize)?;
+        writeln!(writer, "{header}").map_err(RecordingError::Io)?;
+        writer.flush().map_err(RecordingError::Io)?;
+        Ok(Self { writer })
+    }
+
+    pub fn record(&mut self, frame_number: u32, messages: &[GridMessage]) {
+        let messages: Vec<&GridMessage> = messages
+            .iter()
+            .filter(|message| message.affects_simulation())
+            .collect();
+        if messages.is_empty() {
+            return;
+        }
+
+        if let Err(err) = self.write_frame(frame_number, &messages) {
+            error!("Failed to record frame {frame_number}: {err}");
+        }
+    }
+
+    fn write_frame(
+        &mut self,
+        frame_number: u32,
+        messages: &[&GridMessage],
+    ) -> Result<(), RecordingError> {
+        let line = ron::to_string(&(frame_number, messages)).map_err(RecordingError::Serialize)?;
+        writeln!(self.writer, "{line}").map_err(RecordingError::Io)?;
+        // Flush eagerly so the recording is usable even if the app is killed.
+        self.writer.flush().map_err(RecordingError::Io)
+    }
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, RecordingError> {
+        let reader = BufReader::new(File::open(path).map_err(RecordingError::Io)?);
+        let mut lines = reader.lines();
+
+        let header = match lines.next() {
+            Some(line) => {
+                ron::from_str(&line.map_err(RecordingError::Io)?).map_err(RecordingError::Parse)?
+            }
+            None => return Err(RecordingError::MissingHeader),
+        };
+
+        let mut frames = VecDeque::new();
+        for line in lines {
+            let line = line.map_err(RecordingError::Io)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            frames.push_back(ron::from_str(&line).map_err(RecordingError::Parse)?);
+        }
+
+        Ok(Self { header, frames })
+    }
+
+    pub fn header(&self) -> &RecordingHeader {
+        &self.header
+    }
+
+    // Returns the messages that were applied by the tick that produced frame
+    // `frame_number`. Must be called with increasing frame numbers.
+    pub fn take_messages_for_frame(&mut self, frame_number: u32) -> Vec<GridMessage> {
+        match self.frames.front() {
+            Some((recorded_frame_number, _)) if *recorded_frame_number == frame_number => self
+                .frames
+                .pop_front()
+                .map(|(_, messages)| messages)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Plays back a recording at the simulation's tick rate multiplied by the
+// replay speed, which can be changed at any time through the returned sender.
+// Frames are rendered at `target_fps`.
+pub fn new_replay_grid_frame_stream(
+    mut recording: Recording,
+    target_fps: u64,
+    initial_speed: f32,
+) -> (mpsc::Sender<f32>, impl Stream<Item = GridFrame>) {
+    let (speed_sender, mut speed_receiver) = mpsc::channel(16);
+    let header = recording.header().clone();
+    let mut grid = Grid::new_detached(header.width, header.height, header.config);
+
+    let grid_frame_stream = async_stream::stream! {
+        let mut interval = Interval::new(Duration::from_millis(1000 / target_fps));
+        let mut timestep = FixedTimestep::new(TICKS_PER_SECOND);
+        let mut speed = initial_speed.clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED);
+        let mut latest_grid_frame = None;
+        let mut undelivered_events = FrameEvents::default();
+        let mut last_yielded_at = None;
+
+        loop {
+            interval.tick().await;
+
+            while let Ok(Some(new_speed)) = speed_receiver.try_next() {
+                speed = f32::clamp(new_speed, MIN_REPLAY_SPEED, MAX_REPLAY_SPEED);
+            }
+
+            // Run as many ticks as the replay speed calls for, but only show
+            // the last one.
+            for _ in 0..timestep.advance(speed) {
+                let messages = recording.take_messages_for_frame(grid.get_frame_number());
+                let grid_frame = grid.tick(messages);
+                undelivered_events.extend(grid_frame.get_events());
+                latest_grid_frame = Some(grid_frame);
+            }
+
+            if let Some(grid_frame) = &latest_grid_frame {
+                yield grid_frame
+                    .clone()
+                    .with_interpolation(timestep.interpolation())
+                    .with_events(std::mem::take(&mut undelivered_events))
+                    .with_frame_interval(frame_interval(&mut last_yielded_at));
+            }
+        }
+    };
+
+    (speed_sender, grid_frame_stream)
+}
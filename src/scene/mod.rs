@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use std::path::Path;
+
+use crate::forces::DragModel;
+use crate::physics::{
+    Circle, FluidZone, GravityWell, Joint, KinematicRectangle, KinematicSpinner, PortalPair, Rope,
+    Sink, Spawner, Spring, StaticCapsule, StaticCircle, StaticRectangle,
+};
+
+pub mod presets;
+pub mod stress;
+
+// Every body in the grid, as saved to and loaded from a scene file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scene {
+    pub circles: Vec<Circle>,
+    pub static_circles: Vec<StaticCircle>,
+    pub static_rectangles: Vec<StaticRectangle>,
+    // Scenes saved before capsules existed don't have this field.
+    #[serde(default)]
+    pub static_capsules: Vec<StaticCapsule>,
+    #[serde(default)]
+    pub kinematic_rectangles: Vec<KinematicRectangle>,
+    #[serde(default)]
+    pub kinematic_spinners: Vec<KinematicSpinner>,
+    #[serde(default)]
+    pub portal_pairs: Vec<PortalPair>,
+    #[serde(default)]
+    pub sinks: Vec<Sink>,
+    #[serde(default)]
+    pub fluid_zones: Vec<FluidZone>,
+    #[serde(default)]
+    pub gravity_wells: Vec<GravityWell>,
+    #[serde(default)]
+    pub spawners: Vec<Spawner>,
+    // Springs between entries of `circles`, by index.
+    #[serde(default)]
+    pub springs: Vec<Spring>,
+    // Ropes through entries of `circles`, by index.
+    #[serde(default)]
+    pub ropes: Vec<Rope>,
+    // Joints on entries of `circles`, by index.
+    #[serde(default)]
+    pub joints: Vec<Joint>,
+    // How air slows down circles that don't have a drag model of their own.
+    #[serde(default)]
+    pub drag_model: DragModel,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "scene I/O error: {err}"),
+            SceneError::Serialize(err) => write!(f, "failed to serialize scene: {err}"),
+            SceneError::Parse(err) => write!(f, "failed to parse scene: {err}"),
+        }
+    }
+}
+
+impl Scene {
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let contents = std::fs::read_to_string(path).map_err(SceneError::Io)?;
+        ron::from_str(&contents).map_err(SceneError::Parse)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SceneError> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(SceneError::Serialize)?;
+        std::fs::write(path, contents).map_err(SceneError::Io)
+    }
+}
@@ -0,0 +1,246 @@
+use iced::Point;
+
+use std::f32::consts::PI;
+
+use crate::physics::{
+    Circle, Spawner, StaticCapsule, StaticCircle, StaticRectangle, Tether, VelocityDistribution,
+};
+use crate::scene::Scene;
+
+// Hand-built scenes that the app can start with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Preset {
+    // A rounded square in the middle of the window, with circles streaming
+    // in from the top-left corner.
+    #[default]
+    RoundedSquare,
+    // Circles dropped through rows of pegs into bins.
+    Plinko,
+    // A pile of circles trickling through a narrow neck.
+    Hourglass,
+    // A row of touching pendulums, with the first one pulled back.
+    NewtonsCradle,
+}
+
+impl Preset {
+    const ALL: [Preset; 4] = [
+        Preset::RoundedSquare,
+        Preset::Plinko,
+        Preset::Hourglass,
+        Preset::NewtonsCradle,
+    ];
+
+    // The preset after this one, wrapping around.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|preset| *preset == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    // Builds the preset to fit a world of the given size.
+    pub fn build(self, width: f32, height: f32) -> Scene {
+        match self {
+            Preset::RoundedSquare => rounded_square(width, height),
+            Preset::Plinko => plinko(width, height),
+            Preset::Hourglass => hourglass(width, height),
+            Preset::NewtonsCradle => newtons_cradle(width, height),
+        }
+    }
+}
+
+impl std::fmt::Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Preset::RoundedSquare => "rounded square",
+            Preset::Plinko => "plinko",
+            Preset::Hourglass => "hourglass",
+            Preset::NewtonsCradle => "Newton's cradle",
+        };
+        write!(f, "{name}")
+    }
+}
+
+fn rounded_square(width: f32, height: f32) -> Scene {
+    let square_size = 200.0;
+    let mut scene = Scene::default();
+    add_rounded_rectangle(
+        &mut scene,
+        width / 2.0 - square_size / 2.0,
+        height / 2.0 - square_size / 2.0,
+        square_size,
+        square_size,
+        20.0,
+    );
+    scene.spawners.push(Spawner::new(
+        Point::new(10.0, 10.0),
+        10.0,
+        VelocityDistribution::Fixed((10.0, 0.0)),
+        10,
+    ));
+    scene
+}
+
+fn plinko(width: f32, height: f32) -> Scene {
+    let peg_spacing = 50.0;
+    let bin_height = 100.0;
+    let mut scene = Scene::default();
+
+    // Offset every other row by half a peg so that circles can't fall
+    // straight through.
+    let mut row = 0;
+    let mut y_pos = 80.0;
+    while y_pos < height - bin_height - 30.0 {
+        let offset = if row % 2 == 0 { 0.0 } else { peg_spacing / 2.0 };
+        let mut x_pos = peg_spacing / 2.0 + offset;
+        while x_pos < width {
+            scene
+                .static_circles
+                .push(StaticCircle::new(x_pos, y_pos, 5.0));
+            x_pos += peg_spacing;
+        }
+        row += 1;
+        y_pos += 40.0;
+    }
+
+    let mut x_pos = peg_spacing;
+    while x_pos < width {
+        scene.static_rectangles.push(StaticRectangle::new(
+            x_pos - 2.0,
+            height - bin_height,
+            4.0,
+            bin_height,
+        ));
+        x_pos += peg_spacing;
+    }
+
+    let mut spawner = Spawner::new(
+        Point::new(width / 2.0, 20.0),
+        6.0,
+        VelocityDistribution::Uniform {
+            min_speed: 0.5,
+            max_speed: 2.0,
+            min_angle: PI / 4.0,
+            max_angle: 3.0 * PI / 4.0,
+        },
+        15,
+    );
+    spawner.max_alive = Some(150);
+    scene.spawners.push(spawner);
+
+    scene
+}
+
+fn hourglass(width: f32, height: f32) -> Scene {
+    let center_x = width / 2.0;
+    let neck_y = height / 2.0;
+    let half_width = 180.0;
+    let slope_height = 150.0;
+    let neck_half_width = 16.0;
+    let wall_radius = 4.0;
+    let mut scene = Scene::default();
+
+    // Each side is a chain of capsules: down, in to the neck, out again and
+    // down to the floor.
+    for side in [-1.0, 1.0] {
+        let points = [
+            Point::new(center_x + side * half_width, 0.0),
+            Point::new(center_x + side * half_width, neck_y - slope_height),
+            Point::new(center_x + side * neck_half_width, neck_y),
+            Point::new(center_x + side * half_width, neck_y + slope_height),
+            Point::new(center_x + side * half_width, height),
+        ];
+        for pair in points.windows(2) {
+            scene
+                .static_capsules
+                .push(StaticCapsule::new(pair[0], pair[1], wall_radius));
+        }
+    }
+
+    // Fill the top chamber.
+    let sand_radius = 5.0;
+    let sand_spacing = 2.0 * sand_radius + 2.0;
+    let mut y_pos = 2.0 * sand_radius;
+    while y_pos < neck_y - slope_height - sand_radius {
+        let mut x_pos = center_x - half_width + wall_radius + sand_spacing;
+        while x_pos < center_x + half_width - wall_radius - sand_radius {
+            scene
+                .circles
+                .push(Circle::new(x_pos, y_pos, sand_radius, (0.0, 0.0)));
+            x_pos += sand_spacing;
+        }
+        y_pos += sand_spacing;
+    }
+
+    scene
+}
+
+fn newtons_cradle(width: f32, height: f32) -> Scene {
+    let ball_count = 5;
+    let ball_radius = 20.0;
+    let anchor_y = 60.0;
+    let string_length = (height - anchor_y - 2.0 * ball_radius).min(240.0);
+    let pullback_angle: f32 = 50.0_f32.to_radians();
+    let mut scene = Scene::default();
+
+    let first_x = width / 2.0 - (ball_count - 1) as f32 * ball_radius;
+    scene.static_rectangles.push(StaticRectangle::new(
+        first_x - 2.0 * ball_radius,
+        anchor_y - 10.0,
+        2.0 * ball_count as f32 * ball_radius + 2.0 * ball_radius,
+        10.0,
+    ));
+
+    for i in 0..ball_count {
+        let anchor = Point::new(first_x + i as f32 * 2.0 * ball_radius, anchor_y);
+        let angle = if i == 0 { pullback_angle } else { 0.0 };
+        let mut ball = Circle::new(
+            anchor.x - string_length * angle.sin(),
+            anchor.y + string_length * angle.cos(),
+            ball_radius,
+            (0.0, 0.0),
+        );
+        ball.tether = Some(Tether {
+            anchor,
+            length: string_length,
+        });
+        scene.circles.push(ball);
+    }
+
+    scene
+}
+
+// A rectangle with rounded corners, made of an inner rectangle outlined with
+// capsules.
+fn add_rounded_rectangle(
+    scene: &mut Scene,
+    x_pos: f32,
+    y_pos: f32,
+    width: f32,
+    height: f32,
+    border_radius: f32,
+) {
+    let left = x_pos + border_radius;
+    let top = y_pos + border_radius;
+    let right = x_pos + width - border_radius;
+    let bottom = y_pos + height - border_radius;
+    let corners = [
+        Point::new(left, top),
+        Point::new(right, top),
+        Point::new(right, bottom),
+        Point::new(left, bottom),
+    ];
+
+    // Fill the middle, then trace its outline with capsules to round it off.
+    scene
+        .static_rectangles
+        .push(StaticRectangle::new(left, top, right - left, bottom - top));
+    for i in 0..corners.len() {
+        scene.static_capsules.push(StaticCapsule::new(
+            corners[i],
+            corners[(i + 1) % corners.len()],
+            border_radius,
+        ));
+    }
+}
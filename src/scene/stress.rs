@@ -0,0 +1,101 @@
+use iced::{Point, Size};
+
+use crate::physics::{Circle, StaticCapsule, StaticCircle, StaticRectangle};
+use crate::random::Rng;
+use crate::scene::Scene;
+
+// The same circle count always gives the same scene, so that runs before and
+// after a change are comparable.
+const SEED: u64 = 0x57e55;
+const MIN_RADIUS: f32 = 2.0;
+const MAX_RADIUS: f32 = 5.0;
+const MAX_SPEED: f32 = 2.0;
+// Circles start on a lattice with this much room each, jittered by up to
+// the room left over around the biggest ones.
+const SPACING: f32 = 14.0;
+// Width over height, like the window's.
+const ASPECT_RATIO: f32 = 5.0 / 3.0;
+const MIN_WIDTH: f32 = 800.0;
+const PEG_RADIUS: f32 = 6.0;
+const PEG_SPACING: f32 = 60.0;
+// Where things go down the world, as fractions of its height.
+const PEG_ROWS: [f32; 3] = [0.55, 0.62, 0.69];
+const RAMP_TOP: f32 = 0.75;
+const RAMP_BOTTOM: f32 = 0.83;
+const BIN_TOP: f32 = 0.9;
+const RAMP_RADIUS: f32 = 4.0;
+
+// The world a stress test of `circle_count` circles is built for: big enough
+// for them to start in its top half, with the obstacle course below.
+pub fn world_size(circle_count: usize) -> Size {
+    let width = ((2.0 * ASPECT_RATIO * circle_count as f32).sqrt() + 1.0) * SPACING;
+    let width = width.max(MIN_WIDTH);
+    Size::new(width, width / ASPECT_RATIO)
+}
+
+// `circle_count` circles of random sizes and velocities filling the top half
+// of the world, falling onto rows of pegs, a pair of ramps and a row of
+// bins. For measuring how performance changes, so it's built the same way
+// in the headless benchmark and the app.
+pub fn build(circle_count: usize, width: f32, height: f32) -> Scene {
+    let mut rng = Rng::new(SEED);
+    let mut scene = Scene::default();
+
+    let columns = ((width / SPACING) as usize).max(1);
+    let jitter = SPACING / 2.0 - MAX_RADIUS;
+    scene.circles = (0..circle_count)
+        .map(|i| {
+            let column = i % columns;
+            let row = i / columns;
+            Circle::new(
+                (column as f32 + 0.5) * SPACING + rng.range(-jitter, jitter),
+                (row as f32 + 0.5) * SPACING + rng.range(-jitter, jitter),
+                rng.range(MIN_RADIUS, MAX_RADIUS),
+                (
+                    rng.range(-MAX_SPEED, MAX_SPEED),
+                    rng.range(-MAX_SPEED, MAX_SPEED),
+                ),
+            )
+        })
+        .collect();
+
+    // Staggered rows of pegs across the middle of the world.
+    for (row, row_y) in PEG_ROWS.into_iter().enumerate() {
+        let y_pos = height * row_y;
+        let offset = if row % 2 == 0 { 0.0 } else { PEG_SPACING / 2.0 };
+        let mut x_pos = PEG_SPACING / 2.0 + offset;
+        while x_pos < width {
+            scene
+                .static_circles
+                .push(StaticCircle::new(x_pos, y_pos, PEG_RADIUS));
+            x_pos += PEG_SPACING;
+        }
+    }
+
+    // Ramps sloping in from each side below the pegs, leaving a gap in the
+    // middle.
+    scene.static_capsules.push(StaticCapsule::new(
+        Point::new(0.0, height * RAMP_TOP),
+        Point::new(width * 0.4, height * RAMP_BOTTOM),
+        RAMP_RADIUS,
+    ));
+    scene.static_capsules.push(StaticCapsule::new(
+        Point::new(width, height * RAMP_TOP),
+        Point::new(width * 0.6, height * RAMP_BOTTOM),
+        RAMP_RADIUS,
+    ));
+
+    // Bins along the floor.
+    let mut x_pos = PEG_SPACING;
+    while x_pos < width {
+        scene.static_rectangles.push(StaticRectangle::new(
+            x_pos - 2.0,
+            height * BIN_TOP,
+            4.0,
+            height * (1.0 - BIN_TOP),
+        ));
+        x_pos += PEG_SPACING;
+    }
+
+    scene
+}
@@ -0,0 +1,341 @@
+// Rhai scripts that drive the simulation. A script's top level runs once at
+// startup and can build a scene, and it can define any of these callbacks:
+//
+//   fn on_tick(tick) { ... }       // Once for every new frame.
+//   fn on_collision(event) { ... } // Once for every collision event, with
+//                                  // `a`, `b`, `impulse`, `x` and `y` fields.
+//
+// Scripts act on the grid through `GridMessage`s like all other input; see
+// there. This needs the `scripting` feature and isn't available on wasm32.
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+pub use enabled::ScriptHost;
+
+#[cfg(not(all(feature = "scripting", not(target_arch = "wasm32"))))]
+pub use disabled::ScriptHost;
+
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+mod enabled {
+    use iced::{Point, Rectangle, Size};
+    use rhai::{CallFnOptions, Engine, Map, Scope, AST, FLOAT, INT};
+    use simulation::physics::{
+        Circle, EntityId, GravityWell, GridFrame, GridMessage, SoftBody, StaticCircle,
+        StaticRectangle,
+    };
+    use tracing::info;
+
+    use std::cell::RefCell;
+    use std::path::Path;
+    use std::rc::Rc;
+
+    // What the script's native functions can see of the grid, and the
+    // messages they've queued since the last frame.
+    #[derive(Default)]
+    struct ScriptContext {
+        messages: Vec<GridMessage>,
+        width: f32,
+        height: f32,
+        circle_count: usize,
+    }
+
+    pub struct ScriptHost {
+        engine: Engine,
+        ast: AST,
+        scope: Scope<'static>,
+        context: Rc<RefCell<ScriptContext>>,
+        has_on_tick: bool,
+        has_on_collision: bool,
+    }
+
+    impl ScriptHost {
+        pub fn load(path: &Path, width: f32, height: f32) -> Result<Self, String> {
+            let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+            let context = Rc::new(RefCell::new(ScriptContext {
+                width,
+                height,
+                ..ScriptContext::default()
+            }));
+            let engine = new_engine(&context);
+            let ast = engine.compile(source).map_err(|err| err.to_string())?;
+
+            let has_function = |name: &str| ast.iter_functions().any(|f| f.name == name);
+            let has_on_tick = has_function("on_tick");
+            let has_on_collision = has_function("on_collision");
+
+            let mut scope = Scope::new();
+            engine
+                .run_ast_with_scope(&mut scope, &ast)
+                .map_err(|err| err.to_string())?;
+
+            Ok(Self {
+                engine,
+                ast,
+                scope,
+                context,
+                has_on_tick,
+                has_on_collision,
+            })
+        }
+
+        // Runs the callbacks for a new frame and returns every message the
+        // script queued since the last one.
+        pub fn run_frame(&mut self, grid_frame: &GridFrame) -> Result<Vec<GridMessage>, String> {
+            {
+                let mut context = self.context.borrow_mut();
+                let size = grid_frame.get_size();
+                context.width = size.width;
+                context.height = size.height;
+                context.circle_count = grid_frame.get_circle_count();
+            }
+
+            if self.has_on_tick {
+                self.engine
+                    .call_fn_with_options::<()>(
+                        callback_options(),
+                        &mut self.scope,
+                        &self.ast,
+                        "on_tick",
+                        (grid_frame.get_frame_number() as INT,),
+                    )
+                    .map_err(|err| err.to_string())?;
+            }
+
+            if self.has_on_collision {
+                for event in grid_frame.get_collision_events() {
+                    let mut map = Map::new();
+                    map.insert("a".into(), rhai::Dynamic::from(event.a));
+                    map.insert("b".into(), rhai::Dynamic::from(event.b));
+                    map.insert("impulse".into(), (event.impulse as FLOAT).into());
+                    map.insert("x".into(), (event.point.0 as FLOAT).into());
+                    map.insert("y".into(), (event.point.1 as FLOAT).into());
+
+                    self.engine
+                        .call_fn_with_options::<()>(
+                            callback_options(),
+                            &mut self.scope,
+                            &self.ast,
+                            "on_collision",
+                            (map,),
+                        )
+                        .map_err(|err| err.to_string())?;
+                }
+            }
+
+            Ok(std::mem::take(&mut self.context.borrow_mut().messages))
+        }
+    }
+
+    // The top level already ran when the script was loaded, so callbacks
+    // mustn't run it again.
+    fn callback_options() -> CallFnOptions<'static> {
+        CallFnOptions::new().eval_ast(false)
+    }
+
+    fn new_engine(context: &Rc<RefCell<ScriptContext>>) -> Engine {
+        let mut engine = Engine::new();
+
+        engine
+            .register_type_with_name::<EntityId>("EntityId")
+            .register_fn("to_string", |id: &mut EntityId| format!("{id:?}"))
+            .register_fn("to_debug", |id: &mut EntityId| format!("{id:?}"))
+            .register_fn("==", |a: EntityId, b: EntityId| a == b)
+            .register_fn("!=", |a: EntityId, b: EntityId| a != b);
+
+        let queue = |context: &Rc<RefCell<ScriptContext>>| {
+            let context = context.clone();
+            move |message: GridMessage| context.borrow_mut().messages.push(message)
+        };
+
+        let send = queue(context);
+        engine.register_fn(
+            "spawn_circle",
+            move |x: FLOAT, y: FLOAT, radius: FLOAT, vx: FLOAT, vy: FLOAT| {
+                send(GridMessage::AddCircle(
+                    Circle::new(x as f32, y as f32, radius as f32, (vx as f32, vy as f32)),
+                    None,
+                ))
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn(
+            "spawn_soft_ring",
+            move |x: FLOAT,
+                  y: FLOAT,
+                  radius: FLOAT,
+                  particle_count: INT,
+                  particle_radius: FLOAT| {
+                send(GridMessage::AddSoftBody(SoftBody::ring(
+                    Point::new(x as f32, y as f32),
+                    radius as f32,
+                    particle_count.max(0) as usize,
+                    particle_radius as f32,
+                )))
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn(
+            "spawn_soft_lattice",
+            move |x: FLOAT,
+                  y: FLOAT,
+                  columns: INT,
+                  rows: INT,
+                  spacing: FLOAT,
+                  particle_radius: FLOAT| {
+                send(GridMessage::AddSoftBody(SoftBody::lattice(
+                    Point::new(x as f32, y as f32),
+                    columns.max(0) as usize,
+                    rows.max(0) as usize,
+                    spacing as f32,
+                    particle_radius as f32,
+                )))
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn(
+            "spawn_rope",
+            move |x: FLOAT, y: FLOAT, segments: INT, length: FLOAT| {
+                send(GridMessage::AddRope {
+                    anchor: Point::new(x as f32, y as f32),
+                    segments: segments.max(0) as u32,
+                    length: length as f32,
+                    attached_to: None,
+                })
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn(
+            "add_static_circle",
+            move |x: FLOAT, y: FLOAT, radius: FLOAT| {
+                send(GridMessage::AddStaticCircle(StaticCircle::new(
+                    x as f32,
+                    y as f32,
+                    radius as f32,
+                )))
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn(
+            "add_static_rectangle",
+            move |x: FLOAT, y: FLOAT, width: FLOAT, height: FLOAT| {
+                send(GridMessage::AddStaticRectangle(StaticRectangle::new(
+                    x as f32,
+                    y as f32,
+                    width as f32,
+                    height as f32,
+                )))
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn(
+            "add_static_segment",
+            move |ax: FLOAT, ay: FLOAT, bx: FLOAT, by: FLOAT, thickness: FLOAT| {
+                send(GridMessage::AddStaticSegment {
+                    a: Point::new(ax as f32, ay as f32),
+                    b: Point::new(bx as f32, by as f32),
+                    thickness: thickness as f32,
+                })
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn(
+            "add_fluid_zone",
+            move |x: FLOAT, y: FLOAT, width: FLOAT, height: FLOAT, density: FLOAT, drag: FLOAT| {
+                send(GridMessage::AddFluidZone {
+                    rect: Rectangle::new(
+                        Point::new(x as f32, y as f32),
+                        Size::new(width as f32, height as f32),
+                    ),
+                    density: density as f32,
+                    drag: drag as f32,
+                })
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn(
+            "add_gravity_well",
+            move |x: FLOAT, y: FLOAT, strength: FLOAT, radius: FLOAT| {
+                send(GridMessage::AddGravityWell(GravityWell::new(
+                    x as f32,
+                    y as f32,
+                    strength as f32,
+                    radius as f32,
+                )))
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn("set_gravity", move |x: FLOAT, y: FLOAT| {
+            send(GridMessage::SetGravity((x as f32, y as f32)))
+        });
+
+        let send = queue(context);
+        engine.register_fn("set_velocity", move |id: EntityId, vx: FLOAT, vy: FLOAT| {
+            send(GridMessage::SetVelocity(id, (vx as f32, vy as f32)))
+        });
+
+        let send = queue(context);
+        engine.register_fn(
+            "explode",
+            move |x: FLOAT, y: FLOAT, strength: FLOAT, radius: FLOAT| {
+                send(GridMessage::Explode {
+                    x: x as f32,
+                    y: y as f32,
+                    strength: strength as f32,
+                    radius: radius as f32,
+                })
+            },
+        );
+
+        let send = queue(context);
+        engine.register_fn("remove", move |id: EntityId| send(GridMessage::Remove(id)));
+
+        let send = queue(context);
+        engine.register_fn("clear", move || send(GridMessage::ClearDynamic));
+
+        let send = queue(context);
+        engine.register_fn("clear_static", move || send(GridMessage::ClearStatic));
+
+        let send = queue(context);
+        engine.register_fn("reset", move || send(GridMessage::ResetAll));
+
+        let context_ref = context.clone();
+        engine.register_fn("world_width", move || context_ref.borrow().width as FLOAT);
+        let context_ref = context.clone();
+        engine.register_fn("world_height", move || context_ref.borrow().height as FLOAT);
+        let context_ref = context.clone();
+        engine.register_fn("circle_count", move || {
+            context_ref.borrow().circle_count as INT
+        });
+
+        engine.on_print(|text| info!("[script] {text}"));
+
+        engine
+    }
+}
+
+#[cfg(not(all(feature = "scripting", not(target_arch = "wasm32"))))]
+mod disabled {
+    use simulation::physics::{GridFrame, GridMessage};
+
+    use std::path::Path;
+
+    pub struct ScriptHost;
+
+    impl ScriptHost {
+        pub fn load(_path: &Path, _width: f32, _height: f32) -> Result<Self, String> {
+            Err("this build doesn't support scripts, rebuild with `--features scripting`".into())
+        }
+
+        pub fn run_frame(&mut self, _grid_frame: &GridFrame) -> Result<Vec<GridMessage>, String> {
+            Ok(Vec::new())
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// The loops over every circle that run on every subtick, vectorized to work
+// on four circles at a time. Each circle's `(x, y)` pair takes up two lanes.
+// The `scalar` versions do exactly the same arithmetic one circle at a time,
+// and are what's used without the `simd` feature and for the circles left over
+// at the end, so that both give the same results.
+
+#[cfg(feature = "simd")]
+use wide::f32x8;
+
+#[cfg(feature = "simd")]
+const CIRCLES_PER_VECTOR: usize = 4;
+
+// Adds `delta` to the value of every awake circle.
+pub fn add_to_awake(values: &mut [(f32, f32)], delta: (f32, f32), sleeping: &[bool]) {
+    #[cfg(feature = "simd")]
+    {
+        let delta_lanes = splat_pair(delta);
+        let mut values_chunks = values.chunks_exact_mut(CIRCLES_PER_VECTOR);
+        let mut sleeping_chunks = sleeping.chunks_exact(CIRCLES_PER_VECTOR);
+        for (values, sleeping) in (&mut values_chunks).zip(&mut sleeping_chunks) {
+            if sleeping.contains(&true) {
+                scalar::add_to_awake(values, delta, sleeping);
+            } else {
+                store(values, load(values) + delta_lanes);
+            }
+        }
+        scalar::add_to_awake(
+            values_chunks.into_remainder(),
+            delta,
+            sleeping_chunks.remainder(),
+        );
+    }
+    #[cfg(not(feature = "simd"))]
+    scalar::add_to_awake(values, delta, sleeping);
+}
+
+// Multiplies every circle's value by its factor.
+pub fn scale(values: &mut [(f32, f32)], factors: &[f32]) {
+    #[cfg(feature = "simd")]
+    {
+        let mut values_chunks = values.chunks_exact_mut(CIRCLES_PER_VECTOR);
+        let mut factors_chunks = factors.chunks_exact(CIRCLES_PER_VECTOR);
+        for (values, factors) in (&mut values_chunks).zip(&mut factors_chunks) {
+            let factor_lanes = f32x8::from([
+                factors[0], factors[0], factors[1], factors[1], factors[2], factors[2], factors[3],
+                factors[3],
+            ]);
+            store(values, load(values) * factor_lanes);
+        }
+        scalar::scale(values_chunks.into_remainder(), factors_chunks.remainder());
+    }
+    #[cfg(not(feature = "simd"))]
+    scalar::scale(values, factors);
+}
+
+// Moves every awake circle by `velocity * dt`.
+pub fn advance_awake(
+    positions: &mut [(f32, f32)],
+    velocities: &[(f32, f32)],
+    dt: f32,
+    sleeping: &[bool],
+) {
+    #[cfg(feature = "simd")]
+    {
+        let dt_lanes = f32x8::splat(dt);
+        let mut positions_chunks = positions.chunks_exact_mut(CIRCLES_PER_VECTOR);
+        let mut velocities_chunks = velocities.chunks_exact(CIRCLES_PER_VECTOR);
+        let mut sleeping_chunks = sleeping.chunks_exact(CIRCLES_PER_VECTOR);
+        for ((positions, velocities), sleeping) in (&mut positions_chunks)
+            .zip(&mut velocities_chunks)
+            .zip(&mut sleeping_chunks)
+        {
+            if sleeping.contains(&true) {
+                scalar::advance_awake(positions, velocities, dt, sleeping);
+            } else {
+                store(positions, load(positions) + load(velocities) * dt_lanes);
+            }
+        }
+        scalar::advance_awake(
+            positions_chunks.into_remainder(),
+            velocities_chunks.remainder(),
+            dt,
+            sleeping_chunks.remainder(),
+        );
+    }
+    #[cfg(not(feature = "simd"))]
+    scalar::advance_awake(positions, velocities, dt, sleeping);
+}
+
+// Moves every awake circle by the average of its two velocities times `dt`.
+pub fn advance_awake_by_average(
+    positions: &mut [(f32, f32)],
+    start_velocities: &[(f32, f32)],
+    velocities: &[(f32, f32)],
+    dt: f32,
+    sleeping: &[bool],
+) {
+    #[cfg(feature = "simd")]
+    {
+        let half = f32x8::splat(0.5);
+        let dt_lanes = f32x8::splat(dt);
+        let mut positions_chunks = positions.chunks_exact_mut(CIRCLES_PER_VECTOR);
+        let mut start_velocities_chunks = start_velocities.chunks_exact(CIRCLES_PER_VECTOR);
+        let mut velocities_chunks = velocities.chunks_exact(CIRCLES_PER_VECTOR);
+        let mut sleeping_chunks = sleeping.chunks_exact(CIRCLES_PER_VECTOR);
+        for (((positions, start_velocities), velocities), sleeping) in (&mut positions_chunks)
+            .zip(&mut start_velocities_chunks)
+            .zip(&mut velocities_chunks)
+            .zip(&mut sleeping_chunks)
+        {
+            if sleeping.contains(&true) {
+                scalar::advance_awake_by_average(
+                    positions,
+                    start_velocities,
+                    velocities,
+                    dt,
+                    sleeping,
+                );
+            } else {
+                let average = half * (load(start_velocities) + load(velocities));
+                store(positions, load(positions) + average * dt_lanes);
+            }
+        }
+        scalar::advance_awake_by_average(
+            positions_chunks.into_remainder(),
+            start_velocities_chunks.remainder(),
+            velocities_chunks.remainder(),
+            dt,
+            sleeping_chunks.remainder(),
+        );
+    }
+    #[cfg(not(feature = "simd"))]
+    scalar::advance_awake_by_average(positions, start_velocities, velocities, dt, sleeping);
+}
+
+#[cfg(feature = "simd")]
+fn splat_pair(pair: (f32, f32)) -> f32x8 {
+    f32x8::from([
+        pair.0, pair.1, pair.0, pair.1, pair.0, pair.1, pair.0, pair.1,
+    ])
+}
+
+#[cfg(feature = "simd")]
+fn load(pairs: &[(f32, f32)]) -> f32x8 {
+    f32x8::from([
+        pairs[0].0, pairs[0].1, pairs[1].0, pairs[1].1, pairs[2].0, pairs[2].1, pairs[3].0,
+        pairs[3].1,
+    ])
+}
+
+#[cfg(feature = "simd")]
+fn store(pairs: &mut [(f32, f32)], lanes: f32x8) {
+    let lanes = lanes.to_array();
+    for (k, pair) in pairs[..CIRCLES_PER_VECTOR].iter_mut().enumerate() {
+        *pair = (lanes[2 * k], lanes[2 * k + 1]);
+    }
+}
+
+pub mod scalar {
+    pub fn add_to_awake(values: &mut [(f32, f32)], delta: (f32, f32), sleeping: &[bool]) {
+        for (value, _) in values
+            .iter_mut()
+            .zip(sleeping)
+            .filter(|(_, &sleeping)| !sleeping)
+        {
+            value.0 += delta.0;
+            value.1 += delta.1;
+        }
+    }
+
+    pub fn scale(values: &mut [(f32, f32)], factors: &[f32]) {
+        for (value, &factor) in values.iter_mut().zip(factors) {
+            value.0 *= factor;
+            value.1 *= factor;
+        }
+    }
+
+    pub fn advance_awake(
+        positions: &mut [(f32, f32)],
+        velocities: &[(f32, f32)],
+        dt: f32,
+        sleeping: &[bool],
+    ) {
+        for ((position, velocity), _) in positions
+            .iter_mut()
+            .zip(velocities)
+            .zip(sleeping)
+            .filter(|(_, &sleeping)| !sleeping)
+        {
+            position.0 += velocity.0 * dt;
+            position.1 += velocity.1 * dt;
+        }
+    }
+
+    pub fn advance_awake_by_average(
+        positions: &mut [(f32, f32)],
+        start_velocities: &[(f32, f32)],
+        velocities: &[(f32, f32)],
+        dt: f32,
+        sleeping: &[bool],
+    ) {
+        for (((position, start_velocity), velocity), _) in positions
+            .iter_mut()
+            .zip(start_velocities)
+            .zip(velocities)
+            .zip(sleeping)
+            .filter(|(_, &sleeping)| !sleeping)
+        {
+            position.0 += 0.5 * (start_velocity.0 + velocity.0) * dt;
+            position.1 += 0.5 * (start_velocity.1 + velocity.1) * dt;
+        }
+    }
+}
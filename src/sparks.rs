@@ -0,0 +1,120 @@
+use simulation::physics::GridFrame;
+use simulation::random::Rng;
+
+use crate::config_file::AppConfig;
+
+// Sparks don't have to be reproducible, so any seed will do.
+const SEED: u64 = 0x5eed;
+// Every doubling of a collision's impulse past the threshold throws off this
+// many more sparks, up to the maximum.
+const SPARKS_PER_DOUBLING: f32 = 4.0;
+const MAX_SPARKS_PER_COLLISION: usize = 12;
+// Once there are this many, the oldest make way for new ones.
+const MAX_SPARKS: usize = 400;
+const MIN_LIFETIME_TICKS: f32 = 10.0;
+const MAX_LIFETIME_TICKS: f32 = 25.0;
+// Speed, in units per tick, of sparks from a collision right at the threshold.
+// Harder hits throw them faster.
+const BASE_SPEED: f32 = 2.0;
+const GRAVITY: f32 = 0.15;
+// Fraction of their speed that sparks keep from one tick to the next.
+const DRAG: f32 = 0.92;
+
+// A purely visual particle thrown off by a hard collision. Nothing collides
+// with it and it doesn't affect the simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spark {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    age: u32,
+    lifetime: u32,
+}
+
+impl Spark {
+    // How much of the spark's life is left, from 1 when it's thrown off to 0
+    // when it's gone.
+    pub fn get_remaining_life(&self) -> f32 {
+        1.0 - self.age as f32 / self.lifetime.max(1) as f32
+    }
+}
+
+// Throws off sparks for the collisions in each new tick, and moves and ages
+// the ones already thrown off, so that they can be drawn with the frame.
+pub struct Sparks {
+    sparks: Vec<Spark>,
+    rng: Rng,
+    min_impulse: Option<f32>,
+    last_frame_number: Option<u32>,
+}
+
+impl Sparks {
+    pub fn new(config: &AppConfig) -> Self {
+        Self {
+            sparks: Vec::new(),
+            rng: Rng::new(SEED),
+            min_impulse: config.spark_min_impulse,
+            last_frame_number: None,
+        }
+    }
+
+    pub fn update_config(&mut self, config: &AppConfig) {
+        self.min_impulse = config.spark_min_impulse;
+        if self.min_impulse.is_none() {
+            self.sparks.clear();
+        }
+    }
+
+    pub fn get_sparks(&self) -> &[Spark] {
+        &self.sparks
+    }
+
+    // Only called for new ticks.
+    pub fn record_frame(&mut self, grid_frame: &GridFrame) {
+        let frame_number = grid_frame.get_frame_number();
+        // Sparks from a future that was rewound away shouldn't linger.
+        if self
+            .last_frame_number
+            .is_some_and(|last_frame_number| frame_number < last_frame_number)
+        {
+            self.sparks.clear();
+        }
+        self.last_frame_number = Some(frame_number);
+
+        for spark in &mut self.sparks {
+            spark.position.0 += spark.velocity.0;
+            spark.position.1 += spark.velocity.1;
+            spark.velocity.0 *= DRAG;
+            spark.velocity.1 = spark.velocity.1 * DRAG + GRAVITY;
+            spark.age += 1;
+        }
+        self.sparks.retain(|spark| spark.age < spark.lifetime);
+
+        let Some(min_impulse) = self.min_impulse else {
+            return;
+        };
+        for event in grid_frame.get_collision_events() {
+            if event.impulse < min_impulse {
+                continue;
+            }
+
+            let strength = event.impulse / min_impulse.max(f32::EPSILON);
+            let count = ((1.0 + strength.log2()) * SPARKS_PER_DOUBLING) as usize;
+            let speed = BASE_SPEED * strength.sqrt();
+            for _ in 0..count.min(MAX_SPARKS_PER_COLLISION) {
+                let angle = self.rng.range(0.0, std::f32::consts::TAU);
+                let speed = self.rng.range(0.5, 1.0) * speed;
+                self.sparks.push(Spark {
+                    position: event.point,
+                    velocity: (angle.cos() * speed, angle.sin() * speed),
+                    age: 0,
+                    lifetime: self.rng.range(MIN_LIFETIME_TICKS, MAX_LIFETIME_TICKS) as u32,
+                });
+            }
+        }
+
+        if self.sparks.len() > MAX_SPARKS {
+            let excess = self.sparks.len() - MAX_SPARKS;
+            self.sparks.drain(..excess);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use simulation::physics::{GridFrame, PhaseTimings};
+use tracing::{error, info};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Writes one CSV row of aggregate statistics per simulated frame.
+pub struct TelemetrySink {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl TelemetrySink {
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(
+            writer,
+            "frame,circle_count,total_kinetic_energy,max_speed,collision_pair_count,tick_ms"
+        )?;
+        for (phase, _) in PhaseTimings::default().named() {
+            write!(writer, ",{phase}_ms")?;
+        }
+        writeln!(writer)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&mut self, grid_frame: &GridFrame) {
+        let result = write!(
+            self.writer,
+            "{},{},{},{},{},{}",
+            grid_frame.get_frame_number(),
+            grid_frame.get_circle_count(),
+            grid_frame.get_total_kinetic_energy(),
+            grid_frame.get_max_speed(),
+            grid_frame.get_collision_pair_count(),
+            grid_frame.get_tick_duration().as_secs_f64() * 1000.0,
+        )
+        .and_then(|_| {
+            for (_, duration) in grid_frame.get_phase_timings().named() {
+                write!(self.writer, ",{}", duration.as_secs_f64() * 1000.0)?;
+            }
+            writeln!(self.writer)
+        })
+        .and_then(|_| self.writer.flush());
+
+        if let Err(err) = result {
+            error!(
+                "Failed to write telemetry to {}: {err}",
+                self.path.display()
+            );
+        }
+    }
+}
+
+// Opens, closes, or switches the telemetry sink so that it matches `path`.
+pub fn update_telemetry_sink(sink: &mut Option<TelemetrySink>, path: Option<&Path>) {
+    if sink.as_ref().map(TelemetrySink::path) == path {
+        return;
+    }
+
+    *sink = path.and_then(|path| match TelemetrySink::create(path) {
+        Ok(sink) => {
+            info!("Writing telemetry to {}.", path.display());
+            Some(sink)
+        }
+        Err(err) => {
+            error!("Failed to create telemetry file {}: {err}", path.display());
+            None
+        }
+    });
+}
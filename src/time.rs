@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+// `std::time::Instant` panics on wasm32, so all timing goes through
+// `web_time`, which re-exports the std types on native targets.
+pub use web_time::Instant;
+
+// Fires at a fixed rate, used to pace the simulation. Backed by tokio's timer
+// on native targets and by the browser's `setTimeout` on wasm32, where there's
+// no tokio runtime to drive timers.
+pub struct Interval {
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: tokio::time::Interval,
+    #[cfg(target_arch = "wasm32")]
+    period: Duration,
+    #[cfg(target_arch = "wasm32")]
+    next_tick: Instant,
+}
+
+impl Interval {
+    // Creates an interval whose first tick completes immediately.
+    pub fn new(period: Duration) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            Self {
+                inner: tokio::time::interval_at(tokio::time::Instant::now(), period),
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            Self {
+                period,
+                next_tick: Instant::now(),
+            }
+        }
+    }
+
+    pub async fn tick(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.inner.tick().await;
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let now = Instant::now();
+            if self.next_tick > now {
+                gloo_timers::future::sleep(self.next_tick - now).await;
+            }
+            // Don't try to catch up on missed ticks, since a browser tab in the
+            // background can stall timers for seconds at a time.
+            self.next_tick = (self.next_tick + self.period).max(Instant::now());
+        }
+    }
+}
+
+// Longest stretch of wall-clock time a single frame can account for. Anything
+// beyond this is dropped, so a stall doesn't snowball into ever more catch-up
+// ticks.
+const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+// Turns elapsed wall-clock time into a whole number of fixed-length ticks,
+// carrying the remainder over to the next frame. This keeps the simulation
+// rate independent of how often frames are rendered.
+pub struct FixedTimestep {
+    ticks_per_second: f64,
+    last_advance: Instant,
+    pending_ticks: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(ticks_per_second: u64) -> Self {
+        Self {
+            ticks_per_second: ticks_per_second as f64,
+            last_advance: Instant::now(),
+            pending_ticks: 0.0,
+        }
+    }
+
+    // Returns how many ticks are due since the last call. `speed` scales
+    // simulated time relative to wall-clock time.
+    pub fn advance(&mut self, speed: f32) -> u32 {
+        let now = Instant::now();
+        let elapsed = (now - self.last_advance).min(MAX_FRAME_TIME);
+        self.last_advance = now;
+
+        self.pending_ticks += elapsed.as_secs_f64() * self.ticks_per_second * speed as f64;
+        let due_ticks = self.pending_ticks.floor();
+        self.pending_ticks -= due_ticks;
+        due_ticks as u32
+    }
+
+    // How far the current moment is between the last tick and the next one,
+    // from 0 to 1.
+    pub fn interpolation(&self) -> f32 {
+        self.pending_ticks as f32
+    }
+}
+
+// Time since `last_frame_at`, which is then moved up to now, or zero the first
+// time. Frame streams use it to fill in each frame's
+// `FrameTiming::frame_interval` as they yield it.
+pub fn frame_interval(last_frame_at: &mut Option<Instant>) -> Duration {
+    let now = Instant::now();
+    let frame_interval = last_frame_at.map_or(Duration::ZERO, |last| now - last);
+    *last_frame_at = Some(now);
+    frame_interval
+}
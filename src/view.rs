@@ -0,0 +1,68 @@
+use iced::{Point, Rectangle, Size, Vector};
+use serde::{Deserialize, Serialize};
+
+// What resizing the window does to the world.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowResize {
+    // The world keeps its size and is scaled to fit the window without
+    // changing its shape, with bars along whichever sides it doesn't reach.
+    #[default]
+    Scale,
+    // The world keeps its size and is drawn at one pixel per unit in the
+    // middle of the window, with bars around it or its edges cut off.
+    Letterbox,
+    // The world is resized to match the window, moving the walls.
+    ResizeWorld,
+}
+
+// Where the world is drawn in the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    // Window pixels per world unit.
+    pub scale: f32,
+    // Where the world's origin is in the window.
+    pub offset: Vector,
+}
+
+impl View {
+    pub fn new(window_resize: WindowResize, world_size: Size, window_size: Size) -> Self {
+        let scale = match window_resize {
+            WindowResize::Scale if world_size.width > 0.0 && world_size.height > 0.0 => {
+                (window_size.width / world_size.width).min(window_size.height / world_size.height)
+            }
+            WindowResize::Scale | WindowResize::Letterbox | WindowResize::ResizeWorld => 1.0,
+        };
+
+        // A world that follows the window stays in its top left corner, so
+        // that it doesn't jump around while a resize is on its way to the
+        // simulation.
+        let offset = match window_resize {
+            WindowResize::Scale | WindowResize::Letterbox => Vector::new(
+                (window_size.width - world_size.width * scale) / 2.0,
+                (window_size.height - world_size.height * scale) / 2.0,
+            ),
+            WindowResize::ResizeWorld => Vector::new(0.0, 0.0),
+        };
+
+        Self { scale, offset }
+    }
+
+    pub fn to_world(self, window_point: Point) -> Point {
+        Point::new(
+            (window_point.x - self.offset.x) / self.scale,
+            (window_point.y - self.offset.y) / self.scale,
+        )
+    }
+
+    // The part of the world that shows in a window of `window_size`, which
+    // may reach past the world's edges.
+    pub fn visible_world(self, window_size: Size) -> Rectangle {
+        Rectangle::new(
+            self.to_world(Point::ORIGIN),
+            Size::new(
+                window_size.width / self.scale,
+                window_size.height / self.scale,
+            ),
+        )
+    }
+}